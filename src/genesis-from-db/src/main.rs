@@ -6,6 +6,7 @@ use base64::{engine::general_purpose, Engine as _};
 use clap::Parser;
 use many_error::{ManyError, ManyErrorCode};
 use many_ledger::storage::multisig::MultisigTransactionStorage;
+use many_ledger::storage::LedgerStorage;
 use many_modules::account::features::multisig::{MultisigAccountFeature, MultisigTransactionState};
 use many_modules::account::features::{FeatureSet, TryCreateFeature};
 use many_modules::account::{Account, AddressRoleMap, Role};
@@ -32,6 +33,7 @@ enum Extract {
     Genesis,
     Events,
     Multisig,
+    State,
 }
 
 // Implement the `FromStr` trait for `Extract`.
@@ -43,6 +45,7 @@ impl FromStr for Extract {
             "genesis" => Ok(Extract::Genesis),
             "events" => Ok(Extract::Events),
             "multisig" => Ok(Extract::Multisig),
+            "state" => Ok(Extract::State),
             _ => Err(ManyError::unknown("Invalid extract type")),
         }
     }
@@ -150,12 +153,18 @@ fn main() {
 
     let Opts { store, extract } = Opts::parse();
 
+    if let Extract::State = extract {
+        println!("{}", extract_state(store));
+        return;
+    }
+
     let merk = merk::Merk::open(store).expect("Could not open the store.");
 
     let to_print = match extract {
         Extract::Genesis => extract_genesis(&merk),
         Extract::Events => extract_events(&merk),
         Extract::Multisig => extract_multisig(&merk),
+        Extract::State => unreachable!(),
     };
 
     println!("{to_print}");
@@ -183,6 +192,18 @@ fn extract_genesis(merk: &merk::Merk) -> String {
     serde_json::to_string_pretty(&mega).expect("Could not serialize")
 }
 
+/// Extract the full ledger state in the `InitialStateJson` format.
+/// The result can be used as the `--state` of a new ledger.
+fn extract_state(store: PathBuf) -> String {
+    let storage =
+        LedgerStorage::load(store, false, None).expect("Could not load the ledger storage.");
+    let state = storage
+        .export_state()
+        .expect("Could not export the ledger state.");
+
+    serde_json::to_string_pretty(&state).expect("Could not serialize")
+}
+
 fn extract_idstore(merk: &merk::Merk) -> IdStoreJsonRoot {
     const IDSTORE_ROOT: &[u8] = b"/idstore/";
     const IDSTORE_SEED_ROOT: &[u8] = b"/config/idstore_seed";
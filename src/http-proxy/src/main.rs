@@ -1,7 +1,6 @@
 use clap::Parser;
 use many_client::client::blocking::ManyClient;
-use many_identity::{Address, AnonymousIdentity, Identity};
-use many_identity_dsa::CoseKeyIdentity;
+use many_identity::{Address, Identity};
 use many_modules::kvstore::{GetArgs, GetReturns};
 use std::net::SocketAddr;
 use std::path::PathBuf;
@@ -38,6 +37,9 @@ struct Opts {
     #[clap(long)]
     pem: Option<PathBuf>,
 
+    #[clap(flatten)]
+    key_flags: many_cli_helpers::KeyFlags,
+
     /// Number of threads to use for request processing. Defaults to 1.
     #[clap(long)]
     #[clap(value_parser = clap::value_parser!(u8).range(1..))]
@@ -130,6 +132,7 @@ fn main() {
         common_flags,
         addr,
         pem,
+        key_flags,
         server,
         server_id,
         num_threads,
@@ -144,10 +147,9 @@ fn main() {
     );
 
     let server_id = server_id.unwrap_or_default();
-    let key: Box<dyn Identity> = pem.map_or_else(
-        || Box::new(AnonymousIdentity) as Box<dyn Identity>,
-        |p| Box::new(CoseKeyIdentity::from_pem(std::fs::read_to_string(p).unwrap()).unwrap()),
-    );
+    let key: Box<dyn Identity> = key_flags
+        .identity_or_anonymous(pem.as_deref())
+        .expect("Could not load the identity");
 
     let client = Client::new(ManyClient::new(server, server_id, key).unwrap());
     let http = Arc::new(tiny_http::Server::http(addr).unwrap());
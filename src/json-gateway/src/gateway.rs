@@ -0,0 +1,232 @@
+use crate::json;
+use base64::Engine;
+use coset::{CborSerializable, CoseSign1};
+use many_client::client::blocking::ManyClient;
+use many_client::client::wait::async_token;
+use many_error::ManyError;
+use many_identity::Identity;
+use many_modules::base::{Descriptions, EndpointDescription};
+use many_protocol::{RequestMessage, ResponseMessage};
+use many_types::schema::Schema;
+use serde_json::{json, Value};
+use std::collections::BTreeSet;
+use std::sync::{Mutex, RwLock};
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+/// The minimum delay between two fetches of the endpoint descriptions.
+const REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Translates JSON calls to MANY requests, using the endpoint descriptions of
+/// the server to encode arguments and decode results.
+pub struct Gateway {
+    client: ManyClient<Box<dyn Identity>>,
+    descriptions: RwLock<Descriptions>,
+    last_refresh: Mutex<Option<Instant>>,
+    wait: bool,
+    /// The methods the gateway signs requests for.
+    allowed: BTreeSet<String>,
+}
+
+impl Gateway {
+    pub fn new(
+        client: ManyClient<Box<dyn Identity>>,
+        wait: bool,
+        allowed: BTreeSet<String>,
+    ) -> Self {
+        let gateway = Self {
+            client,
+            descriptions: Default::default(),
+            last_refresh: Mutex::new(None),
+            wait,
+            allowed,
+        };
+        gateway.refresh_descriptions();
+        gateway
+    }
+
+    /// Fetch the endpoint descriptions of the server. Servers that cannot
+    /// describe their endpoints have their values converted without schema.
+    fn refresh_descriptions(&self) {
+        {
+            let mut last_refresh = self.last_refresh.lock().unwrap();
+            if last_refresh.map_or(false, |last| last.elapsed() < REFRESH_INTERVAL) {
+                return;
+            }
+            *last_refresh = Some(Instant::now());
+        }
+
+        let descriptions = self.client.call_("describe", ()).and_then(|bytes| {
+            minicbor::decode::<Descriptions>(&bytes).map_err(ManyError::deserialization_error)
+        });
+        match descriptions {
+            Ok(descriptions) => *self.descriptions.write().unwrap() = descriptions,
+            Err(e) => warn!("Could not fetch the endpoint descriptions: {e}"),
+        }
+    }
+
+    fn description(&self, method: &str) -> Option<EndpointDescription> {
+        let description = self.descriptions.read().unwrap().0.get(method).cloned();
+        if description.is_some() {
+            return description;
+        }
+        // The server may have been upgraded since the descriptions were fetched.
+        self.refresh_descriptions();
+        self.descriptions.read().unwrap().0.get(method).cloned()
+    }
+
+    /// Call a method with a JSON argument, signing the request with the
+    /// identity of the gateway. Only allowed methods can be called.
+    pub fn call(&self, method: &str, params: &Value) -> Result<Value, ManyError> {
+        if !self.allowed.contains(method) {
+            return Err(ManyError::method_not_allowed(method));
+        }
+
+        let description = self.description(method);
+        let argument = match description.as_ref().map(|d| &d.argument) {
+            Some(None) if params.is_null() => {
+                minicbor::to_vec(()).map_err(|e| ManyError::serialization_error(e.to_string()))?
+            }
+            Some(None) => {
+                return Err(ManyError::deserialization_error(format!(
+                    "Method '{method}' does not take an argument."
+                )))
+            }
+            Some(Some(schema)) => json::to_cbor(schema, params)
+                .map_err(|e| ManyError::deserialization_error(format!("Invalid argument: {e}")))?,
+            None => json::to_cbor(&Schema::Any, params)
+                .map_err(|e| ManyError::deserialization_error(format!("Invalid argument: {e}")))?,
+        };
+
+        let mut response = self.client.call_raw(method, &argument)?;
+        if self.wait {
+            response = self.client.wait_response(response)?;
+        }
+        let returns = description.map_or(Schema::Any, |d| d.returns);
+        result_json(&returns, &response)
+    }
+
+    /// Send a request envelope signed by the caller, and return the result
+    /// along with the response envelope signed by the server.
+    pub fn relay(&self, envelope: &[u8]) -> Result<Value, ManyError> {
+        let envelope = CoseSign1::from_slice(envelope)
+            .map_err(|e| ManyError::deserialization_error(e.to_string()))?;
+        let payload = envelope
+            .payload
+            .as_ref()
+            .ok_or_else(ManyError::empty_envelope)?;
+        let request =
+            RequestMessage::from_bytes(payload).map_err(ManyError::deserialization_error)?;
+
+        let response_envelope = self.client.send_envelope(envelope)?;
+        let response = ResponseMessage::from_bytes(
+            response_envelope
+                .payload
+                .as_ref()
+                .ok_or_else(ManyError::unexpected_empty_response)?,
+        )
+        .map_err(ManyError::deserialization_error)?;
+        let response_bytes = response_envelope
+            .to_vec()
+            .map_err(|e| ManyError::serialization_error(e.to_string()))?;
+
+        let returns = self
+            .description(&request.method)
+            .map_or(Schema::Any, |d| d.returns);
+        Ok(json!({
+            "result": result_json(&returns, &response)?,
+            "envelope": base64::engine::general_purpose::STANDARD.encode(response_bytes),
+        }))
+    }
+}
+
+/// The JSON result of a response, or its async token if the server has not
+/// finished processing the request.
+fn result_json(returns: &Schema, response: &ResponseMessage) -> Result<Value, ManyError> {
+    if let Some(token) = async_token(response) {
+        return Ok(json!({ "async_token": hex::encode(&token) }));
+    }
+    let data = response.data.as_ref().map_err(Clone::clone)?;
+    json::to_json(returns, data).map_err(ManyError::deserialization_error)
+}
+
+/// The JSON representation of an error.
+pub fn error_json(error: &ManyError) -> Value {
+    json!({
+        "code": i64::from(error.code()),
+        "message": error.to_string(),
+        "arguments": error.arguments(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use many_identity::{AcceptAllVerifier, Address, AnonymousIdentity};
+    use many_modules::ledger::{
+        BalanceArgs, BalanceReturns, InfoArgs, InfoReturns, LedgerModule, LedgerModuleBackend,
+    };
+    use many_protocol::context::Context;
+    use many_server::transport::tcp::TcpServer;
+    use many_server::ManyServer;
+    use std::collections::BTreeMap;
+    use std::sync::Arc;
+    use tokio::net::TcpListener;
+
+    /// A ledger where the balance of an account is the height it is queried at.
+    struct Ledger;
+
+    impl LedgerModuleBackend for Ledger {
+        fn info(&self, _: &Address, _: InfoArgs, _: Context) -> Result<InfoReturns, ManyError> {
+            Err(ManyError::unknown("Not implemented."))
+        }
+
+        fn balance(
+            &self,
+            _: &Address,
+            args: BalanceArgs,
+            _: Context,
+        ) -> Result<BalanceReturns, ManyError> {
+            let account = args
+                .account
+                .ok_or_else(|| ManyError::required_field_missing("account"))?;
+            let amount = args.height.unwrap_or_default().into();
+            Ok(BalanceReturns {
+                balances: BTreeMap::from([(account, amount)]),
+            })
+        }
+    }
+
+    async fn gateway(allowed: &[&str]) -> Gateway {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("tcp://{}", listener.local_addr().unwrap());
+        let many = ManyServer::simple("gateway", AnonymousIdentity, AcceptAllVerifier, None);
+        many.lock()
+            .unwrap()
+            .add_module(LedgerModule::new(Arc::new(Mutex::new(Ledger))));
+        let server = TcpServer::new(many);
+        tokio::spawn(async move { server.serve(listener).await });
+
+        let identity: Box<dyn Identity> = Box::new(AnonymousIdentity);
+        let client = ManyClient::new(url, Address::anonymous(), identity).unwrap();
+        Gateway::new(
+            client,
+            false,
+            allowed.iter().map(ToString::to_string).collect(),
+        )
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn call() {
+        let gateway = gateway(&["ledger.balance"]).await;
+        let result = gateway.call("ledger.balance", &json!({ "account": "maa", "height": 5 }));
+        assert_eq!(result.unwrap(), json!({ "balances": { "maa": "5" } }));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn not_allowed() {
+        let gateway = gateway(&["ledger.balance"]).await;
+        let err = gateway.call("ledger.info", &Value::Null).unwrap_err();
+        assert_eq!(err.code(), ManyError::method_not_allowed("").code());
+    }
+}
@@ -0,0 +1,511 @@
+//! Conversion between JSON values and CBOR values of a [`Schema`].
+//!
+//! Values use their natural JSON representation, with a few conventions:
+//! - structs are objects keyed by field name;
+//! - byte strings are hex strings;
+//! - addresses are their textual representation (e.g. `"maa"`);
+//! - token amounts are decimal strings (numbers are also accepted);
+//! - the keys of maps are strings, e.g. `"1"` for a map with integer keys;
+//! - other tags are transparent.
+//!
+//! Values of unknown schemas (`Any`), and of recursive types nested in
+//! themselves, are converted as is, with byte strings decoded as hex strings.
+//! When encoding them, strings that are valid addresses are encoded as
+//! addresses, and object keys that are integers are encoded as integers, as
+//! the keys of structs are.
+use many_identity::Address;
+use many_types::ledger::TokenAmount;
+use many_types::schema::Schema;
+use minicbor::data::Type;
+use minicbor::{Decoder, Encoder};
+use num_bigint::BigUint;
+use serde_json::{Map, Number, Value};
+use std::str::FromStr;
+
+const ADDRESS_TAG: u64 = 10000;
+const BIGNUM_TAG: u64 = 2;
+
+type Error = String;
+
+/// Encode a JSON value as CBOR, following a schema.
+pub fn to_cbor(schema: &Schema, value: &Value) -> Result<Vec<u8>, Error> {
+    let mut e = Encoder::new(Vec::new());
+    encode(schema, value, &mut e)?;
+    Ok(e.into_writer())
+}
+
+/// Decode a CBOR value as JSON, following a schema.
+pub fn to_json(schema: &Schema, bytes: &[u8]) -> Result<Value, Error> {
+    let mut d = Decoder::new(bytes);
+    let value = decode(schema, &mut d)?;
+    if d.position() != bytes.len() {
+        return Err("Unexpected data after the value.".to_string());
+    }
+    Ok(value)
+}
+
+fn mismatch(expected: &str, value: &Value) -> Error {
+    format!("Expected {expected}, found {value}.")
+}
+
+fn encode(schema: &Schema, value: &Value, e: &mut Encoder<Vec<u8>>) -> Result<(), Error> {
+    let err = |e: minicbor::encode::Error<_>| e.to_string();
+
+    match (schema, value) {
+        (Schema::Any | Schema::Ref(_), value) => encode_any(value, e)?,
+        (Schema::Null, Value::Null) => {
+            e.null().map_err(err)?;
+        }
+        (Schema::Bool, Value::Bool(b)) => {
+            e.bool(*b).map_err(err)?;
+        }
+        (Schema::Uint, Value::Number(n)) if n.is_u64() => {
+            e.u64(n.as_u64().unwrap_or_default()).map_err(err)?;
+        }
+        (Schema::Int, Value::Number(n)) if n.is_u64() || n.is_i64() => {
+            match n.as_u64() {
+                Some(n) => e.u64(n),
+                None => e.i64(n.as_i64().unwrap_or_default()),
+            }
+            .map_err(err)?;
+        }
+        (Schema::Float, Value::Number(n)) => {
+            e.f64(n.as_f64().unwrap_or_default()).map_err(err)?;
+        }
+        (Schema::Bytes, Value::String(s)) => {
+            let bytes = hex::decode(s).map_err(|_| mismatch("a hex string", value))?;
+            e.bytes(&bytes).map_err(err)?;
+        }
+        (Schema::Text, Value::String(s)) => {
+            e.str(s).map_err(err)?;
+        }
+        (Schema::Literal(literal), Value::Number(n)) if n.as_u64() == Some(*literal) => {
+            e.u64(*literal).map_err(err)?;
+        }
+        (Schema::Array(schema), Value::Array(items)) => {
+            e.array(items.len() as u64).map_err(err)?;
+            for item in items {
+                encode(schema, item, e)?;
+            }
+        }
+        (Schema::Map(key, value), Value::Object(entries)) => {
+            e.map(entries.len() as u64).map_err(err)?;
+            for (k, v) in entries {
+                encode(key, &key_value(key, k), e)?;
+                encode(value, v, e)?;
+            }
+        }
+        (Schema::Tuple(schemas), Value::Array(items)) => {
+            if items.len() > schemas.len() {
+                return Err(format!("Expected an array of {} values.", schemas.len()));
+            }
+            e.array(items.len() as u64).map_err(err)?;
+            for (schema, item) in schemas.iter().zip(items) {
+                encode(schema, item, e)?;
+            }
+        }
+        (Schema::Struct(fields), Value::Object(entries)) => {
+            if let Some(name) = entries
+                .keys()
+                .find(|name| !fields.iter().any(|f| &f.name == *name))
+            {
+                return Err(format!("Unknown field '{name}'."));
+            }
+            let present = fields
+                .iter()
+                .filter_map(|f| match entries.get(&f.name) {
+                    None | Some(Value::Null) if f.optional => None,
+                    None => Some(Err(format!("Missing field '{}'.", f.name))),
+                    Some(value) => Some(Ok((f, value))),
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+
+            e.map(present.len() as u64).map_err(err)?;
+            for (field, value) in present {
+                e.u64(field.key).map_err(err)?;
+                encode(&field.schema, value, e)
+                    .map_err(|e| format!("Invalid field '{}': {e}", field.name))?;
+            }
+        }
+        (Schema::Choice(schemas), value) => {
+            let bytes = schemas
+                .iter()
+                .find_map(|schema| to_cbor(schema, value).ok())
+                .ok_or_else(|| mismatch("one of the possible values", value))?;
+            e.writer_mut().extend_from_slice(&bytes);
+        }
+        (Schema::Tag(tag, schema), value) => {
+            e.tag(minicbor::data::Tag::from(*tag)).map_err(err)?;
+            encode(schema, value, e)?;
+        }
+        (Schema::Named(name, _), Value::String(s)) if name == "Address" => {
+            let address = Address::from_str(s).map_err(|_| mismatch("an address", value))?;
+            e.encode(address).map_err(err)?;
+        }
+        (Schema::Named(name, _), Value::String(_) | Value::Number(_)) if name == "TokenAmount" => {
+            let amount = match value {
+                Value::String(s) => BigUint::from_str(s).ok(),
+                _ => value.as_u64().map(BigUint::from),
+            }
+            .ok_or_else(|| mismatch("a token amount", value))?;
+            e.encode(TokenAmount::from(amount)).map_err(err)?;
+        }
+        (Schema::Named(name, schema), value) => {
+            encode(schema, value, e).map_err(|e| format!("Invalid {name}: {e}"))?
+        }
+        (schema, value) => {
+            return Err(mismatch(
+                &format!("a value of {}", schema.to_cddl("")),
+                value,
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Encode a value of an unknown schema.
+fn encode_any(value: &Value, e: &mut Encoder<Vec<u8>>) -> Result<(), Error> {
+    let err = |e: minicbor::encode::Error<_>| e.to_string();
+
+    match value {
+        Value::Null => {
+            e.null().map_err(err)?;
+        }
+        Value::Bool(b) => {
+            e.bool(*b).map_err(err)?;
+        }
+        Value::Number(n) => {
+            if let Some(n) = n.as_u64() {
+                e.u64(n)
+            } else if let Some(n) = n.as_i64() {
+                e.i64(n)
+            } else {
+                e.f64(n.as_f64().unwrap_or_default())
+            }
+            .map_err(err)?;
+        }
+        Value::String(s) => match Address::from_str(s) {
+            Ok(address) => {
+                e.encode(address).map_err(err)?;
+            }
+            Err(_) => {
+                e.str(s).map_err(err)?;
+            }
+        },
+        Value::Array(items) => {
+            e.array(items.len() as u64).map_err(err)?;
+            for item in items {
+                encode_any(item, e)?;
+            }
+        }
+        Value::Object(entries) => {
+            e.map(entries.len() as u64).map_err(err)?;
+            for (k, v) in entries {
+                match serde_json::from_str::<Number>(k) {
+                    Ok(n) if n.is_u64() || n.is_i64() => encode_any(&Value::Number(n), e)?,
+                    _ => encode_any(&Value::String(k.clone()), e)?,
+                }
+                encode_any(v, e)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The JSON value of an object key, for a map with keys of this schema.
+fn key_value(schema: &Schema, key: &str) -> Value {
+    match schema {
+        Schema::Text | Schema::Bytes => Value::String(key.to_string()),
+        Schema::Named(name, _) if name == "Address" || name == "TokenAmount" => {
+            Value::String(key.to_string())
+        }
+        Schema::Named(_, schema) => key_value(schema, key),
+        _ => serde_json::from_str(key).unwrap_or_else(|_| Value::String(key.to_string())),
+    }
+}
+
+/// The object key of a JSON value.
+fn object_key(value: Value) -> String {
+    match value {
+        Value::String(s) => s,
+        value => value.to_string(),
+    }
+}
+
+fn decode(schema: &Schema, d: &mut Decoder) -> Result<Value, Error> {
+    let datatype = d.datatype().map_err(|e| e.to_string())?;
+    let position = d.position();
+    let mismatch =
+        |expected: &str| format!("Expected {expected}, found {datatype:?} at position {position}.");
+    let err = |e: minicbor::decode::Error| e.to_string();
+
+    Ok(match schema {
+        Schema::Any | Schema::Ref(_) => decode_any(d)?,
+        Schema::Null => match datatype {
+            Type::Null | Type::Undefined => {
+                d.skip().map_err(err)?;
+                Value::Null
+            }
+            _ => return Err(mismatch("null")),
+        },
+        Schema::Bool => Value::Bool(d.bool().map_err(err)?),
+        Schema::Uint => match datatype {
+            Type::U8 | Type::U16 | Type::U32 | Type::U64 => Value::from(d.u64().map_err(err)?),
+            _ => return Err(mismatch("an unsigned integer")),
+        },
+        Schema::Int => match datatype {
+            Type::U8 | Type::U16 | Type::U32 | Type::U64 => Value::from(d.u64().map_err(err)?),
+            Type::I8 | Type::I16 | Type::I32 | Type::I64 => Value::from(d.i64().map_err(err)?),
+            _ => return Err(mismatch("an integer")),
+        },
+        Schema::Float => match datatype {
+            Type::F16 | Type::F32 | Type::F64 => float(d)?,
+            _ => return Err(mismatch("a float")),
+        },
+        Schema::Bytes => Value::String(hex::encode(d.bytes().map_err(err)?)),
+        Schema::Text => Value::String(d.str().map_err(err)?.to_string()),
+        Schema::Literal(literal) => match d.u64() {
+            Ok(x) if x == *literal => Value::from(x),
+            _ => return Err(mismatch(&literal.to_string())),
+        },
+        Schema::Array(schema) => {
+            let len = d.array().map_err(err)?;
+            Value::Array(decode_items(d, len, |d, _| decode(schema, d))?)
+        }
+        Schema::Map(key, value) => {
+            let len = d.map().map_err(err)?;
+            let entries = decode_items(d, len, |d, _| {
+                Ok((object_key(decode(key, d)?), decode(value, d)?))
+            })?;
+            Value::Object(entries.into_iter().collect())
+        }
+        Schema::Tuple(schemas) => {
+            let len = d.array().map_err(err)?;
+            Value::Array(decode_items(d, len, |d, i| match schemas.get(i) {
+                Some(schema) => decode(schema, d),
+                None => Err(format!("Expected an array of {} values.", schemas.len())),
+            })?)
+        }
+        Schema::Struct(fields) => {
+            let len = d.map().map_err(err)?;
+            let entries = decode_items(d, len, |d, _| {
+                let key = decode_any(d)?;
+                match fields.iter().find(|f| key.as_u64() == Some(f.key)) {
+                    Some(field) => Ok((
+                        field.name.clone(),
+                        decode(&field.schema, d)
+                            .map_err(|e| format!("Invalid field '{}': {e}", field.name))?,
+                    )),
+                    None => Ok((object_key(key), decode_any(d)?)),
+                }
+            })?;
+            let entries: Map<String, Value> = entries.into_iter().collect();
+            if let Some(field) = fields
+                .iter()
+                .find(|f| !f.optional && !entries.contains_key(&f.name))
+            {
+                return Err(format!("Missing field '{}'.", field.name));
+            }
+            Value::Object(entries)
+        }
+        Schema::Choice(schemas) => {
+            for schema in schemas {
+                let mut attempt = d.clone();
+                if let Ok(value) = decode(schema, &mut attempt) {
+                    d.set_position(attempt.position());
+                    return Ok(value);
+                }
+            }
+            return Err(mismatch("one of the possible values"));
+        }
+        Schema::Tag(tag, schema) => match datatype {
+            Type::Tag if u64::from(d.tag().map_err(err)?) == *tag => decode(schema, d)?,
+            _ => return Err(mismatch(&format!("tag {tag}"))),
+        },
+        Schema::Named(name, _) if name == "Address" => {
+            let address: Address = d.decode().map_err(err)?;
+            Value::String(address.to_string())
+        }
+        Schema::Named(name, _) if name == "TokenAmount" => {
+            let amount: TokenAmount = d.decode().map_err(err)?;
+            Value::String(amount.to_string())
+        }
+        Schema::Named(name, schema) => {
+            decode(schema, d).map_err(|e| format!("Invalid {name}: {e}"))?
+        }
+    })
+}
+
+/// Decode a value of an unknown schema.
+fn decode_any(d: &mut Decoder) -> Result<Value, Error> {
+    let err = |e: minicbor::decode::Error| e.to_string();
+
+    Ok(match d.datatype().map_err(err)? {
+        Type::Null | Type::Undefined => {
+            d.skip().map_err(err)?;
+            Value::Null
+        }
+        Type::Bool => Value::Bool(d.bool().map_err(err)?),
+        Type::U8 | Type::U16 | Type::U32 | Type::U64 => Value::from(d.u64().map_err(err)?),
+        Type::I8 | Type::I16 | Type::I32 | Type::I64 => Value::from(d.i64().map_err(err)?),
+        Type::F16 | Type::F32 | Type::F64 => float(d)?,
+        Type::Bytes => Value::String(hex::encode(d.bytes().map_err(err)?)),
+        Type::String => Value::String(d.str().map_err(err)?.to_string()),
+        Type::Array | Type::ArrayIndef => {
+            let len = d.array().map_err(err)?;
+            Value::Array(decode_items(d, len, |d, _| decode_any(d))?)
+        }
+        Type::Map | Type::MapIndef => {
+            let len = d.map().map_err(err)?;
+            let entries = decode_items(d, len, |d, _| {
+                Ok((object_key(decode_any(d)?), decode_any(d)?))
+            })?;
+            Value::Object(entries.into_iter().collect())
+        }
+        Type::Tag => {
+            let tag = u64::from(d.clone().tag().map_err(err)?);
+            match tag {
+                ADDRESS_TAG => Value::String(d.decode::<Address>().map_err(err)?.to_string()),
+                BIGNUM_TAG => Value::String(d.decode::<TokenAmount>().map_err(err)?.to_string()),
+                _ => {
+                    d.tag().map_err(err)?;
+                    decode_any(d)?
+                }
+            }
+        }
+        datatype => return Err(format!("Unsupported CBOR type {datatype:?}.")),
+    })
+}
+
+fn float(d: &mut Decoder) -> Result<Value, Error> {
+    let err = |e: minicbor::decode::Error| e.to_string();
+    let value = match d.datatype().map_err(err)? {
+        Type::F16 => d.f16().map_err(err)? as f64,
+        Type::F32 => d.f32().map_err(err)? as f64,
+        _ => d.f64().map_err(err)?,
+    };
+    Ok(Number::from_f64(value).map_or(Value::Null, Value::Number))
+}
+
+/// Decode the items of an array or map of length `len` (or indefinite length).
+fn decode_items<'b, T>(
+    d: &mut Decoder<'b>,
+    len: Option<u64>,
+    mut decode: impl FnMut(&mut Decoder<'b>, usize) -> Result<T, Error>,
+) -> Result<Vec<T>, Error> {
+    match len {
+        Some(len) => (0..len as usize).map(|i| decode(d, i)).collect(),
+        None => {
+            let mut items = vec![];
+            while d.datatype().map_err(|e| e.to_string())? != Type::Break {
+                items.push(decode(d, items.len())?);
+            }
+            d.skip().map_err(|e| e.to_string())?;
+            Ok(items)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use many_modules::ledger::BalanceArgs;
+    use many_types::schema::{CborSchema, Field};
+    use serde_json::json;
+
+    fn args_schema() -> Schema {
+        Schema::named(
+            "SendArgs",
+            Schema::Struct(vec![
+                Field::new(0, "from", Address::schema()).optional(),
+                Field::new(1, "to", Address::schema()),
+                Field::new(2, "amount", TokenAmount::schema()),
+                Field::new(3, "symbol", Address::schema()),
+                Field::new(4, "memo", Schema::Bytes).optional(),
+            ]),
+        )
+    }
+
+    #[test]
+    fn roundtrip() {
+        let schema = args_schema();
+        let value = json!({
+            "to": "maa",
+            "amount": "100000000000000000000",
+            "symbol": "maa",
+            "memo": "0102",
+        });
+        let bytes = to_cbor(&schema, &value).unwrap();
+        schema.validate(&bytes).unwrap();
+        assert_eq!(to_json(&schema, &bytes).unwrap(), value);
+
+        // Numbers are accepted as token amounts.
+        let bytes = to_cbor(
+            &schema,
+            &json!({ "to": "maa", "amount": 5, "symbol": "maa" }),
+        );
+        assert_eq!(
+            to_json(&schema, &bytes.unwrap()).unwrap()["amount"],
+            json!("5")
+        );
+    }
+
+    #[test]
+    fn invalid() {
+        let schema = args_schema();
+        let err = to_cbor(&schema, &json!({ "to": "maa", "symbol": "maa" })).unwrap_err();
+        assert!(err.contains("'amount'"), "{err}");
+        let err = to_cbor(
+            &schema,
+            &json!({ "to": "maa", "amount": 1, "symbol": "maa", "x": 1 }),
+        )
+        .unwrap_err();
+        assert!(err.contains("'x'"), "{err}");
+        assert!(to_cbor(&schema, &json!({ "to": "x", "amount": 1, "symbol": "maa" })).is_err());
+    }
+
+    #[test]
+    fn collections() {
+        let schema = Schema::Tuple(vec![
+            Schema::map(Address::schema(), Schema::Uint),
+            Schema::array(Schema::Choice(vec![Schema::Literal(0), Schema::Text])),
+            Option::<u64>::schema(),
+        ]);
+        let value = json!([{ "maa": 1 }, [0, "a"], null]);
+        let bytes = to_cbor(&schema, &value).unwrap();
+        schema.validate(&bytes).unwrap();
+        assert_eq!(to_json(&schema, &bytes).unwrap(), value);
+    }
+
+    #[test]
+    fn any() {
+        let bytes = minicbor::to_vec((Address::anonymous(), 1u8, -1i8, "a")).unwrap();
+        assert_eq!(
+            to_json(&Schema::Any, &bytes).unwrap(),
+            json!(["maa", 1, -1, "a"])
+        );
+        let value = json!({ "a": [null, true, 1.5] });
+        let bytes = to_cbor(&Schema::Any, &value).unwrap();
+        assert_eq!(to_json(&Schema::Any, &bytes).unwrap(), value);
+    }
+
+    #[test]
+    fn any_map() {
+        // Without a schema, integer keys and addresses keep their encoding.
+        let value = json!({ "0": "maa", "2": 5 });
+        let bytes = to_cbor(&Schema::Any, &value).unwrap();
+        let args: BalanceArgs = minicbor::decode(&bytes).unwrap();
+        assert_eq!(args.account, Some(Address::anonymous()));
+        assert_eq!(args.height, Some(5));
+        assert_eq!(to_json(&Schema::Any, &bytes).unwrap(), value);
+
+        let bytes = to_cbor(
+            &BalanceArgs::schema(),
+            &json!({ "account": "maa", "height": 5 }),
+        );
+        assert_eq!(
+            minicbor::decode::<BalanceArgs>(&bytes.unwrap()).unwrap(),
+            args
+        );
+    }
+}
@@ -0,0 +1,310 @@
+use base64::Engine;
+use clap::Parser;
+use gateway::{error_json, Gateway};
+use many_client::client::blocking::ManyClient;
+use many_error::{ManyError, ManyErrorCode};
+use many_identity::{Address, Identity};
+use serde_json::{json, Value};
+use std::io::Read;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::thread;
+use tiny_http::{Header, Method, Request, Response, Server, StatusCode};
+use tracing::{debug, info, warn};
+
+mod gateway;
+mod json;
+
+/// The maximum size of a request body, in bytes.
+const MAX_BODY_SIZE: u64 = 1024 * 1024;
+
+// JSON-RPC 2.0 error codes, for requests that cannot be sent to the server.
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+
+#[derive(Debug, Parser)]
+struct Opts {
+    #[clap(flatten)]
+    common_flags: many_cli_helpers::CommonCliFlags,
+
+    /// Many server URL to connect to.
+    #[clap(default_value = "http://localhost:8000")]
+    server: String,
+
+    /// Port and address to bind to.
+    #[clap(long, default_value = "127.0.0.1:8890")]
+    addr: SocketAddr,
+
+    /// The identity of the server (an identity string), or anonymous if you don't know it.
+    server_id: Option<Address>,
+
+    /// A PEM file for the identity signing the requests. If not specified, anonymous will
+    /// be used.
+    #[clap(long)]
+    pem: Option<PathBuf>,
+
+    #[clap(flatten)]
+    key_flags: many_cli_helpers::KeyFlags,
+
+    /// A method that the gateway signs requests for. Can be repeated. Defaults
+    /// to the queries of the standard modules. Other requests must be signed by
+    /// the client and sent to `/envelope`.
+    #[clap(long = "allow", value_name = "METHOD")]
+    allow: Vec<String>,
+
+    /// Return the async token of commands instead of waiting for their result.
+    #[clap(long)]
+    no_wait: bool,
+
+    /// The value of the Access-Control-Allow-Origin header of responses, for
+    /// browser clients.
+    #[clap(long)]
+    allow_origin: Option<String>,
+
+    /// Number of threads to use for request processing. Defaults to 1.
+    #[clap(long)]
+    #[clap(value_parser = clap::value_parser!(u8).range(1..))]
+    num_threads: Option<u8>,
+}
+
+struct State {
+    gateway: Gateway,
+    allow_origin: Option<String>,
+}
+
+fn process_request(http: Arc<Server>, state: Arc<State>) -> impl Fn() {
+    move || {
+        for mut request in http.incoming_requests() {
+            let method = request.method().clone();
+            let url = request.url().to_string();
+            let (status, body) = match (&method, url.as_str()) {
+                (Method::Options, _) => (204, None),
+                (Method::Post, "/") => match read_body(&mut request) {
+                    Ok(body) => (200, Some(handle_rpc(&state.gateway, &body))),
+                    Err(e) => (200, Some(rpc_error(Value::Null, PARSE_ERROR, e))),
+                },
+                (Method::Post, "/envelope") => handle_envelope(&state.gateway, &mut request),
+                (Method::Post, url) if url.starts_with("/v1/") => {
+                    handle_call(&state.gateway, &url["/v1/".len()..], &mut request)
+                }
+                (Method::Post, _) => (404, None),
+                (x, _) => {
+                    warn!("Received unknown method: {}", x);
+                    (405, None)
+                }
+            };
+            respond(request, status, body, state.allow_origin.as_deref());
+        }
+    }
+}
+
+fn read_body(request: &mut Request) -> Result<Vec<u8>, String> {
+    let mut body = vec![];
+    request
+        .as_reader()
+        .take(MAX_BODY_SIZE + 1)
+        .read_to_end(&mut body)
+        .map_err(|e| e.to_string())?;
+    if body.len() as u64 > MAX_BODY_SIZE {
+        return Err(format!(
+            "Request body is larger than {MAX_BODY_SIZE} bytes."
+        ));
+    }
+    Ok(body)
+}
+
+/// The HTTP status of a REST call that failed with this error.
+fn error_status(error: &ManyError) -> u16 {
+    match error.code() {
+        ManyErrorCode::CouldNotRouteMessage | ManyErrorCode::InvalidMethodName => 404,
+        ManyErrorCode::MethodNotAllowed => 403,
+        ManyErrorCode::RateLimited => 429,
+        ManyErrorCode::InternalServerError => 500,
+        ManyErrorCode::UnexpectedTransportError => 502,
+        _ => 400,
+    }
+}
+
+fn rest_response(result: Result<Value, ManyError>) -> (u16, Option<Value>) {
+    match result {
+        Ok(result) => (200, Some(result)),
+        Err(e) => (error_status(&e), Some(json!({ "error": error_json(&e) }))),
+    }
+}
+
+/// `POST /v1/<method>`, with the JSON argument as body. An empty body means
+/// no argument.
+fn handle_call(gateway: &Gateway, method: &str, request: &mut Request) -> (u16, Option<Value>) {
+    debug!("Received call of {method}");
+    let params = read_body(request)
+        .map_err(ManyError::deserialization_error)
+        .and_then(|body| match body.iter().all(u8::is_ascii_whitespace) {
+            true => Ok(Value::Null),
+            false => serde_json::from_slice(&body).map_err(ManyError::deserialization_error),
+        });
+    rest_response(params.and_then(|params| gateway.call(method, &params)))
+}
+
+/// `POST /envelope`, with a request envelope signed by the client. The body is
+/// either the envelope itself (with an `application/cbor` content type), or a
+/// JSON object with its base64 encoding, i.e. `{"envelope": "..."}`.
+fn handle_envelope(gateway: &Gateway, request: &mut Request) -> (u16, Option<Value>) {
+    let is_cbor = request
+        .headers()
+        .iter()
+        .any(|h| h.field.equiv("content-type") && h.value.as_str().starts_with("application/cbor"));
+    let envelope = read_body(request)
+        .map_err(ManyError::deserialization_error)
+        .and_then(|body| {
+            if is_cbor {
+                return Ok(body);
+            }
+            let value: Value =
+                serde_json::from_slice(&body).map_err(ManyError::deserialization_error)?;
+            let envelope = value["envelope"]
+                .as_str()
+                .ok_or_else(|| ManyError::required_field_missing("envelope"))?;
+            base64::engine::general_purpose::STANDARD
+                .decode(envelope)
+                .map_err(ManyError::deserialization_error)
+        });
+    rest_response(envelope.and_then(|envelope| gateway.relay(&envelope)))
+}
+
+/// `POST /`, with a JSON-RPC 2.0 request or batch of requests.
+fn handle_rpc(gateway: &Gateway, body: &[u8]) -> Value {
+    match serde_json::from_slice::<Value>(body) {
+        Ok(Value::Array(requests)) if !requests.is_empty() => Value::Array(
+            requests
+                .iter()
+                .map(|request| handle_rpc_request(gateway, request))
+                .collect(),
+        ),
+        Ok(request) => handle_rpc_request(gateway, &request),
+        Err(e) => rpc_error(Value::Null, PARSE_ERROR, e),
+    }
+}
+
+fn handle_rpc_request(gateway: &Gateway, request: &Value) -> Value {
+    let id = request.get("id").cloned().unwrap_or(Value::Null);
+    let method = match (request.get("jsonrpc"), request.get("method")) {
+        (Some(Value::String(version)), Some(Value::String(method))) if version == "2.0" => method,
+        _ => return rpc_error(id, INVALID_REQUEST, "Invalid JSON-RPC 2.0 request."),
+    };
+    debug!("Received call of {method}");
+    let params = request.get("params").unwrap_or(&Value::Null);
+
+    match gateway.call(method, params) {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err(e) => {
+            let error = error_json(&e);
+            json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": {
+                    "code": error["code"],
+                    "message": error["message"],
+                    "data": { "arguments": error["arguments"] },
+                },
+            })
+        }
+    }
+}
+
+fn rpc_error(id: Value, code: i64, message: impl ToString) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": code, "message": message.to_string() },
+    })
+}
+
+fn respond(request: Request, status: u16, body: Option<Value>, allow_origin: Option<&str>) {
+    let mut response = match body {
+        Some(body) => {
+            let body = body.to_string().into_bytes();
+            let len = body.len();
+            Response::empty(StatusCode::from(status))
+                .with_data(std::io::Cursor::new(body), Some(len))
+                .with_header(
+                    Header::from_bytes("Content-Type", "application/json").expect("Invalid header"),
+                )
+        }
+        None => Response::empty(StatusCode::from(status))
+            .with_data(std::io::Cursor::new(vec![]), Some(0)),
+    };
+
+    if let Some(origin) = allow_origin {
+        for (field, value) in [
+            ("Access-Control-Allow-Origin", origin),
+            ("Access-Control-Allow-Methods", "POST, OPTIONS"),
+            ("Access-Control-Allow-Headers", "Content-Type"),
+        ] {
+            match Header::from_bytes(field, value) {
+                Ok(header) => response = response.with_header(header),
+                Err(_) => warn!("Failed to create header {field}: {value}"),
+            }
+        }
+    }
+
+    if let Err(e) = request.respond(response) {
+        warn!("Failed to send response: {}", e);
+    }
+}
+
+fn main() {
+    let Opts {
+        common_flags,
+        server,
+        addr,
+        server_id,
+        pem,
+        key_flags,
+        allow,
+        no_wait,
+        allow_origin,
+        num_threads,
+    } = Opts::parse();
+
+    common_flags.init_logging().unwrap();
+
+    debug!("{:?}", Opts::parse());
+    info!(
+        version = env!("CARGO_PKG_VERSION"),
+        git_sha = env!("VERGEN_GIT_SHA")
+    );
+
+    let server_id = server_id.unwrap_or_default();
+    let key: Box<dyn Identity> = key_flags
+        .identity_or_anonymous(pem.as_deref())
+        .expect("Could not load the identity");
+
+    let allowed = if allow.is_empty() {
+        many_modules::modules()
+            .into_iter()
+            .flat_map(|info| info.queries.iter().cloned())
+            .collect()
+    } else {
+        allow.into_iter().collect()
+    };
+
+    let client = ManyClient::new(server, server_id, key).unwrap();
+    let state = Arc::new(State {
+        gateway: Gateway::new(client, !no_wait, allowed),
+        allow_origin,
+    });
+    let http = Arc::new(Server::http(addr).unwrap());
+
+    let mut handles = Vec::new();
+
+    for _ in 0..num_threads.unwrap_or(1) {
+        let http = http.clone();
+        let state = state.clone();
+        handles.push(thread::spawn(process_request(http, state)));
+    }
+
+    for h in handles {
+        h.join().unwrap();
+    }
+}
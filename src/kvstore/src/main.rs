@@ -1,19 +1,15 @@
 use clap::Parser;
-use many_client::client::blocking::ManyClient;
+use many_client::client::blocking::{KvStoreClient, ManyClient};
 use many_error::{ManyError, Reason};
-use many_identity::{Address, AnonymousIdentity, Identity};
-use many_identity_dsa::CoseKeyIdentity;
-use many_modules::kvstore::list::{ListArgs, ListReturns};
+use many_identity::{Address, Identity};
+use many_modules::kvstore;
+use many_modules::kvstore::list::ListArgs;
 use many_modules::kvstore::{KeyFilterType, TransferArgs};
-use many_modules::r#async::{StatusArgs, StatusReturn};
-use many_modules::{kvstore, r#async};
-use many_protocol::ResponseMessage;
 use many_types::{Either, SortOrder};
 use std::collections::BTreeMap;
 use std::io::Read;
 use std::path::PathBuf;
-use std::time::Duration;
-use tracing::{debug, error, info};
+use tracing::{debug, error};
 use tracing_subscriber::filter::LevelFilter;
 
 #[derive(clap::ArgEnum, Clone, Debug)]
@@ -37,6 +33,9 @@ struct Opts {
     #[clap(long)]
     pem: Option<PathBuf>,
 
+    #[clap(flatten)]
+    key_flags: many_cli_helpers::KeyFlags,
+
     /// An alternative owner Address
     #[clap(long)]
     alt_owner: Option<Address>,
@@ -167,26 +166,20 @@ fn get(client: ManyClient<impl Identity>, key: &[u8], hex: bool) -> Result<(), M
         key: key.to_vec().into(),
     };
 
-    let payload = client.call_("kvstore.get", arguments)?;
-    if payload.is_empty() {
-        Err(ManyError::unexpected_empty_response())
-    } else {
-        let result: kvstore::GetReturns =
-            minicbor::decode(&payload).map_err(ManyError::deserialization_error)?;
-        let value = result.value;
+    let result = KvStoreClient::new(client).get(arguments)?;
+    let value = result.value;
 
-        if let Some(value) = value {
-            if hex {
-                println!("{}", hex::encode(value.as_slice()));
-            } else {
-                std::io::Write::write_all(&mut std::io::stdout(), &value).unwrap();
-            }
+    if let Some(value) = value {
+        if hex {
+            println!("{}", hex::encode(value.as_slice()));
         } else {
-            println!("{value:?}");
+            std::io::Write::write_all(&mut std::io::stdout(), &value).unwrap();
         }
-
-        Ok(())
+    } else {
+        println!("{value:?}");
     }
+
+    Ok(())
 }
 
 fn query(client: ManyClient<impl Identity>, key: &[u8]) -> Result<(), ManyError> {
@@ -194,23 +187,16 @@ fn query(client: ManyClient<impl Identity>, key: &[u8]) -> Result<(), ManyError>
         key: key.to_vec().into(),
     };
 
-    let payload = client.call_("kvstore.query", arguments)?;
-    if payload.is_empty() {
-        Err(ManyError::unexpected_empty_response())
-    } else {
-        let result: kvstore::QueryReturns =
-            minicbor::decode(&payload).map_err(ManyError::deserialization_error)?;
-
-        let owner = result.owner.to_string();
-
-        match result.disabled {
-            Some(Either::Left(true)) => println!("{owner}, disabled"),
-            Some(Either::Right(reason)) => println!("{owner}, disabled ({reason})"),
-            _ => println!("{owner}"),
-        }
+    let result = KvStoreClient::new(client).query(arguments)?;
+    let owner = result.owner.to_string();
 
-        Ok(())
+    match result.disabled {
+        Some(Either::Left(true)) => println!("{owner}, disabled"),
+        Some(Either::Right(reason)) => println!("{owner}, disabled ({reason})"),
+        _ => println!("{owner}"),
     }
+
+    Ok(())
 }
 
 fn put(
@@ -225,8 +211,8 @@ fn put(
         alternative_owner: alt_owner,
     };
 
-    let response = client.call("kvstore.put", arguments)?;
-    let payload = wait_response(client, response)?;
+    let result = KvStoreClient::new(client).put(arguments)?;
+    let payload = minicbor::to_vec(result).map_err(ManyError::serialization_error)?;
     println!("{}", minicbor::display(&payload));
     Ok(())
 }
@@ -243,8 +229,8 @@ fn disable(
         reason,
     };
 
-    let response = client.call("kvstore.disable", arguments)?;
-    let payload = wait_response(client, response)?;
+    let result = KvStoreClient::new(client).disable(arguments)?;
+    let payload = minicbor::to_vec(result).map_err(ManyError::serialization_error)?;
     println!("{}", minicbor::display(&payload));
     Ok(())
 }
@@ -261,8 +247,8 @@ fn transfer(
         new_owner,
     };
 
-    let response = client.call("kvstore.transfer", args)?;
-    let payload = wait_response(client, response)?;
+    let result = KvStoreClient::new(client).transfer(args)?;
+    let payload = minicbor::to_vec(result).map_err(ManyError::serialization_error)?;
     println!("{}", minicbor::display(&payload));
     Ok(())
 }
@@ -278,97 +264,23 @@ fn list(
         order,
         filter,
     };
-    let response = client.call("kvstore.list", args)?;
-    let payload = wait_response(client, response)?;
-    if payload.is_empty() {
-        Err(ManyError::unexpected_empty_response())
-    } else {
-        let result: ListReturns =
-            minicbor::decode(&payload).map_err(ManyError::deserialization_error)?;
-
-        let keys = result.keys;
-
-        for key in keys {
-            if hex_key {
-                println!("{}", hex::encode(key.as_slice()));
-            } else {
-                let key = String::from_utf8(key.into()).map_err(ManyError::unknown)?;
-                println!("{key}");
-            }
+    let result = KvStoreClient::new(client).list(args)?;
+    for key in result.keys {
+        if hex_key {
+            println!("{}", hex::encode(key.as_slice()));
+        } else {
+            let key = String::from_utf8(key.into()).map_err(ManyError::unknown)?;
+            println!("{key}");
         }
-
-        Ok(())
     }
-}
 
-pub(crate) fn wait_response(
-    client: ManyClient<impl Identity>,
-    response: ResponseMessage,
-) -> Result<Vec<u8>, ManyError> {
-    let ResponseMessage {
-        data, attributes, ..
-    } = response;
-
-    let payload = data?;
-    debug!("response: {}", hex::encode(&payload));
-    if payload.is_empty() {
-        let attr = match attributes.get::<r#async::attributes::AsyncAttribute>() {
-            Ok(attr) => attr,
-            _ => {
-                info!("Empty payload.");
-                return Ok(Vec::new());
-            }
-        };
-        info!("Async token: {}", hex::encode(&attr.token));
-
-        let progress =
-            indicatif::ProgressBar::new_spinner().with_message("Waiting for async response");
-        progress.enable_steady_tick(Duration::from_millis(100));
-
-        // TODO: improve on this by using duration and thread and watchdog.
-        // Wait for the server for ~60 seconds by pinging it every second.
-        for _ in 0..60 {
-            let response = client.call(
-                "async.status",
-                StatusArgs {
-                    token: attr.token.clone(),
-                },
-            )?;
-            let status: StatusReturn =
-                minicbor::decode(&response.data?).map_err(ManyError::deserialization_error)?;
-            match status {
-                StatusReturn::Done { response } => {
-                    progress.finish();
-                    let response: ResponseMessage =
-                        minicbor::decode(&response.payload.ok_or_else(|| {
-                            ManyError::deserialization_error(
-                                "Empty payload. Expected ResponseMessage.",
-                            )
-                        })?)
-                        .map_err(ManyError::deserialization_error)?;
-                    return wait_response(client, response);
-                }
-                StatusReturn::Expired => {
-                    progress.finish();
-                    info!("Async token expired before we could check it.");
-                    return Ok(Vec::new());
-                }
-                _ => {
-                    std::thread::sleep(Duration::from_secs(1));
-                }
-            }
-        }
-        Err(ManyError::unknown(
-            "Transport timed out waiting for async result.",
-        ))
-    } else {
-        Ok(payload)
-    }
+    Ok(())
 }
 
 fn main() {
     let Opts {
         pem,
+        key_flags,
         alt_owner,
         server,
         server_id,
@@ -408,10 +320,9 @@ fn main() {
 
     debug!("{:?}", Opts::parse());
 
-    let key = pem.map_or_else(
-        || Box::new(AnonymousIdentity) as Box<dyn Identity>,
-        |p| Box::new(CoseKeyIdentity::from_pem(std::fs::read_to_string(p).unwrap()).unwrap()),
-    );
+    let key = key_flags
+        .identity_or_anonymous(pem.as_deref())
+        .expect("Could not load the identity");
 
     let client = ManyClient::new(server, server_id, key).unwrap();
     let result = match subcommand {
@@ -0,0 +1,449 @@
+use anyhow::anyhow;
+use clap::Parser;
+use many_cli_helpers::error::ClientServerError;
+use many_client::client::blocking::{EventsClient, LedgerClient, ManyClient};
+use many_identity::{Address, Identity};
+use many_modules::events::{
+    AccountMultisigTransaction, EventFilter, EventInfo, EventKind, EventLog, ListArgs,
+};
+use many_types::ledger::{LedgerTokensAddressMap, Symbol, TokenAmount};
+use many_types::{CborRange, Memo, SortOrder, Timestamp, VecOrSingle};
+use serde_json::{json, Value};
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::ops::Bound;
+use std::time::SystemTime;
+
+#[derive(clap::ArgEnum, Clone, Debug)]
+enum Format {
+    Text,
+    Csv,
+    Json,
+}
+
+#[derive(Parser)]
+pub struct HistoryOpt {
+    /// The account to show the history of.
+    account: Address,
+
+    /// Only show events of this kind, e.g. `send`, `token-mint` or
+    /// `account-multisig-approve`. Can be repeated.
+    #[clap(long)]
+    kind: Vec<EventKind>,
+
+    /// Only show events of this symbol. This can either be an identity or a
+    /// local name for a symbol. Can be repeated.
+    #[clap(long)]
+    symbol: Vec<String>,
+
+    /// Only show events at or after this date (UTC), e.g. `2023-01-31T12:00:00Z`
+    /// or `2023-01-31 12:00:00`.
+    #[clap(long)]
+    since: Option<humantime::Timestamp>,
+
+    /// Only show events before this date.
+    #[clap(long)]
+    until: Option<humantime::Timestamp>,
+
+    /// The output format.
+    #[clap(long, arg_enum, default_value_t = Format::Text)]
+    format: Format,
+
+    /// The number of events to request at a time.
+    #[clap(long, default_value_t = 100)]
+    page_size: u64,
+}
+
+/// A line of the history. Events moving tokens between several addresses
+/// (e.g. mints) have a line per address.
+#[derive(Clone)]
+struct Entry {
+    id: String,
+    time: SystemTime,
+    kind: EventKind,
+    from: Option<Address>,
+    to: Option<Address>,
+    symbol: Option<Symbol>,
+    amount: Option<TokenAmount>,
+    memo: Option<String>,
+    details: Option<String>,
+}
+
+impl Entry {
+    fn new(event: &EventLog) -> Result<Self, ClientServerError> {
+        Ok(Self {
+            id: hex::encode(&event.id),
+            time: event.time.as_system_time()?,
+            kind: event.kind(),
+            from: None,
+            to: None,
+            symbol: None,
+            amount: None,
+            memo: event.content.memo().map(memo_text),
+            details: None,
+        })
+    }
+}
+
+fn memo_text(memo: &Memo) -> String {
+    memo.iter_str()
+        .cloned()
+        .chain(memo.iter_bytes().map(hex::encode))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Whether an address of a token distribution should be shown in the history
+/// of `account`. If the account is not part of the distribution (e.g. it is
+/// the symbol), all addresses are.
+fn in_distribution(
+    address: &Address,
+    account: &Address,
+    distribution: &LedgerTokensAddressMap,
+) -> bool {
+    address == account || !distribution.contains_key(account)
+}
+
+fn entries(event: &EventLog, account: &Address) -> Result<Vec<Entry>, ClientServerError> {
+    let entry = Entry::new(event)?;
+    let entries = match &event.content {
+        EventInfo::Send {
+            from,
+            to,
+            symbol,
+            amount,
+            ..
+        } => vec![Entry {
+            from: Some(*from),
+            to: Some(*to),
+            symbol: Some(*symbol),
+            amount: Some(amount.clone()),
+            ..entry
+        }],
+        EventInfo::TokenMint {
+            symbol,
+            distribution,
+            ..
+        } => distribution
+            .iter()
+            .filter(|(to, _)| in_distribution(to, account, distribution))
+            .map(|(to, amount)| Entry {
+                to: Some(*to),
+                symbol: Some(*symbol),
+                amount: Some(amount.clone()),
+                ..entry.clone()
+            })
+            .collect(),
+        EventInfo::TokenBurn {
+            symbol,
+            distribution,
+            ..
+        } => distribution
+            .iter()
+            .filter(|(from, _)| in_distribution(from, account, distribution))
+            .map(|(from, amount)| Entry {
+                from: Some(*from),
+                symbol: Some(*symbol),
+                amount: Some(amount.clone()),
+                ..entry.clone()
+            })
+            .collect(),
+        EventInfo::AccountMultisigSubmit {
+            submitter,
+            account,
+            transaction,
+            token,
+            ..
+        } => {
+            let token = token.as_ref().map(|t| hex::encode(t.as_slice()));
+            let details = format!(
+                "transaction {} submitted by {submitter}",
+                token.as_deref().unwrap_or("?")
+            );
+            match transaction.as_ref() {
+                AccountMultisigTransaction::Send(send) => vec![Entry {
+                    from: Some(send.from.unwrap_or(*account)),
+                    to: Some(send.to),
+                    symbol: Some(send.symbol),
+                    amount: Some(send.amount.clone()),
+                    details: Some(details),
+                    ..entry
+                }],
+                _ => vec![Entry {
+                    from: Some(*account),
+                    details: Some(details),
+                    ..entry
+                }],
+            }
+        }
+        EventInfo::AccountMultisigApprove {
+            account,
+            token,
+            approver,
+        } => vec![multisig_entry(entry, account, token, "approved", approver)],
+        EventInfo::AccountMultisigRevoke {
+            account,
+            token,
+            revoker,
+        } => vec![multisig_entry(entry, account, token, "revoked", revoker)],
+        EventInfo::AccountMultisigWithdraw {
+            account,
+            token,
+            withdrawer,
+        } => vec![multisig_entry(
+            entry,
+            account,
+            token,
+            "withdrawn",
+            withdrawer,
+        )],
+        EventInfo::AccountMultisigExecute {
+            account,
+            token,
+            executer,
+            ..
+        } => vec![Entry {
+            from: Some(*account),
+            details: Some(match executer {
+                Some(executer) => format!(
+                    "transaction {} executed by {executer}",
+                    hex::encode(token.as_slice())
+                ),
+                None => format!("transaction {} executed", hex::encode(token.as_slice())),
+            }),
+            ..entry
+        }],
+        EventInfo::AccountMultisigExpired { account, token, .. } => vec![Entry {
+            from: Some(*account),
+            details: Some(format!(
+                "transaction {} expired",
+                hex::encode(token.as_slice())
+            )),
+            ..entry
+        }],
+        _ => vec![entry],
+    };
+    Ok(entries)
+}
+
+fn multisig_entry(
+    entry: Entry,
+    account: &Address,
+    token: &minicbor::bytes::ByteVec,
+    action: &str,
+    by: &Address,
+) -> Entry {
+    Entry {
+        from: Some(*account),
+        details: Some(format!(
+            "transaction {} {action} by {by}",
+            hex::encode(token.as_slice())
+        )),
+        ..entry
+    }
+}
+
+/// Whether an entry is about one of the symbols. Entries without symbol are
+/// not.
+fn has_symbol(entry: &Entry, symbols: &[Symbol]) -> bool {
+    symbols.is_empty() || entry.symbol.map_or(false, |s| symbols.contains(&s))
+}
+
+/// Read all events matching the filter, in ascending order, one page at a
+/// time.
+fn list_events(
+    client: &EventsClient<impl Identity>,
+    mut filter: EventFilter,
+    page_size: u64,
+) -> Result<Vec<EventLog>, ClientServerError> {
+    let mut events = Vec::new();
+    loop {
+        let page = client.list(ListArgs {
+            count: Some(page_size),
+            order: Some(SortOrder::Ascending),
+            filter: Some(filter.clone()),
+        })?;
+        let Some(last) = page.events.last() else {
+            return Ok(events);
+        };
+        filter.id_range = Some(CborRange {
+            start: Bound::Excluded(last.id.clone()),
+            end: Bound::Unbounded,
+        });
+        events.extend(page.events);
+    }
+}
+
+fn symbol_text(symbol: &Symbol, local_names: &BTreeMap<Symbol, String>) -> String {
+    local_names
+        .get(symbol)
+        .cloned()
+        .unwrap_or_else(|| symbol.to_string())
+}
+
+fn time_text(time: SystemTime) -> String {
+    humantime::format_rfc3339_seconds(time).to_string()
+}
+
+/// A readable description of an entry, from the point of view of `account`.
+fn text(entry: &Entry, account: &Address, local_names: &BTreeMap<Symbol, String>) -> String {
+    let tokens = match (&entry.amount, &entry.symbol) {
+        (Some(amount), Some(symbol)) => format!("{amount} {}", symbol_text(symbol, local_names)),
+        _ => String::new(),
+    };
+    let mut line = match (entry.kind, entry.from, entry.to) {
+        (EventKind::Send, Some(from), Some(to)) if &from == account => {
+            format!("sent {tokens} to {to}")
+        }
+        (EventKind::Send, Some(from), Some(_)) => format!("received {tokens} from {from}"),
+        (EventKind::TokenMint, _, Some(to)) => format!("minted {tokens} to {to}"),
+        (EventKind::TokenBurn, Some(from), _) => format!("burned {tokens} from {from}"),
+        (kind, _, to) => match (&entry.details, to) {
+            (Some(details), Some(to)) => format!("{kind}: {details} (send {tokens} to {to})"),
+            (Some(details), None) => format!("{kind}: {details}"),
+            (None, _) => kind.to_string(),
+        },
+    };
+    if let Some(memo) = &entry.memo {
+        line += &format!(" (memo: {memo})");
+    }
+    format!("{}  {line}", time_text(entry.time))
+}
+
+/// Quote a CSV field if needed.
+fn csv_field(field: &str) -> Cow<str> {
+    if field.contains([',', '"', '\n', '\r']) {
+        Cow::Owned(format!("\"{}\"", field.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(field)
+    }
+}
+
+const CSV_HEADER: &str = "id,time,kind,from,to,symbol,symbol_name,amount,memo,details";
+
+fn csv_line(entry: &Entry, local_names: &BTreeMap<Symbol, String>) -> String {
+    let address = |a: Option<Address>| a.map(|a| a.to_string()).unwrap_or_default();
+    [
+        entry.id.clone(),
+        time_text(entry.time),
+        entry.kind.to_string(),
+        address(entry.from),
+        address(entry.to),
+        address(entry.symbol),
+        entry
+            .symbol
+            .and_then(|s| local_names.get(&s).cloned())
+            .unwrap_or_default(),
+        entry
+            .amount
+            .as_ref()
+            .map(ToString::to_string)
+            .unwrap_or_default(),
+        entry.memo.clone().unwrap_or_default(),
+        entry.details.clone().unwrap_or_default(),
+    ]
+    .iter()
+    .map(|f| csv_field(f))
+    .collect::<Vec<_>>()
+    .join(",")
+}
+
+fn json_value(entry: &Entry, local_names: &BTreeMap<Symbol, String>) -> Value {
+    json!({
+        "id": entry.id,
+        "time": time_text(entry.time),
+        "kind": entry.kind.to_string(),
+        "from": entry.from.map(|a| a.to_string()),
+        "to": entry.to.map(|a| a.to_string()),
+        "symbol": entry.symbol.map(|a| a.to_string()),
+        "symbol_name": entry.symbol.and_then(|s| local_names.get(&s).cloned()),
+        // Amounts can be larger than what JSON numbers can represent exactly.
+        "amount": entry.amount.as_ref().map(ToString::to_string),
+        "memo": entry.memo,
+        "details": entry.details,
+    })
+}
+
+pub fn history(
+    client: ManyClient<impl Identity>,
+    opts: HistoryOpt,
+) -> Result<(), ClientServerError> {
+    let HistoryOpt {
+        account,
+        kind,
+        symbol,
+        since,
+        until,
+        format,
+        page_size,
+    } = opts;
+
+    let local_names = LedgerClient::new(client.clone()).info()?.local_names;
+    let symbols = symbol
+        .into_iter()
+        .map(|s| crate::resolve_symbol(&client, s))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let date = |date: Option<humantime::Timestamp>, bound: fn(Timestamp) -> Bound<Timestamp>| {
+        date.map(|d| {
+            let secs = d
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .map_err(|e| anyhow!(e))?
+                .as_secs();
+            Ok::<_, ClientServerError>(bound(Timestamp::new(secs)?))
+        })
+        .transpose()
+        .map(|b| b.unwrap_or(Bound::Unbounded))
+    };
+    let date_range = if since.is_some() || until.is_some() {
+        Some(CborRange {
+            start: date(since, Bound::Included)?,
+            end: date(until, Bound::Excluded)?,
+        })
+    } else {
+        None
+    };
+
+    let filter = EventFilter {
+        account: Some(VecOrSingle(vec![account])),
+        kind: (!kind.is_empty()).then(|| VecOrSingle(kind)),
+        symbol: (!symbols.is_empty()).then(|| VecOrSingle(symbols.clone())),
+        date_range,
+        ..Default::default()
+    };
+
+    let events = list_events(&EventsClient::new(client), filter, page_size)?;
+    let mut lines = Vec::new();
+    for event in &events {
+        lines.extend(
+            entries(event, &account)?
+                .into_iter()
+                .filter(|e| has_symbol(e, &symbols)),
+        );
+    }
+
+    match format {
+        Format::Text => {
+            for entry in &lines {
+                println!("{}", text(entry, &account, &local_names));
+            }
+        }
+        Format::Csv => {
+            println!("{CSV_HEADER}");
+            for entry in &lines {
+                println!("{}", csv_line(entry, &local_names));
+            }
+        }
+        Format::Json => {
+            let values: Vec<Value> = lines
+                .iter()
+                .map(|entry| json_value(entry, &local_names))
+                .collect();
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&values).map_err(|e| anyhow!(e))?
+            );
+        }
+    }
+    Ok(())
+}
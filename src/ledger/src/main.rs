@@ -1,13 +1,12 @@
 use anyhow::anyhow;
 use clap::{ArgGroup, Parser};
 use many_cli_helpers::error::ClientServerError;
-use many_client::client::blocking::ManyClient;
-use many_identity::{Address, AnonymousIdentity, Identity};
-use many_identity_dsa::CoseKeyIdentity;
-use many_identity_hsm::{Hsm, HsmIdentity, HsmMechanismType, HsmSessionType, HsmUserType};
-use many_modules::r#async::{StatusArgs, StatusReturn};
-use many_modules::{ledger, r#async};
-use many_protocol::ResponseMessage;
+use many_cli_helpers::keystore::identity_from_pem_file;
+use many_cli_helpers::offline::{self, OfflineMessage};
+use many_client::client::blocking::{LedgerClient, ManyClient};
+use many_identity::{Address, Identity};
+use many_identity_hsm::{HsmIdentity, HsmModule, HsmSession, HsmSessionType, HsmUserType};
+use many_modules::ledger;
 use many_types::ledger::{Symbol, TokenAmount};
 use many_types::Memo;
 use minicbor::data::Tag;
@@ -18,9 +17,9 @@ use std::collections::BTreeMap;
 use std::fmt::{Display, Formatter};
 use std::path::PathBuf;
 use std::str::FromStr;
-use std::time::Duration;
-use tracing::{debug, error, info, trace};
+use tracing::{error, info, trace};
 
+mod history;
 mod multisig;
 mod tokens;
 
@@ -76,22 +75,42 @@ struct Opts {
     #[clap(long)]
     pem: Option<PathBuf>,
 
+    #[clap(flatten)]
+    key_flags: many_cli_helpers::KeyFlags,
+
     /// HSM PKCS#11 module path
-    #[clap(long, conflicts_with("pem"))]
+    #[clap(long, conflicts_with_all(&["pem", "key"]))]
     module: Option<PathBuf>,
 
     /// HSM PKCS#11 slot ID
-    #[clap(long, conflicts_with("pem"))]
+    #[clap(long, conflicts_with_all(&["pem", "key"]))]
     slot: Option<u64>,
 
     /// HSM PKCS#11 key ID
-    #[clap(long, conflicts_with("pem"))]
+    #[clap(long, conflicts_with_all(&["pem", "key"]))]
     keyid: Option<String>,
 
+    /// Write the request of a command to this file instead of sending it. The
+    /// request can then be signed and sent with `many request`.
+    #[clap(long)]
+    unsigned_out: Option<PathBuf>,
+
+    /// The identity which will sign the exported request, if different than
+    /// the one provided by the PEM argument.
+    #[clap(long, requires("unsigned-out"))]
+    signer: Option<Address>,
+
     #[clap(subcommand)]
     subcommand: SubCommand,
 }
 
+/// Where to write the requests of commands, instead of sending them.
+pub(crate) struct Export {
+    path: PathBuf,
+    from: Address,
+    to: Address,
+}
+
 #[derive(Parser)]
 enum SubCommand {
     /// Read the balance of an account.
@@ -105,6 +124,9 @@ enum SubCommand {
 
     /// Perform a token operation
     Token(tokens::CommandOpt),
+
+    /// Show the transactions and other events of an account.
+    History(history::HistoryOpt),
 }
 
 #[derive(Parser)]
@@ -113,6 +135,11 @@ struct BalanceOpt {
     /// identity) or an identity string. If omitted it will use the identity of the caller.
     identity: Option<String>,
 
+    /// Read the balance at a past block height instead of the latest state.
+    /// The server must keep the history for that height.
+    #[clap(long)]
+    height: Option<u64>,
+
     /// The symbol to check the balance of. This can either be an identity or
     /// a local name for a symbol. If it doesn't parse to an identity an
     /// additional call will be made to retrieve local names.
@@ -151,8 +178,7 @@ pub fn resolve_symbol(
         Ok(symbol)
     } else {
         // Get info.
-        let info: ledger::InfoReturns =
-            minicbor::decode(&client.call_("ledger.info", ())?).unwrap();
+        let info = LedgerClient::new(client.clone()).info()?;
         info.local_names
             .into_iter()
             .find(|(_, y)| y == &symbol)
@@ -165,9 +191,12 @@ fn balance(
     client: ManyClient<impl Identity>,
     account: Option<Address>,
     symbols: Vec<String>,
+    height: Option<u64>,
 ) -> Result<(), ClientServerError> {
+    let client = LedgerClient::new(client);
+
     // Get info.
-    let info: ledger::InfoReturns = minicbor::decode(&client.call_("ledger.info", ())?)?;
+    let info = client.info()?;
     let local_names: BTreeMap<String, Symbol> = info
         .local_names
         .iter()
@@ -195,86 +224,40 @@ fn balance(
                     .into(),
             )
         },
+        height,
     };
-    let payload = client.call_("ledger.balance", argument)?;
-
-    if payload.is_empty() {
-        Err(anyhow!("Unexpected empty response.").into())
-    } else {
-        let balance: ledger::BalanceReturns = minicbor::decode(&payload).unwrap();
-        for (symbol, amount) in balance.balances {
-            if let Some(symbol_name) = info.local_names.get(&symbol) {
-                println!("{amount:>12} {symbol_name} ({symbol})");
-            } else {
-                println!("{amount:>12} {symbol}");
-            }
+    let balance = client.balance(argument)?;
+    for (symbol, amount) in balance.balances {
+        if let Some(symbol_name) = info.local_names.get(&symbol) {
+            println!("{amount:>12} {symbol_name} ({symbol})");
+        } else {
+            println!("{amount:>12} {symbol}");
         }
-
-        Ok(())
     }
+
+    Ok(())
 }
 
-pub(crate) fn wait_response(
-    client: ManyClient<impl Identity>,
-    response: ResponseMessage,
-) -> Result<Vec<u8>, ClientServerError> {
-    let ResponseMessage {
-        data, attributes, ..
-    } = response;
-
-    let payload = data?;
-    debug!("response: {}", hex::encode(&payload));
-    if payload.is_empty() {
-        let attr = match attributes.get::<r#async::attributes::AsyncAttribute>() {
-            Ok(attr) => attr,
-            _ => {
-                info!("Empty payload.");
-                return Ok(Vec::new());
-            }
-        };
-        info!("Async token: {}", hex::encode(&attr.token));
-
-        let progress =
-            indicatif::ProgressBar::new_spinner().with_message("Waiting for async response");
-        progress.enable_steady_tick(Duration::from_micros(100));
-
-        // TODO: improve on this by using duration and thread and watchdog.
-        // Wait for the server for ~60 seconds by pinging it every second.
-        for _ in 0..60 {
-            let response = client.call(
-                "async.status",
-                StatusArgs {
-                    token: attr.token.clone(),
-                },
-            )?;
-            let status: StatusReturn = minicbor::decode(&response.data?)?;
-            match status {
-                StatusReturn::Done { response } => {
-                    progress.finish();
-                    let response: ResponseMessage =
-                        minicbor::decode(&response.payload.ok_or_else(|| {
-                            anyhow!("Empty payload. Expected ResponseMessage.",)
-                        })?)?;
-                    return wait_response(client, response);
-                }
-                StatusReturn::Expired => {
-                    progress.finish();
-                    info!("Async token expired before we could check it.");
-                    return Ok(Vec::new());
-                }
-                _ => {
-                    std::thread::sleep(Duration::from_secs(1));
-                }
-            }
-        }
-        Err(anyhow!("Transport timed out waiting for async result.").into())
-    } else {
-        Ok(payload)
-    }
+/// Write the request of a command to a file instead of sending it, when
+/// exporting. Returns whether the request was exported.
+pub(crate) fn export_request(
+    export: Option<&Export>,
+    method: &str,
+    arguments: impl minicbor::Encode<()>,
+) -> Result<bool, ClientServerError> {
+    let Some(export) = export else {
+        return Ok(false);
+    };
+    let data = minicbor::to_vec(arguments).map_err(|e| anyhow!("{e}"))?;
+    let request = offline::request(export.from, export.to, method, data)?;
+    offline::write(&export.path, &OfflineMessage::Request(request))?;
+    info!("Request written to {}.", export.path.display());
+    Ok(true)
 }
 
 fn send(
     client: ManyClient<impl Identity>,
+    export: Option<&Export>,
     from: Address,
     to: Address,
     amount: BigUint,
@@ -293,8 +276,11 @@ fn send(
             amount: TokenAmount::from(amount),
             memo,
         };
-        let response = client.call("ledger.send", arguments)?;
-        let payload = wait_response(client, response)?;
+        if export_request(export, "ledger.send", &arguments)? {
+            return Ok(());
+        }
+        let result = LedgerClient::new(client).send(arguments)?;
+        let payload = minicbor::to_vec(result).map_err(|e| anyhow!("{e}"))?;
         println!("{}", minicbor::display(&payload));
         Ok(())
     }
@@ -304,9 +290,12 @@ fn main() {
     let Opts {
         common_flags,
         pem,
+        key_flags,
         module,
         slot,
         keyid,
+        unsigned_out,
+        signer,
         server,
         server_id,
         subcommand,
@@ -314,53 +303,58 @@ fn main() {
 
     common_flags.init_logging().unwrap();
 
-    let key: Box<dyn Identity> = if let (Some(module), Some(slot), Some(keyid)) =
-        (module, slot, keyid)
-    {
-        trace!("Getting user PIN");
-        let pin = rpassword::prompt_password("Please enter the HSM user PIN: ")
-            .expect("I/O error when reading HSM PIN");
-        let keyid = hex::decode(keyid).expect("Failed to decode keyid to hex");
-
-        {
-            let mut hsm = Hsm::get_instance().expect("HSM mutex poisoned");
-            hsm.init(module, keyid)
-                .expect("Failed to initialize HSM module");
-
-            // The session will stay open until the application terminates
-            hsm.open_session(slot, HsmSessionType::RO, Some(HsmUserType::User), Some(pin))
-                .expect("Failed to open HSM session");
-        }
+    let key: Box<dyn Identity> =
+        if let (Some(module), Some(slot), Some(keyid)) = (module, slot, keyid) {
+            trace!("Getting user PIN");
+            let pin = rpassword::prompt_password("Please enter the HSM user PIN: ")
+                .expect("I/O error when reading HSM PIN");
+            let keyid = hex::decode(keyid).expect("Failed to decode keyid to hex");
+
+            let module = HsmModule::load(module).expect("Failed to initialize HSM module");
+            let session = HsmSession::open(
+                &module,
+                slot,
+                HsmSessionType::RO,
+                Some(HsmUserType::User),
+                Some(pin),
+            )
+            .expect("Failed to open HSM session");
 
-        trace!("Creating CoseKeyIdentity");
-        // Only ECDSA is supported at the moment. It should be easy to add support for new EC mechanisms
-        Box::new(
-            HsmIdentity::new(HsmMechanismType::ECDSA)
-                .expect("Unable to create CoseKeyIdentity from HSM"),
-        )
-    } else {
-        pem.map_or_else(
-            || Box::new(AnonymousIdentity) as Box<dyn Identity>,
-            |p| Box::new(CoseKeyIdentity::from_pem(std::fs::read_to_string(p).unwrap()).unwrap()),
-        )
-    };
+            trace!("Creating HsmIdentity");
+            Box::new(HsmIdentity::new(session, keyid).expect("Unable to create identity from HSM"))
+        } else {
+            key_flags
+                .identity_or_anonymous(pem.as_deref())
+                .expect("Could not load the identity")
+        };
 
-    let client_address = key.address();
+    let client_address = signer.unwrap_or_else(|| key.address());
     let client = ManyClient::new(server, server_id, key).unwrap();
+    let export = unsigned_out.map(|path| Export {
+        path,
+        from: client_address,
+        to: server_id,
+    });
     let result = match subcommand {
-        SubCommand::Balance(BalanceOpt { identity, symbols }) => {
+        SubCommand::Balance(_) | SubCommand::Token(_) | SubCommand::History(_)
+            if export.is_some() =>
+        {
+            Err(anyhow!("Only send and multisig commands can be exported.").into())
+        }
+        SubCommand::Balance(BalanceOpt {
+            identity,
+            height,
+            symbols,
+        }) => {
             let identity = identity.map(|identity| {
-                Address::from_str(&identity)
-                    .or_else(|_| {
-                        let bytes = std::fs::read_to_string(PathBuf::from(identity))?;
-
-                        Ok(CoseKeyIdentity::from_pem(bytes).unwrap().address())
-                    })
-                    .map_err(|_: std::io::Error| ())
-                    .expect("Unable to decode identity command-line argument")
+                Address::from_str(&identity).unwrap_or_else(|_| {
+                    identity_from_pem_file(PathBuf::from(identity))
+                        .expect("Unable to decode identity command-line argument")
+                        .address()
+                })
             });
 
-            balance(client, identity, symbols)
+            balance(client, identity, symbols, height)
         }
         SubCommand::Send(TargetCommandOpt {
             account,
@@ -372,6 +366,7 @@ fn main() {
             let from = account.unwrap_or(client_address);
             send(
                 client,
+                export.as_ref(),
                 from,
                 identity,
                 amount,
@@ -379,8 +374,9 @@ fn main() {
                 memo.map(|m| Memo::try_from(m.as_str()).unwrap()),
             )
         }
-        SubCommand::Multisig(opts) => multisig::multisig(client, opts),
+        SubCommand::Multisig(opts) => multisig::multisig(client, export.as_ref(), opts),
         SubCommand::Token(opts) => tokens::tokens(client, opts),
+        SubCommand::History(opts) => history::history(client, opts),
     };
 
     if let Err(err) = result {
@@ -1,11 +1,11 @@
-use crate::TargetCommandOpt;
+use crate::{Export, TargetCommandOpt};
+use anyhow::anyhow;
 use clap::Parser;
 use many_cli_helpers::error::ClientServerError;
-use many_client::client::blocking::ManyClient;
+use many_client::client::blocking::{ManyClient, MultisigClient};
 use many_identity::{Address, Identity};
 use many_modules::account::features::multisig;
 use many_modules::{events, ledger};
-use many_protocol::ResponseMessage;
 use many_types::ledger::TokenAmount;
 use many_types::memo::MemoLegacy;
 use many_types::Memo;
@@ -104,6 +104,7 @@ struct MultisigArgOpt {
 
 fn submit_send(
     client: ManyClient<impl Identity>,
+    export: Option<&Export>,
     account: Address,
     multisig_arg: MultisigArgOpt,
     opts: TargetCommandOpt,
@@ -140,10 +141,10 @@ fn submit_send(
         data_: None,
         memo_: legacy_memo.map(|x| MemoLegacy::try_from(x).unwrap()),
     };
-    let response = client.call("account.multisigSubmitTransaction", arguments)?;
-
-    let payload = crate::wait_response(client, response)?;
-    let result: multisig::SubmitTransactionReturn = minicbor::decode(&payload)?;
+    if crate::export_request(export, "account.multisigSubmitTransaction", &arguments)? {
+        return Ok(());
+    }
+    let result = MultisigClient::new(client).multisig_submit_transaction(arguments)?;
 
     info!(
         "Transaction Token: {}",
@@ -154,6 +155,7 @@ fn submit_send(
 
 fn submit_set_defaults(
     client: ManyClient<impl Identity>,
+    export: Option<&Export>,
     account: Address,
     multisig_arg: MultisigArgOpt,
     target: Address,
@@ -182,10 +184,10 @@ fn submit_set_defaults(
         data_: None,
         memo_: None,
     };
-    let response = client.call("account.multisigSubmitTransaction", arguments)?;
-
-    let payload = crate::wait_response(client, response)?;
-    let result: multisig::SubmitTransactionReturn = minicbor::decode(&payload)?;
+    if crate::export_request(export, "account.multisigSubmitTransaction", &arguments)? {
+        return Ok(());
+    }
+    let result = MultisigClient::new(client).multisig_submit_transaction(arguments)?;
 
     info!(
         "Transaction Token: {}",
@@ -196,6 +198,7 @@ fn submit_set_defaults(
 
 fn submit(
     client: ManyClient<impl Identity>,
+    export: Option<&Export>,
     account: Address,
     multisig_arg: MultisigArgOpt,
     opts: SubmitOpt,
@@ -203,25 +206,32 @@ fn submit(
     legacy_memo: Option<String>,
 ) -> Result<(), ClientServerError> {
     match opts {
-        SubmitOpt::Send(target) => {
-            submit_send(client, account, multisig_arg, target, memo, legacy_memo)
-        }
+        SubmitOpt::Send(target) => submit_send(
+            client,
+            export,
+            account,
+            multisig_arg,
+            target,
+            memo,
+            legacy_memo,
+        ),
         SubmitOpt::SetDefaults(SetDefaultsOpt {
             target_account,
             opts,
-        }) => submit_set_defaults(client, account, multisig_arg, target_account, opts),
+        }) => submit_set_defaults(client, export, account, multisig_arg, target_account, opts),
     }
 }
 
 fn approve(
     client: ManyClient<impl Identity>,
+    export: Option<&Export>,
     opts: TransactionOpt,
 ) -> Result<(), ClientServerError> {
     let arguments = multisig::ApproveArgs { token: opts.token };
-    let response = client.call("account.multisigApprove", arguments)?;
-
-    let payload = crate::wait_response(client, response)?;
-    let _result: multisig::ApproveReturn = minicbor::decode(&payload)?;
+    if crate::export_request(export, "account.multisigApprove", &arguments)? {
+        return Ok(());
+    }
+    MultisigClient::new(client).multisig_approve(arguments)?;
 
     info!("Approved.");
 
@@ -230,13 +240,14 @@ fn approve(
 
 fn revoke(
     client: ManyClient<impl Identity>,
+    export: Option<&Export>,
     opts: TransactionOpt,
 ) -> Result<(), ClientServerError> {
     let arguments = multisig::RevokeArgs { token: opts.token };
-    let response = client.call("account.multisigRevoke", arguments)?;
-
-    let payload = crate::wait_response(client, response)?;
-    let _result: multisig::RevokeReturn = minicbor::decode(&payload)?;
+    if crate::export_request(export, "account.multisigRevoke", &arguments)? {
+        return Ok(());
+    }
+    MultisigClient::new(client).multisig_revoke(arguments)?;
 
     info!("Revoked.");
 
@@ -245,13 +256,14 @@ fn revoke(
 
 fn execute(
     client: ManyClient<impl Identity>,
+    export: Option<&Export>,
     opts: TransactionOpt,
 ) -> Result<(), ClientServerError> {
     let arguments = multisig::ExecuteArgs { token: opts.token };
-    let response = client.call("account.multisigExecute", arguments)?;
-
-    let payload = crate::wait_response(client, response)?;
-    let result: ResponseMessage = minicbor::decode(&payload)?;
+    if crate::export_request(export, "account.multisigExecute", &arguments)? {
+        return Ok(());
+    }
+    let result = MultisigClient::new(client).multisig_execute(arguments)?;
 
     info!("Executed:");
     println!("{}", minicbor::display(&result.data?));
@@ -260,10 +272,7 @@ fn execute(
 
 fn info(client: ManyClient<impl Identity>, opts: TransactionOpt) -> Result<(), ClientServerError> {
     let arguments = multisig::InfoArgs { token: opts.token };
-    let response = client.call("account.multisigInfo", arguments)?;
-
-    let payload = crate::wait_response(client, response)?;
-    let result: multisig::InfoReturn = minicbor::decode(&payload)?;
+    let result = MultisigClient::new(client).multisig_info(arguments)?;
 
     println!("{result:#?}");
     Ok(())
@@ -271,6 +280,7 @@ fn info(client: ManyClient<impl Identity>, opts: TransactionOpt) -> Result<(), C
 
 fn set_defaults(
     client: ManyClient<impl Identity>,
+    export: Option<&Export>,
     account: Address,
     opts: MultisigArgOpt,
 ) -> Result<(), ClientServerError> {
@@ -280,10 +290,10 @@ fn set_defaults(
         timeout_in_secs: opts.timeout.map(|d| d.as_secs()),
         execute_automatically: opts.execute_automatically,
     };
-    let response = client.call("account.multisigSetDefaults", arguments)?;
-
-    let payload = crate::wait_response(client, response)?;
-    let _result: multisig::SetDefaultsReturn = minicbor::decode(&payload)?;
+    if crate::export_request(export, "account.multisigSetDefaults", &arguments)? {
+        return Ok(());
+    }
+    MultisigClient::new(client).multisig_set_defaults(arguments)?;
 
     info!("Defaults set.");
     Ok(())
@@ -291,6 +301,7 @@ fn set_defaults(
 
 pub fn multisig(
     client: ManyClient<impl Identity>,
+    export: Option<&Export>,
     opts: CommandOpt,
 ) -> Result<(), ClientServerError> {
     match opts.subcommand {
@@ -300,14 +311,25 @@ pub fn multisig(
             subcommand,
             memo,
             legacy_memo,
-        } => submit(client, account, multisig_arg, subcommand, memo, legacy_memo),
-        SubcommandOpt::Approve(sub_opts) => approve(client, sub_opts),
-        SubcommandOpt::Revoke(sub_opts) => revoke(client, sub_opts),
-        SubcommandOpt::Execute(sub_opts) => execute(client, sub_opts),
+        } => submit(
+            client,
+            export,
+            account,
+            multisig_arg,
+            subcommand,
+            memo,
+            legacy_memo,
+        ),
+        SubcommandOpt::Approve(sub_opts) => approve(client, export, sub_opts),
+        SubcommandOpt::Revoke(sub_opts) => revoke(client, export, sub_opts),
+        SubcommandOpt::Execute(sub_opts) => execute(client, export, sub_opts),
+        SubcommandOpt::Info(_) if export.is_some() => {
+            Err(anyhow!("Only multisig transactions can be exported.").into())
+        }
         SubcommandOpt::Info(sub_opts) => info(client, sub_opts),
         SubcommandOpt::SetDefaults(SetDefaultsOpt {
             target_account,
             opts,
-        }) => set_defaults(client, target_account, opts),
+        }) => set_defaults(client, export, target_account, opts),
     }
 }
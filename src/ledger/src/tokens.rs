@@ -1,15 +1,14 @@
 use anyhow::anyhow;
 use clap::{Args, Parser};
 use many_cli_helpers::error::ClientServerError;
-use many_client::client::blocking::ManyClient;
+use many_client::client::blocking::{LedgerClient, ManyClient, MintBurnClient, TokensClient};
 use many_identity::{Address, Identity};
 use many_modules::ledger::extended_info::visual_logo::VisualTokenLogo;
 use many_modules::ledger::extended_info::TokenExtendedInfo;
 use many_modules::ledger::{
-    TokenAddExtendedInfoArgs, TokenAddExtendedInfoReturns, TokenBurnArgs, TokenBurnReturns,
-    TokenCreateArgs, TokenCreateReturns, TokenInfoArgs, TokenInfoReturns, TokenMintArgs,
-    TokenMintReturns, TokenRemoveExtendedInfoArgs, TokenRemoveExtendedInfoReturns, TokenUpdateArgs,
-    TokenUpdateReturns,
+    MintPolicy, MintRateLimit, TokenAddExtendedInfoArgs, TokenBurnArgs, TokenCreateArgs,
+    TokenFreezeArgs, TokenInfoArgs, TokenMintArgs, TokenPauseArgs, TokenRemoveExtendedInfoArgs,
+    TokenSetMintPolicyArgs, TokenUnfreezeArgs, TokenUnpauseArgs, TokenUpdateArgs,
 };
 use many_types::cbor::CborNull;
 use many_types::ledger::{LedgerTokensAddressMap, TokenAmount, TokenInfoSummary, TokenMaybeOwner};
@@ -46,6 +45,21 @@ enum SubcommandOpt {
 
     /// Burn tokens
     Burn(BurnOpt),
+
+    /// Set or remove the mint policy of a token
+    SetMintPolicy(SetMintPolicyOpt),
+
+    /// Freeze the balance of an address for a token
+    Freeze(FreezeOpt),
+
+    /// Unfreeze the balance of an address for a token
+    Unfreeze(FreezeOpt),
+
+    /// Pause all transfers of a token
+    Pause(PauseOpt),
+
+    /// Resume the transfers of a token
+    Unpause(PauseOpt),
 }
 
 #[derive(Args)]
@@ -73,6 +87,46 @@ struct BurnOpt {
     error_on_under_burn: bool,
 }
 
+#[derive(Args)]
+struct SetMintPolicyOpt {
+    symbol: Address,
+
+    /// Remaining amount each minter is allowed to mint, as a JSON map of
+    /// address to amount. Allowances only limit identities which can mint.
+    #[clap(long, parse(try_from_str = serde_json::from_str))]
+    allowances: Option<LedgerTokensAddressMap>,
+
+    /// Maximum amount that can be minted per period.
+    #[clap(long, requires = "period_in_secs")]
+    rate_limit: Option<u64>,
+
+    /// Length of the rate limit period, in seconds.
+    #[clap(long, requires = "rate_limit")]
+    period_in_secs: Option<u64>,
+
+    /// Remove the mint policy of the token.
+    #[clap(long, conflicts_with_all = &["allowances", "rate_limit"])]
+    remove: bool,
+}
+
+#[derive(Args)]
+struct FreezeOpt {
+    symbol: Address,
+
+    address: Address,
+
+    #[clap(long, parse(try_from_str = Memo::try_from))]
+    memo: Option<Memo>,
+}
+
+#[derive(Args)]
+struct PauseOpt {
+    symbol: Address,
+
+    #[clap(long, parse(try_from_str = Memo::try_from))]
+    memo: Option<Memo>,
+}
+
 #[derive(Args)]
 struct InfoOpt {
     symbol: Address,
@@ -80,6 +134,10 @@ struct InfoOpt {
     #[clap(long)]
     #[clap(value_parser = attribute_related_index)]
     indices: Option<Vec<AttributeRelatedIndex>>,
+
+    /// Read the token info at a past block height instead of the latest state.
+    #[clap(long)]
+    height: Option<u64>,
 }
 
 #[derive(Args)]
@@ -251,9 +309,7 @@ fn create_token(
         extended_info,
         memo: opts.memo,
     };
-    let response = client.call("tokens.create", args)?;
-    let payload = crate::wait_response(client, response)?;
-    let result: TokenCreateReturns = minicbor::decode(&payload)?;
+    let result = TokensClient::new(client).create(args)?;
 
     println!("{result:#?}");
     Ok(())
@@ -271,9 +327,7 @@ fn update_token(
         owner: opts.owner,
         memo: opts.memo,
     };
-    let response = client.call("tokens.update", args)?;
-    let payload = crate::wait_response(client, response)?;
-    let _result: TokenUpdateReturns = minicbor::decode(&payload)?;
+    TokensClient::new(client).update(args)?;
 
     Ok(())
 }
@@ -289,9 +343,7 @@ fn add_ext_info(
         extended_info,
         memo: opts.memo,
     };
-    let response = client.call("tokens.addExtendedInfo", args)?;
-    let payload = crate::wait_response(client, response)?;
-    let _result: TokenAddExtendedInfoReturns = minicbor::decode(&payload)?;
+    TokensClient::new(client).add_extended_info(args)?;
     Ok(())
 }
 
@@ -304,9 +356,7 @@ fn remove_ext_info(
         extended_info: opts.indices,
         memo: opts.memo,
     };
-    let response = client.call("tokens.removeExtendedInfo", args)?;
-    let payload = crate::wait_response(client, response)?;
-    let _result: TokenRemoveExtendedInfoReturns = minicbor::decode(&payload)?;
+    TokensClient::new(client).remove_extended_info(args)?;
     Ok(())
 }
 
@@ -314,10 +364,9 @@ fn info_token(client: ManyClient<impl Identity>, opts: InfoOpt) -> Result<(), Cl
     let args = TokenInfoArgs {
         symbol: opts.symbol,
         extended_info: opts.indices,
+        height: opts.height,
     };
-    let response = client.call("tokens.info", args)?;
-    let payload = crate::wait_response(client, response)?;
-    let result: TokenInfoReturns = minicbor::decode(&payload)?;
+    let result = TokensClient::new(client).info(args)?;
 
     println!("{result:#?}");
     Ok(())
@@ -326,8 +375,7 @@ fn info_token(client: ManyClient<impl Identity>, opts: InfoOpt) -> Result<(), Cl
 fn mint_token(client: ManyClient<impl Identity>, opts: MintOpt) -> Result<(), ClientServerError> {
     let symbol = Address::try_from(opts.symbol.as_str()).or_else(|_| {
         // Get symbol address from name
-        let info: many_modules::ledger::InfoReturns =
-            minicbor::decode(&client.call_("ledger.info", ())?).unwrap();
+        let info = LedgerClient::new(client.clone()).info()?;
         let local_names: BTreeMap<String, Address> = info
             .local_names
             .iter()
@@ -343,9 +391,7 @@ fn mint_token(client: ManyClient<impl Identity>, opts: MintOpt) -> Result<(), Cl
         distribution: opts.distribution,
         memo: opts.memo,
     };
-    let response = client.call("tokens.mint", args)?;
-    let payload = crate::wait_response(client, response)?;
-    let result: TokenMintReturns = minicbor::decode(&payload)?;
+    let result = MintBurnClient::new(client).mint(args)?;
 
     println!("{result:#?}");
     Ok(())
@@ -354,8 +400,7 @@ fn mint_token(client: ManyClient<impl Identity>, opts: MintOpt) -> Result<(), Cl
 fn burn_token(client: ManyClient<impl Identity>, opts: BurnOpt) -> Result<(), ClientServerError> {
     let symbol = Address::try_from(opts.symbol.as_str()).or_else(|_| {
         // Get symbol address from name
-        let info: many_modules::ledger::InfoReturns =
-            minicbor::decode(&client.call_("ledger.info", ())?).unwrap();
+        let info = LedgerClient::new(client.clone()).info()?;
         let local_names: BTreeMap<String, Address> = info
             .local_names
             .iter()
@@ -372,14 +417,72 @@ fn burn_token(client: ManyClient<impl Identity>, opts: BurnOpt) -> Result<(), Cl
         memo: opts.memo,
         error_on_under_burn: Some(opts.error_on_under_burn),
     };
-    let response = client.call("tokens.burn", args)?;
-    let payload = crate::wait_response(client, response)?;
-    let result: TokenBurnReturns = minicbor::decode(&payload)?;
+    let result = MintBurnClient::new(client).burn(args)?;
 
     println!("{result:#?}");
     Ok(())
 }
 
+fn set_mint_policy(
+    client: ManyClient<impl Identity>,
+    opts: SetMintPolicyOpt,
+) -> Result<(), ClientServerError> {
+    let policy = (!opts.remove).then(|| MintPolicy {
+        allowances: opts.allowances.unwrap_or_default(),
+        rate_limit: opts
+            .rate_limit
+            .zip(opts.period_in_secs)
+            .map(|(amount, period_in_secs)| MintRateLimit {
+                amount: TokenAmount::from(amount),
+                period_in_secs,
+            }),
+    });
+    let args = TokenSetMintPolicyArgs {
+        symbol: opts.symbol,
+        policy,
+    };
+    MintBurnClient::new(client).set_mint_policy(args)?;
+    Ok(())
+}
+
+fn freeze(client: ManyClient<impl Identity>, opts: FreezeOpt) -> Result<(), ClientServerError> {
+    let args = TokenFreezeArgs {
+        symbol: opts.symbol,
+        address: opts.address,
+        memo: opts.memo,
+    };
+    TokensClient::new(client).freeze(args)?;
+    Ok(())
+}
+
+fn unfreeze(client: ManyClient<impl Identity>, opts: FreezeOpt) -> Result<(), ClientServerError> {
+    let args = TokenUnfreezeArgs {
+        symbol: opts.symbol,
+        address: opts.address,
+        memo: opts.memo,
+    };
+    TokensClient::new(client).unfreeze(args)?;
+    Ok(())
+}
+
+fn pause(client: ManyClient<impl Identity>, opts: PauseOpt) -> Result<(), ClientServerError> {
+    let args = TokenPauseArgs {
+        symbol: opts.symbol,
+        memo: opts.memo,
+    };
+    TokensClient::new(client).pause(args)?;
+    Ok(())
+}
+
+fn unpause(client: ManyClient<impl Identity>, opts: PauseOpt) -> Result<(), ClientServerError> {
+    let args = TokenUnpauseArgs {
+        symbol: opts.symbol,
+        memo: opts.memo,
+    };
+    TokensClient::new(client).unpause(args)?;
+    Ok(())
+}
+
 pub fn tokens(
     client: ManyClient<impl Identity>,
     opts: CommandOpt,
@@ -392,5 +495,10 @@ pub fn tokens(
         SubcommandOpt::Info(opts) => info_token(client, opts),
         SubcommandOpt::Mint(opts) => mint_token(client, opts),
         SubcommandOpt::Burn(opts) => burn_token(client, opts),
+        SubcommandOpt::SetMintPolicy(opts) => set_mint_policy(client, opts),
+        SubcommandOpt::Freeze(opts) => freeze(client, opts),
+        SubcommandOpt::Unfreeze(opts) => unfreeze(client, opts),
+        SubcommandOpt::Pause(opts) => pause(client, opts),
+        SubcommandOpt::Unpause(opts) => unpause(client, opts),
     }
 }
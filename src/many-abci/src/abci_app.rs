@@ -1,15 +1,19 @@
 use crate::migration::error_code::LEGACY_ERROR_CODE_TRIGGER;
 use crate::migration::{AbciAppMigrations, MIGRATIONS};
 use coset::{CborSerializable, CoseSign1};
-use many_client::client::blocking::{block_on, ManyClient};
+use many_client::client::blocking::ManyClient;
+use many_client::client::tls::TlsConfig;
 use many_error::{ManyError, ManyErrorCode};
 use many_identity::{Address, AnonymousIdentity};
 use many_migration::MigrationConfig;
 use many_modules::abci_backend::{AbciBlock, AbciCommitInfo, AbciInfo};
 use many_protocol::{RequestMessage, ResponseMessage};
+use many_server::server::TimeFn;
 use many_server::RequestValidator;
-use reqwest::{IntoUrl, Url};
+use many_types::Timestamp;
+use reqwest::IntoUrl;
 use std::sync::{Arc, RwLock};
+use std::time::SystemTime;
 use tendermint_abci::Application;
 use tendermint_proto::abci::*;
 use tracing::{debug, error};
@@ -58,7 +62,6 @@ fn get_abci_info_(client: &ManyClient<AnonymousIdentity>) -> Result<AbciInfo, Ma
 pub struct AbciApp {
     app_name: String,
     many_client: ManyClient<AnonymousIdentity>,
-    many_url: Url,
     cache: Arc<RwLock<dyn RequestValidator + Send + Sync>>,
 
     /// We need interior mutability, safely.
@@ -71,6 +74,7 @@ impl AbciApp {
     pub fn create<U>(
         many_url: U,
         server_id: Address,
+        tls: &TlsConfig,
         migration_config: Option<MigrationConfig>,
     ) -> Result<Self, String>
     where
@@ -85,7 +89,9 @@ impl AbciApp {
         //     server_id
         // };
 
-        let many_client = ManyClient::new(many_url.clone(), server_id, AnonymousIdentity)?;
+        let many_client = ManyClient::new(many_url, server_id, AnonymousIdentity)?
+            .with_tls(tls)
+            .map_err(|e| e.to_string())?;
         let status = many_client.status().map_err(|x| x.to_string())?;
         let app_name = status.name;
 
@@ -104,7 +110,6 @@ impl AbciApp {
 
         Ok(Self {
             app_name,
-            many_url,
             many_client,
             cache: Arc::new(RwLock::new(())),
             migrations: Arc::new(migrations),
@@ -112,13 +117,28 @@ impl AbciApp {
         })
     }
 
+    /// Returns the time of the current block, or the system time before the
+    /// first block. Validators use it to expire requests the same way
+    /// `check_tx` validates their timestamps.
+    pub fn time_fn(&self) -> TimeFn {
+        let block_time = self.block_time.clone();
+        Arc::new(move || {
+            let time = *block_time
+                .read()
+                .map_err(|e| ManyError::unknown(e.to_string()))?;
+            time.map_or_else(
+                || Ok(SystemTime::now()),
+                |secs| Timestamp::new(secs)?.as_system_time(),
+            )
+        })
+    }
+
     pub fn with_validator<C: RequestValidator + Send + Sync + 'static>(mut self, cache: C) -> Self {
         self.cache = Arc::new(RwLock::new(cache));
         self
     }
 
     fn do_check_tx(&self, tx: impl AsRef<[u8]>) -> Result<(), (ManyAbciCheckErrorCodes, String)> {
-        use many_types::Timestamp;
         let cose = CoseSign1::from_slice(tx.as_ref()).map_err(|log| {
             (
                 ManyAbciCheckErrorCodes::CoseDeserializeError,
@@ -224,10 +244,7 @@ impl Application for AbciApp {
                 }
             }
         };
-        let value = match block_on(many_client::client::send_envelope(
-            self.many_url.clone(),
-            cose,
-        )) {
+        let value = match self.many_client.send_envelope(cose) {
             Ok(cose_sign) => cose_sign,
 
             Err(err) => {
@@ -310,10 +327,7 @@ impl Application for AbciApp {
                 }
             }
         };
-        match block_on(many_client::client::send_envelope(
-            self.many_url.clone(),
-            cose.clone(),
-        )) {
+        match self.many_client.send_envelope(cose.clone()) {
             Ok(cose_sign) => {
                 let payload = cose_sign.payload.unwrap_or_default();
                 let mut response = ResponseMessage::from_bytes(&payload).unwrap_or_default();
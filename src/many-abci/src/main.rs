@@ -1,10 +1,12 @@
 #![feature(used_with_arg)]
 
 use clap::Parser;
+use many_cli_helpers::keystore::identity_from_pem_file;
+use many_client::client::tls::TlsConfig;
 use many_client::ManyClient;
 use many_identity::verifiers::AnonymousVerifier;
 use many_identity::{Address, AnonymousIdentity, Identity};
-use many_identity_dsa::{CoseKeyIdentity, CoseKeyVerifier};
+use many_identity_dsa::CoseKeyVerifier;
 use many_identity_webauthn::WebAuthnVerifier;
 use many_migration::MigrationConfig;
 use many_modules::{base, blockchain, r#async};
@@ -34,6 +36,12 @@ struct Opts {
     #[clap(flatten)]
     common_flags: many_cli_helpers::CommonCliFlags,
 
+    #[clap(flatten)]
+    http_flags: many_cli_helpers::HttpServerFlags,
+
+    #[clap(flatten)]
+    rate_limit_flags: many_cli_helpers::RateLimitFlags,
+
     /// Address and port to bind the ABCI server to.
     #[clap(long)]
     abci: String,
@@ -46,6 +54,25 @@ struct Opts {
     #[clap(long)]
     many_app: String,
 
+    /// Also trust the certificates of this PEM file when connecting to the MANY
+    /// application over HTTPS.
+    #[clap(long)]
+    many_app_ca: Option<PathBuf>,
+
+    /// Only accept this certificate from the MANY application, as the hex encoded
+    /// SHA-256 digest of its DER encoding. Can be given multiple times.
+    #[clap(long)]
+    many_app_pin: Vec<String>,
+
+    /// A PEM file with the certificate to present to the MANY application, for
+    /// mutual TLS.
+    #[clap(long, requires = "many-app-key")]
+    many_app_cert: Option<PathBuf>,
+
+    /// A PEM file with the private key of `--many-app-cert`.
+    #[clap(long, requires = "many-app-cert")]
+    many_app_key: Option<PathBuf>,
+
     /// Address and port to bind the MANY server to.
     #[clap(long)]
     many: String,
@@ -86,9 +113,15 @@ struct Opts {
 async fn main() {
     let Opts {
         common_flags,
+        http_flags,
+        rate_limit_flags,
         abci,
         tendermint,
         many_app,
+        many_app_ca,
+        many_app_pin,
+        many_app_cert,
+        many_app_key,
         many,
         many_pem,
         abci_read_buf_size,
@@ -115,7 +148,31 @@ async fn main() {
     });
 
     // Try to get the status of the backend MANY app.
-    let many_client = ManyClient::new(&many_app, Address::anonymous(), AnonymousIdentity).unwrap();
+    let tls = {
+        let mut tls = TlsConfig::new();
+        if let Some(path) = many_app_ca {
+            tls = tls
+                .add_ca_certificates(&std::fs::read(path).unwrap())
+                .expect("Could not read the MANY application CA");
+        }
+        for pin in many_app_pin {
+            let digest = hex::decode(pin)
+                .ok()
+                .and_then(|d| <[u8; 32]>::try_from(d).ok())
+                .expect("Invalid certificate digest");
+            tls = tls.add_pinned_certificate(digest);
+        }
+        if let (Some(cert), Some(key)) = (many_app_cert, many_app_key) {
+            tls = tls
+                .with_client_identity(&std::fs::read(cert).unwrap(), &std::fs::read(key).unwrap())
+                .expect("Could not read the MANY application client certificate");
+        }
+        tls
+    };
+    let many_client = ManyClient::new(&many_app, Address::anonymous(), AnonymousIdentity)
+        .unwrap()
+        .with_tls(&tls)
+        .unwrap();
 
     let start = std::time::SystemTime::now();
     trace!("Connecting to the backend app...");
@@ -146,9 +203,10 @@ async fn main() {
     let abci_app = {
         let rocksdb_cache = rocksdb_cache.clone();
         tokio::task::spawn_blocking(move || {
-            AbciApp::create(many_app, Address::anonymous(), maybe_migrations)
-                .unwrap()
-                .with_validator(RequestCacheValidator::new(rocksdb_cache))
+            let app =
+                AbciApp::create(many_app, Address::anonymous(), &tls, maybe_migrations).unwrap();
+            let validator = RequestCacheValidator::new(rocksdb_cache).with_time_fn(app.time_fn());
+            app.with_validator(validator)
         })
         .await
         .unwrap()
@@ -176,7 +234,8 @@ async fn main() {
         std::thread::sleep(std::time::Duration::from_secs(1));
     }
 
-    let key = CoseKeyIdentity::from_pem(std::fs::read_to_string(many_pem).unwrap()).unwrap();
+    let key =
+        identity_from_pem_file(&many_pem).expect("Could not generate identity from PEM file.");
     info!(many_address = key.address().to_string().as_str());
     let server = ManyServer::new(
         format!("AbciModule({})", &status.name),
@@ -215,9 +274,15 @@ async fn main() {
         s.add_validator(ValidateOnlyRequestValidator::new(
             RequestCacheValidator::new(rocksdb_cache.clone()),
         ));
+        if let Some(validator) = rate_limit_flags.validator() {
+            s.add_validator(validator);
+        }
     }
 
-    let mut many_server = HttpServer::new(server);
+    let mut many_server = HttpServer::new(server.clone());
+    http_flags
+        .configure(&mut many_server)
+        .expect("Could not configure the HTTP server");
 
     signal_hook::flag::register(signal_hook::consts::SIGTERM, many_server.term_signal())
         .expect("Could not register signal handler");
@@ -227,7 +292,7 @@ async fn main() {
         .expect("Could not register signal handler");
 
     info!("Starting MANY server on addr {}", many.clone());
-    match many_server.bind(many).await {
+    match http_flags.bind(&mut many_server, server, many).await {
         Ok(_) => {}
         Err(error) => {
             error!("{}", error);
@@ -9,7 +9,7 @@ use many_modules::abci_backend::{AbciInit, EndpointInfo, ABCI_MODULE_ATTRIBUTE};
 use many_modules::base;
 use many_protocol::{
     decode_request_from_cose_sign1, decode_response_from_cose_sign1,
-    encode_cose_sign1_from_request, encode_cose_sign1_from_response, ManyUrl,
+    encode_cose_sign1_from_request, encode_cose_sign1_from_response, ManyUrl, RequestMessage,
     RequestMessageBuilder, ResponseMessage,
 };
 use many_server::transport::LowLevelManyRequestHandler;
@@ -25,6 +25,7 @@ pub struct AbciModuleMany<C: Client> {
     backend_status: base::Status,
     identity: CoseKeyIdentity,
     backend_endpoints: BTreeMap<String, EndpointInfo>,
+    backend_descriptions: BTreeMap<String, base::EndpointDescription>,
     allow_addrs: Option<BTreeSet<Address>>,
     allow_origin: Option<Vec<ManyUrl>>,
 }
@@ -37,18 +38,59 @@ impl<C: Client + Sync> AbciModuleMany<C> {
         allow_addrs: Option<BTreeSet<Address>>,
         allow_origin: Option<Vec<ManyUrl>>,
     ) -> Self {
-        let init_message = RequestMessageBuilder::default()
+        let init_message: AbciInit = minicbor::decode(
+            &Self::query(&client, &identity, "abci.init", &allow_origin)
+                .await
+                .unwrap(),
+        )
+        .unwrap();
+
+        // Backends of previous versions do not describe their endpoints.
+        let backend_descriptions = Self::query(&client, &identity, "describe", &allow_origin)
+            .await
+            .ok()
+            .and_then(|data| minicbor::decode::<base::Descriptions>(&data).ok())
+            .map_or_else(BTreeMap::new, |descriptions| {
+                descriptions
+                    .0
+                    .into_iter()
+                    .filter(|(method, _)| init_message.endpoints.contains_key(method))
+                    .collect()
+            });
+
+        Self {
+            client,
+            backend_status,
+            identity,
+            backend_endpoints: init_message.endpoints,
+            backend_descriptions,
+            allow_addrs,
+            allow_origin,
+        }
+    }
+
+    /// Send a query to the backend, and return the data of its response.
+    async fn query(
+        client: &C,
+        identity: &CoseKeyIdentity,
+        method: &str,
+        allow_origin: &Option<Vec<ManyUrl>>,
+    ) -> Result<Vec<u8>, ManyError> {
+        let message = RequestMessageBuilder::default()
             .from(identity.address())
-            .method("abci.init".to_string())
+            .method(method.to_string())
             .build()
-            .unwrap();
-        let data = encode_cose_sign1_from_request(init_message, &identity)
-            .unwrap()
+            .map_err(ManyError::unknown)?;
+        let data = encode_cose_sign1_from_request(message, identity)?
             .to_vec()
-            .unwrap();
+            .map_err(ManyError::unexpected_transport_error)?;
 
-        let response = client.abci_query(None, data, None, false).await.unwrap();
-        let response = CoseSign1::from_slice(&response.value).unwrap();
+        let response = client
+            .abci_query(None, data, None, false)
+            .await
+            .map_err(ManyError::unexpected_transport_error)?;
+        let response = CoseSign1::from_slice(&response.value)
+            .map_err(ManyError::unexpected_transport_error)?;
         let response = decode_response_from_cose_sign1(
             &response,
             None,
@@ -57,18 +99,8 @@ impl<C: Client + Sync> AbciModuleMany<C> {
                 CoseKeyVerifier,
                 WebAuthnVerifier::new(allow_origin.clone()),
             ),
-        )
-        .unwrap();
-        let init_message: AbciInit = minicbor::decode(&response.data.unwrap()).unwrap();
-
-        Self {
-            client,
-            backend_status,
-            identity,
-            backend_endpoints: init_message.endpoints,
-            allow_addrs,
-            allow_origin,
-        }
+        )?;
+        response.data
     }
 
     async fn execute_message(&self, envelope: CoseSign1) -> Result<CoseSign1, ManyError> {
@@ -87,12 +119,7 @@ impl<C: Client + Sync> AbciModuleMany<C> {
                 .map_err(ManyError::unexpected_transport_error)?;
 
             if is_command {
-                // TODO: Refactor this when `is_some_and` and/or `let-chains` are stabilized
-                if self.allow_addrs.is_some()
-                    && !self.allow_addrs.as_ref().unwrap().contains(&message.from())
-                {
-                    return Err(ManyError::invalid_from_identity());
-                }
+                check_allowed(&self.allow_addrs, &message)?;
 
                 // The returned error if broadcast_tx_sync fails is not very helpful.
                 let response = self
@@ -126,6 +153,21 @@ impl<C: Client + Sync> AbciModuleMany<C> {
     }
 }
 
+/// Check that the sender of a command is allowed to send commands through
+/// this node. `message` must have been decoded by
+/// [decode_request_from_cose_sign1], which verifies delegation chains, so its
+/// `from` cannot be forged.
+fn check_allowed(
+    allow_addrs: &Option<BTreeSet<Address>>,
+    message: &RequestMessage,
+) -> Result<(), ManyError> {
+    // TODO: Refactor this when `is_some_and` and/or `let-chains` are stabilized
+    if allow_addrs.is_some() && !allow_addrs.as_ref().unwrap().contains(&message.from()) {
+        return Err(ManyError::invalid_from_identity());
+    }
+    Ok(())
+}
+
 impl<C: Client> Debug for AbciModuleMany<C> {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         f.write_str("AbciModuleFrontend")
@@ -154,6 +196,10 @@ impl<C: Client + Sync + Send> base::BaseModuleBackend for AbciModuleMany<C> {
         )))
     }
 
+    fn describe(&self) -> Result<base::Descriptions, ManyError> {
+        Ok(base::Descriptions(self.backend_descriptions.clone()))
+    }
+
     fn status(&self) -> Result<base::Status, ManyError> {
         let attributes: BTreeSet<Attribute> = self
             .backend_status
@@ -179,3 +225,108 @@ impl<C: Client + Sync + Send> base::BaseModuleBackend for AbciModuleMany<C> {
         builder.build().map_err(ManyError::unknown)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use many_identity_dsa::ed25519::generate_random_ed25519_identity;
+    use many_protocol::{DelegationAttribute, DelegationCertificate};
+    use many_types::Timestamp;
+
+    /// Decode a command like [AbciModuleMany::execute_message] does, and check
+    /// its sender.
+    fn execute(
+        allow_addrs: &Option<BTreeSet<Address>>,
+        envelope: &CoseSign1,
+    ) -> Result<(), ManyError> {
+        let message = decode_request_from_cose_sign1(
+            envelope,
+            &(
+                AnonymousVerifier,
+                CoseKeyVerifier,
+                WebAuthnVerifier::new(None),
+            ),
+        )?;
+        check_allowed(allow_addrs, &message)
+    }
+
+    fn command(
+        from: Address,
+        signer: &impl Identity,
+        certificates: Option<Vec<CoseSign1>>,
+    ) -> CoseSign1 {
+        let mut message = RequestMessageBuilder::default()
+            .from(from)
+            .method("ledger.send".to_string())
+            .build()
+            .unwrap();
+        if let Some(certificates) = certificates {
+            message = message.with_attribute(DelegationAttribute::new(certificates).into());
+        }
+        encode_cose_sign1_from_request(message, signer).unwrap()
+    }
+
+    #[test]
+    fn allow_addrs() {
+        let allowed = generate_random_ed25519_identity();
+        let delegate = generate_random_ed25519_identity();
+        let other = generate_random_ed25519_identity();
+        let allow_addrs = Some(BTreeSet::from([allowed.address()]));
+        let expiration = Timestamp::new(u32::MAX as u64).unwrap();
+
+        assert!(execute(&allow_addrs, &command(allowed.address(), &allowed, None)).is_ok());
+        assert!(execute(&allow_addrs, &command(other.address(), &other, None)).is_err());
+        assert!(execute(&None, &command(other.address(), &other, None)).is_ok());
+
+        // A delegate of an allowed address acts as that address.
+        let certificate =
+            DelegationCertificate::new(allowed.address(), delegate.address(), expiration)
+                .sign(&allowed)
+                .unwrap();
+        assert!(execute(
+            &allow_addrs,
+            &command(allowed.address(), &delegate, Some(vec![certificate]))
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn forged_delegation() {
+        let allowed = generate_random_ed25519_identity();
+        let attacker = generate_random_ed25519_identity();
+        let allow_addrs = Some(BTreeSet::from([allowed.address()]));
+        let expiration = Timestamp::new(u32::MAX as u64).unwrap();
+
+        // A certificate claiming the allowed address as delegator, but signed
+        // by the attacker.
+        let mut certificate =
+            DelegationCertificate::new(attacker.address(), attacker.address(), expiration)
+                .sign(&attacker)
+                .unwrap();
+        certificate.payload = Some(
+            minicbor::to_vec(DelegationCertificate::new(
+                allowed.address(),
+                attacker.address(),
+                expiration,
+            ))
+            .unwrap(),
+        );
+        assert!(execute(
+            &allow_addrs,
+            &command(allowed.address(), &attacker, Some(vec![certificate]))
+        )
+        .is_err());
+
+        // A certificate the attacker signed for itself does not make it act as
+        // the allowed address either.
+        let certificate =
+            DelegationCertificate::new(attacker.address(), attacker.address(), expiration)
+                .sign(&attacker)
+                .unwrap();
+        assert!(execute(
+            &allow_addrs,
+            &command(allowed.address(), &attacker, Some(vec![certificate]))
+        )
+        .is_err());
+    }
+}
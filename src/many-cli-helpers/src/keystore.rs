@@ -0,0 +1,315 @@
+//! A directory of named, password-encrypted private keys.
+use anyhow::{anyhow, bail};
+use many_identity::{Address, Identity};
+use many_identity_dsa::encryption::{decrypt_pem, encrypt_pem, is_encrypted_pem};
+use many_identity_dsa::CoseKeyIdentity;
+use std::path::{Path, PathBuf};
+
+/// Environment variable overriding the default keystore directory.
+pub const KEYSTORE_ENV: &str = "MANY_KEYSTORE";
+
+/// Environment variable holding the password of the keys, for non-interactive
+/// use. If unset, the password is prompted for.
+pub const PASSWORD_ENV: &str = "MANY_KEY_PASSWORD";
+
+const EXTENSION: &str = "pem";
+
+/// The password from the environment, if set.
+fn password_from_env() -> anyhow::Result<Option<String>> {
+    env_password(std::env::var(PASSWORD_ENV).ok())
+}
+
+/// Validate the value of [PASSWORD_ENV]. An empty password is refused, as it
+/// is most likely a mistake in a script.
+fn env_password(value: Option<String>) -> anyhow::Result<Option<String>> {
+    match value {
+        Some(password) if password.is_empty() => bail!("{PASSWORD_ENV} is set but empty."),
+        password => Ok(password),
+    }
+}
+
+/// Read the password of a key from the environment, or prompt for it.
+pub fn read_password(prompt: &str) -> anyhow::Result<String> {
+    match password_from_env()? {
+        Some(password) => Ok(password),
+        None => Ok(rpassword::prompt_password(prompt)?),
+    }
+}
+
+/// Read a new password, asking for it twice unless it comes from the environment.
+pub fn read_new_password() -> anyhow::Result<String> {
+    if let Some(password) = password_from_env()? {
+        return Ok(password);
+    }
+
+    let password = rpassword::prompt_password("New password: ")?;
+    if password.is_empty() {
+        bail!("The password cannot be empty.");
+    }
+    if rpassword::prompt_password("Confirm password: ")? != password {
+        bail!("Passwords do not match.");
+    }
+    Ok(password)
+}
+
+/// Load an identity from a PEM file, prompting for its password if it is
+/// encrypted.
+pub fn identity_from_pem_file(path: impl AsRef<Path>) -> anyhow::Result<CoseKeyIdentity> {
+    let pem = std::fs::read_to_string(path.as_ref())?;
+    if is_encrypted_pem(&pem) {
+        let password = read_password(&format!("Password for \"{}\": ", path.as_ref().display()))?;
+        Ok(CoseKeyIdentity::from_encrypted_pem(pem, password)?)
+    } else {
+        Ok(CoseKeyIdentity::from_pem(pem)?)
+    }
+}
+
+pub struct Keystore {
+    root: PathBuf,
+}
+
+impl Keystore {
+    /// The default keystore directory, `$MANY_KEYSTORE` or `$HOME/.many/keys`.
+    pub fn default_root() -> Option<PathBuf> {
+        std::env::var_os(KEYSTORE_ENV)
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".many/keys")))
+    }
+
+    fn root(root: Option<PathBuf>) -> anyhow::Result<PathBuf> {
+        root.or_else(Self::default_root)
+            .ok_or_else(|| anyhow!("Could not find the keystore directory."))
+    }
+
+    /// Open the existing keystore at `root`, or at the default location.
+    pub fn open(root: Option<PathBuf>) -> anyhow::Result<Self> {
+        let root = Self::root(root)?;
+        if !root.is_dir() {
+            bail!("The keystore \"{}\" does not exist.", root.display());
+        }
+        Ok(Self { root })
+    }
+
+    /// Open the keystore at `root`, or at the default location, creating its
+    /// directory if it does not exist. Only use this to add keys.
+    pub fn open_or_create(root: Option<PathBuf>) -> anyhow::Result<Self> {
+        let root = Self::root(root)?;
+        if !root.exists() {
+            std::fs::create_dir_all(&root)?;
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::PermissionsExt;
+                std::fs::set_permissions(&root, std::fs::Permissions::from_mode(0o700))?;
+            }
+        }
+        Ok(Self { root })
+    }
+
+    fn path(&self, name: &str) -> anyhow::Result<PathBuf> {
+        let valid = !name.is_empty()
+            && !name.starts_with('.')
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+        if !valid {
+            bail!("Invalid key name \"{name}\". Use letters, digits, '-', '_' and '.'.");
+        }
+        Ok(self.root.join(format!("{name}.{EXTENSION}")))
+    }
+
+    fn write(&self, name: &str, encrypted_pem: &str) -> anyhow::Result<()> {
+        let path = self.path(name)?;
+        if path.exists() {
+            bail!("A key named \"{name}\" already exists.");
+        }
+
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        std::io::Write::write_all(&mut options.open(path)?, encrypted_pem.as_bytes())?;
+        Ok(())
+    }
+
+    /// The names of all the keys in the keystore, sorted.
+    pub fn list(&self) -> anyhow::Result<Vec<String>> {
+        let mut names = std::fs::read_dir(&self.root)?
+            .filter_map(|entry| {
+                let path = entry.ok()?.path();
+                if path.extension()? == EXTENSION {
+                    Some(path.file_stem()?.to_string_lossy().to_string())
+                } else {
+                    None
+                }
+            })
+            .collect::<Vec<_>>();
+        names.sort();
+        Ok(names)
+    }
+
+    /// Generate a new Ed25519 key, and return its address.
+    pub fn generate(&self, name: &str, password: &str) -> anyhow::Result<Address> {
+        let pem = many_identity_dsa::ed25519::generate_ed25519_pem()?;
+        self.import(name, &pem, password)
+    }
+
+    /// Import a private key PEM, and return its address. Plaintext keys are
+    /// encrypted with the password; encrypted keys are stored as is, and the
+    /// password must decrypt them.
+    pub fn import(&self, name: &str, pem: &str, password: &str) -> anyhow::Result<Address> {
+        let (encrypted, identity) = if is_encrypted_pem(pem) {
+            (
+                pem.to_string(),
+                CoseKeyIdentity::from_encrypted_pem(pem, password)?,
+            )
+        } else {
+            (encrypt_pem(pem, password)?, CoseKeyIdentity::from_pem(pem)?)
+        };
+        self.write(name, &encrypted)?;
+        Ok(identity.address())
+    }
+
+    /// Return the decrypted PEM of a key.
+    pub fn export(&self, name: &str, password: &str) -> anyhow::Result<String> {
+        let pem = self.read_encrypted(name)?;
+        Ok(decrypt_pem(&pem, password)?)
+    }
+
+    /// Load the identity of a key.
+    pub fn load(&self, name: &str, password: &str) -> anyhow::Result<CoseKeyIdentity> {
+        let pem = self.read_encrypted(name)?;
+        Ok(CoseKeyIdentity::from_encrypted_pem(pem, password)?)
+    }
+
+    /// Return the encrypted PEM of a key, as stored.
+    pub fn read_encrypted(&self, name: &str) -> anyhow::Result<String> {
+        let path = self.path(name)?;
+        if !path.exists() {
+            bail!("No key named \"{name}\" in the keystore.");
+        }
+        Ok(std::fs::read_to_string(path)?)
+    }
+}
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct KeyFlags {
+    /// The name of a key in the keystore to use as identity.
+    #[clap(long)]
+    pub key: Option<String>,
+
+    /// The keystore directory. By default, `$MANY_KEYSTORE` or `$HOME/.many/keys`.
+    #[clap(long)]
+    pub keystore: Option<PathBuf>,
+}
+
+impl KeyFlags {
+    /// Load the identity selected on the command line, either a PEM file (which
+    /// can be encrypted) or a key from the keystore. Returns `None` if neither
+    /// was passed.
+    pub fn identity(&self, pem: Option<&Path>) -> anyhow::Result<Option<CoseKeyIdentity>> {
+        match (pem, &self.key) {
+            (Some(_), Some(_)) => bail!("Cannot use both a PEM file and a keystore key."),
+            (Some(pem), None) => identity_from_pem_file(pem).map(Some),
+            (None, Some(name)) => {
+                let keystore = Keystore::open(self.keystore.clone())?;
+                let password = read_password(&format!("Password for key \"{name}\": "))?;
+                keystore.load(name, &password).map(Some)
+            }
+            (None, None) => Ok(None),
+        }
+    }
+
+    /// Same as [KeyFlags::identity], but returns the anonymous identity if no
+    /// key was passed.
+    pub fn identity_or_anonymous(&self, pem: Option<&Path>) -> anyhow::Result<Box<dyn Identity>> {
+        Ok(match self.identity(pem)? {
+            Some(identity) => Box::new(identity),
+            None => Box::new(many_identity::AnonymousIdentity),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keystore() -> (tempfile::TempDir, Keystore) {
+        let dir = tempfile::tempdir().unwrap();
+        let keystore = Keystore::open_or_create(Some(dir.path().join("keys"))).unwrap();
+        (dir, keystore)
+    }
+
+    #[test]
+    fn open_does_not_create() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("keys");
+        assert!(Keystore::open(Some(root.clone())).is_err());
+        assert!(!root.exists());
+
+        Keystore::open_or_create(Some(root.clone())).unwrap();
+        assert!(Keystore::open(Some(root))
+            .unwrap()
+            .list()
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn empty_env_password() {
+        assert!(env_password(Some(String::new())).is_err());
+        assert_eq!(
+            env_password(Some("password".to_string())).unwrap(),
+            Some("password".to_string())
+        );
+        assert_eq!(env_password(None).unwrap(), None);
+    }
+
+    #[test]
+    fn generate_load_export() {
+        let (_dir, keystore) = keystore();
+        let address = keystore.generate("alice", "password").unwrap();
+        assert_eq!(keystore.list().unwrap(), vec!["alice".to_string()]);
+
+        let identity = keystore.load("alice", "password").unwrap();
+        assert_eq!(identity.address(), address);
+        assert!(keystore.load("alice", "wrong").is_err());
+
+        let pem = keystore.export("alice", "password").unwrap();
+        assert!(!is_encrypted_pem(&pem));
+        assert_eq!(CoseKeyIdentity::from_pem(pem).unwrap().address(), address);
+
+        // The file on disk is encrypted.
+        let stored = std::fs::read_to_string(keystore.path("alice").unwrap()).unwrap();
+        assert!(is_encrypted_pem(&stored));
+    }
+
+    #[test]
+    fn import() {
+        let (_dir, keystore) = keystore();
+        let pem = many_identity_dsa::ed25519::generate_ed25519_pem().unwrap();
+        let address = keystore.import("bob", &pem, "password").unwrap();
+        assert_eq!(keystore.load("bob", "password").unwrap().address(), address);
+
+        // Names are unique.
+        assert!(keystore.import("bob", &pem, "password").is_err());
+
+        // Encrypted keys are stored as is, but need the right password.
+        let encrypted = encrypt_pem(&pem, "other").unwrap();
+        assert!(keystore.import("carol", &encrypted, "password").is_err());
+        assert_eq!(
+            keystore.import("carol", &encrypted, "other").unwrap(),
+            address
+        );
+    }
+
+    #[test]
+    fn invalid_names() {
+        let (_dir, keystore) = keystore();
+        for name in ["", ".hidden", "../escape", "a/b", "a b"] {
+            assert!(keystore.generate(name, "password").is_err(), "{name}");
+        }
+    }
+}
@@ -1,8 +1,21 @@
+use many_server::transport::http::HttpServer;
+use many_server::transport::tcp::TcpServer;
+use many_server::transport::websocket::WebSocketServer;
+use many_server::transport::LowLevelManyRequestHandler;
+use many_server::validator::rate_limit::Limit;
+use many_server::validator::RateLimitValidator;
+use std::net::{SocketAddr, ToSocketAddrs};
 use std::os::unix::ffi::OsStrExt;
+use std::path::PathBuf;
+use std::time::Duration;
 use tracing::metadata::LevelFilter;
 use tracing_subscriber::fmt::Subscriber;
 
 pub mod error;
+pub mod keystore;
+pub mod offline;
+
+pub use keystore::KeyFlags;
 
 #[derive(clap::ArgEnum, Clone, Debug)]
 enum LogStrategy {
@@ -82,3 +95,150 @@ impl CommonCliFlags {
         Ok(())
     }
 }
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct HttpServerFlags {
+    /// Maximum number of HTTP requests executed concurrently. Requests that
+    /// change the state are always executed one at a time.
+    #[clap(long, default_value_t = 1)]
+    http_workers: usize,
+
+    /// Respond with a timeout to HTTP requests that take longer than this, in
+    /// seconds. The requests still run to completion.
+    #[clap(long)]
+    http_request_timeout: Option<u64>,
+
+    /// Time to wait for HTTP requests in flight when shutting down, in seconds.
+    #[clap(long, default_value_t = 30)]
+    http_drain_timeout: u64,
+
+    /// Serve HTTPS with the certificate chain of this PEM file.
+    #[clap(long, requires = "tls-key")]
+    tls_cert: Option<PathBuf>,
+
+    /// The PEM file of the private key of the TLS certificate.
+    #[clap(long, requires = "tls-cert")]
+    tls_key: Option<PathBuf>,
+
+    /// Only accept HTTPS clients with a certificate signed by one of the
+    /// certificates of this PEM file (mutual TLS).
+    #[clap(long, requires = "tls-cert")]
+    tls_client_ca: Option<PathBuf>,
+
+    /// Also serve requests over raw TCP connections on this address.
+    #[clap(long)]
+    tcp: Option<SocketAddr>,
+
+    /// Also serve requests over WebSocket connections on this address.
+    #[clap(long)]
+    ws: Option<SocketAddr>,
+}
+
+impl HttpServerFlags {
+    pub fn configure<E: LowLevelManyRequestHandler + 'static>(
+        &self,
+        server: &mut HttpServer<E>,
+    ) -> Result<(), anyhow::Error> {
+        server.set_workers(self.http_workers);
+        if let Some(timeout) = self.http_request_timeout {
+            server.set_request_timeout(Duration::from_secs(timeout));
+        }
+        server.set_drain_timeout(Duration::from_secs(self.http_drain_timeout));
+
+        if let (Some(cert), Some(key)) = (&self.tls_cert, &self.tls_key) {
+            let client_ca = self.tls_client_ca.as_ref().map(std::fs::read).transpose()?;
+            server.set_tls(many_server::transport::tls::server_config(
+                &std::fs::read(cert)?,
+                &std::fs::read(key)?,
+                client_ca.as_deref(),
+            )?);
+        }
+        Ok(())
+    }
+
+    /// Serve `executor` over HTTP on `addr`, and over TCP and WebSocket if
+    /// their addresses were given. All the transports stop when the term signal
+    /// of the HTTP server is set.
+    pub async fn bind<E: LowLevelManyRequestHandler + Clone + 'static>(
+        &self,
+        server: &mut HttpServer<E>,
+        executor: E,
+        addr: impl ToSocketAddrs,
+    ) -> Result<(), anyhow::Error> {
+        let term_signal = server.term_signal();
+        let tcp = self.tcp.map(|addr| {
+            let mut tcp = TcpServer::new(executor.clone());
+            tcp.set_term_signal(term_signal.clone());
+            (tcp, addr)
+        });
+        let ws = self.ws.map(|addr| {
+            let mut ws = WebSocketServer::new(executor);
+            ws.set_term_signal(term_signal);
+            (ws, addr)
+        });
+
+        tokio::try_join!(
+            server.bind(addr),
+            async {
+                match &tcp {
+                    Some((tcp, addr)) => tcp.bind(addr).await,
+                    None => Ok(()),
+                }
+            },
+            async {
+                match &ws {
+                    Some((ws, addr)) => ws.bind(addr).await,
+                    None => Ok(()),
+                }
+            },
+        )?;
+        Ok(())
+    }
+}
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct RateLimitFlags {
+    /// Limit the requests of each sender, as BURST:PER_SECOND.
+    #[clap(long)]
+    rate_limit: Option<Limit>,
+
+    /// Limit the requests of all anonymous senders, as BURST:PER_SECOND.
+    #[clap(long)]
+    anonymous_rate_limit: Option<Limit>,
+
+    /// Limit the requests of each sender to a method, as METHOD=BURST:PER_SECOND.
+    /// Can be given multiple times.
+    #[clap(long, parse(try_from_str = parse_method_limit))]
+    method_rate_limit: Vec<(String, Limit)>,
+}
+
+fn parse_method_limit(s: &str) -> Result<(String, Limit), String> {
+    let (method, limit) = s.split_once('=').ok_or_else(|| {
+        format!("Invalid method rate limit '{s}', expected METHOD=BURST:PER_SECOND.")
+    })?;
+    Ok((method.to_string(), limit.parse()?))
+}
+
+impl RateLimitFlags {
+    /// The rate limit validator, if any limit was given.
+    pub fn validator(&self) -> Option<RateLimitValidator> {
+        if self.rate_limit.is_none()
+            && self.anonymous_rate_limit.is_none()
+            && self.method_rate_limit.is_empty()
+        {
+            return None;
+        }
+
+        let mut validator = RateLimitValidator::new();
+        if let Some(limit) = self.rate_limit {
+            validator = validator.with_sender_limit(limit);
+        }
+        if let Some(limit) = self.anonymous_rate_limit {
+            validator = validator.with_anonymous_limit(limit);
+        }
+        for (method, limit) in &self.method_rate_limit {
+            validator = validator.with_method_limit(method, *limit);
+        }
+        Some(validator)
+    }
+}
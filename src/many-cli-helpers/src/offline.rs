@@ -0,0 +1,211 @@
+//! Files of unsigned requests and signed envelopes, to sign requests on an
+//! offline machine and send them to a server later.
+//!
+//! Both are stored as CBOR. Files containing the hexadecimal encoding of the
+//! CBOR (e.g. the output of `many message --hex`) are also accepted.
+use anyhow::{anyhow, bail};
+use coset::{CborSerializable, CoseSign1, TaggedCborSerializable};
+use many_identity::verifiers::AnonymousVerifier;
+use many_identity::{Address, Identity};
+use many_identity_dsa::CoseKeyVerifier;
+use many_protocol::{RequestMessage, RequestMessageBuilder};
+use many_types::Timestamp;
+use std::fmt::Write;
+use std::path::Path;
+
+/// The content of an offline file.
+pub enum OfflineMessage {
+    /// A request which still needs to be signed.
+    Request(RequestMessage),
+
+    /// A signed request, ready to be sent.
+    Envelope(CoseSign1),
+}
+
+impl OfflineMessage {
+    pub fn from_bytes(bytes: &[u8]) -> anyhow::Result<Self> {
+        if let Ok(request) = RequestMessage::from_bytes(bytes) {
+            return Ok(Self::Request(request));
+        }
+        CoseSign1::from_tagged_slice(bytes)
+            .or_else(|_| CoseSign1::from_slice(bytes))
+            .map(Self::Envelope)
+            .map_err(|_| anyhow!("Not an unsigned request nor a signed envelope."))
+    }
+
+    pub fn to_bytes(&self) -> anyhow::Result<Vec<u8>> {
+        match self {
+            Self::Request(request) => request.to_bytes().map_err(|e| anyhow!(e)),
+            Self::Envelope(envelope) => envelope.clone().to_tagged_vec().map_err(|e| anyhow!(e)),
+        }
+    }
+
+    /// The request, which is the payload of an envelope.
+    pub fn request(&self) -> anyhow::Result<RequestMessage> {
+        match self {
+            Self::Request(request) => Ok(request.clone()),
+            Self::Envelope(envelope) => {
+                let payload = envelope
+                    .payload
+                    .as_ref()
+                    .ok_or_else(|| anyhow!("The envelope has no payload."))?;
+                RequestMessage::from_bytes(payload).map_err(|e| anyhow!(e))
+            }
+        }
+    }
+
+    /// The envelope, or an error if the request is not signed yet.
+    pub fn into_envelope(self) -> anyhow::Result<CoseSign1> {
+        match self {
+            Self::Request(_) => bail!("The request is not signed."),
+            Self::Envelope(envelope) => Ok(envelope),
+        }
+    }
+}
+
+/// Read an offline file, in binary or hexadecimal.
+pub fn read(path: impl AsRef<Path>) -> anyhow::Result<OfflineMessage> {
+    let content = std::fs::read(path)?;
+    let bytes = match std::str::from_utf8(&content).map(str::trim) {
+        Ok(text) if !text.is_empty() && text.bytes().all(|b| b.is_ascii_hexdigit()) => {
+            hex::decode(text)?
+        }
+        _ => content,
+    };
+    OfflineMessage::from_bytes(&bytes)
+}
+
+/// Write an offline file, in binary.
+pub fn write(path: impl AsRef<Path>, message: &OfflineMessage) -> anyhow::Result<()> {
+    std::fs::write(path, message.to_bytes()?)?;
+    Ok(())
+}
+
+/// Create an unsigned request from `from`, with a random nonce. The request
+/// is timestamped again when it is signed.
+pub fn request(
+    from: Address,
+    to: Address,
+    method: impl Into<String>,
+    data: Vec<u8>,
+) -> anyhow::Result<RequestMessage> {
+    let mut nonce = [0u8; 16];
+    rand::RngCore::fill_bytes(&mut rand::thread_rng(), &mut nonce);
+
+    RequestMessageBuilder::default()
+        .version(1)
+        .from(from)
+        .to(to)
+        .method(method.into())
+        .data(data)
+        .nonce(nonce.to_vec())
+        .timestamp(Timestamp::now())
+        .build()
+        .map_err(|e| anyhow!("Could not build request: {e}"))
+}
+
+/// Sign a request. Servers reject requests whose timestamp is too old, so
+/// unless `keep_timestamp` is set the request is timestamped now. Anonymous
+/// requests are signed as coming from the identity, or from the delegator if
+/// the request carries delegation certificates.
+pub fn sign(
+    request: RequestMessage,
+    identity: &impl Identity,
+    keep_timestamp: bool,
+) -> anyhow::Result<CoseSign1> {
+    Ok(many_protocol::sign_request(
+        request,
+        identity,
+        keep_timestamp,
+    )?)
+}
+
+/// A human readable description of an offline file, for a signer to review
+/// before signing or sending it.
+pub fn describe(message: &OfflineMessage) -> anyhow::Result<String> {
+    let request = message.request()?;
+    let mut out = String::new();
+
+    match message {
+        OfflineMessage::Request(_) => writeln!(out, "Unsigned request")?,
+        OfflineMessage::Envelope(envelope) => {
+            match many_protocol::decode_request_from_cose_sign1(
+                envelope,
+                &(AnonymousVerifier, CoseKeyVerifier),
+            ) {
+                Ok(_) => writeln!(out, "Signed request (valid signature)")?,
+                Err(e) => writeln!(out, "Signed request (could not verify signature: {e})")?,
+            }
+        }
+    }
+    writeln!(out, "From:      {}", request.from())?;
+    writeln!(out, "To:        {}", request.to)?;
+    writeln!(out, "Method:    {}", request.method)?;
+    if let Some(timestamp) = request.timestamp {
+        writeln!(out, "Timestamp: {}", timestamp.secs())?;
+    }
+    if let Some(nonce) = &request.nonce {
+        writeln!(out, "Nonce:     {}", hex::encode(nonce))?;
+    }
+    for attribute in request.attributes.iter() {
+        writeln!(out, "Attribute: {}", attribute.id)?;
+    }
+    if !request.data.is_empty() {
+        let data = cbor_diag::parse_bytes(&request.data)
+            .map_err(|e| anyhow!("Invalid argument: {e:?}"))?;
+        writeln!(out, "Argument:")?;
+        for line in data.to_diag_pretty().lines() {
+            writeln!(out, "  {line}")?;
+        }
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use many_identity::testing::identity;
+    use many_identity_dsa::CoseKeyIdentity;
+
+    fn key() -> CoseKeyIdentity {
+        CoseKeyIdentity::from_pem(many_identity_dsa::ed25519::generate_ed25519_pem().unwrap())
+            .unwrap()
+    }
+
+    #[test]
+    fn sign_and_read() {
+        let key = key();
+        let request = request(key.address(), identity(1), "ledger.send", vec![0xa0]).unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("request");
+        write(&path, &OfflineMessage::Request(request)).unwrap();
+        let request = read(&path).unwrap().request().unwrap();
+        assert_eq!(request.method, "ledger.send");
+
+        let envelope = sign(request, &key, false).unwrap();
+        std::fs::write(&path, hex::encode(envelope.to_tagged_vec().unwrap())).unwrap();
+        let message = read(&path).unwrap();
+        assert!(describe(&message)
+            .unwrap()
+            .starts_with("Signed request (valid signature)"));
+        assert_eq!(message.request().unwrap().from(), key.address());
+        assert!(message.into_envelope().is_ok());
+    }
+
+    #[test]
+    fn sign_wrong_identity() {
+        let key = key();
+        let request = request(identity(1), identity(2), "ledger.send", vec![]).unwrap();
+        assert!(sign(request.clone(), &key, false).is_err());
+
+        // Anonymous requests are signed by anyone.
+        let request = RequestMessage {
+            from: None,
+            ..request
+        };
+        let envelope = sign(request, &key, false).unwrap();
+        let message = OfflineMessage::Envelope(envelope);
+        assert_eq!(message.request().unwrap().from(), key.address());
+    }
+}
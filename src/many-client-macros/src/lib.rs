@@ -30,17 +30,38 @@ impl Parse for MacroArguments {
     }
 }
 
+/// The name of a method on the server, e.g. `getFromAddress` for `get_from_address`.
+fn server_method_name(ident: &syn::Ident) -> String {
+    let mut name = String::new();
+    let mut upper = false;
+    for c in ident.to_string().trim_start_matches("r#").chars() {
+        if c == '_' {
+            upper = true;
+        } else if upper {
+            name.extend(c.to_uppercase());
+            upper = false;
+        } else {
+            name.push(c);
+        }
+    }
+    name
+}
+
+/// Generate the async methods of a client type from a trait, and a blocking
+/// client type of the same name in a `blocking` submodule. Blocking clients
+/// are only available with the `native` feature of `many-client`.
 #[proc_macro_attribute]
 pub fn many_client(attr: TokenStream, input: TokenStream) -> TokenStream {
     let MacroArguments { r#type, namespace } = parse_macro_input!(attr as MacroArguments);
 
     let input_trait = parse_macro_input!(input as ItemTrait);
 
-    let methods_vec = input_trait.items.iter().map(|func| {
+    let methods_vec = input_trait.items.iter().map(|func| -> syn::Result<(TokenStream2, TokenStream2)> {
         let namespace = namespace.clone();
         let func = func.to_token_stream();
         let method: syn::TraitItemFn =
             parse2(func)?;
+        let blocking_method = method.sig.clone();
         let mut method = method.sig;
         method.asyncness = parse_quote! { async };
         let mut args_iter = method.inputs.iter();
@@ -53,19 +74,25 @@ pub fn many_client(attr: TokenStream, input: TokenStream) -> TokenStream {
             quote! { () }
         };
         let server_method = if let Some(namespace) = namespace {
-            format!("{}.{}", namespace.value(), method.ident)
+            format!("{}.{}", namespace.value(), server_method_name(&method.ident))
         } else {
-            format!("{}", method.ident)
+            server_method_name(&method.ident)
         };
         let server_method: LitStr = parse_quote! { #server_method };
         let q = quote! {
             pub #method {
-                let response = self.0.call_(#server_method, #args_var).await?;
+                let response = self.0.call_and_wait(#server_method, #args_var).await?.data?;
+                minicbor::decode(&response).map_err(many_error::ManyError::deserialization_error)
+            }
+        };
+        let blocking_q = quote! {
+            pub #blocking_method {
+                let response = self.0.call_and_wait(#server_method, #args_var)?.data?;
                 minicbor::decode(&response).map_err(many_error::ManyError::deserialization_error)
             }
         };
-        Ok(q.into_token_stream())
-    }).try_fold(vec![], |mut acc, curr: syn::Result<TokenStream2>| {
+        Ok((q, blocking_q))
+    }).try_fold(vec![], |mut acc, curr: syn::Result<(TokenStream2, TokenStream2)>| {
         match curr {
             Ok(c) => acc.push(c),
             Err(e) => return Err(e)
@@ -76,9 +103,7 @@ pub fn many_client(attr: TokenStream, input: TokenStream) -> TokenStream {
         Ok(v) => v,
         Err(e) => return e.to_compile_error().into(),
     };
-    let methods_iter = methods_vec.into_iter();
-
-    let methods = TokenStream2::from_iter(methods_iter);
+    let (methods, blocking_methods): (TokenStream2, TokenStream2) = methods_vec.into_iter().unzip();
 
     let q = quote! {
         impl<I: many_identity::Identity> #r#type<I> {
@@ -88,6 +113,50 @@ pub fn many_client(attr: TokenStream, input: TokenStream) -> TokenStream {
                 Self(client)
             }
         }
+
+        #[cfg(feature = "native")]
+        pub mod blocking {
+            #[allow(unused_imports)]
+            use super::*;
+
+            #[derive(Debug, Clone)]
+            pub struct #r#type<I: many_identity::Identity>(crate::client::blocking::ManyClient<I>);
+
+            impl<I: many_identity::Identity> #r#type<I> {
+                #blocking_methods
+
+                pub fn new(client: crate::client::blocking::ManyClient<I>) -> Self {
+                    Self(client)
+                }
+            }
+        }
     };
     q.into()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proc_macro2::Span;
+
+    fn method_name(ident: &str) -> String {
+        server_method_name(&syn::Ident::new(ident, Span::call_site()))
+    }
+
+    #[test]
+    fn server_method_names() {
+        assert_eq!(method_name("status"), "status");
+        assert_eq!(method_name("get_from_address"), "getFromAddress");
+        assert_eq!(
+            method_name("multisig_submit_transaction"),
+            "multisigSubmitTransaction"
+        );
+        assert_eq!(method_name("set_mint_policy"), "setMintPolicy");
+    }
+
+    #[test]
+    fn raw_identifiers() {
+        let ident = syn::Ident::new_raw("type", Span::call_site());
+        assert_eq!(server_method_name(&ident), "type");
+    }
+}
@@ -1,9 +1,48 @@
+pub mod account;
+pub mod r#async;
 pub mod base;
 pub mod blockchain;
+#[cfg(feature = "native")]
 pub mod blocking;
+pub mod compute;
+pub mod data;
+pub mod events;
+pub mod failover;
+pub mod idstore;
+pub mod kvstore;
 pub mod ledger;
+pub mod mintburn;
+pub mod multisig;
+#[cfg(all(test, feature = "native"))]
+mod testutils;
+mod time;
+#[cfg(feature = "native")]
+pub mod tls;
+pub mod tokens;
+#[cfg(feature = "native")]
+pub mod transport;
+pub mod wait;
+pub mod web;
 
+pub use account::AccountClient;
+pub use base::BaseClient;
+pub use blockchain::BlockchainClient;
+pub use compute::ComputeClient;
+pub use data::DataClient;
+pub use events::EventsClient;
+pub use idstore::IdStoreClient;
+pub use kvstore::KvStoreClient;
 pub use ledger::LedgerClient;
+pub use mintburn::MintBurnClient;
+pub use multisig::MultisigClient;
+pub use r#async::AsyncClient;
+pub use tokens::TokensClient;
+pub use web::WebClient;
+
+use failover::{Endpoints, FailoverOptions};
+#[cfg(feature = "native")]
+use tls::TlsConfig;
+use wait::WaitOptions;
 
 use coset::{CoseSign1, TaggedCborSerializable};
 use many_error::ManyError;
@@ -11,19 +50,49 @@ use many_identity::verifiers::AnonymousVerifier;
 use many_identity::{verifiers, Address, Identity};
 use many_identity_dsa::CoseKeyVerifier;
 use many_modules::base::Status;
+use many_modules::r#async::StatusArgs;
 use many_protocol::{
-    encode_cose_sign1_from_request, RequestMessage, RequestMessageBuilder, ResponseMessage,
+    encode_cose_sign1_from_request, DelegationAttribute, RequestMessage, RequestMessageBuilder,
+    ResponseMessage,
 };
+use many_types::attributes::AttributeSet;
 use minicbor::Encode;
 use reqwest::{IntoUrl, Url};
 use std::fmt::{Debug, Formatter};
+use std::sync::Arc;
+
+#[cfg(not(any(feature = "native", feature = "wasm")))]
+compile_error!("Either the `native` or the `wasm` feature of many-client must be enabled.");
+
+fn new_nonce() -> Vec<u8> {
+    let mut nonce = [0u8; 16];
+    rand::RngCore::fill_bytes(&mut rand::thread_rng(), &mut nonce);
+    nonce.to_vec()
+}
 
-#[derive(Clone)]
 pub struct ManyClient<I: Identity> {
-    identity: I,
+    identity: Arc<I>,
     to: Option<Address>,
-    url: Url,
+    endpoints: Arc<Endpoints>,
     verifier: (AnonymousVerifier, CoseKeyVerifier),
+    delegation: Option<DelegationAttribute>,
+    http: reqwest::Client,
+    wait: WaitOptions,
+}
+
+// Clients share their identity, so they can be cloned even if it cannot.
+impl<I: Identity> Clone for ManyClient<I> {
+    fn clone(&self) -> Self {
+        Self {
+            identity: self.identity.clone(),
+            to: self.to,
+            endpoints: self.endpoints.clone(),
+            verifier: self.verifier.clone(),
+            delegation: self.delegation.clone(),
+            http: self.http.clone(),
+            wait: self.wait.clone(),
+        }
+    }
 }
 
 impl<I: Identity + Debug> Debug for ManyClient<I> {
@@ -31,19 +100,26 @@ impl<I: Identity + Debug> Debug for ManyClient<I> {
         f.debug_struct("ManyClient")
             .field("id", &self.identity)
             .field("to", &self.to)
-            .field("url", &self.url)
+            .field("urls", &self.endpoints.urls())
             .finish()
     }
 }
 
 pub async fn send_envelope<S: IntoUrl>(url: S, message: CoseSign1) -> Result<CoseSign1, ManyError> {
+    send_envelope_with_client(&reqwest::Client::new(), url, message).await
+}
+
+async fn send_envelope_with_client<S: IntoUrl>(
+    client: &reqwest::Client,
+    url: S,
+    message: CoseSign1,
+) -> Result<CoseSign1, ManyError> {
     let bytes = message
         .to_tagged_vec()
         .map_err(|_| ManyError::internal_server_error())?;
     let len = bytes.len();
     tracing::debug!("Message length in bytes: {}", len);
 
-    let client = reqwest::Client::new();
     tracing::debug!("request {}", hex::encode(&bytes));
     let response = client
         .post(url)
@@ -58,10 +134,13 @@ pub async fn send_envelope<S: IntoUrl>(url: S, message: CoseSign1) -> Result<Cos
                 "413: Content Too Large : {len} bytes"
             )))
         }
-        500 => {
-            return Err(ManyError::unexpected_transport_error(
-                "500: Internal Server Error".to_string(),
-            ))
+        500..=599 => {
+            let status = response.status();
+            return Err(ManyError::unexpected_transport_error(format!(
+                "{}: {}",
+                status.as_u16(),
+                status.canonical_reason().unwrap_or_default()
+            )));
         }
         _ => {}
     }
@@ -77,26 +156,147 @@ pub async fn send_envelope<S: IntoUrl>(url: S, message: CoseSign1) -> Result<Cos
 
 impl<I: Identity> ManyClient<I> {
     pub fn new<S: IntoUrl>(url: S, to: Address, identity: I) -> Result<Self, String> {
+        let client = Self::new_failover([url], to, identity)?;
+        Ok(client.with_failover_options(FailoverOptions::new().with_retries(0)))
+    }
+
+    /// Create a client that sends requests to one of several equivalent
+    /// endpoints, and retries them on the other endpoints after transport
+    /// errors.
+    pub fn new_failover<S: IntoUrl>(
+        urls: impl IntoIterator<Item = S>,
+        to: Address,
+        identity: I,
+    ) -> Result<Self, String> {
         let verifier = (verifiers::AnonymousVerifier, CoseKeyVerifier);
+        let urls = urls
+            .into_iter()
+            .map(|url| url.into_url().map_err(|e| e.to_string()))
+            .collect::<Result<Vec<Url>, _>>()?;
 
         Ok(Self {
-            identity,
+            identity: Arc::new(identity),
             to: Some(to),
-            url: url.into_url().map_err(|e| e.to_string())?,
+            endpoints: Arc::new(Endpoints::new(urls, FailoverOptions::default())?),
             verifier,
+            delegation: None,
+            http: reqwest::Client::new(),
+            wait: WaitOptions::default(),
         })
     }
 
+    /// Use a custom trust configuration, or a client certificate, for `https://`
+    /// and `wss://` URLs.
+    #[cfg(feature = "native")]
+    pub fn with_tls(mut self, tls: &TlsConfig) -> Result<Self, ManyError> {
+        let config = tls.client_config()?;
+        self.http = reqwest::Client::builder()
+            .use_preconfigured_tls(rustls::ClientConfig::clone(&config))
+            .build()
+            .map_err(ManyError::unexpected_transport_error)?;
+        self.endpoints = Arc::new(self.endpoints.with_tls(config));
+        Ok(self)
+    }
+
+    /// How to select endpoints and retry failed requests.
+    pub fn with_failover_options(mut self, options: FailoverOptions) -> Self {
+        self.endpoints = Arc::new(self.endpoints.with_options(options));
+        self
+    }
+
+    /// How to wait for the results of requests answered with an async token.
+    pub fn with_wait_options(mut self, options: WaitOptions) -> Self {
+        self.wait = options;
+        self
+    }
+
+    /// Send all requests on behalf of another identity, using a chain of signed
+    /// delegation certificates. The last certificate of the chain must delegate
+    /// to this client's identity.
+    pub fn with_delegation(mut self, certificates: Vec<CoseSign1>) -> Result<Self, ManyError> {
+        let delegation = DelegationAttribute::new(certificates);
+        // Fail early if the chain is malformed.
+        let _ = delegation.delegator()?;
+        self.delegation = Some(delegation);
+        Ok(self)
+    }
+
+    /// The address requests are sent from; the root delegator if this client
+    /// uses a delegation chain.
+    pub fn address(&self) -> Address {
+        self.delegation
+            .as_ref()
+            .and_then(|d| d.delegator().ok())
+            .unwrap_or_else(|| self.identity.address())
+    }
+
     pub async fn send_message(
         &self,
         message: RequestMessage,
     ) -> Result<ResponseMessage, ManyError> {
-        let cose = encode_cose_sign1_from_request(message, &self.identity).unwrap();
-        let cose_sign1 = send_envelope(self.url.clone(), cose).await?;
+        let cose = encode_cose_sign1_from_request(message.clone(), self.identity.as_ref())?;
+        let cose_sign1 = if self.endpoints.is_query(&message.method) {
+            // Retries of a query are new requests, with a new nonce and timestamp.
+            let sign = || {
+                let message = RequestMessage {
+                    nonce: Some(new_nonce()),
+                    timestamp: None,
+                    ..message.clone()
+                };
+                encode_cose_sign1_from_request(message, self.identity.as_ref())
+            };
+            self.endpoints.send_query(&self.http, cose, sign).await?
+        } else {
+            self.send_envelope(cose).await?
+        };
 
         ResponseMessage::decode_and_verify(&cose_sign1, &self.verifier)
     }
 
+    /// Send an envelope that is already signed, and return the response envelope
+    /// without verifying it.
+    pub async fn send_envelope(&self, envelope: CoseSign1) -> Result<CoseSign1, ManyError> {
+        self.endpoints.send(&self.http, envelope).await
+    }
+
+    /// Check the health of each endpoint with a heartbeat. Endpoints that fail
+    /// are only used if all the others fail too, until they are healthy again.
+    pub async fn check_endpoints(&self) -> Vec<bool> {
+        let mut health = vec![];
+        for index in 0..self.endpoints.urls().len() {
+            let healthy = self.heartbeat_endpoint(index).await.is_ok();
+            self.endpoints.set_health(index, healthy);
+            health.push(healthy);
+        }
+        health
+    }
+
+    /// Check the health of the endpoints periodically, until the returned task
+    /// is aborted.
+    #[cfg(feature = "native")]
+    pub fn spawn_health_checks(&self, interval: std::time::Duration) -> tokio::task::JoinHandle<()>
+    where
+        I: Send + Sync + 'static,
+    {
+        let client = self.clone();
+        tokio::spawn(async move {
+            loop {
+                client.check_endpoints().await;
+                tokio::time::sleep(interval).await;
+            }
+        })
+    }
+
+    async fn heartbeat_endpoint(&self, index: usize) -> Result<(), ManyError> {
+        let argument =
+            minicbor::to_vec(()).map_err(|e| ManyError::serialization_error(e.to_string()))?;
+        let message = self.build_message("heartbeat".to_string(), &argument)?;
+        let cose = encode_cose_sign1_from_request(message, self.identity.as_ref())?;
+        let response = self.endpoints.send_to(index, &self.http, cose).await?;
+        ResponseMessage::decode_and_verify(&response, &self.verifier)?.data?;
+        Ok(())
+    }
+
     pub async fn call_raw<M>(
         &self,
         method: M,
@@ -105,27 +305,31 @@ impl<I: Identity> ManyClient<I> {
     where
         M: Into<String>,
     {
-        let mut nonce = [0u8; 16];
-        rand::RngCore::fill_bytes(&mut rand::thread_rng(), &mut nonce);
+        let message = self.build_message(method.into(), argument)?;
+        self.send_message(message).await
+    }
 
+    fn build_message(&self, method: String, argument: &[u8]) -> Result<RequestMessage, ManyError> {
         let mut builder = RequestMessageBuilder::default();
 
         builder
             .version(1)
-            .from(self.identity.address())
-            .method(method.into())
+            .from(self.address())
+            .method(method)
             .data(argument.to_vec())
-            .nonce(nonce.to_vec());
+            .nonce(new_nonce());
+
+        if let Some(delegation) = &self.delegation {
+            builder.attributes(AttributeSet::from_iter([delegation.clone().into()]));
+        }
 
-        let message: RequestMessage = if let Some(to) = self.to {
+        if let Some(to) = self.to {
             builder.to(to)
         } else {
             &mut builder
         }
         .build()
-        .map_err(|_| ManyError::internal_server_error())?;
-
-        self.send_message(message).await
+        .map_err(|_| ManyError::internal_server_error())
     }
 
     pub async fn call<M, A>(&self, method: M, argument: A) -> Result<ResponseMessage, ManyError>
@@ -147,6 +351,47 @@ impl<I: Identity> ManyClient<I> {
         self.call(method, argument).await?.data
     }
 
+    /// If the server answered with an async token, poll it until the result is
+    /// available and return the final response. Other responses are returned
+    /// as is.
+    pub async fn wait_response(
+        &self,
+        mut response: ResponseMessage,
+    ) -> Result<ResponseMessage, ManyError> {
+        // The result of an async request can itself be async.
+        while let Some(token) = wait::async_token(&response) {
+            tracing::debug!("Async token: {}", hex::encode(&token));
+            response = wait::wait(&token, &self.wait, || async {
+                let status = self
+                    .call_(
+                        "async.status",
+                        StatusArgs {
+                            token: token.clone(),
+                        },
+                    )
+                    .await?;
+                minicbor::decode(&status).map_err(ManyError::deserialization_error)
+            })
+            .await?;
+        }
+        Ok(response)
+    }
+
+    /// Call a method and wait for its final response, even if the server
+    /// answered with an async token.
+    pub async fn call_and_wait<M, A>(
+        &self,
+        method: M,
+        argument: A,
+    ) -> Result<ResponseMessage, ManyError>
+    where
+        M: Into<String>,
+        A: Encode<()>,
+    {
+        let response = self.call(method, argument).await?;
+        self.wait_response(response).await
+    }
+
     pub async fn status(&self) -> Result<Status, ManyError> {
         let response = self.call_("status", ()).await?;
 
@@ -0,0 +1,48 @@
+use many_client_macros::many_client;
+use many_error::ManyError;
+pub use many_identity::Identity;
+pub use many_modules::account::{
+    AddFeaturesArgs, AddFeaturesReturn, AddRolesArgs, AddRolesReturn, CreateArgs, CreateReturn,
+    DisableArgs, DisableReturn, GetRolesArgs, GetRolesReturn, InfoArgs, InfoReturn, ListRolesArgs,
+    ListRolesReturn, RemoveRolesArgs, RemoveRolesReturn, SetDescriptionArgs, SetDescriptionReturn,
+};
+
+use crate::ManyClient;
+
+#[many_client(AccountClient, "account")]
+trait AccountClientTrait {
+    fn create(&self, args: CreateArgs) -> Result<CreateReturn, ManyError>;
+    fn set_description(&self, args: SetDescriptionArgs) -> Result<SetDescriptionReturn, ManyError>;
+    fn list_roles(&self, args: ListRolesArgs) -> Result<ListRolesReturn, ManyError>;
+    fn get_roles(&self, args: GetRolesArgs) -> Result<GetRolesReturn, ManyError>;
+    fn add_roles(&self, args: AddRolesArgs) -> Result<AddRolesReturn, ManyError>;
+    fn remove_roles(&self, args: RemoveRolesArgs) -> Result<RemoveRolesReturn, ManyError>;
+    fn info(&self, args: InfoArgs) -> Result<InfoReturn, ManyError>;
+    fn disable(&self, args: DisableArgs) -> Result<DisableReturn, ManyError>;
+    fn add_features(&self, args: AddFeaturesArgs) -> Result<AddFeaturesReturn, ManyError>;
+}
+
+#[derive(Debug, Clone)]
+pub struct AccountClient<I: Identity>(ManyClient<I>);
+
+#[cfg(all(test, feature = "native"))]
+mod tests {
+    use super::*;
+    use crate::client::testutils::round_trip;
+    use many_identity::Address;
+    use many_modules::EmptyReturn;
+
+    #[tokio::test]
+    async fn disable() {
+        let args = DisableArgs {
+            account: Address::anonymous(),
+        };
+        round_trip(
+            "account.disable",
+            args.clone(),
+            EmptyReturn,
+            |client| async move { AccountClient::new(client).disable(args).await },
+        )
+        .await;
+    }
+}
@@ -0,0 +1,34 @@
+use many_client_macros::many_client;
+use many_error::ManyError;
+pub use many_identity::Identity;
+pub use many_modules::r#async::{StatusArgs, StatusReturn};
+
+use crate::ManyClient;
+
+#[many_client(AsyncClient, "async")]
+trait AsyncClientTrait {
+    fn status(&self, args: StatusArgs) -> Result<StatusReturn, ManyError>;
+}
+
+#[derive(Debug, Clone)]
+pub struct AsyncClient<I: Identity>(ManyClient<I>);
+
+#[cfg(all(test, feature = "native"))]
+mod tests {
+    use super::*;
+    use crate::client::testutils::round_trip;
+
+    #[tokio::test]
+    async fn status() {
+        let args = StatusArgs {
+            token: vec![1, 2, 3].into(),
+        };
+        round_trip(
+            "async.status",
+            args.clone(),
+            StatusReturn::Unknown,
+            |client| async move { AsyncClient::new(client).status(args).await },
+        )
+        .await;
+    }
+}
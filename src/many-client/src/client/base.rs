@@ -2,7 +2,7 @@ use many_client_macros::many_client;
 use many_error::ManyError;
 pub use many_identity::Identity;
 use many_modules::base::HeartbeatReturn;
-pub use many_modules::base::{Endpoints, Status};
+pub use many_modules::base::{Descriptions, Endpoints, Status};
 
 use crate::ManyClient;
 
@@ -11,7 +11,25 @@ trait BaseClientTrait {
     fn status(&self) -> Result<Status, ManyError>;
     fn heartbeat(&self) -> Result<HeartbeatReturn, ManyError>;
     fn endpoints(&self) -> Result<Endpoints, ManyError>;
+    fn describe(&self) -> Result<Descriptions, ManyError>;
 }
 
 #[derive(Debug, Clone)]
 pub struct BaseClient<I: Identity>(ManyClient<I>);
+
+#[cfg(all(test, feature = "native"))]
+mod tests {
+    use super::*;
+    use crate::client::testutils::serve;
+    use many_identity::{AcceptAllVerifier, AnonymousIdentity};
+    use many_server::ManyServer;
+
+    #[tokio::test]
+    async fn status_and_endpoints() {
+        let many = ManyServer::simple("base", AnonymousIdentity, AcceptAllVerifier, None);
+        let client = BaseClient::new(serve(many).await);
+
+        assert_eq!(client.status().await.unwrap().name, "base");
+        assert!(client.endpoints().await.unwrap().0.contains("status"));
+    }
+}
@@ -19,3 +19,22 @@ trait BlockchainClientTrait {
 
 #[derive(Debug, Clone)]
 pub struct BlockchainClient<I: Identity>(ManyClient<I>);
+
+#[cfg(all(test, feature = "native"))]
+mod tests {
+    use super::*;
+    use crate::client::testutils::round_trip;
+
+    #[tokio::test]
+    async fn info() {
+        let info = InfoReturns {
+            latest_block: BlockIdentifier::new(vec![1; 32], 10),
+            app_hash: None,
+            retained_height: Some(1),
+        };
+        round_trip("blockchain.info", (), info, |client| async move {
+            BlockchainClient::new(client).info().await
+        })
+        .await;
+    }
+}
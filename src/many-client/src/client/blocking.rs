@@ -1,15 +1,43 @@
+use coset::CoseSign1;
 use many_error::ManyError;
 use many_identity::{Address, Identity};
 use many_modules::base::Status;
 use many_protocol::{RequestMessage, ResponseMessage};
 use minicbor::Encode;
 use reqwest::IntoUrl;
+use std::sync::OnceLock;
 
-use crate::ManyClient as AsyncClient;
+use crate::client::failover::FailoverOptions;
+use crate::client::tls::TlsConfig;
+use crate::client::wait::WaitOptions;
+use crate::ManyClient as InnerClient;
 
-#[derive(Debug, Clone)]
+pub use crate::client::account::blocking::AccountClient;
+pub use crate::client::base::blocking::BaseClient;
+pub use crate::client::blockchain::blocking::BlockchainClient;
+pub use crate::client::compute::blocking::ComputeClient;
+pub use crate::client::data::blocking::DataClient;
+pub use crate::client::events::blocking::EventsClient;
+pub use crate::client::idstore::blocking::IdStoreClient;
+pub use crate::client::kvstore::blocking::KvStoreClient;
+pub use crate::client::ledger::blocking::LedgerClient;
+pub use crate::client::mintburn::blocking::MintBurnClient;
+pub use crate::client::multisig::blocking::MultisigClient;
+pub use crate::client::r#async::blocking::AsyncClient;
+pub use crate::client::tokens::blocking::TokensClient;
+pub use crate::client::web::blocking::WebClient;
+
+#[derive(Debug)]
 pub struct ManyClient<I: Identity> {
-    client: AsyncClient<I>,
+    client: InnerClient<I>,
+}
+
+impl<I: Identity> Clone for ManyClient<I> {
+    fn clone(&self) -> Self {
+        Self {
+            client: self.client.clone(),
+        }
+    }
 }
 
 pub fn block_on<F>(future: F) -> F::Output
@@ -19,22 +47,67 @@ where
     match tokio::runtime::Handle::try_current() {
         Ok(handle) => tokio::task::block_in_place(|| handle.block_on(future)),
         Err(_) => {
-            let runtime = tokio::runtime::Runtime::new().unwrap();
-            runtime.block_on(future)
+            // Share a runtime between calls, as persistent connections are bound to
+            // the runtime that opened them.
+            static RUNTIME: OnceLock<tokio::runtime::Runtime> = OnceLock::new();
+            RUNTIME
+                .get_or_init(|| tokio::runtime::Runtime::new().unwrap())
+                .block_on(future)
         }
     }
 }
 
 impl<I: Identity> ManyClient<I> {
     pub fn new<S: IntoUrl>(url: S, to: Address, identity: I) -> Result<Self, String> {
-        let client = AsyncClient::new(url, to, identity)?;
+        let client = InnerClient::new(url, to, identity)?;
+        Ok(Self { client })
+    }
+
+    pub fn new_failover<S: IntoUrl>(
+        urls: impl IntoIterator<Item = S>,
+        to: Address,
+        identity: I,
+    ) -> Result<Self, String> {
+        let client = InnerClient::new_failover(urls, to, identity)?;
         Ok(Self { client })
     }
 
+    pub fn with_failover_options(self, options: FailoverOptions) -> Self {
+        Self {
+            client: self.client.with_failover_options(options),
+        }
+    }
+
+    pub fn with_tls(self, tls: &TlsConfig) -> Result<Self, ManyError> {
+        Ok(Self {
+            client: self.client.with_tls(tls)?,
+        })
+    }
+
+    pub fn with_wait_options(self, options: WaitOptions) -> Self {
+        Self {
+            client: self.client.with_wait_options(options),
+        }
+    }
+
+    pub fn with_delegation(self, certificates: Vec<CoseSign1>) -> Result<Self, ManyError> {
+        Ok(Self {
+            client: self.client.with_delegation(certificates)?,
+        })
+    }
+
     pub fn send_message(&self, message: RequestMessage) -> Result<ResponseMessage, ManyError> {
         block_on(self.client.send_message(message))
     }
 
+    pub fn send_envelope(&self, envelope: CoseSign1) -> Result<CoseSign1, ManyError> {
+        block_on(self.client.send_envelope(envelope))
+    }
+
+    pub fn check_endpoints(&self) -> Vec<bool> {
+        block_on(self.client.check_endpoints())
+    }
+
     pub fn call_raw<M>(&self, method: M, argument: &[u8]) -> Result<ResponseMessage, ManyError>
     where
         M: Into<String>,
@@ -58,6 +131,18 @@ impl<I: Identity> ManyClient<I> {
         block_on(self.client.call_(method, argument))
     }
 
+    pub fn wait_response(&self, response: ResponseMessage) -> Result<ResponseMessage, ManyError> {
+        block_on(self.client.wait_response(response))
+    }
+
+    pub fn call_and_wait<M, A>(&self, method: M, argument: A) -> Result<ResponseMessage, ManyError>
+    where
+        M: Into<String>,
+        A: Encode<()>,
+    {
+        block_on(self.client.call_and_wait(method, argument))
+    }
+
     pub fn status(&self) -> Result<Status, ManyError> {
         block_on(self.client.status())
     }
@@ -0,0 +1,38 @@
+use many_client_macros::many_client;
+use many_error::ManyError;
+pub use many_identity::Identity;
+pub use many_modules::compute::{
+    CloseArgs, CloseReturns, DeployArgs, DeployReturns, InfoReturns, ListArgs, ListReturns,
+};
+
+use crate::ManyClient;
+
+#[many_client(ComputeClient, "compute")]
+trait ComputeClientTrait {
+    fn info(&self) -> Result<InfoReturns, ManyError>;
+    fn deploy(&self, args: DeployArgs) -> Result<DeployReturns, ManyError>;
+    fn close(&self, args: CloseArgs) -> Result<CloseReturns, ManyError>;
+    fn list(&self, args: ListArgs) -> Result<ListReturns, ManyError>;
+}
+
+#[derive(Debug, Clone)]
+pub struct ComputeClient<I: Identity>(ManyClient<I>);
+
+#[cfg(all(test, feature = "native"))]
+mod tests {
+    use super::*;
+    use crate::client::testutils::round_trip;
+    use many_modules::EmptyReturn;
+
+    #[tokio::test]
+    async fn close() {
+        let args = CloseArgs { dseq: 1 };
+        round_trip(
+            "compute.close",
+            args.clone(),
+            EmptyReturn,
+            |client| async move { ComputeClient::new(client).close(args).await },
+        )
+        .await;
+    }
+}
@@ -0,0 +1,35 @@
+use many_client_macros::many_client;
+use many_error::ManyError;
+pub use many_identity::Identity;
+pub use many_modules::data::{
+    DataGetInfoArgs, DataGetInfoReturns, DataInfoArgs, DataInfoReturns, DataQueryArgs,
+    DataQueryReturns,
+};
+
+use crate::ManyClient;
+
+#[many_client(DataClient, "data")]
+trait DataClientTrait {
+    fn info(&self, args: DataInfoArgs) -> Result<DataInfoReturns, ManyError>;
+    fn get_info(&self, args: DataGetInfoArgs) -> Result<DataGetInfoReturns, ManyError>;
+    fn query(&self, args: DataQueryArgs) -> Result<DataQueryReturns, ManyError>;
+}
+
+#[derive(Debug, Clone)]
+pub struct DataClient<I: Identity>(ManyClient<I>);
+
+#[cfg(all(test, feature = "native"))]
+mod tests {
+    use super::*;
+    use crate::client::testutils::round_trip;
+    use many_modules::EmptyArg;
+
+    #[tokio::test]
+    async fn info() {
+        let info = DataInfoReturns { indices: vec![] };
+        round_trip("data.info", EmptyArg, info, |client| async move {
+            DataClient::new(client).info(EmptyArg).await
+        })
+        .await;
+    }
+}
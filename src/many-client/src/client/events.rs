@@ -0,0 +1,34 @@
+use many_client_macros::many_client;
+use many_error::ManyError;
+pub use many_identity::Identity;
+pub use many_modules::events::{EventFilter, InfoArgs, InfoReturn, ListArgs, ListReturns};
+
+use crate::ManyClient;
+
+#[many_client(EventsClient, "events")]
+trait EventsClientTrait {
+    fn info(&self, args: InfoArgs) -> Result<InfoReturn, ManyError>;
+    fn list(&self, args: ListArgs) -> Result<ListReturns, ManyError>;
+}
+
+#[derive(Debug, Clone)]
+pub struct EventsClient<I: Identity>(ManyClient<I>);
+
+#[cfg(all(test, feature = "native"))]
+mod tests {
+    use super::*;
+    use crate::client::testutils::round_trip;
+    use many_modules::EmptyArg;
+
+    #[tokio::test]
+    async fn info() {
+        let info = InfoReturn {
+            total: 3,
+            event_types: vec![],
+        };
+        round_trip("events.info", EmptyArg, info, |client| async move {
+            EventsClient::new(client).info(EmptyArg).await
+        })
+        .await;
+    }
+}
@@ -0,0 +1,461 @@
+//! Sending requests to one of several equivalent endpoints (e.g. the nodes of a
+//! network), and retrying them on other endpoints after transport errors.
+//!
+//! Commands are retried by re-sending the same signed envelope, and only to
+//! endpoints that were not tried yet. Retrying a command is safe as long as the
+//! servers reject duplicated messages (e.g. with a request cache); a command
+//! that was executed by a previous attempt then fails with a `DuplicatedMessage`
+//! error.
+//!
+//! Queries (see [FailoverOptions::with_queries]) are signed again with a new
+//! nonce and timestamp for each retry, so they can also be retried on an
+//! endpoint that already failed, e.g. when there is a single endpoint.
+use crate::client::time::{sleep, Instant};
+#[cfg(feature = "native")]
+use crate::client::transport::Connection;
+use coset::CoseSign1;
+use many_error::{ManyError, ManyErrorCode};
+use reqwest::Url;
+#[cfg(feature = "native")]
+use rustls::ClientConfig;
+use std::collections::BTreeSet;
+use std::sync::atomic::{AtomicUsize, Ordering};
+#[cfg(feature = "native")]
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// How to pick the endpoint of a request.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Selection {
+    /// Use the same endpoint until it fails.
+    #[default]
+    Sticky,
+
+    /// Use the endpoints in turn.
+    RoundRobin,
+}
+
+/// How to select endpoints and retry failed requests.
+#[derive(Clone, Debug)]
+pub struct FailoverOptions {
+    selection: Selection,
+    retries: usize,
+    backoff: Duration,
+    max_backoff: Duration,
+    down_for: Duration,
+    queries: BTreeSet<String>,
+}
+
+impl Default for FailoverOptions {
+    fn default() -> Self {
+        Self {
+            selection: Selection::Sticky,
+            retries: 2,
+            backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(2),
+            down_for: Duration::from_secs(30),
+            queries: ["describe", "endpoints", "heartbeat", "status"]
+                .map(String::from)
+                .into(),
+        }
+    }
+}
+
+impl FailoverOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_selection(mut self, selection: Selection) -> Self {
+        self.selection = selection;
+        self
+    }
+
+    /// The number of times a request is retried after a transport error.
+    pub fn with_retries(mut self, retries: usize) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    /// The delay before the first retry. It doubles on each retry, up to
+    /// `max_backoff`.
+    pub fn with_backoff(mut self, backoff: Duration, max_backoff: Duration) -> Self {
+        self.backoff = backoff;
+        self.max_backoff = max_backoff;
+        self
+    }
+
+    /// How long an endpoint that failed is only used if all the others failed
+    /// too, unless a health check succeeds before.
+    pub fn with_down_for(mut self, down_for: Duration) -> Self {
+        self.down_for = down_for;
+        self
+    }
+
+    /// Also treat these methods as queries, which don't change the state of
+    /// the servers. The methods of the base module are always queries.
+    pub fn with_queries<S: ToString>(mut self, methods: impl IntoIterator<Item = S>) -> Self {
+        self.queries
+            .extend(methods.into_iter().map(|m| m.to_string()));
+        self
+    }
+}
+
+/// Whether a request that failed with this error can be sent again.
+fn is_retriable(error: &ManyError) -> bool {
+    error.code() == ManyErrorCode::UnexpectedTransportError
+}
+
+/// The TLS configuration of `wss://` connections. Browsers only send HTTP
+/// requests, with their own TLS configuration.
+#[cfg(feature = "native")]
+type Tls = Option<Arc<ClientConfig>>;
+#[cfg(not(feature = "native"))]
+type Tls = Option<()>;
+
+struct Endpoint {
+    url: Url,
+    #[cfg(feature = "native")]
+    connection: Option<Connection>,
+    down_until: Mutex<Option<Instant>>,
+}
+
+impl Endpoint {
+    fn new(url: Url, tls: Tls) -> Self {
+        #[cfg(feature = "native")]
+        let connection = {
+            let connection = Connection::new(&url);
+            match tls {
+                Some(tls) => connection.map(|c| c.with_tls(tls)),
+                None => connection,
+            }
+        };
+        #[cfg(not(feature = "native"))]
+        let _ = tls;
+
+        Self {
+            url,
+            #[cfg(feature = "native")]
+            connection,
+            down_until: Mutex::new(None),
+        }
+    }
+
+    fn is_up(&self, now: Instant) -> bool {
+        self.down_until
+            .lock()
+            .unwrap()
+            .map_or(true, |until| until <= now)
+    }
+}
+
+/// The endpoints of a client, with their health.
+pub(crate) struct Endpoints {
+    endpoints: Vec<Endpoint>,
+    current: AtomicUsize,
+    options: FailoverOptions,
+    tls: Tls,
+}
+
+impl Endpoints {
+    pub fn new(urls: Vec<Url>, options: FailoverOptions) -> Result<Self, String> {
+        if urls.is_empty() {
+            return Err("At least one endpoint is required.".to_string());
+        }
+        Ok(Self::with_tls_config(urls, options, None))
+    }
+
+    fn with_tls_config(urls: Vec<Url>, options: FailoverOptions, tls: Tls) -> Self {
+        Self {
+            endpoints: urls
+                .into_iter()
+                .map(|url| Endpoint::new(url, tls.clone()))
+                .collect(),
+            current: AtomicUsize::new(0),
+            options,
+            tls,
+        }
+    }
+
+    /// New endpoints (without health information) using these options.
+    pub fn with_options(&self, options: FailoverOptions) -> Self {
+        Self::with_tls_config(self.urls(), options, self.tls.clone())
+    }
+
+    /// New endpoints (without health information) using a TLS configuration
+    /// for `wss://` URLs.
+    #[cfg(feature = "native")]
+    pub fn with_tls(&self, tls: Arc<ClientConfig>) -> Self {
+        Self::with_tls_config(self.urls(), self.options.clone(), Some(tls))
+    }
+
+    pub fn urls(&self) -> Vec<Url> {
+        self.endpoints.iter().map(|e| e.url.clone()).collect()
+    }
+
+    /// Whether requests to the endpoint at `index` use a persistent connection.
+    #[cfg(all(test, feature = "native"))]
+    pub fn is_persistent(&self, index: usize) -> bool {
+        self.endpoints[index].connection.is_some()
+    }
+
+    /// The order in which to try the endpoints for a request; endpoints that
+    /// are down come last.
+    fn order(&self) -> Vec<usize> {
+        let len = self.endpoints.len();
+        let start = match self.options.selection {
+            Selection::Sticky => self.current.load(Ordering::Relaxed),
+            Selection::RoundRobin => self.current.fetch_add(1, Ordering::Relaxed),
+        };
+        let now = Instant::now();
+        let (mut up, down): (Vec<usize>, Vec<usize>) = (0..len)
+            .map(|i| (start + i) % len)
+            .partition(|i| self.endpoints[*i].is_up(now));
+        up.extend(down);
+        up
+    }
+
+    pub fn is_query(&self, method: &str) -> bool {
+        self.options.queries.contains(method)
+    }
+
+    pub fn set_health(&self, index: usize, up: bool) {
+        let endpoint = &self.endpoints[index];
+        *endpoint.down_until.lock().unwrap() = if up {
+            None
+        } else {
+            Some(Instant::now() + self.options.down_for)
+        };
+    }
+
+    /// Send an envelope to a specific endpoint, without retrying.
+    pub async fn send_to(
+        &self,
+        index: usize,
+        http: &reqwest::Client,
+        envelope: CoseSign1,
+    ) -> Result<CoseSign1, ManyError> {
+        let endpoint = &self.endpoints[index];
+        #[cfg(feature = "native")]
+        if let Some(connection) = &endpoint.connection {
+            return connection.send_envelope(envelope).await;
+        }
+        super::send_envelope_with_client(http, endpoint.url.clone(), envelope).await
+    }
+
+    /// Send an envelope to the first endpoint that answers, retrying after
+    /// transport errors. The envelope is sent at most once to each endpoint.
+    pub async fn send(
+        &self,
+        http: &reqwest::Client,
+        envelope: CoseSign1,
+    ) -> Result<CoseSign1, ManyError> {
+        self.send_attempts(http, envelope, None::<fn() -> Result<CoseSign1, ManyError>>)
+            .await
+    }
+
+    /// Send a query to the first endpoint that answers, retrying after
+    /// transport errors with a new envelope from `sign`.
+    pub async fn send_query(
+        &self,
+        http: &reqwest::Client,
+        envelope: CoseSign1,
+        sign: impl FnMut() -> Result<CoseSign1, ManyError>,
+    ) -> Result<CoseSign1, ManyError> {
+        self.send_attempts(http, envelope, Some(sign)).await
+    }
+
+    async fn send_attempts(
+        &self,
+        http: &reqwest::Client,
+        mut envelope: CoseSign1,
+        mut sign: Option<impl FnMut() -> Result<CoseSign1, ManyError>>,
+    ) -> Result<CoseSign1, ManyError> {
+        let order = self.order();
+        let mut backoff = self.options.backoff;
+        // Only envelopes signed again can be sent twice to the same endpoint.
+        let count = match sign {
+            Some(_) => self.options.retries + 1,
+            None => (self.options.retries + 1).min(order.len()),
+        };
+        let mut attempts = order.into_iter().cycle().take(count);
+
+        // There is always a first attempt.
+        let mut index = attempts.next().unwrap_or_default();
+        loop {
+            let error = match self.send_to(index, http, envelope.clone()).await {
+                Ok(response) => {
+                    self.set_health(index, true);
+                    if self.options.selection == Selection::Sticky {
+                        self.current.store(index, Ordering::Relaxed);
+                    }
+                    return Ok(response);
+                }
+                Err(error) if is_retriable(&error) => error,
+                Err(error) => return Err(error),
+            };
+
+            if self.endpoints.len() > 1 {
+                tracing::warn!("Endpoint {} failed: {error}", self.endpoints[index].url);
+                self.set_health(index, false);
+            }
+            index = match attempts.next() {
+                Some(next) => next,
+                None => return Err(error),
+            };
+            if let Some(sign) = &mut sign {
+                envelope = sign()?;
+            }
+            sleep(backoff).await;
+            backoff = (backoff * 2).min(self.options.max_backoff);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ManyClient;
+    use many_identity::{AcceptAllVerifier, Address, AnonymousIdentity};
+    use many_server::transport::http::HttpServer;
+    use many_server::transport::tcp::TcpServer;
+    use many_server::ManyServer;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
+
+    fn free_port() -> u16 {
+        std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port()
+    }
+
+    /// Start servers named after their index, and return their URLs.
+    async fn servers(count: usize) -> Vec<String> {
+        let mut urls = vec![];
+        for i in 0..count {
+            let port = free_port();
+            let many =
+                ManyServer::simple(i.to_string(), AnonymousIdentity, AcceptAllVerifier, None);
+            let server = HttpServer::new(many);
+            tokio::spawn(async move { server.bind(format!("127.0.0.1:{port}")).await });
+            urls.push(format!("http://127.0.0.1:{port}"));
+        }
+
+        // Wait for the servers to start.
+        for url in &urls {
+            let client = ManyClient::new(url, Address::anonymous(), AnonymousIdentity).unwrap();
+            for _ in 0..20 {
+                if client.status().await.is_ok() {
+                    break;
+                }
+                tokio::time::sleep(Duration::from_millis(50)).await;
+            }
+        }
+        urls
+    }
+
+    fn client(urls: Vec<String>, options: FailoverOptions) -> ManyClient<AnonymousIdentity> {
+        ManyClient::new_failover(urls, Address::anonymous(), AnonymousIdentity)
+            .unwrap()
+            .with_failover_options(options.with_backoff(Duration::ZERO, Duration::ZERO))
+    }
+
+    async fn name(client: &ManyClient<AnonymousIdentity>) -> String {
+        client.status().await.unwrap().name
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn failover() {
+        let dead = format!("http://127.0.0.1:{}", free_port());
+        let urls = servers(1).await;
+        let client = client(vec![dead.clone(), urls[0].clone()], FailoverOptions::new());
+
+        assert_eq!(name(&client).await, "0");
+        assert_eq!(client.check_endpoints().await, vec![false, true]);
+        // Requests stick to the endpoint that answered.
+        assert_eq!(name(&client).await, "0");
+
+        // Without retries, errors are returned.
+        let client = self::client(vec![dead], FailoverOptions::new().with_retries(0));
+        assert_eq!(
+            client.status().await.unwrap_err().code(),
+            ManyErrorCode::UnexpectedTransportError
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn selection() {
+        let urls = servers(2).await;
+
+        let sticky = client(urls.clone(), FailoverOptions::new());
+        assert_eq!(name(&sticky).await, "0");
+        assert_eq!(name(&sticky).await, "0");
+
+        let round_robin = client(
+            urls,
+            FailoverOptions::new().with_selection(Selection::RoundRobin),
+        );
+        assert_eq!(name(&round_robin).await, "0");
+        assert_eq!(name(&round_robin).await, "1");
+        assert_eq!(name(&round_robin).await, "0");
+    }
+
+    async fn read_frame(stream: &mut TcpStream) -> Vec<u8> {
+        let mut len = [0u8; 4];
+        stream.read_exact(&mut len).await.unwrap();
+        let mut bytes = vec![0u8; u32::from_be_bytes(len) as usize];
+        stream.read_exact(&mut bytes).await.unwrap();
+        bytes
+    }
+
+    async fn write_frame(stream: &mut TcpStream, bytes: &[u8]) {
+        let len = bytes.len() as u32;
+        stream.write_all(&len.to_be_bytes()).await.unwrap();
+        stream.write_all(bytes).await.unwrap();
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn single_endpoint_retries() {
+        let backend = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let backend_addr = backend.local_addr().unwrap();
+        let many = ManyServer::simple("0", AnonymousIdentity, AcceptAllVerifier, None);
+        let server = TcpServer::new(many);
+        tokio::spawn(async move { server.serve(backend).await });
+
+        // The client connects to a proxy that can drop connections.
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("tcp://{}", listener.local_addr().unwrap());
+        let proxy = tokio::spawn(async move {
+            // The first attempt of a query fails.
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let first = read_frame(&mut stream).await;
+            drop(stream);
+
+            // It is retried on the same endpoint, with a new envelope.
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let retry = read_frame(&mut stream).await;
+            assert_ne!(first, retry);
+            let mut backend = TcpStream::connect(backend_addr).await.unwrap();
+            write_frame(&mut backend, &retry).await;
+            write_frame(&mut stream, &read_frame(&mut backend).await).await;
+
+            // A command is not sent twice to the same endpoint.
+            read_frame(&mut stream).await;
+            drop(stream);
+            tokio::time::timeout(Duration::from_millis(200), listener.accept())
+                .await
+                .is_err()
+        });
+
+        let client = client(vec![url], FailoverOptions::new().with_retries(2));
+        assert_eq!(name(&client).await, "0");
+        assert_eq!(
+            client.call_("ledger.send", ()).await.unwrap_err().code(),
+            ManyErrorCode::UnexpectedTransportError
+        );
+        assert!(proxy.await.unwrap());
+    }
+}
@@ -0,0 +1,45 @@
+use many_client_macros::many_client;
+use many_error::ManyError;
+pub use many_identity::Identity;
+pub use many_modules::idstore::{
+    GetFromAddressArgs, GetFromRecallPhraseArgs, GetReturns, StoreArgs, StoreReturns,
+};
+
+use crate::ManyClient;
+
+#[many_client(IdStoreClient, "idstore")]
+trait IdStoreClientTrait {
+    fn store(&self, args: StoreArgs) -> Result<StoreReturns, ManyError>;
+    fn get_from_recall_phrase(
+        &self,
+        args: GetFromRecallPhraseArgs,
+    ) -> Result<GetReturns, ManyError>;
+    fn get_from_address(&self, args: GetFromAddressArgs) -> Result<GetReturns, ManyError>;
+}
+
+#[derive(Debug, Clone)]
+pub struct IdStoreClient<I: Identity>(ManyClient<I>);
+
+#[cfg(all(test, feature = "native"))]
+mod tests {
+    use super::*;
+    use crate::client::testutils::round_trip;
+    use many_identity::Address;
+    use many_modules::idstore::{CredentialId, PublicKey};
+
+    #[tokio::test]
+    async fn get_from_address() {
+        let args = GetFromAddressArgs(Address::anonymous());
+        let returns = GetReturns {
+            cred_id: CredentialId(vec![1; 16].into()),
+            public_key: PublicKey(vec![2; 32].into()),
+        };
+        round_trip(
+            "idstore.getFromAddress",
+            args.clone(),
+            returns,
+            |client| async move { IdStoreClient::new(client).get_from_address(args).await },
+        )
+        .await;
+    }
+}
@@ -0,0 +1,47 @@
+use many_client_macros::many_client;
+use many_error::ManyError;
+pub use many_identity::Identity;
+pub use many_modules::kvstore::list::{ListArgs, ListReturns};
+pub use many_modules::kvstore::{
+    DisableArgs, DisableReturn, GetArgs, GetReturns, InfoReturns, PutArgs, PutReturn, QueryArgs,
+    QueryReturns, TransferArgs, TransferReturn,
+};
+
+use crate::ManyClient;
+
+#[many_client(KvStoreClient, "kvstore")]
+trait KvStoreClientTrait {
+    fn info(&self) -> Result<InfoReturns, ManyError>;
+    fn get(&self, args: GetArgs) -> Result<GetReturns, ManyError>;
+    fn query(&self, args: QueryArgs) -> Result<QueryReturns, ManyError>;
+    fn list(&self, args: ListArgs) -> Result<ListReturns, ManyError>;
+    fn put(&self, args: PutArgs) -> Result<PutReturn, ManyError>;
+    fn disable(&self, args: DisableArgs) -> Result<DisableReturn, ManyError>;
+    fn transfer(&self, args: TransferArgs) -> Result<TransferReturn, ManyError>;
+}
+
+#[derive(Debug, Clone)]
+pub struct KvStoreClient<I: Identity>(ManyClient<I>);
+
+#[cfg(all(test, feature = "native"))]
+mod tests {
+    use super::*;
+    use crate::client::testutils::round_trip;
+    use many_modules::EmptyReturn;
+
+    #[tokio::test]
+    async fn put() {
+        let args = PutArgs {
+            key: b"key".to_vec().into(),
+            value: b"value".to_vec().into(),
+            alternative_owner: None,
+        };
+        round_trip(
+            "kvstore.put",
+            args.clone(),
+            EmptyReturn,
+            |client| async move { KvStoreClient::new(client).put(args).await },
+        )
+        .await;
+    }
+}
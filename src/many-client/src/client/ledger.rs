@@ -15,3 +15,29 @@ trait LedgerClientTrait {
 
 #[derive(Debug, Clone)]
 pub struct LedgerClient<I: Identity>(ManyClient<I>);
+
+#[cfg(all(test, feature = "native"))]
+mod tests {
+    use super::*;
+    use crate::client::testutils::round_trip;
+    use many_identity::Address;
+
+    #[tokio::test]
+    async fn balance() {
+        let args = BalanceArgs {
+            account: Some(Address::anonymous()),
+            symbols: None,
+            height: None,
+        };
+        let balances = BalanceReturns {
+            balances: [(Address::anonymous(), TokenAmount::from(100u64))].into(),
+        };
+        round_trip(
+            "ledger.balance",
+            args.clone(),
+            balances,
+            |client| async move { LedgerClient::new(client).balance(args).await },
+        )
+        .await;
+    }
+}
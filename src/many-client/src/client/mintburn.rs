@@ -0,0 +1,45 @@
+use many_client_macros::many_client;
+use many_error::ManyError;
+pub use many_identity::Identity;
+pub use many_modules::ledger::{
+    TokenBurnArgs, TokenBurnReturns, TokenMintArgs, TokenMintReturns, TokenSetMintPolicyArgs,
+    TokenSetMintPolicyReturns,
+};
+
+use crate::ManyClient;
+
+#[many_client(MintBurnClient, "tokens")]
+trait MintBurnClientTrait {
+    fn mint(&self, args: TokenMintArgs) -> Result<TokenMintReturns, ManyError>;
+    fn burn(&self, args: TokenBurnArgs) -> Result<TokenBurnReturns, ManyError>;
+    fn set_mint_policy(
+        &self,
+        args: TokenSetMintPolicyArgs,
+    ) -> Result<TokenSetMintPolicyReturns, ManyError>;
+}
+
+#[derive(Debug, Clone)]
+pub struct MintBurnClient<I: Identity>(ManyClient<I>);
+
+#[cfg(all(test, feature = "native"))]
+mod tests {
+    use super::*;
+    use crate::client::testutils::round_trip;
+    use many_identity::Address;
+    use many_modules::EmptyReturn;
+
+    #[tokio::test]
+    async fn set_mint_policy() {
+        let args = TokenSetMintPolicyArgs {
+            symbol: Address::anonymous(),
+            policy: None,
+        };
+        round_trip(
+            "tokens.setMintPolicy",
+            args.clone(),
+            EmptyReturn,
+            |client| async move { MintBurnClient::new(client).set_mint_policy(args).await },
+        )
+        .await;
+    }
+}
@@ -0,0 +1,49 @@
+use many_client_macros::many_client;
+use many_error::ManyError;
+pub use many_identity::Identity;
+pub use many_modules::account::features::multisig::{
+    ApproveArgs, ApproveReturn, ExecuteArgs, InfoArgs, InfoReturn, RevokeArgs, RevokeReturn,
+    SetDefaultsArgs, SetDefaultsReturn, SubmitTransactionArgs, SubmitTransactionReturn,
+    WithdrawArgs, WithdrawReturn,
+};
+pub use many_protocol::ResponseMessage;
+
+use crate::ManyClient;
+
+#[many_client(MultisigClient, "account")]
+trait MultisigClientTrait {
+    fn multisig_submit_transaction(
+        &self,
+        args: SubmitTransactionArgs,
+    ) -> Result<SubmitTransactionReturn, ManyError>;
+    fn multisig_info(&self, args: InfoArgs) -> Result<InfoReturn, ManyError>;
+    fn multisig_set_defaults(&self, args: SetDefaultsArgs) -> Result<SetDefaultsReturn, ManyError>;
+    fn multisig_approve(&self, args: ApproveArgs) -> Result<ApproveReturn, ManyError>;
+    fn multisig_revoke(&self, args: RevokeArgs) -> Result<RevokeReturn, ManyError>;
+    fn multisig_execute(&self, args: ExecuteArgs) -> Result<ResponseMessage, ManyError>;
+    fn multisig_withdraw(&self, args: WithdrawArgs) -> Result<WithdrawReturn, ManyError>;
+}
+
+#[derive(Debug, Clone)]
+pub struct MultisigClient<I: Identity>(ManyClient<I>);
+
+#[cfg(all(test, feature = "native"))]
+mod tests {
+    use super::*;
+    use crate::client::testutils::round_trip;
+    use many_modules::EmptyReturn;
+
+    #[tokio::test]
+    async fn approve() {
+        let args = ApproveArgs {
+            token: vec![1, 2, 3].into(),
+        };
+        round_trip(
+            "account.multisigApprove",
+            args.clone(),
+            EmptyReturn,
+            |client| async move { MultisigClient::new(client).multisig_approve(args).await },
+        )
+        .await;
+    }
+}
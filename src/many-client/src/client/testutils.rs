@@ -0,0 +1,91 @@
+//! A server answering a single endpoint with a fixed response, to test the
+//! generated clients.
+use crate::ManyClient;
+use async_trait::async_trait;
+use many_error::ManyError;
+use many_identity::{AcceptAllVerifier, Address, AnonymousIdentity};
+use many_modules::{ManyModule, ManyModuleInfo};
+use many_protocol::{RequestMessage, ResponseMessage};
+use many_server::transport::tcp::TcpServer;
+use many_server::ManyServer;
+use minicbor::Encode;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use tokio::net::TcpListener;
+
+/// Answers its endpoint with a fixed response, if it is called with the
+/// expected argument.
+#[derive(Debug)]
+struct FixedModule {
+    info: ManyModuleInfo,
+    argument: Vec<u8>,
+    response: Vec<u8>,
+}
+
+#[async_trait]
+impl ManyModule for FixedModule {
+    fn info(&self) -> &ManyModuleInfo {
+        &self.info
+    }
+
+    async fn execute(&self, message: RequestMessage) -> Result<ResponseMessage, ManyError> {
+        let data = if message.data == self.argument {
+            Ok(self.response.clone())
+        } else {
+            Err(ManyError::unknown("Unexpected argument."))
+        };
+        Ok(ResponseMessage::from_request(
+            &message,
+            &Address::anonymous(),
+            data,
+        ))
+    }
+}
+
+/// Serve a server over TCP, and return a client connected to it.
+pub async fn serve(many: Arc<Mutex<ManyServer>>) -> ManyClient<AnonymousIdentity> {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    // The server stops with the runtime of the test.
+    let server = TcpServer::new(many);
+    tokio::spawn(async move { server.serve(listener).await });
+
+    ManyClient::new(
+        format!("tcp://{addr}"),
+        Address::anonymous(),
+        AnonymousIdentity,
+    )
+    .unwrap()
+}
+
+/// Call `endpoint` through a client, and check that the server received
+/// `argument` and that the client decoded `response`.
+pub async fn round_trip<F, Fut, R>(
+    endpoint: &str,
+    argument: impl Encode<()>,
+    response: impl Encode<()>,
+    call: F,
+) where
+    F: FnOnce(ManyClient<AnonymousIdentity>) -> Fut,
+    Fut: Future<Output = Result<R, ManyError>>,
+    R: Encode<()>,
+{
+    let argument = minicbor::to_vec(argument).unwrap();
+    let response = minicbor::to_vec(response).unwrap();
+
+    let many = ManyServer::simple("client", AnonymousIdentity, AcceptAllVerifier, None);
+    many.lock().unwrap().add_module(FixedModule {
+        info: ManyModuleInfo {
+            name: "FixedModule".to_string(),
+            attribute: None,
+            endpoints: vec![endpoint.to_string()],
+            queries: Default::default(),
+            descriptions: Default::default(),
+        },
+        argument,
+        response: response.clone(),
+    });
+
+    let result = call(serve(many).await).await.unwrap();
+    assert_eq!(minicbor::to_vec(result).unwrap(), response);
+}
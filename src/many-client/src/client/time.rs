@@ -0,0 +1,28 @@
+//! Clocks and timers, from tokio or from the browser in WebAssembly.
+#[cfg(feature = "native")]
+pub(crate) use tokio::time::{sleep, Instant};
+
+#[cfg(all(feature = "wasm", not(feature = "native")))]
+pub(crate) use instant::Instant;
+
+#[cfg(all(feature = "wasm", not(feature = "native")))]
+mod js {
+    use wasm_bindgen::prelude::*;
+
+    #[wasm_bindgen]
+    extern "C" {
+        // The global function, so that it works in windows and in workers.
+        #[wasm_bindgen(js_name = setTimeout)]
+        pub fn set_timeout(handler: &js_sys::Function, timeout: i32) -> JsValue;
+    }
+}
+
+#[cfg(all(feature = "wasm", not(feature = "native")))]
+pub(crate) async fn sleep(duration: std::time::Duration) {
+    let timeout = i32::try_from(duration.as_millis()).unwrap_or(i32::MAX);
+    let promise = js_sys::Promise::new(&mut |resolve, _| {
+        js::set_timeout(&resolve, timeout);
+    });
+    // The promise never rejects.
+    let _ = wasm_bindgen_futures::JsFuture::from(promise).await;
+}
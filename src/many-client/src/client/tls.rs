@@ -0,0 +1,225 @@
+//! TLS options for HTTPS (`https://` URLs) and secure WebSocket (`wss://` URLs)
+//! connections.
+use many_error::ManyError;
+use many_types::tls::{certificates_from_pem, private_key_from_pem};
+use rustls::client::{ServerCertVerified, ServerCertVerifier, WebPkiVerifier};
+use rustls::{Certificate, ClientConfig, OwnedTrustAnchor, PrivateKey, RootCertStore, ServerName};
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+use std::time::SystemTime;
+
+/// The SHA-256 digest of a certificate, as used for pinning.
+pub fn certificate_digest(certificate: &Certificate) -> [u8; 32] {
+    Sha256::digest(&certificate.0).into()
+}
+
+/// Accepts only the pinned certificates, without verifying their chain.
+struct PinnedCertificates(Vec<[u8; 32]>);
+
+impl ServerCertVerifier for PinnedCertificates {
+    fn verify_server_cert(
+        &self,
+        end_entity: &Certificate,
+        _intermediates: &[Certificate],
+        _server_name: &ServerName,
+        _scts: &mut dyn Iterator<Item = &[u8]>,
+        _ocsp_response: &[u8],
+        _now: SystemTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        if self.0.contains(&certificate_digest(end_entity)) {
+            Ok(ServerCertVerified::assertion())
+        } else {
+            Err(rustls::Error::InvalidCertificate(
+                rustls::CertificateError::ApplicationVerificationFailure,
+            ))
+        }
+    }
+}
+
+/// Trust configuration of a client. By default, servers are verified against the
+/// web PKI roots.
+#[derive(Clone, Default)]
+pub struct TlsConfig {
+    ca_certificates: Vec<Certificate>,
+    pinned_certificates: Vec<[u8; 32]>,
+    identity: Option<(Vec<Certificate>, PrivateKey)>,
+}
+
+impl TlsConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Also trust the certificates of a PEM bundle as roots.
+    pub fn add_ca_certificates(mut self, pem: &[u8]) -> Result<Self, ManyError> {
+        self.ca_certificates.extend(certificates_from_pem(pem)?);
+        Ok(self)
+    }
+
+    /// Pin a certificate by the SHA-256 digest of its DER encoding. Once a
+    /// certificate is pinned, servers must present one of the pinned
+    /// certificates and the roots are not used, so pinned certificates can be
+    /// self-signed.
+    pub fn add_pinned_certificate(mut self, sha256: [u8; 32]) -> Self {
+        self.pinned_certificates.push(sha256);
+        self
+    }
+
+    /// Present a client certificate, for servers that use mutual TLS.
+    pub fn with_client_identity(
+        mut self,
+        certificate: &[u8],
+        key: &[u8],
+    ) -> Result<Self, ManyError> {
+        self.identity = Some((
+            certificates_from_pem(certificate)?,
+            private_key_from_pem(key)?,
+        ));
+        Ok(self)
+    }
+
+    pub fn client_config(&self) -> Result<Arc<ClientConfig>, ManyError> {
+        let builder = ClientConfig::builder().with_safe_defaults();
+        let builder = if self.pinned_certificates.is_empty() {
+            let mut roots = RootCertStore::empty();
+            roots.add_trust_anchors(webpki_roots::TLS_SERVER_ROOTS.iter().map(|ta| {
+                OwnedTrustAnchor::from_subject_spki_name_constraints(
+                    ta.subject,
+                    ta.spki,
+                    ta.name_constraints,
+                )
+            }));
+            for certificate in &self.ca_certificates {
+                roots.add(certificate).map_err(ManyError::unknown)?;
+            }
+            // Same as `with_root_certificates`, but the builder state must
+            // match the one of the pinned certificates branch.
+            builder.with_custom_certificate_verifier(Arc::new(WebPkiVerifier::new(roots, None)))
+        } else {
+            builder.with_custom_certificate_verifier(Arc::new(PinnedCertificates(
+                self.pinned_certificates.clone(),
+            )))
+        };
+
+        let mut config = match &self.identity {
+            Some((certificates, key)) => builder
+                .with_client_auth_cert(certificates.clone(), key.clone())
+                .map_err(ManyError::unknown)?,
+            None => builder.with_no_client_auth(),
+        };
+        config.alpn_protocols = vec![b"http/1.1".to_vec()];
+        Ok(Arc::new(config))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ManyClient;
+    use many_identity::{AcceptAllVerifier, Address, AnonymousIdentity};
+    use many_server::transport::http::HttpServer;
+    use many_server::transport::tls::server_config;
+    use many_server::ManyServer;
+    use rcgen::{BasicConstraints, Certificate as RcgenCertificate, CertificateParams, IsCa};
+    use std::sync::atomic::Ordering;
+    use std::time::Duration;
+
+    /// A CA, with a server and a client certificates it signed.
+    struct Pki {
+        ca: String,
+        server: String,
+        server_key: String,
+        client: String,
+        client_key: String,
+    }
+
+    impl Pki {
+        fn new() -> Self {
+            let mut params = CertificateParams::new(vec![]);
+            params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+            let ca = RcgenCertificate::from_params(params).unwrap();
+            let server = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+            let client = rcgen::generate_simple_self_signed(vec!["client".to_string()]).unwrap();
+
+            Self {
+                ca: ca.serialize_pem().unwrap(),
+                server: server.serialize_pem_with_signer(&ca).unwrap(),
+                server_key: server.serialize_private_key_pem(),
+                client: client.serialize_pem_with_signer(&ca).unwrap(),
+                client_key: client.serialize_private_key_pem(),
+            }
+        }
+
+        fn server_digest(&self) -> [u8; 32] {
+            certificate_digest(&certificates_from_pem(self.server.as_bytes()).unwrap()[0])
+        }
+    }
+
+    async fn status(port: u16, tls: TlsConfig) -> Result<String, ManyError> {
+        let client = ManyClient::new(
+            format!("https://localhost:{port}"),
+            Address::anonymous(),
+            AnonymousIdentity,
+        )
+        .unwrap()
+        .with_tls(&tls)?;
+
+        // Retry while the server starts.
+        let mut result = client.status().await;
+        for _ in 0..20 {
+            if result.is_ok() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            result = client.status().await;
+        }
+        result.map(|s| s.name)
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn mutual_tls() {
+        let pki = Pki::new();
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+
+        let many = ManyServer::simple("tls", AnonymousIdentity, AcceptAllVerifier, None);
+        let mut server = HttpServer::new(many);
+        server.set_tls(
+            server_config(
+                pki.server.as_bytes(),
+                pki.server_key.as_bytes(),
+                Some(pki.ca.as_bytes()),
+            )
+            .unwrap(),
+        );
+        let term_signal = server.term_signal();
+        tokio::spawn(async move { server.bind(format!("127.0.0.1:{port}")).await });
+
+        let with_identity = |tls: TlsConfig| {
+            tls.with_client_identity(pki.client.as_bytes(), pki.client_key.as_bytes())
+                .unwrap()
+        };
+        let trusted = TlsConfig::new()
+            .add_ca_certificates(pki.ca.as_bytes())
+            .unwrap();
+
+        assert_eq!(
+            status(port, with_identity(trusted.clone())).await.unwrap(),
+            "tls"
+        );
+        // The server requires a client certificate.
+        assert!(status(port, trusted).await.is_err());
+        // The server is not trusted.
+        assert!(status(port, with_identity(TlsConfig::new())).await.is_err());
+
+        let pinned = TlsConfig::new().add_pinned_certificate(pki.server_digest());
+        assert_eq!(status(port, with_identity(pinned)).await.unwrap(), "tls");
+        let pinned = TlsConfig::new().add_pinned_certificate([0; 32]);
+        assert!(status(port, with_identity(pinned)).await.is_err());
+
+        term_signal.store(true, Ordering::Relaxed);
+    }
+}
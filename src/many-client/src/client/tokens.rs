@@ -0,0 +1,57 @@
+use many_client_macros::many_client;
+use many_error::ManyError;
+pub use many_identity::Identity;
+pub use many_modules::ledger::{
+    TokenAddExtendedInfoArgs, TokenAddExtendedInfoReturns, TokenCreateArgs, TokenCreateReturns,
+    TokenFreezeArgs, TokenFreezeReturns, TokenInfoArgs, TokenInfoReturns, TokenPauseArgs,
+    TokenPauseReturns, TokenRemoveExtendedInfoArgs, TokenRemoveExtendedInfoReturns,
+    TokenUnfreezeArgs, TokenUnfreezeReturns, TokenUnpauseArgs, TokenUnpauseReturns,
+    TokenUpdateArgs, TokenUpdateReturns,
+};
+
+use crate::ManyClient;
+
+#[many_client(TokensClient, "tokens")]
+trait TokensClientTrait {
+    fn create(&self, args: TokenCreateArgs) -> Result<TokenCreateReturns, ManyError>;
+    fn info(&self, args: TokenInfoArgs) -> Result<TokenInfoReturns, ManyError>;
+    fn update(&self, args: TokenUpdateArgs) -> Result<TokenUpdateReturns, ManyError>;
+    fn add_extended_info(
+        &self,
+        args: TokenAddExtendedInfoArgs,
+    ) -> Result<TokenAddExtendedInfoReturns, ManyError>;
+    fn remove_extended_info(
+        &self,
+        args: TokenRemoveExtendedInfoArgs,
+    ) -> Result<TokenRemoveExtendedInfoReturns, ManyError>;
+    fn freeze(&self, args: TokenFreezeArgs) -> Result<TokenFreezeReturns, ManyError>;
+    fn unfreeze(&self, args: TokenUnfreezeArgs) -> Result<TokenUnfreezeReturns, ManyError>;
+    fn pause(&self, args: TokenPauseArgs) -> Result<TokenPauseReturns, ManyError>;
+    fn unpause(&self, args: TokenUnpauseArgs) -> Result<TokenUnpauseReturns, ManyError>;
+}
+
+#[derive(Debug, Clone)]
+pub struct TokensClient<I: Identity>(ManyClient<I>);
+
+#[cfg(all(test, feature = "native"))]
+mod tests {
+    use super::*;
+    use crate::client::testutils::round_trip;
+    use many_identity::Address;
+    use many_modules::EmptyReturn;
+
+    #[tokio::test]
+    async fn pause() {
+        let args = TokenPauseArgs {
+            symbol: Address::anonymous(),
+            memo: None,
+        };
+        round_trip(
+            "tokens.pause",
+            args.clone(),
+            EmptyReturn,
+            |client| async move { TokensClient::new(client).pause(args).await },
+        )
+        .await;
+    }
+}
@@ -0,0 +1,206 @@
+//! Persistent connections to a MANY server, over WebSocket (`ws://` URLs) or
+//! raw TCP (`tcp://` URLs). Other URLs use a new HTTP request per call.
+use coset::{CoseSign1, TaggedCborSerializable};
+use futures_util::{SinkExt, StreamExt};
+use many_error::ManyError;
+use reqwest::Url;
+use rustls::ClientConfig;
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::sync::Mutex;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{Connector, MaybeTlsStream, WebSocketStream};
+
+/// Maximum of 5MB per message, as for HTTP requests.
+const MAX_MESSAGE_LEN: usize = 1024 * 1024 * 5;
+
+enum Stream {
+    WebSocket(Box<WebSocketStream<MaybeTlsStream<TcpStream>>>),
+    Tcp(TcpStream),
+}
+
+impl Stream {
+    async fn connect(url: &Url, tls: Option<Arc<ClientConfig>>) -> Result<Self, ManyError> {
+        match url.scheme() {
+            "ws" | "wss" => {
+                let (ws, _) = tokio_tungstenite::connect_async_tls_with_config(
+                    url.as_str(),
+                    None,
+                    true,
+                    tls.map(Connector::Rustls),
+                )
+                .await
+                .map_err(ManyError::unexpected_transport_error)?;
+                Ok(Self::WebSocket(Box::new(ws)))
+            }
+            "tcp" => {
+                let addrs = url
+                    .socket_addrs(|| None)
+                    .map_err(ManyError::unexpected_transport_error)?;
+                let stream = TcpStream::connect(addrs.as_slice())
+                    .await
+                    .map_err(ManyError::unexpected_transport_error)?;
+                stream
+                    .set_nodelay(true)
+                    .map_err(ManyError::unexpected_transport_error)?;
+                Ok(Self::Tcp(stream))
+            }
+            scheme => Err(ManyError::unexpected_transport_error(format!(
+                "Unsupported scheme: {scheme}"
+            ))),
+        }
+    }
+
+    async fn roundtrip(&mut self, bytes: Vec<u8>) -> Result<Vec<u8>, ManyError> {
+        match self {
+            Self::WebSocket(ws) => {
+                ws.send(Message::Binary(bytes))
+                    .await
+                    .map_err(ManyError::unexpected_transport_error)?;
+                loop {
+                    match ws.next().await {
+                        Some(Ok(Message::Binary(bytes))) => return Ok(bytes),
+                        Some(Ok(Message::Ping(_) | Message::Pong(_) | Message::Frame(_))) => {}
+                        Some(Ok(Message::Text(_) | Message::Close(_))) | None => {
+                            return Err(ManyError::unexpected_transport_error("Connection closed"))
+                        }
+                        Some(Err(e)) => return Err(ManyError::unexpected_transport_error(e)),
+                    }
+                }
+            }
+            Self::Tcp(stream) => {
+                let len = u32::try_from(bytes.len())
+                    .map_err(|_| ManyError::unexpected_transport_error("Message too large"))?;
+                stream
+                    .write_all(&len.to_be_bytes())
+                    .await
+                    .map_err(ManyError::unexpected_transport_error)?;
+                stream
+                    .write_all(&bytes)
+                    .await
+                    .map_err(ManyError::unexpected_transport_error)?;
+
+                let mut len = [0u8; 4];
+                stream
+                    .read_exact(&mut len)
+                    .await
+                    .map_err(ManyError::unexpected_transport_error)?;
+                let len = u32::from_be_bytes(len) as usize;
+                if len > MAX_MESSAGE_LEN {
+                    return Err(ManyError::unexpected_transport_error(format!(
+                        "Content Too Large : {len} bytes"
+                    )));
+                }
+                let mut bytes = vec![0u8; len];
+                stream
+                    .read_exact(&mut bytes)
+                    .await
+                    .map_err(ManyError::unexpected_transport_error)?;
+                Ok(bytes)
+            }
+        }
+    }
+}
+
+/// A connection shared by all the clones of a client. It is opened on the first
+/// request, and reopened on the next request after a transport error. Requests
+/// are not retried on the connection.
+#[derive(Clone)]
+pub struct Connection {
+    url: Url,
+    stream: Arc<Mutex<Option<Stream>>>,
+    tls: Option<Arc<ClientConfig>>,
+}
+
+impl Connection {
+    /// Returns `None` if the URL does not use a persistent transport.
+    pub fn new(url: &Url) -> Option<Self> {
+        match url.scheme() {
+            "ws" | "wss" | "tcp" => Some(Self {
+                url: url.clone(),
+                stream: Arc::new(Mutex::new(None)),
+                tls: None,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Use a TLS configuration for `wss://` URLs.
+    pub fn with_tls(self, config: Arc<ClientConfig>) -> Self {
+        Self {
+            tls: Some(config),
+            ..self
+        }
+    }
+
+    pub async fn send_envelope(&self, message: CoseSign1) -> Result<CoseSign1, ManyError> {
+        let bytes = message
+            .to_tagged_vec()
+            .map_err(|_| ManyError::internal_server_error())?;
+        tracing::debug!("Message length in bytes: {}", bytes.len());
+
+        // The stream is only put back after a complete roundtrip, so that it is
+        // dropped on errors, or if this future is cancelled.
+        let mut guard = self.stream.lock().await;
+        let mut stream = match guard.take() {
+            Some(stream) => stream,
+            None => Stream::connect(&self.url, self.tls.clone()).await?,
+        };
+        let bytes = stream.roundtrip(bytes).await?;
+        *guard = Some(stream);
+
+        tracing::debug!("Response body length: {}", bytes.len());
+        if bytes.is_empty() {
+            return Err(ManyError::unexpected_transport_error(
+                "Internal Server Error".to_string(),
+            ));
+        }
+        CoseSign1::from_tagged_slice(&bytes)
+            .map_err(|e| ManyError::deserialization_error(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ManyClient;
+    use many_identity::{AcceptAllVerifier, Address, AnonymousIdentity};
+    use many_server::transport::{tcp::TcpServer, websocket::WebSocketServer};
+    use many_server::ManyServer;
+    use std::sync::atomic::Ordering;
+    use tokio::net::TcpListener;
+
+    async fn status_twice(url: String) {
+        let client = ManyClient::new(url, Address::anonymous(), AnonymousIdentity).unwrap();
+        assert!(client.endpoints.is_persistent(0));
+        for _ in 0..2 {
+            assert_eq!(client.status().await.unwrap().name, "transport");
+        }
+    }
+
+    #[tokio::test]
+    async fn tcp() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let many = ManyServer::simple("transport", AnonymousIdentity, AcceptAllVerifier, None);
+        let mut server = TcpServer::new(many);
+        let term_signal = server.term_signal();
+        tokio::spawn(async move { server.serve(listener).await });
+
+        status_twice(format!("tcp://{addr}")).await;
+        term_signal.store(true, Ordering::Relaxed);
+    }
+
+    #[tokio::test]
+    async fn websocket() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let many = ManyServer::simple("transport", AnonymousIdentity, AcceptAllVerifier, None);
+        let mut server = WebSocketServer::new(many);
+        let term_signal = server.term_signal();
+        tokio::spawn(async move { server.serve(listener).await });
+
+        status_twice(format!("ws://{addr}")).await;
+        term_signal.store(true, Ordering::Relaxed);
+    }
+}
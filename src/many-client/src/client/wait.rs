@@ -0,0 +1,237 @@
+//! Waiting for the result of requests the server answered with an async token.
+use crate::client::time::{sleep, Instant};
+use many_error::ManyError;
+use many_modules::r#async::attributes::AsyncAttribute;
+use many_modules::r#async::{self, AsyncToken, StatusReturn};
+use many_protocol::ResponseMessage;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// How to poll the server for the result of an async token. Polls start at
+/// `interval` and are spaced by `backoff` times the previous interval, up to
+/// `max_interval`.
+#[derive(Clone, Debug)]
+pub struct WaitOptions {
+    interval: Duration,
+    max_interval: Duration,
+    backoff: f64,
+    timeout: Option<Duration>,
+    cancel: Option<Arc<AtomicBool>>,
+}
+
+impl Default for WaitOptions {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_millis(250),
+            max_interval: Duration::from_secs(5),
+            backoff: 1.5,
+            timeout: Some(Duration::from_secs(60)),
+            cancel: None,
+        }
+    }
+}
+
+impl WaitOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The delay before the first poll.
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// The maximum delay between two polls.
+    pub fn with_max_interval(mut self, max_interval: Duration) -> Self {
+        self.max_interval = max_interval;
+        self
+    }
+
+    /// The factor applied to the delay after each poll. Values below 1 are
+    /// treated as 1 (constant delay).
+    pub fn with_backoff(mut self, backoff: f64) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// Stop waiting after this long, or never if `None`.
+    pub fn with_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Stop waiting once this flag is set. It is checked between polls.
+    pub fn with_cancel(mut self, cancel: Arc<AtomicBool>) -> Self {
+        self.cancel = Some(cancel);
+        self
+    }
+
+    fn next_interval(&self, interval: Duration) -> Duration {
+        interval
+            .mul_f64(self.backoff.max(1.))
+            .min(self.max_interval)
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancel
+            .as_ref()
+            .map_or(false, |cancel| cancel.load(Ordering::Relaxed))
+    }
+}
+
+/// The async token of a response, if its result is not available yet.
+pub fn async_token(response: &ResponseMessage) -> Option<AsyncToken> {
+    match &response.data {
+        Ok(data) if data.is_empty() => response
+            .attributes
+            .get::<AsyncAttribute>()
+            .ok()
+            .map(|attr| attr.token),
+        _ => None,
+    }
+}
+
+/// Poll the status of `token` with `status` until the server has its result.
+pub(crate) async fn wait<F, Fut>(
+    token: &AsyncToken,
+    options: &WaitOptions,
+    status: F,
+) -> Result<ResponseMessage, ManyError>
+where
+    F: Fn() -> Fut,
+    Fut: std::future::Future<Output = Result<StatusReturn, ManyError>>,
+{
+    let token_hex = hex::encode(token);
+    let deadline = options.timeout.map(|timeout| Instant::now() + timeout);
+    let mut interval = options.interval;
+
+    loop {
+        if options.is_cancelled() {
+            return Err(r#async::wait_cancelled(token_hex));
+        }
+        let delay = match deadline {
+            Some(deadline) if Instant::now() + interval > deadline => {
+                let remaining = deadline.saturating_duration_since(Instant::now());
+                if remaining.is_zero() {
+                    return Err(r#async::wait_timed_out(token_hex));
+                }
+                remaining
+            }
+            _ => interval,
+        };
+        sleep(delay).await;
+        interval = options.next_interval(interval);
+
+        match status().await? {
+            StatusReturn::Done { response } => {
+                let payload = response.payload.ok_or_else(ManyError::empty_envelope)?;
+                return ResponseMessage::from_bytes(&payload)
+                    .map_err(ManyError::deserialization_error);
+            }
+            StatusReturn::Expired => return Err(r#async::token_expired(token_hex)),
+            StatusReturn::Unknown | StatusReturn::Queued | StatusReturn::Processing => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use many_error::ManyErrorCode;
+    use std::sync::atomic::AtomicUsize;
+
+    fn token() -> AsyncToken {
+        vec![1, 2, 3].into()
+    }
+
+    fn fast() -> WaitOptions {
+        WaitOptions::new()
+            .with_interval(Duration::from_millis(1))
+            .with_max_interval(Duration::from_millis(4))
+    }
+
+    #[test]
+    fn backoff() {
+        let options = WaitOptions::new()
+            .with_interval(Duration::from_secs(1))
+            .with_max_interval(Duration::from_secs(3))
+            .with_backoff(2.);
+        assert_eq!(
+            options.next_interval(Duration::from_secs(1)),
+            Duration::from_secs(2)
+        );
+        assert_eq!(
+            options.next_interval(Duration::from_secs(2)),
+            Duration::from_secs(3)
+        );
+    }
+
+    #[tokio::test]
+    async fn done() {
+        let response = ResponseMessage {
+            data: Ok(vec![1, 2, 3]),
+            ..Default::default()
+        };
+        let envelope = coset::CoseSign1Builder::new()
+            .payload(response.to_bytes().unwrap())
+            .build();
+        let result = wait(&token(), &fast(), || async {
+            Ok(StatusReturn::Done {
+                response: Box::new(envelope.clone()),
+            })
+        })
+        .await
+        .unwrap();
+        assert_eq!(result.data, Ok(vec![1, 2, 3]));
+    }
+
+    #[tokio::test]
+    async fn expired() {
+        let polls = AtomicUsize::new(0);
+        let err = wait(&token(), &fast(), || async {
+            if polls.fetch_add(1, Ordering::Relaxed) < 2 {
+                Ok(StatusReturn::Processing)
+            } else {
+                Ok(StatusReturn::Expired)
+            }
+        })
+        .await
+        .unwrap_err();
+        assert_eq!(err.code(), r#async::token_expired("").code());
+        assert_eq!(polls.load(Ordering::Relaxed), 3);
+    }
+
+    #[tokio::test]
+    async fn timed_out() {
+        let options = fast().with_timeout(Some(Duration::from_millis(20)));
+        let err = wait(&token(), &options, || async { Ok(StatusReturn::Queued) })
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), r#async::wait_timed_out("").code());
+    }
+
+    #[tokio::test]
+    async fn cancelled() {
+        let cancel = Arc::new(AtomicBool::new(false));
+        let options = fast().with_timeout(None).with_cancel(cancel.clone());
+        let err = wait(&token(), &options, || async {
+            cancel.store(true, Ordering::Relaxed);
+            Ok(StatusReturn::Processing)
+        })
+        .await
+        .unwrap_err();
+        assert_eq!(err.code(), r#async::wait_cancelled("").code());
+    }
+
+    #[tokio::test]
+    async fn status_error() {
+        let err = wait(&token(), &fast(), || async {
+            Err(ManyError::unknown("status"))
+        })
+        .await
+        .unwrap_err();
+        assert_eq!(err.code(), ManyErrorCode::Unknown);
+    }
+}
@@ -0,0 +1,44 @@
+use many_client_macros::many_client;
+use many_error::ManyError;
+pub use many_identity::Identity;
+pub use many_modules::web::{
+    DeployArgs, DeployReturns, InfoReturns, ListArgs, ListReturns, RemoveArgs, RemoveReturns,
+    UpdateArgs, UpdateReturns,
+};
+
+use crate::ManyClient;
+
+#[many_client(WebClient, "web")]
+trait WebClientTrait {
+    fn info(&self) -> Result<InfoReturns, ManyError>;
+    fn list(&self, args: ListArgs) -> Result<ListReturns, ManyError>;
+    fn deploy(&self, args: DeployArgs) -> Result<DeployReturns, ManyError>;
+    fn remove(&self, args: RemoveArgs) -> Result<RemoveReturns, ManyError>;
+    fn update(&self, args: UpdateArgs) -> Result<UpdateReturns, ManyError>;
+}
+
+#[derive(Debug, Clone)]
+pub struct WebClient<I: Identity>(ManyClient<I>);
+
+#[cfg(all(test, feature = "native"))]
+mod tests {
+    use super::*;
+    use crate::client::testutils::round_trip;
+    use many_modules::EmptyReturn;
+
+    #[tokio::test]
+    async fn remove() {
+        let args = RemoveArgs {
+            owner: None,
+            site_name: "site".to_string(),
+            memo: None,
+        };
+        round_trip(
+            "web.remove",
+            args.clone(),
+            EmptyReturn,
+            |client| async move { WebClient::new(client).remove(args).await },
+        )
+        .await;
+    }
+}
@@ -2,10 +2,12 @@
 
 use crate::allow_addrs::AllowAddrsModule;
 use clap::Parser;
+use many_cli_helpers::keystore::identity_from_pem_file;
 use many_identity::verifiers::AnonymousVerifier;
 use many_identity::Address;
-use many_identity_dsa::{CoseKeyIdentity, CoseKeyVerifier};
+use many_identity_dsa::CoseKeyVerifier;
 use many_identity_webauthn::WebAuthnVerifier;
+use many_modules::abci_backend::ManyAbciModuleBackend;
 use many_modules::compute;
 use many_protocol::ManyUrl;
 use many_server::transport::http::HttpServer;
@@ -29,6 +31,9 @@ struct Opts {
     #[clap(flatten)]
     common_flags: many_cli_helpers::CommonCliFlags,
 
+    #[clap(flatten)]
+    http_flags: many_cli_helpers::HttpServerFlags,
+
     /// The location of a PEM file for the identity of this server.
     #[clap(long)]
     pem: PathBuf,
@@ -73,6 +78,7 @@ struct Opts {
 fn main() {
     let Opts {
         common_flags,
+        http_flags,
         pem,
         addr,
         mut state,
@@ -105,7 +111,7 @@ fn main() {
         state = None;
     }
 
-    let key = CoseKeyIdentity::from_pem(std::fs::read_to_string(pem).unwrap()).unwrap();
+    let key = identity_from_pem_file(&pem).expect("Could not generate identity from PEM file.");
 
     let state = state.map(|state| {
         let content = std::fs::read_to_string(state).unwrap();
@@ -145,6 +151,8 @@ fn main() {
 
     {
         let mut s = many.lock().unwrap();
+        let init = ManyAbciModuleBackend::init(&mut *module.lock().unwrap()).unwrap();
+        s.add_query_endpoints(init.queries());
         let compute_module = compute::ComputeModule::new(module);
         if let Some(path) = allow_addrs {
             let allow_addrs: BTreeSet<Address> =
@@ -157,7 +165,10 @@ fn main() {
             s.add_module(compute_module);
         }
     }
-    let mut many_server = HttpServer::new(many);
+    let mut many_server = HttpServer::new(many.clone());
+    http_flags
+        .configure(&mut many_server)
+        .expect("Could not configure the HTTP server");
 
     signal_hook::flag::register(signal_hook::consts::SIGTERM, many_server.term_signal())
         .expect("Could not register signal handler");
@@ -167,5 +178,7 @@ fn main() {
         .expect("Could not register signal handler");
 
     let runtime = tokio::runtime::Runtime::new().unwrap();
-    runtime.block_on(many_server.bind(addr)).unwrap();
+    runtime
+        .block_on(http_flags.bind(&mut many_server, many, addr))
+        .unwrap();
 }
@@ -2,6 +2,7 @@ use crate::Reason;
 use std::collections::BTreeMap;
 use std::fmt::{Display, Formatter};
 use std::iter::FromIterator;
+use std::time::Duration;
 
 #[cfg(feature = "minicbor")]
 mod minicbor;
@@ -163,6 +164,16 @@ many_error! {
             => "Non-WebAuthn request denied for endpoint '{endpoint}'.",
     -1009: DuplicatedMessage as duplicated_message()
             => "This message was already processed.",
+    -1010: InvalidDelegation as invalid_delegation(details)
+            => "Invalid delegation: {details}.",
+    -1011: DelegationExpired as delegation_expired(delegator)
+            => r#"The delegation certificate of "{delegator}" has expired."#,
+    -1012: MethodNotDelegated as method_not_delegated(method)
+            => r#"Method "{method}" was not delegated to the sender."#,
+    -1013: RateLimited as rate_limited(retry_after)
+            => "Too many requests. Retry after {retry_after} milliseconds.",
+    -1014: MethodNotAllowed as method_not_allowed(method)
+            => r#"Method "{method}" is not allowed."#,
 
     // -2000 - -2999 is for server errors.
     -2000: InternalServerError as internal_server_error()
@@ -282,6 +293,17 @@ impl ManyError {
         Self(self.0.with_code(code))
     }
 
+    /// The time to wait before retrying a rate limited request.
+    pub fn retry_after(&self) -> Option<Duration> {
+        if self.code() != ManyErrorCode::RateLimited {
+            return None;
+        }
+        self.argument("retry_after")?
+            .parse()
+            .ok()
+            .map(Duration::from_millis)
+    }
+
     #[inline]
     pub const fn attribute_specific(
         code: i32,
@@ -398,4 +420,18 @@ mod tests {
 
         assert_eq!(e.to_string(), "/{}{ZERO}{}}{TWO.");
     }
+
+    #[test]
+    fn retry_after() {
+        let e = ManyError::rate_limited(1500);
+        assert_eq!(
+            e.to_string(),
+            "Too many requests. Retry after 1500 milliseconds."
+        );
+        assert_eq!(
+            e.retry_after(),
+            Some(std::time::Duration::from_millis(1500))
+        );
+        assert_eq!(ManyError::unknown("1500").retry_after(), None);
+    }
 }
@@ -0,0 +1,394 @@
+//! Hierarchical deterministic (HD) derivation of identities from a BIP-39
+//! mnemonic, following SLIP-0010 for Ed25519 and NIST P-256 keys.
+//!
+//! Keys are only derived along hardened indices. A path can end with a single
+//! non-hardened index, which is then the subresource ID of the key at the
+//! parent path (see [Address::with_subresource_id]). Since subresource IDs and
+//! non-hardened indices share the same range, every address of a wallet,
+//! including its subresources, can be recovered from its mnemonic.
+use crate::CoseKeyIdentity;
+use hmac::{Hmac, Mac};
+use many_error::ManyError;
+use many_identity::{Address, Identity};
+use sha2::Sha512;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use zeroize::{Zeroize, Zeroizing};
+
+/// Indices at or above this value are hardened.
+pub const HARDENED: u32 = 0x8000_0000;
+
+/// The path used when none is specified.
+pub const DEFAULT_DERIVATION_PATH: &str = "m/0'";
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum HdKeyAlgorithm {
+    #[cfg(feature = "ed25519")]
+    Ed25519,
+    EcDsa,
+}
+
+impl FromStr for HdKeyAlgorithm {
+    type Err = ManyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            #[cfg(feature = "ed25519")]
+            "ed25519" => Ok(Self::Ed25519),
+            "ecdsa" | "p256" => Ok(Self::EcDsa),
+            _ => Err(ManyError::unknown(format!("Unsupported algorithm: {s}"))),
+        }
+    }
+}
+
+/// A derivation path, e.g. `m/0'/1'/2`. All indices but the last must be
+/// hardened.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct DerivationPath(Vec<u32>);
+
+impl DerivationPath {
+    pub fn new(indices: Vec<u32>) -> Result<Self, ManyError> {
+        if let Some((_, parents)) = indices.split_last() {
+            if parents.iter().any(|i| *i < HARDENED) {
+                return Err(ManyError::unknown(
+                    "Only the last index of a derivation path can be non-hardened.",
+                ));
+            }
+        }
+        Ok(Self(indices))
+    }
+
+    pub fn indices(&self) -> &[u32] {
+        &self.0
+    }
+
+    /// The subresource ID, if the last index is non-hardened.
+    pub fn subresource_id(&self) -> Option<u32> {
+        self.0.last().copied().filter(|i| *i < HARDENED)
+    }
+
+    /// The indices of the key, i.e. this path without its subresource ID.
+    pub fn key_indices(&self) -> &[u32] {
+        match self.subresource_id() {
+            Some(_) => &self.0[..self.0.len() - 1],
+            None => &self.0,
+        }
+    }
+
+    /// Return the path of the `n`-th following sibling, incrementing the last
+    /// index while keeping it hardened or not.
+    pub fn sibling(&self, n: u32) -> Result<Self, ManyError> {
+        if n == 0 {
+            return Ok(self.clone());
+        }
+        let mut indices = self.0.clone();
+        let last = indices
+            .last_mut()
+            .ok_or_else(|| ManyError::unknown("The root path has no sibling."))?;
+        let hardened = *last & HARDENED;
+        let index = (*last & !HARDENED)
+            .checked_add(n)
+            .filter(|i| *i < HARDENED)
+            .ok_or_else(|| ManyError::unknown("Derivation index out of range."))?;
+        *last = hardened | index;
+        Ok(Self(indices))
+    }
+}
+
+impl FromStr for DerivationPath {
+    type Err = ManyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.trim().split('/');
+        if parts.next() != Some("m") {
+            return Err(ManyError::unknown("Derivation paths must start with 'm'."));
+        }
+
+        let indices = parts
+            .map(|part| {
+                let (index, hardened) = match part.strip_suffix(['\'', 'h', 'H']) {
+                    Some(index) => (index, HARDENED),
+                    None => (part, 0),
+                };
+                index
+                    .parse::<u32>()
+                    .ok()
+                    .filter(|i| *i < HARDENED)
+                    .map(|i| i | hardened)
+                    .ok_or_else(|| ManyError::unknown(format!("Invalid derivation index: {part}")))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Self::new(indices)
+    }
+}
+
+impl Display for DerivationPath {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str("m")?;
+        for i in &self.0 {
+            if *i >= HARDENED {
+                write!(f, "/{}'", i & !HARDENED)?;
+            } else {
+                write!(f, "/{i}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Generate a new random BIP-39 mnemonic of 12, 15, 18, 21 or 24 words.
+pub fn generate_mnemonic(word_count: usize) -> Result<String, ManyError> {
+    use rand::RngCore;
+    if !(12..=24).contains(&word_count) || word_count % 3 != 0 {
+        return Err(ManyError::unknown(format!(
+            "Invalid mnemonic word count: {word_count}"
+        )));
+    }
+
+    let mut entropy = vec![0u8; word_count / 3 * 4];
+    rand::rngs::OsRng.fill_bytes(&mut entropy);
+    bip39::Mnemonic::from_entropy(&entropy)
+        .map(|m| m.to_string())
+        .map_err(ManyError::unknown)
+}
+
+type HmacSha512 = Hmac<Sha512>;
+
+fn hmac_sha512(key: &[u8], data: &[&[u8]]) -> ([u8; 32], [u8; 32]) {
+    let mut mac = HmacSha512::new_from_slice(key).expect("HMAC can take a key of any size");
+    for d in data {
+        mac.update(d);
+    }
+    let mut result = mac.finalize().into_bytes();
+
+    let (mut left, mut right) = ([0u8; 32], [0u8; 32]);
+    left.copy_from_slice(&result[..32]);
+    right.copy_from_slice(&result[32..]);
+    result.as_mut_slice().zeroize();
+    (left, right)
+}
+
+#[cfg(feature = "ed25519")]
+fn derive_ed25519(seed: &[u8], indices: &[u32]) -> Zeroizing<[u8; 32]> {
+    let (key, chain) = hmac_sha512(b"ed25519 seed", &[seed]);
+    let (mut key, mut chain) = (Zeroizing::new(key), Zeroizing::new(chain));
+    for index in indices {
+        let (k, c) = hmac_sha512(&*chain, &[&[0u8], &*key, &index.to_be_bytes()]);
+        (key, chain) = (Zeroizing::new(k), Zeroizing::new(c));
+    }
+    key
+}
+
+fn derive_p256(seed: &[u8], indices: &[u32]) -> Zeroizing<[u8; 32]> {
+    use p256::elliptic_curve::{Field, PrimeField};
+
+    // Returns None if the bytes are not a valid scalar (i.e. >= n).
+    let scalar = |bytes: &[u8; 32]| -> Option<p256::Scalar> {
+        p256::Scalar::from_repr(p256::FieldBytes::clone_from_slice(bytes)).into()
+    };
+
+    let (mut left, mut chain) = hmac_sha512(b"Nist256p1 seed", &[seed]);
+    let mut key = loop {
+        match scalar(&left) {
+            Some(k) if !bool::from(k.is_zero()) => break k,
+            _ => (left, chain) = hmac_sha512(b"Nist256p1 seed", &[&left, &chain]),
+        }
+    };
+
+    for index in indices {
+        let index = index.to_be_bytes();
+        let (mut left, mut right) = hmac_sha512(&chain, &[&[0u8], &key.to_repr(), &index]);
+        key = loop {
+            if let Some(child) = scalar(&left).map(|il| il + key) {
+                if !bool::from(child.is_zero()) {
+                    break child;
+                }
+            }
+            (left, right) = hmac_sha512(&chain, &[&[1u8], &right, &index]);
+        };
+        left.zeroize();
+        chain.zeroize();
+        chain = right;
+        right.zeroize();
+    }
+    left.zeroize();
+    chain.zeroize();
+
+    let mut repr = key.to_repr();
+    let secret = Zeroizing::new(repr.into());
+    repr.as_mut_slice().zeroize();
+    secret
+}
+
+/// The seed of a BIP-39 mnemonic, from which keys are derived. It is
+/// zeroized when dropped.
+#[derive(Clone)]
+pub struct HdSeed(Zeroizing<Vec<u8>>);
+
+impl HdSeed {
+    pub fn from_bytes(seed: &[u8]) -> Self {
+        Self(Zeroizing::new(seed.to_vec()))
+    }
+
+    /// Compute the seed of a mnemonic, with an optional passphrase (use an
+    /// empty string if none).
+    pub fn from_mnemonic(phrase: &str, passphrase: &str) -> Result<Self, ManyError> {
+        let mnemonic = bip39::Mnemonic::parse(phrase).map_err(ManyError::unknown)?;
+        let mut seed = mnemonic.to_seed(passphrase);
+        let result = Self::from_bytes(&seed);
+        seed.zeroize();
+        Ok(result)
+    }
+
+    fn secret_key(&self, algorithm: HdKeyAlgorithm, path: &DerivationPath) -> Zeroizing<[u8; 32]> {
+        match algorithm {
+            #[cfg(feature = "ed25519")]
+            HdKeyAlgorithm::Ed25519 => derive_ed25519(&self.0, path.key_indices()),
+            HdKeyAlgorithm::EcDsa => derive_p256(&self.0, path.key_indices()),
+        }
+    }
+
+    /// The PKCS#8 PEM of the key at the path. If the path ends with a
+    /// subresource ID, this is the key of the parent path.
+    pub fn pem(
+        &self,
+        algorithm: HdKeyAlgorithm,
+        path: &DerivationPath,
+    ) -> Result<String, ManyError> {
+        let secret = self.secret_key(algorithm, path);
+        match algorithm {
+            #[cfg(feature = "ed25519")]
+            HdKeyAlgorithm::Ed25519 => {
+                use ed25519_dalek::pkcs8::EncodePrivateKey;
+                ed25519_dalek::SigningKey::from_bytes(&secret)
+                    .to_pkcs8_pem(pkcs8::LineEnding::LF)
+                    .map(|pem| pem.to_string())
+                    .map_err(ManyError::unknown)
+            }
+            HdKeyAlgorithm::EcDsa => {
+                use p256::pkcs8::EncodePrivateKey;
+                p256::SecretKey::from_bytes(&(*secret).into())
+                    .map_err(ManyError::unknown)?
+                    .to_pkcs8_pem(pkcs8::LineEnding::LF)
+                    .map(|pem| pem.to_string())
+                    .map_err(ManyError::unknown)
+            }
+        }
+    }
+
+    /// The identity of the key at the path. It can sign for all the
+    /// subresources of its address.
+    pub fn identity(
+        &self,
+        algorithm: HdKeyAlgorithm,
+        path: &DerivationPath,
+    ) -> Result<CoseKeyIdentity, ManyError> {
+        CoseKeyIdentity::from_pem(self.pem(algorithm, path)?)
+    }
+
+    /// The address at the path, including its subresource ID if any.
+    pub fn address(
+        &self,
+        algorithm: HdKeyAlgorithm,
+        path: &DerivationPath,
+    ) -> Result<Address, ManyError> {
+        let address = self.identity(algorithm, path)?.address();
+        match path.subresource_id() {
+            Some(id) => address.with_subresource_id(id),
+            None => Ok(address),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // From the SLIP-0010 specification, test vector 1.
+    const SEED: &str = "000102030405060708090a0b0c0d0e0f";
+
+    #[test]
+    fn path_parse() {
+        let path: DerivationPath = "m/44'/0h/1".parse().unwrap();
+        assert_eq!(path.indices(), &[44 | HARDENED, HARDENED, 1]);
+        assert_eq!(path.to_string(), "m/44'/0'/1");
+        assert_eq!(path.subresource_id(), Some(1));
+        assert_eq!(path.key_indices(), &[44 | HARDENED, HARDENED]);
+        assert_eq!(path.sibling(2).unwrap().to_string(), "m/44'/0'/3");
+
+        assert_eq!(
+            "m".parse::<DerivationPath>().unwrap(),
+            DerivationPath::default()
+        );
+        for invalid in ["", "44'", "m/1/2'", "m/x", "m/2147483648", "m//"] {
+            assert!(invalid.parse::<DerivationPath>().is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "ed25519")]
+    fn slip10_ed25519() {
+        let seed = HdSeed::from_bytes(&hex::decode(SEED).unwrap());
+        assert_eq!(
+            hex::encode(*seed.secret_key(HdKeyAlgorithm::Ed25519, &"m".parse().unwrap())),
+            "2b4be7f19ee27bbf30c667b642d5f4aa69fd169872f8fc3059c08ebae2eb19e7"
+        );
+        assert_eq!(
+            hex::encode(*seed.secret_key(HdKeyAlgorithm::Ed25519, &"m/0'".parse().unwrap())),
+            "68e0fe46dfb67e368c75379acec591dad19df3cde26e63b93a8e704f1dade7a3"
+        );
+    }
+
+    #[test]
+    fn slip10_p256() {
+        let seed = HdSeed::from_bytes(&hex::decode(SEED).unwrap());
+        assert_eq!(
+            hex::encode(*seed.secret_key(HdKeyAlgorithm::EcDsa, &"m".parse().unwrap())),
+            "612091aaa12e22dd2abef664f8a01a82cae99ad7441b7ef8110424915c268bc2"
+        );
+        assert_eq!(
+            hex::encode(*seed.secret_key(HdKeyAlgorithm::EcDsa, &"m/0'".parse().unwrap())),
+            "6939694369114c67917a182c59ddb8cafc3004e63ca5d3b84403ba8613debc0c"
+        );
+    }
+
+    #[test]
+    fn bip39_seed() {
+        // From the BIP-39 test vectors.
+        let phrase = "abandon abandon abandon abandon abandon abandon \
+                      abandon abandon abandon abandon abandon about";
+        let seed = HdSeed::from_mnemonic(phrase, "TREZOR").unwrap();
+        assert_eq!(
+            hex::encode(&*seed.0),
+            "c55257c360c07c72029aebc1b53c05ed0362ada38ead3e3e9efa3708e53495531f09a6987599d18264c1e1c92f2cf141630c7a3c4ab7c81b2f001698e7463b04"
+        );
+
+        assert!(HdSeed::from_mnemonic("abandon abandon", "").is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "ed25519")]
+    fn subresource_addresses() {
+        let phrase = generate_mnemonic(24).unwrap();
+        assert_eq!(phrase.split(' ').count(), 24);
+        let seed = HdSeed::from_mnemonic(&phrase, "").unwrap();
+
+        let key_path: DerivationPath = "m/0'".parse().unwrap();
+        let root = seed.address(HdKeyAlgorithm::Ed25519, &key_path).unwrap();
+        let sub = seed
+            .address(HdKeyAlgorithm::Ed25519, &"m/0'/5".parse().unwrap())
+            .unwrap();
+        assert_eq!(sub, root.with_subresource_id(5u32).unwrap());
+        assert!(sub.matches(&root));
+
+        // Different paths and algorithms give different keys.
+        let other = seed
+            .address(HdKeyAlgorithm::Ed25519, &key_path.sibling(1).unwrap())
+            .unwrap();
+        assert_ne!(other, root);
+        assert_ne!(
+            seed.address(HdKeyAlgorithm::EcDsa, &key_path).unwrap(),
+            root
+        );
+    }
+}
@@ -9,6 +9,9 @@ pub mod ed25519;
 #[cfg(feature = "ecdsa")]
 pub mod ecdsa;
 
+#[cfg(feature = "secp256k1")]
+pub mod secp256k1;
+
 /// Assert a COSE key as valid.
 fn check_key(
     cose_key: &CoseKey,
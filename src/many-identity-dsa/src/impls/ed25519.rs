@@ -14,7 +14,7 @@ use std::collections::BTreeSet;
 ///
 /// * `x` - Public key
 /// * `d` - Private key
-fn eddsa_cose_key(x: Vec<u8>, d: Option<Vec<u8>>) -> CoseKey {
+pub fn eddsa_cose_key(x: Vec<u8>, d: Option<Vec<u8>>) -> CoseKey {
     let mut params: Vec<(Label, Value)> = Vec::from([
         (
             Label::Int(OkpKeyParameter::Crv.to_i64()),
@@ -292,6 +292,18 @@ impl Verifier for Ed25519Verifier {
     }
 }
 
+/// Generate a new random Ed25519 private key, encoded as a PKCS#8 PEM. Used to
+/// create keys that will be stored encrypted.
+#[cfg(feature = "encryption")]
+pub fn generate_ed25519_pem() -> Result<String, ManyError> {
+    use ed25519_dalek::pkcs8::EncodePrivateKey;
+    let signing_key = SigningKey::generate(&mut rand::rngs::OsRng);
+    signing_key
+        .to_pkcs8_pem(pkcs8::LineEnding::LF)
+        .map(|pem| pem.to_string())
+        .map_err(ManyError::unknown)
+}
+
 #[cfg(feature = "testing")]
 pub(crate) fn generate_random_ed25519_cose_key() -> CoseKey {
     use rand::rngs::OsRng;
@@ -1,33 +1,28 @@
 use coset::{CoseKey, CoseSign1, CoseSign1Builder};
 use cryptoki::context::{CInitializeArgs, Pkcs11};
+use cryptoki::error::{Error as Pkcs11Error, RvError};
 use cryptoki::mechanism::{Mechanism, MechanismType};
-use cryptoki::object::{Attribute, AttributeType, ObjectHandle};
-use cryptoki::session::{Session, SessionFlags, UserType};
+use cryptoki::object::{Attribute, AttributeType, KeyType, ObjectHandle};
+use cryptoki::session::{Session, SessionFlags, SessionState, UserType};
 use cryptoki::slot::Slot;
 use many_error::ManyError;
 use many_identity::cose::add_keyset_header;
 use many_identity::{cose, Address};
 use once_cell::sync::Lazy;
-use std::path::PathBuf;
-use std::sync::{Mutex, MutexGuard};
-use tracing::{error, trace};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tracing::trace;
 
-/// HSM Singleton
-/// PKCS#11 v2.40 specifies that
-///
-/// "An application should never make multiple simultaneous function call to
-/// Cryptoki which use a common session. If multiple threads of an application
-/// attempt to use a common session concurrently in this fashion, Cryptoki does
-/// not define what happens. This means that if a multiple threads of an
-/// application all need to use Cryptoki to access a particular token, it might
-/// be appropriate for each thread to have its own session with the token,
-/// unless the application can ensure by some other means (e.g., by some locking
-/// mechanism) that no sessions are ever used by multiple threads
-/// simultaneously."
-///
-/// If one ever modify this behavior, make sure that the application/tests don't
-/// hit the Cryptoki simultaneously
-static HSM_INSTANCE: Lazy<Mutex<Hsm>> = Lazy::new(|| Mutex::new(Hsm::default()));
+/// PKCS#11 modules that were loaded and initialized by this process, by path.
+/// A module can only be initialized once per process, but it can be shared by
+/// any number of sessions.
+static HSM_MODULES: Lazy<Mutex<BTreeMap<PathBuf, HsmModule>>> =
+    Lazy::new(|| Mutex::new(BTreeMap::new()));
+
+/// DER encoding of the NIST P-256 curve OID (1.2.840.10045.3.1.7), as found in
+/// the EC_PARAMS attribute of P-256 keys.
+const SECP256R1_OID: &[u8] = &[0x06, 0x08, 0x2A, 0x86, 0x48, 0xCE, 0x3D, 0x03, 0x01, 0x07];
 
 /// Same as cryptoki::session::UserType
 pub type HsmUserType = UserType;
@@ -39,6 +34,7 @@ pub type HsmMechanism = Mechanism;
 pub type HsmMechanismType = MechanismType;
 
 /// HSM session type.
+#[derive(Clone, Copy, Debug)]
 pub enum HsmSessionType {
     /// Read-only
     RO,
@@ -46,291 +42,333 @@ pub enum HsmSessionType {
     RW,
 }
 
-/// Holds the PKCS#11 context, the PKCS#11 session and the HSM Key ID to use to
-/// perform the cryptographic operations
-#[derive(Debug, Default)]
-pub struct Hsm {
-    pkcs11: Option<Pkcs11>,
-    session: Option<Session>,
-    keyid: Option<Vec<u8>>,
+/// The algorithm of a key stored on the HSM.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum HsmKeyAlgorithm {
+    /// ECDSA over the NIST P-256 curve.
+    EcDsaP256,
+    /// EdDSA over Curve25519, if the token supports it.
+    Ed25519,
 }
 
-impl Hsm {
-    /// Return the HSM global instance
-    pub fn get_instance() -> Result<MutexGuard<'static, Hsm>, ManyError> {
-        HSM_INSTANCE.lock().map_err(ManyError::hsm_mutex_poisoned)
+/// A loaded and initialized PKCS#11 module.
+#[derive(Clone)]
+pub struct HsmModule {
+    pkcs11: Arc<Pkcs11>,
+}
+
+impl HsmModule {
+    /// Load and initialize the PKCS#11 module at `path`, or return the module
+    /// already loaded from that path.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ManyError> {
+        let path = path.as_ref().to_path_buf();
+        let mut modules = HSM_MODULES.lock().map_err(ManyError::hsm_mutex_poisoned)?;
+        if let Some(module) = modules.get(&path) {
+            return Ok(module.clone());
+        }
+
+        trace!("Loading and initializing PKCS#11 module");
+        let pkcs11 = Pkcs11::new(&path).map_err(|e| ManyError::hsm_init_error(e.to_string()))?;
+        pkcs11
+            .initialize(CInitializeArgs::OsThreads)
+            .map_err(|e| ManyError::hsm_init_error(e.to_string()))?;
+        trace!("PKCS#11 context initialized");
+
+        let module = Self {
+            pkcs11: Arc::new(pkcs11),
+        };
+        modules.insert(path, module.clone());
+        Ok(module)
     }
+}
 
-    /// Perform message signature on the HSM using the given mechanism
-    ///
-    /// Note: The NIST P-256 curve requires the user to hash the message with
-    /// SHA256, and to sign the result.
-    pub fn sign(&self, msg: &[u8], mechanism: &HsmMechanism) -> Result<Vec<u8>, ManyError> {
-        let session = self
-            .session
-            .as_ref()
-            .ok_or_else(|| ManyError::hsm_session_error("No PKCS#11 open session found"))?;
+/// A PKCS#11 session on a slot.
+///
+/// PKCS#11 v2.40 specifies that
+///
+/// "An application should never make multiple simultaneous function call to
+/// Cryptoki which use a common session. If multiple threads of an application
+/// attempt to use a common session concurrently in this fashion, Cryptoki does
+/// not define what happens."
+///
+/// The session is therefore behind a mutex. Identities that need to sign
+/// concurrently should each open their own session.
+///
+/// If the session is closed or logged out by the token (e.g. after a timeout),
+/// it is opened again and logged in with the same credentials.
+pub struct HsmSession {
+    module: HsmModule,
+    slot: Slot,
+    session_type: HsmSessionType,
+    user_type: Option<HsmUserType>,
+    pin: Option<String>,
+    session: Mutex<Option<Session>>,
+}
 
-        let signer = self.signer()?;
-        trace!("Signing message using HSM");
-        let signature = session
-            .sign(mechanism, signer, msg)
-            .map_err(ManyError::hsm_sign_error)?;
-        Ok(signature)
+impl HsmSession {
+    /// Open a session on the HSM
+    ///
+    /// Public RO session and private RO/RW sessions are supported
+    /// Read-only (RO) and read-write (RW) serial sessions are supported
+    pub fn open(
+        module: &HsmModule,
+        slot: u64,
+        session_type: HsmSessionType,
+        user_type: Option<HsmUserType>,
+        pin: Option<String>,
+    ) -> Result<Arc<Self>, ManyError> {
+        let slot = Slot::try_from(slot).map_err(|e| ManyError::hsm_session_error(e.to_string()))?;
+        let this = Self {
+            module: module.clone(),
+            slot,
+            session_type,
+            user_type,
+            pin,
+            session: Mutex::new(None),
+        };
+        let session = this.login()?;
+        this.session
+            .lock()
+            .map_err(ManyError::hsm_mutex_poisoned)?
+            .replace(session);
+        Ok(Arc::new(this))
     }
 
-    /// Return the object handle of the HSM singing key (private key)
-    fn signer(&self) -> Result<ObjectHandle, ManyError> {
+    fn login(&self) -> Result<Session, ManyError> {
+        let mut flags = SessionFlags::new();
+        flags.set_serial_session(true);
+        if let HsmSessionType::RW = self.session_type {
+            flags.set_rw_session(true);
+        }
+
+        trace!("Opening HSM session");
         let session = self
-            .session
-            .as_ref()
-            .ok_or_else(|| ManyError::hsm_session_error("No PKCS#11 open session found"))?;
-        let keyid = self
-            .keyid
-            .as_ref()
-            .ok_or_else(|| ManyError::hsm_keyid_error("No PKCS#11 key ID found"))?;
-
-        trace!("Looking for private key");
-        let template = &[Attribute::Id(keyid.clone()), Attribute::Sign(true)];
-        let mut signers = session
-            .find_objects(template)
-            .map_err(|e| ManyError::hsm_sign_error(format!("{e}")))?;
-
-        trace!("Making sure we found one and only one private key");
-        let signer = match signers.len() {
-            0 => {
-                panic!("Unable to find private key")
+            .module
+            .pkcs11
+            .open_session_no_callback(self.slot, flags)
+            .map_err(|e| ManyError::hsm_session_error(format!("{e}")))?;
+
+        // A user type means that the user needs to login. The login state is
+        // shared by all the sessions of a token, so another session might
+        // already have logged in.
+        if let Some(u) = self.user_type {
+            trace!("Login user to HSM as {:?}", u);
+            match session.login(u, self.pin.as_deref()) {
+                Ok(()) | Err(Pkcs11Error::Pkcs11(RvError::UserAlreadyLoggedIn)) => {}
+                Err(e) => return Err(ManyError::hsm_login_error(format!("{e}"))),
             }
-            1 => signers
-                .pop()
-                .ok_or_else(|| ManyError::hsm_sign_error("Unable to fetch private key"))?,
-            _ => {
-                panic!("Multiple private key found")
+        }
+        trace!("Session to HSM opened successfully");
+        Ok(session)
+    }
+
+    /// Whether the session was closed, or logged out while it should be logged in.
+    fn is_expired(&self, session: &Session) -> bool {
+        match session.get_session_info() {
+            Ok(info) => {
+                self.user_type.is_some()
+                    && matches!(
+                        info.session_state(),
+                        SessionState::RO_PUBLIC_SESSION | SessionState::RW_PUBLIC_SESSION
+                    )
             }
-        };
-        Ok(signer)
+            Err(_) => true,
+        }
     }
 
-    /// Perform message signature verification on the HSM using the given mechanism
-    ///
-    /// Note: The NIST P-256 curve requires the user to hash the message with
-    /// SHA256, and to verify the result.
-    pub fn verify(
+    /// Run a PKCS#11 operation on this session, opening the session again if the
+    /// token closed it or logged it out.
+    fn with_session<T>(
         &self,
-        msg: &[u8],
-        signature: &[u8],
-        mechanism: &HsmMechanism,
-    ) -> Result<(), ManyError> {
-        let session = self
-            .session
-            .as_ref()
-            .ok_or_else(|| ManyError::hsm_session_error("No PKCS#11 open session found"))?;
+        f: impl Fn(&Session) -> Result<T, Pkcs11Error>,
+        map_err: impl Fn(Pkcs11Error) -> ManyError,
+    ) -> Result<T, ManyError> {
+        let mut session = self.session.lock().map_err(ManyError::hsm_mutex_poisoned)?;
+        if session.as_ref().map_or(true, |s| self.is_expired(s)) {
+            session.replace(self.login()?);
+        }
 
-        let verifier = self.verifier()?;
-        session
-            .verify(mechanism, verifier, msg, signature)
-            .map_err(|e| ManyError::hsm_verify_error(format!("{e}")))?;
-        Ok(())
+        match f(session.as_ref().unwrap()) {
+            Err(Pkcs11Error::Pkcs11(
+                RvError::UserNotLoggedIn | RvError::SessionHandleInvalid | RvError::SessionClosed,
+            )) => {
+                trace!("HSM session expired, opening a new one");
+                session.take();
+                let new_session = self.login()?;
+                let result = f(&new_session).map_err(map_err);
+                session.replace(new_session);
+                result
+            }
+            result => result.map_err(map_err),
+        }
     }
 
-    /// Return the object handle of the HSM verification key (public key)
-    fn verifier(&self) -> Result<ObjectHandle, ManyError> {
-        let session = self
-            .session
-            .as_ref()
-            .ok_or_else(|| ManyError::hsm_session_error("No PKCS#11 open session found"))?;
-        let keyid = self
-            .keyid
-            .as_ref()
-            .ok_or_else(|| ManyError::hsm_keyid_error("No PKCS#11 key ID found".to_string()))?;
-
-        trace!("Looking for public key");
-        let template = &[Attribute::Id(keyid.clone()), Attribute::Verify(true)];
-        let mut verifiers = session
-            .find_objects(template)
-            .map_err(|e| ManyError::hsm_verify_error(format!("{e}")))?;
-
-        trace!("Making sure we found one and only one public key");
-        let verifier = match verifiers.len() {
-            0 => {
-                panic!("Unable to find public key")
+    /// Return the single object matching the template.
+    fn find_object(&self, template: &[Attribute]) -> Result<ObjectHandle, ManyError> {
+        let mut objects =
+            self.with_session(|s| s.find_objects(template), ManyError::hsm_keyid_error)?;
+
+        match objects.len() {
+            0 => Err(ManyError::hsm_keyid_error("Unable to find key")),
+            1 => Ok(objects.pop().unwrap()),
+            _ => Err(ManyError::hsm_keyid_error("Multiple keys found")),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct HsmIdentity {
+    session: Arc<HsmSession>,
+    keyid: Vec<u8>,
+    algorithm: HsmKeyAlgorithm,
+    address: Address,
+    key: CoseKey,
+}
+
+impl HsmIdentity {
+    /// Create an identity from the key pair with the given ID, using the
+    /// session to sign. The algorithm is detected from the public key.
+    /// Several identities can share a session.
+    pub fn new(session: Arc<HsmSession>, keyid: Vec<u8>) -> Result<Self, ManyError> {
+        let verifier =
+            session.find_object(&[Attribute::Id(keyid.clone()), Attribute::Verify(true)])?;
+
+        let key_type = session.with_session(
+            |s| s.get_attributes(verifier, &[AttributeType::KeyType]),
+            ManyError::hsm_keyid_error,
+        )?;
+        let algorithm = match key_type.first() {
+            Some(Attribute::KeyType(KeyType::EC)) => {
+                // EC keys can be on any curve; only P-256 is supported.
+                let params = session.with_session(
+                    |s| s.get_attributes(verifier, &[AttributeType::EcParams]),
+                    ManyError::hsm_keyid_error,
+                )?;
+                match params.first() {
+                    Some(Attribute::EcParams(oid)) if oid == SECP256R1_OID => {
+                        HsmKeyAlgorithm::EcDsaP256
+                    }
+                    _ => return Err(ManyError::hsm_keyid_error("Unsupported curve")),
+                }
             }
-            1 => verifiers.pop().ok_or_else(|| {
-                ManyError::hsm_verify_error("Unable to fetch public key".to_string())
-            })?,
-            _ => {
-                panic!("Multiple public key found")
+            Some(Attribute::KeyType(KeyType::EC_EDWARDS)) => HsmKeyAlgorithm::Ed25519,
+            _ => return Err(ManyError::hsm_keyid_error("Unsupported key type")),
+        };
+
+        let raw_points = Self::ec_point(&session, verifier, algorithm)?;
+        let key = match algorithm {
+            HsmKeyAlgorithm::EcDsaP256 => {
+                trace!("Creating NIST P-256 SEC1 encoded point");
+                let points =
+                    p256::EncodedPoint::from_bytes(raw_points).map_err(ManyError::unknown)?;
+                let (x, y) = points
+                    .x()
+                    .zip(points.y())
+                    .ok_or_else(|| ManyError::hsm_ec_point_error("Invalid EC point"))?;
+                many_identity_dsa::ecdsa::ecdsa_cose_key((x.to_vec(), y.to_vec()), None)
+            }
+            HsmKeyAlgorithm::Ed25519 => {
+                many_identity_dsa::ed25519::eddsa_cose_key(raw_points, None)
             }
         };
-        Ok(verifier)
-    }
 
-    /// Retrieve the EC_POINT and EC_PARAMS key parameters
-    ///
-    /// EC_POINT is returned in raw, uncompressed form, i.e., NOT ASN.1 DER
-    ///
-    /// Note: Only works with EC keys
-    pub fn ec_info(&self, mechanism: HsmMechanismType) -> Result<(Vec<u8>, Vec<u8>), ManyError> {
-        let pkcs11 = self
-            .pkcs11
-            .as_ref()
-            .ok_or_else(|| ManyError::hsm_init_error("No PKCS#11 context found.".to_string()))?;
-        let session = self.session.as_ref().ok_or_else(|| {
-            ManyError::hsm_session_error("No PKCS#11 open session found".to_string())
-        })?;
-
-        trace!("Making sure we can fetch uncompressed EC_POINT");
-        let slot = session
-            .get_session_info()
-            .map_err(|e| ManyError::hsm_ec_point_error(e.to_string()))?
-            .slot_id();
-        let uncompress = pkcs11
-            .get_mechanism_info(slot, mechanism)
-            .map_err(|e| ManyError::hsm_ec_point_error(e.to_string()))?
-            .flags()
-            .ec_uncompress();
-        if !uncompress {
-            panic!("Could not fetch uncompressed EC_POINT");
+        let public_key = match algorithm {
+            HsmKeyAlgorithm::EcDsaP256 => many_identity_dsa::ecdsa::public_key(&key)?,
+            HsmKeyAlgorithm::Ed25519 => many_identity_dsa::ed25519::public_key(&key)?,
         }
+        .ok_or_else(|| ManyError::unknown("Could not load key."))?;
+        let address = unsafe { cose::address_unchecked(&public_key) }?;
+        Ok(Self {
+            session,
+            keyid,
+            algorithm,
+            address,
+            key,
+        })
+    }
 
-        let verifier = self.verifier()?;
-        let results = session
-            .get_attributes(verifier, &[AttributeType::EcPoint])
-            .map_err(|e| ManyError::hsm_ec_point_error(format!("{e}")))?;
-        let ec_points = if let Some(Attribute::EcPoint(points)) = results.get(0) {
-            points
-        } else {
-            panic!("Public EC point attribute not available")
-        };
+    pub fn algorithm(&self) -> HsmKeyAlgorithm {
+        self.algorithm
+    }
 
-        trace!("Fetching EC public key params");
-        let results = session
-            .get_attributes(verifier, &[AttributeType::EcParams])
-            .map_err(|e| ManyError::hsm_ec_params_error(format!("{e}")))?;
-        let ec_params = if let Some(Attribute::EcParams(params)) = results.get(0) {
-            params
-        } else {
-            panic!("Public EC params attribute not available")
+    /// Retrieve the EC_POINT key parameter, in raw form, i.e., NOT ASN.1 DER.
+    /// For P-256 keys, the point is uncompressed.
+    fn ec_point(
+        session: &HsmSession,
+        verifier: ObjectHandle,
+        algorithm: HsmKeyAlgorithm,
+    ) -> Result<Vec<u8>, ManyError> {
+        if algorithm == HsmKeyAlgorithm::EcDsaP256 {
+            trace!("Making sure we can fetch uncompressed EC_POINT");
+            let uncompress = session
+                .module
+                .pkcs11
+                .get_mechanism_info(session.slot, MechanismType::ECDSA)
+                .map_err(|e| ManyError::hsm_ec_point_error(e.to_string()))?
+                .flags()
+                .ec_uncompress();
+            if !uncompress {
+                return Err(ManyError::hsm_ec_point_error(
+                    "Could not fetch uncompressed EC_POINT",
+                ));
+            }
+        }
+
+        let results = session.with_session(
+            |s| s.get_attributes(verifier, &[AttributeType::EcPoint]),
+            ManyError::hsm_ec_point_error,
+        )?;
+        let ec_points = match results.first() {
+            Some(Attribute::EcPoint(points)) => points,
+            _ => {
+                return Err(ManyError::hsm_ec_point_error(
+                    "Public EC point attribute not available",
+                ))
+            }
         };
 
         trace!("Decoding EC_POINT using ASN.1 DER");
         let raw_points: &[u8] = asn1::parse_single(ec_points)
             .map_err(|e| ManyError::hsm_ec_point_error(format!("{e:?}")))?;
-        trace!("Raw, uncompressed EC_POINT: {}", hex::encode(raw_points));
-        Ok((raw_points.to_vec(), ec_params.clone()))
+        trace!("Raw EC_POINT: {}", hex::encode(raw_points));
+        Ok(raw_points.to_vec())
     }
 
-    /// Initialize the PKCS#11 context and set the HSM keyid. You should run
-    /// this only once at the beginning of your application
-    pub fn init(&mut self, module: PathBuf, keyid: Vec<u8>) -> Result<(), ManyError> {
-        match &self.pkcs11 {
-            None => {
-                trace!("Loading and initializing PKCS#11 module");
-                let pkcs11 = Pkcs11::new(module).expect("Unable to load PKCS#11 module");
-                pkcs11
-                    .initialize(CInitializeArgs::OsThreads)
-                    .map_err(|e| ManyError::hsm_init_error(e.to_string()))?;
-                self.pkcs11.replace(pkcs11);
-                trace!("PKCS#11 context initialized");
-            }
-            Some(_) => {
-                error!("PKCS#11 context already initialized!");
-            }
+    fn mechanism(&self) -> HsmMechanism {
+        match self.algorithm {
+            HsmKeyAlgorithm::EcDsaP256 => HsmMechanism::Ecdsa,
+            HsmKeyAlgorithm::Ed25519 => HsmMechanism::Eddsa,
         }
-
-        match &self.keyid {
-            None => {
-                self.keyid.replace(keyid);
-                trace!("keyid initialized");
-            }
-            Some(_) => {
-                error!("Key ID already initialized!");
-            }
-        }
-        Ok(())
     }
 
-    /// Open a session on the HSM
+    /// Perform message signature on the HSM.
     ///
-    /// Public RO session and private RO/RW sessions are supported
-    /// Read-only (RO) and read-write (RW) serial sessions are supported
-    pub fn open_session(
-        &mut self,
-        slot: u64,
-        session_type: HsmSessionType,
-        user_type: Option<HsmUserType>,
-        pin: Option<String>,
-    ) -> Result<(), ManyError> {
-        let pkcs11 = self
-            .pkcs11
-            .as_ref()
-            .ok_or_else(|| ManyError::hsm_init_error("No PKCS#11 context found.".to_string()))?;
-        let slot = Slot::try_from(slot).map_err(|e| ManyError::hsm_session_error(e.to_string()))?;
-        match &self.session {
-            None => {
-                let session_flags = match session_type {
-                    // Read-only PKCS#11 session
-                    HsmSessionType::RO => {
-                        trace!("Creating RO session flags");
-                        let mut flags = SessionFlags::new();
-                        flags.set_serial_session(true);
-                        flags
-                    }
-                    // Read-write PKCS#11 session
-                    HsmSessionType::RW => {
-                        trace!("Creating RW session flags");
-                        let mut flags = SessionFlags::new();
-                        flags.set_serial_session(true).set_rw_session(true);
-                        flags
-                    }
-                };
-                trace!("Opening HSM session");
-                let session = pkcs11
-                    .open_session_no_callback(slot, session_flags)
-                    .map_err(|e| ManyError::hsm_session_error(format!("{e}")))?;
-
-                // A user type means that the user needs to login
-                match user_type {
-                    None => {}
-                    Some(u) => {
-                        trace!("Login user to HSM as {:?}", u);
-                        session
-                            .login(u, pin.as_deref())
-                            .map_err(|e| ManyError::hsm_login_error(format!("{e}")))?;
-                    }
-                }
-                trace!("Session to HSM opened successfully");
-                self.session.replace(session);
-            }
-            Some(_) => {
-                error!("A session is already opened!");
-            }
-        }
-        Ok(())
+    /// Note: The NIST P-256 curve requires the user to hash the message with
+    /// SHA256, and to sign the result. Ed25519 signs the message itself.
+    pub fn sign(&self, msg: &[u8]) -> Result<Vec<u8>, ManyError> {
+        let signer = self
+            .session
+            .find_object(&[Attribute::Id(self.keyid.clone()), Attribute::Sign(true)])?;
+        let mechanism = self.mechanism();
+
+        trace!("Signing message using HSM");
+        self.session.with_session(
+            |s| s.sign(&mechanism, signer, msg),
+            ManyError::hsm_sign_error,
+        )
     }
-}
 
-#[derive(Clone)]
-pub struct HsmIdentity {
-    address: Address,
-    key: CoseKey,
-}
+    /// Perform message signature verification on the HSM.
+    pub fn verify(&self, msg: &[u8], signature: &[u8]) -> Result<(), ManyError> {
+        let verifier = self
+            .session
+            .find_object(&[Attribute::Id(self.keyid.clone()), Attribute::Verify(true)])?;
+        let mechanism = self.mechanism();
 
-impl HsmIdentity {
-    pub fn new(mechanism: HsmMechanismType) -> Result<Self, ManyError> {
-        let hsm = Hsm::get_instance()?;
-        let (raw_points, _) = hsm.ec_info(mechanism)?;
-        trace!("Creating NIST P-256 SEC1 encoded point");
-        let points = p256::EncodedPoint::from_bytes(raw_points).map_err(ManyError::unknown)?;
-
-        let key = many_identity_dsa::ecdsa::ecdsa_cose_key(
-            (points.x().unwrap().to_vec(), points.y().unwrap().to_vec()),
-            None,
-        );
-        let public_key = many_identity_dsa::ecdsa::public_key(&key)?
-            .ok_or_else(|| ManyError::unknown("Could not load key."))?;
-        let address = unsafe { cose::address_unchecked(&public_key) }?;
-        Ok(Self { address, key })
+        self.session.with_session(
+            |s| s.verify(&mechanism, verifier, msg, signature),
+            ManyError::hsm_verify_error,
+        )
     }
 }
 
@@ -344,12 +382,13 @@ impl many_identity::Identity for HsmIdentity {
     }
 
     fn sign_1(&self, envelope: CoseSign1) -> Result<CoseSign1, ManyError> {
-        let hsm = Hsm::get_instance()?;
         let mut envelope = add_keyset_header(envelope, self)?;
 
         // Add the algorithm and key id.
-        envelope.protected.header.alg =
-            Some(coset::Algorithm::Assigned(coset::iana::Algorithm::ES256));
+        envelope.protected.header.alg = Some(coset::Algorithm::Assigned(match self.algorithm {
+            HsmKeyAlgorithm::EcDsaP256 => coset::iana::Algorithm::ES256,
+            HsmKeyAlgorithm::Ed25519 => coset::iana::Algorithm::EdDSA,
+        }));
         envelope.protected.header.key_id = self.address.to_vec();
 
         let builder = CoseSign1Builder::new()
@@ -366,11 +405,14 @@ impl many_identity::Identity for HsmIdentity {
             .try_create_signature(&[], |bytes| {
                 use sha2::Digest;
 
-                trace!("Digesting message using SHA256 (CPU)");
-                let digest = sha2::Sha256::digest(bytes);
-
-                trace!("Singning message using HSM");
-                let msg_signature = hsm.sign(digest.as_slice(), &HsmMechanism::Ecdsa)?;
+                let msg_signature = match self.algorithm {
+                    HsmKeyAlgorithm::EcDsaP256 => {
+                        trace!("Digesting message using SHA256 (CPU)");
+                        let digest = sha2::Sha256::digest(bytes);
+                        self.sign(digest.as_slice())?
+                    }
+                    HsmKeyAlgorithm::Ed25519 => self.sign(bytes)?,
+                };
                 trace!("Message signature is {}", hex::encode(&msg_signature));
 
                 Ok(msg_signature)
@@ -383,133 +425,75 @@ impl many_identity::Identity for HsmIdentity {
 mod tests {
     use std::env;
 
-    use cryptoki::{
-        object::{KeyType, ObjectHandle},
-        session::SessionState,
-    };
+    use many_identity::{Identity, Verifier as _};
     use p256::ecdsa::signature::Verifier;
     use sha2::Digest;
 
     use super::*;
 
-    type HSMAttribute = Attribute;
-    type HSMObjectHandle = ObjectHandle;
-
-    const KEYPAIR_TEST_ID: &[u8] = &[15, 15];
     const SO_PIN: &str = "0000";
     const USER_PIN: &str = "0000";
     const MSG: &str = "FOOBAR";
-    // 1.2.840.10045.3.1.7
-    const SECP256R1_OID: &[u8] = &[0x06, 0x08, 0x2A, 0x86, 0x48, 0xCE, 0x3D, 0x03, 0x01, 0x07];
-    static ECDSA_PUB_KEY_TEMPLATE: Lazy<Vec<Attribute>> = Lazy::new(|| {
+    // 1.3.132.0.10
+    const SECP256K1_OID: &[u8] = &[0x06, 0x05, 0x2B, 0x81, 0x04, 0x00, 0x0A];
+    // 1.3.101.112
+    const ED25519_OID: &[u8] = &[0x06, 0x03, 0x2B, 0x65, 0x70];
+
+    /// Tests re-initialize the token, so they cannot run concurrently.
+    static TEST_LOCK: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+
+    fn pub_key_template(key_type: KeyType, params: &[u8], keyid: &[u8]) -> Vec<Attribute> {
         vec![
             Attribute::Token(true),
             Attribute::Private(false),
-            Attribute::KeyType(KeyType::EC),
+            Attribute::KeyType(key_type),
             Attribute::Verify(true),
-            Attribute::EcParams(SECP256R1_OID.to_vec()),
-            Attribute::Id(KEYPAIR_TEST_ID.to_vec()),
+            Attribute::EcParams(params.to_vec()),
+            Attribute::Id(keyid.to_vec()),
         ]
-    });
-    static ECDSA_PRIV_KEY_TEMPLATE: Lazy<Vec<Attribute>> = Lazy::new(|| {
+    }
+
+    fn priv_key_template(keyid: &[u8]) -> Vec<Attribute> {
         vec![
             Attribute::Token(true),
             Attribute::Private(true),
             Attribute::Sensitive(true),
             Attribute::Extractable(false),
             Attribute::Sign(true),
-            Attribute::Id(KEYPAIR_TEST_ID.to_vec()),
+            Attribute::Id(keyid.to_vec()),
         ]
-    });
+    }
 
     /// HSM methods only used for testing purposes
-    impl Hsm {
-        /// Initialize user PIN using an SO FW session
-        fn init_user_pin(&self, pin: String) -> Result<(), ManyError> {
-            match &self.session {
-                None => {
-                    panic!("You need to open a SO session in order to initialize the user PIN")
-                }
-                Some(s) => {
-                    let info = s
-                        .get_session_info()
-                        .map_err(|e| ManyError::hsm_session_error(format!("{e}")))?;
-                    match info.session_state() {
-                        SessionState::RW_SO_FUNCTIONS => {
-                            self.session
-                                .as_ref()
-                                .ok_or_else(|| {
-                                    ManyError::hsm_session_error(
-                                        "Unable to access session".to_string(),
-                                    )
-                                })?
-                                .init_pin(&pin)
-                                .map_err(|e| ManyError::hsm_session_error(format!("{e}")))?;
-                        }
-                        _ => {
-                            panic!(
-                                "You need to open a SO session in order to initialize the user PIN"
-                            )
-                        }
-                    }
-                }
-            }
-            Ok(())
-        }
-
-        /// Initialize a new token on the HSM
-        fn init_token(&self, slot: Slot, pin: String, label: String) -> Result<(), ManyError> {
-            match &self.pkcs11 {
-                None => {
-                    panic!("PKCS#11 context not initialized")
-                }
-                Some(_) => {
-                    self.pkcs11
-                        .as_ref()
-                        .ok_or_else(|| {
-                            ManyError::hsm_init_error(
-                                "Unable to access PKCS#11 context".to_string(),
-                            )
-                        })?
-                        .init_token(slot, &pin, &label)
-                        .map_err(|e| ManyError::hsm_init_error(format!("{e}")))?;
-                }
-            }
-
-            Ok(())
-        }
-
+    impl HsmSession {
         /// Generate a new keypair on the HSM
         fn generate_key_pair(
             &self,
             mechanism: &HsmMechanism,
-            pub_template: &[HSMAttribute],
-            priv_template: &[HSMAttribute],
-        ) -> Result<(HSMObjectHandle, HSMObjectHandle), ManyError> {
-            let session = self.session.as_ref().ok_or_else(|| {
-                ManyError::hsm_session_error("No PKCS#11 open session found".to_string())
-            })?;
-
-            session
-                .generate_key_pair(mechanism, pub_template, priv_template)
-                .map_err(|e| ManyError::hsm_keygen_error(format!("{e}")))
+            pub_template: &[Attribute],
+            priv_template: &[Attribute],
+        ) -> Result<(ObjectHandle, ObjectHandle), ManyError> {
+            self.with_session(
+                |s| s.generate_key_pair(mechanism, pub_template, priv_template),
+                ManyError::hsm_keygen_error,
+            )
+        }
+
+        fn generate_ecdsa(&self, keyid: &[u8]) -> Result<(ObjectHandle, ObjectHandle), ManyError> {
+            self.generate_key_pair(
+                &Mechanism::EccKeyPairGen,
+                &pub_key_template(KeyType::EC, SECP256R1_OID, keyid),
+                &priv_key_template(keyid),
+            )
         }
 
         /// Destroy test keys after test run
         fn destroy(&self, obj: ObjectHandle) -> Result<(), ManyError> {
-            let session = self.session.as_ref().ok_or_else(|| {
-                ManyError::hsm_session_error("No PKCS#11 open session found".to_string())
-            })?;
-
-            session
-                .destroy_object(obj)
-                .map_err(|e| ManyError::hsm_session_error(e.to_string()))?;
-            Ok(())
+            self.with_session(|s| s.destroy_object(obj), ManyError::hsm_session_error)
         }
 
-        /// Close the HSM session
-        fn close_session(&mut self) {
-            self.session = None;
+        fn logout(&self) -> Result<(), ManyError> {
+            self.with_session(|s| s.logout(), ManyError::hsm_login_error)
         }
     }
 
@@ -524,41 +508,46 @@ mod tests {
     /// $ PKCS11_SOFTHSM2_MODULE=/usr/local/lib/softhsm/libsofthsm2.so cargo test
     ///
     /// This function will
-    /// - Creates and initialize a PKCS#11 context
+    /// - Load the PKCS#11 module
     /// - Initialize a new token and set SO PIN
     /// - Open a SO RW session
     ///     - Set the user PIN
-    /// - Open a user RW session
-    ///     - Generate a new ECDSA (secp256r1) keypair
-    /// - Return the slot number
-    fn init() -> Result<u64, ManyError> {
-        let mut hsm = Hsm::get_instance()?;
+    /// - Open and return a user RW session
+    fn init() -> Result<Arc<HsmSession>, ManyError> {
         let module = env::var("PKCS11_SOFTHSM2_MODULE")
             .unwrap_or_else(|_| "/usr/lib/softhsm/libsofthsm2.so".to_string());
-        hsm.init(PathBuf::from(module), KEYPAIR_TEST_ID.to_vec())
-            .expect("Unable to init PKCS#11");
-        let pkcs11 = hsm.pkcs11.as_ref().ok_or_else(|| {
-            ManyError::hsm_init_error("Unable to access PKCS#11 context".to_string())
-        })?;
+        let module = HsmModule::load(module)?;
 
-        let mut slots = pkcs11
+        let slot = module
+            .pkcs11
             .get_slots_with_token()
-            .map_err(|e| ManyError::hsm_init_error(e.to_string()))?;
-        let slot = slots.pop().ok_or_else(|| {
-            ManyError::hsm_session_error("Unable to fetch slots with token".to_string())
-        })?;
-        hsm.init_token(slot, SO_PIN.to_string(), "Test Token".to_string())?;
+            .map_err(|e| ManyError::hsm_init_error(e.to_string()))?
+            .pop()
+            .ok_or_else(|| ManyError::hsm_session_error("Unable to fetch slots with token"))?;
+        module
+            .pkcs11
+            .init_token(slot, SO_PIN, "Test Token")
+            .map_err(|e| ManyError::hsm_init_error(format!("{e}")))?;
         let slot = slot.id();
 
-        hsm.open_session(
+        let so_session = HsmSession::open(
+            &module,
             slot,
             HsmSessionType::RW,
             Some(HsmUserType::So),
             Some(SO_PIN.to_string()),
         )?;
-        hsm.init_user_pin(USER_PIN.to_string())?;
-        hsm.close_session();
-        Ok(slot)
+        so_session.with_session(|s| s.init_pin(USER_PIN), ManyError::hsm_session_error)?;
+        drop(so_session);
+
+        // We need to open a RW session since we're destroying the keys at the end of the test
+        HsmSession::open(
+            &module,
+            slot,
+            HsmSessionType::RW,
+            Some(HsmUserType::User),
+            Some(USER_PIN.to_string()),
+        )
     }
 
     /// Test that message signing and signature verification works on the HSM
@@ -567,33 +556,21 @@ mod tests {
     /// The keypair will be destroyed at the end of the test, but the token will remain initialized.
     #[test]
     fn hsm_ecdsa_sign_verify() -> Result<(), ManyError> {
-        let slot = init()?;
-
-        let mut hsm = Hsm::get_instance()?;
-        hsm.open_session(
-            slot,
-            HsmSessionType::RW, // We need to open a RW session since we're destroying the keys at the end of the test
-            Some(HsmUserType::User),
-            Some(USER_PIN.to_string()),
-        )?;
-        let (public, private) = hsm.generate_key_pair(
-            &Mechanism::EccKeyPairGen,
-            &ECDSA_PUB_KEY_TEMPLATE,
-            &ECDSA_PRIV_KEY_TEMPLATE,
-        )?;
+        let _lock = TEST_LOCK.lock().unwrap();
+        let session = init()?;
+        let (public, private) = session.generate_ecdsa(&[15, 15])?;
+        let identity = HsmIdentity::new(session.clone(), vec![15, 15])?;
+        assert_eq!(identity.algorithm(), HsmKeyAlgorithm::EcDsaP256);
 
         // TODO: This operation should be done on the HSM, but cryptoki doesn't support it yet
         // See https://github.com/parallaxsecond/rust-cryptoki/issues/88
         let digest = sha2::Sha256::digest(MSG);
 
-        let hsm_signature = hsm.sign(digest.as_slice(), &HsmMechanism::Ecdsa)?;
-        hsm.verify(digest.as_slice(), &hsm_signature, &HsmMechanism::Ecdsa)?;
-
-        hsm.destroy(public)?;
-        hsm.destroy(private)?;
-
-        hsm.close_session();
+        let hsm_signature = identity.sign(digest.as_slice())?;
+        identity.verify(digest.as_slice(), &hsm_signature)?;
 
+        session.destroy(public)?;
+        session.destroy(private)?;
         Ok(())
     }
 
@@ -604,28 +581,18 @@ mod tests {
     /// The keypair will be destroyed at the end of the test, but the token will remain initialized.
     #[test]
     fn hsm_ecdsa_sign_p256_verify() -> Result<(), ManyError> {
-        let slot = init()?;
+        let _lock = TEST_LOCK.lock().unwrap();
+        let session = init()?;
+        let (public, private) = session.generate_ecdsa(&[15, 15])?;
+        let identity = HsmIdentity::new(session.clone(), vec![15, 15])?;
 
-        let mut hsm = Hsm::get_instance()?;
-        hsm.open_session(
-            slot,
-            HsmSessionType::RW, // We need to open a RW session since we're destroying the keys at the end of the test
-            Some(HsmUserType::User),
-            Some(USER_PIN.to_string()),
-        )?;
-
-        let (public, private) = hsm.generate_key_pair(
-            &Mechanism::EccKeyPairGen,
-            &ECDSA_PUB_KEY_TEMPLATE,
-            &ECDSA_PRIV_KEY_TEMPLATE,
-        )?;
         // TODO: This operation should be done on the HSM, but cryptoki doesn't support it yet
         // See https://github.com/parallaxsecond/rust-cryptoki/issues/88
         let digest = sha2::Sha256::digest(MSG);
 
-        let hsm_signature = hsm.sign(digest.as_slice(), &HsmMechanism::Ecdsa)?;
+        let hsm_signature = identity.sign(digest.as_slice())?;
 
-        let (ec_points, _ec_params) = hsm.ec_info(HsmMechanismType::ECDSA)?;
+        let ec_points = HsmIdentity::ec_point(&session, public, HsmKeyAlgorithm::EcDsaP256)?;
         let points =
             p256::EncodedPoint::from_bytes(ec_points).expect("Unable to create p256::EncodedPoint");
         let verify_key = p256::ecdsa::VerifyingKey::from_encoded_point(&points).unwrap();
@@ -634,10 +601,85 @@ mod tests {
             .verify(MSG.as_bytes(), &p256_signature)
             .expect("Unable to verify signature");
 
-        hsm.destroy(private)?;
-        hsm.destroy(public)?;
+        session.destroy(private)?;
+        session.destroy(public)?;
+        Ok(())
+    }
 
-        hsm.close_session();
+    /// Test that an Ed25519 key on the HSM signs envelopes that the CPU verifier
+    /// accepts.
+    #[test]
+    fn hsm_ed25519_sign_1() -> Result<(), ManyError> {
+        let _lock = TEST_LOCK.lock().unwrap();
+        let session = init()?;
+        let (public, private) = session.generate_key_pair(
+            &Mechanism::EccEdwardsKeyPairGen,
+            &pub_key_template(KeyType::EC_EDWARDS, ED25519_OID, &[16, 16]),
+            &priv_key_template(&[16, 16]),
+        )?;
+        let identity = HsmIdentity::new(session.clone(), vec![16, 16])?;
+        assert_eq!(identity.algorithm(), HsmKeyAlgorithm::Ed25519);
+
+        let envelope = identity.sign_1(
+            CoseSign1Builder::new()
+                .payload(MSG.as_bytes().to_vec())
+                .build(),
+        )?;
+        assert_eq!(
+            many_identity_dsa::CoseKeyVerifier.verify_1(&envelope)?,
+            identity.address()
+        );
+
+        session.destroy(private)?;
+        session.destroy(public)?;
+        Ok(())
+    }
+
+    /// Test that EC keys on other curves than P-256 are refused.
+    #[test]
+    fn hsm_unsupported_curve() -> Result<(), ManyError> {
+        let _lock = TEST_LOCK.lock().unwrap();
+        let session = init()?;
+        let (public, private) = session.generate_key_pair(
+            &Mechanism::EccKeyPairGen,
+            &pub_key_template(KeyType::EC, SECP256K1_OID, &[17, 17]),
+            &priv_key_template(&[17, 17]),
+        )?;
+        assert_eq!(
+            HsmIdentity::new(session.clone(), vec![17, 17])
+                .err()
+                .map(|e| e.code()),
+            Some(ManyError::hsm_keyid_error("").code())
+        );
+
+        session.destroy(private)?;
+        session.destroy(public)?;
+        Ok(())
+    }
+
+    /// Test that several keys can be used from the same session, and that the
+    /// session is logged in again after the token logged it out.
+    #[test]
+    fn hsm_multiple_keys_relogin() -> Result<(), ManyError> {
+        let _lock = TEST_LOCK.lock().unwrap();
+        let session = init()?;
+        let (public1, private1) = session.generate_ecdsa(&[1])?;
+        let (public2, private2) = session.generate_ecdsa(&[2])?;
+        let id1 = HsmIdentity::new(session.clone(), vec![1])?;
+        let id2 = HsmIdentity::new(session.clone(), vec![2])?;
+        assert_ne!(id1.address(), id2.address());
+
+        let digest = sha2::Sha256::digest(MSG);
+        let signature = id1.sign(digest.as_slice())?;
+        assert!(id2.verify(digest.as_slice(), &signature).is_err());
+
+        session.logout()?;
+        let signature = id2.sign(digest.as_slice())?;
+        id2.verify(digest.as_slice(), &signature)?;
+
+        for obj in [public1, private1, public2, private2] {
+            session.destroy(obj)?;
+        }
         Ok(())
     }
 }
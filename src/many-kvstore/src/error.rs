@@ -16,6 +16,7 @@ define_attribute_many_error!(
 define_application_many_error!(
     {
         1: pub fn storage_apply_failed(desc) => "Unable to apply change to persistent storage: {desc}.",
-        2: pub fn storage_get_failed(desc) => "Unable to get data from persistent storage: {desc}."
+        2: pub fn storage_get_failed(desc) => "Unable to get data from persistent storage: {desc}.",
+        3: pub fn height_not_supported() => "Historical queries at a past height are not supported.",
     }
 );
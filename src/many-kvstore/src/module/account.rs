@@ -228,6 +228,10 @@ impl AccountModuleBackend for KvStoreModuleImpl {
         args: account::InfoArgs,
         context: Context,
     ) -> Result<account::InfoReturn, ManyError> {
+        if args.height.is_some() {
+            return Err(error::height_not_supported());
+        }
+
         let (account, account_key) = self.storage.get_account_even_disabled(&args.account);
         account
             .ok_or_else(|| account::errors::unknown_account(args.account))
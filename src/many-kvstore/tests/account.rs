@@ -24,6 +24,7 @@ fn account_info(
         id,
         account::InfoArgs {
             account: *account_id,
+            height: None,
         },
         Context::new(RequestMessage::default(), unbounded().0),
     );
@@ -308,6 +309,7 @@ fn disable() {
         &id,
         account::InfoArgs {
             account: account_id,
+            height: None,
         },
         Context::new(RequestMessage::default(), unbounded().0),
     );
@@ -346,6 +348,7 @@ fn add_feature() {
         &id,
         account::InfoArgs {
             account: account_id,
+            height: None,
         },
         Context::new(RequestMessage::default(), unbounded().0),
     )
@@ -375,6 +378,7 @@ fn add_feature() {
         &id,
         account::InfoArgs {
             account: account_id,
+            height: None,
         },
         Context::new(RequestMessage::default(), unbounded().0),
     )
@@ -0,0 +1,144 @@
+use many_identity::verifiers::AnonymousVerifier;
+use many_identity::{AnonymousIdentity, Identity};
+use many_identity_dsa::ed25519::generate_random_ed25519_identity;
+use many_identity_dsa::CoseKeyVerifier;
+use many_kvstore::module::account::AccountFeatureModule;
+use many_kvstore::module::KvStoreModuleImpl;
+use many_modules::account::features::Feature;
+use many_modules::kvstore::{DisableArgs, GetArgs, GetReturns, PutArgs};
+use many_modules::{abci_backend, account, events, kvstore};
+use many_server::simulator::{tx, Simulator, Start};
+use many_server::ManyServer;
+use std::sync::{Arc, Mutex};
+
+/// Create a simulator of `n` kvstore nodes.
+fn simulator(n: usize) -> Simulator {
+    let dirs = (0..n)
+        .map(|_| tempfile::tempdir().expect("Could not create a temporary dir."))
+        .collect::<Vec<_>>();
+
+    Simulator::new(n, move |i, start| {
+        let module_impl = match start {
+            Start::Genesis => {
+                let content = std::fs::read_to_string("../../staging/kvstore_state.json5")
+                    .or_else(|_| std::fs::read_to_string("staging/kvstore_state.json5"))
+                    .expect("Could not read initial state.");
+                let state = json5::from_str(&content).unwrap();
+                KvStoreModuleImpl::new(state, dirs[i].path(), true)?
+            }
+            Start::Restart => KvStoreModuleImpl::load(dirs[i].path(), true)?,
+        };
+        Ok(server(module_impl))
+    })
+}
+
+/// Create the server of a node, with the modules of many-kvstore.
+fn server(module_impl: KvStoreModuleImpl) -> Arc<Mutex<ManyServer>> {
+    let module_impl = Arc::new(Mutex::new(module_impl));
+    let many = ManyServer::simple(
+        "many-kvstore",
+        AnonymousIdentity,
+        (AnonymousVerifier, CoseKeyVerifier),
+        None,
+    );
+    {
+        let mut s = many.lock().unwrap();
+        s.add_module(kvstore::KvStoreModule::new(module_impl.clone()));
+        s.add_module(kvstore::KvStoreCommandsModule::new(module_impl.clone()));
+        s.add_module(kvstore::KvStoreTransferModule::new(module_impl.clone()));
+        s.add_module(events::EventsModule::new(module_impl.clone()));
+        s.add_module(AccountFeatureModule::new(
+            account::AccountModule::new(module_impl.clone()),
+            [Feature::with_id(2)],
+        ));
+        s.add_module(abci_backend::AbciModule::new(module_impl));
+    }
+    many
+}
+
+fn put(from: &impl Identity, key: &[u8], value: &[u8]) -> coset::CoseSign1 {
+    tx(
+        from,
+        "kvstore.put",
+        PutArgs {
+            key: key.to_vec().into(),
+            value: value.to_vec().into(),
+            alternative_owner: None,
+        },
+    )
+}
+
+fn get(simulator: &Simulator, node: usize, key: &[u8]) -> Result<Option<Vec<u8>>, String> {
+    simulator
+        .query(
+            node,
+            tx(
+                &AnonymousIdentity,
+                "kvstore.get",
+                GetArgs {
+                    key: key.to_vec().into(),
+                },
+            ),
+        )
+        .map(|data| {
+            minicbor::decode::<GetReturns>(&data)
+                .unwrap()
+                .value
+                .map(|v| v.to_vec())
+        })
+        .map_err(|e| e.to_string())
+}
+
+#[test]
+fn puts() {
+    let owner = generate_random_ed25519_identity();
+    let other = generate_random_ed25519_identity();
+    let mut simulator = simulator(4);
+
+    let results = simulator.block(vec![
+        put(&owner, b"foo", b"bar"),
+        put(&other, b"foo", b"baz"),
+        put(&AnonymousIdentity, b"anonymous", b"value"),
+        put(&other, b"other", b"value"),
+    ]);
+    assert!(results[0].is_ok());
+    assert!(results[1].is_err());
+    assert!(results[2].is_err());
+    assert!(results[3].is_ok());
+
+    for node in 0..4 {
+        assert_eq!(get(&simulator, node, b"foo"), Ok(Some(b"bar".to_vec())));
+        assert_eq!(get(&simulator, node, b"anonymous"), Ok(None));
+        assert_eq!(get(&simulator, node, b"other"), Ok(Some(b"value".to_vec())));
+    }
+}
+
+#[test]
+fn restarts() {
+    let owner = generate_random_ed25519_identity();
+    let mut simulator = simulator(3);
+
+    // Node 2 misses all blocks, and replays them when it starts again.
+    simulator.stop(2);
+    for i in 0..5u8 {
+        simulator.block(vec![put(&owner, &[i], &[i, i])]);
+        simulator.restart(0);
+    }
+    let results = simulator.block(vec![tx(
+        &owner,
+        "kvstore.disable",
+        DisableArgs {
+            key: vec![0].into(),
+            alternative_owner: None,
+            reason: None,
+        },
+    )]);
+    assert!(results[0].is_ok());
+    simulator.start(2);
+    simulator.empty_blocks(1);
+
+    for node in 0..3 {
+        assert!(get(&simulator, node, &[0]).is_err());
+        assert_eq!(get(&simulator, node, &[4]), Ok(Some(vec![4, 4])));
+    }
+}
@@ -17,6 +17,7 @@ define_attribute_many_error!(
         9: pub fn amount_is_zero()
             => "Unable to send zero (0) token.",
         10: pub fn storage_key_not_found(key) => "Key not found in storage: {key:?}.",
+        11: pub fn height_not_available(height) => "State at height {height} is not available.",
     }
 );
 
@@ -28,6 +29,8 @@ define_attribute_many_error!(
         4: pub fn ticker_exists(ticker) => "Token ticker already exists on this network: {ticker}.",
         5: pub fn subresource_exhausted(key) => "Subresources are exhausted for: {key}.",
         6: pub fn invalid_ticker_length(ticker) => "Token ticker length is invalid (<3 or >5): {ticker}.",
+        7: pub fn token_paused(symbol) => "Transfers of {symbol} are paused.",
+        8: pub fn address_frozen(address, symbol) => "The {symbol} balance of {address} is frozen.",
     }
 );
 
@@ -38,7 +41,10 @@ define_attribute_many_error!(
         3: pub fn missing_funds(symbol, amount, balance) => "Unable to burn, missing funds: {amount} > {balance} {symbol}.",
         4: pub fn unable_to_distribute_zero(symbol) => "The mint/burn distribution contains zero for {symbol}.",
         5: pub fn partial_burn_disabled() => "Partial burns are disabled.",
-        6: pub fn no_token_owner() => "Token doesn't have an owner."
+        6: pub fn no_token_owner() => "Token doesn't have an owner.",
+        7: pub fn over_mint_allowance(minter, amount, allowance) => "Unable to mint over the allowance of {minter}: {amount} > {allowance}.",
+        8: pub fn over_mint_rate_limit(symbol, amount, limit) => "Unable to mint over the rate limit of {symbol} for this period: {amount} (limit {limit}).",
+        9: pub fn no_mint_allowance(minter, symbol) => "{minter} has no allowance to mint {symbol}.",
     }
 );
 
@@ -49,5 +55,6 @@ define_application_many_error!(
         3: pub fn storage_commit_failed(desc) => "Unable to commit data to persistent storage: {desc}.",
         4: pub fn storage_open_failed(desc) => "Unable to open persistent storage: {desc}.",
         5: pub fn unable_to_load_migrations(desc) => "Unable to load migrations: {desc}.",
+        6: pub fn storage_checkpoint_failed(desc) => "Unable to create or read storage checkpoint: {desc}.",
     }
 );
@@ -1,25 +1,97 @@
 use crate::storage::account::AccountMeta;
 use crate::storage::ledger_tokens::SymbolMeta;
-use many_error::ManyError;
+use base64::{engine::general_purpose, Engine as _};
+use many_error::{ManyError, Reason};
 use many_identity::Address;
 use many_modules::account;
 use many_modules::account::features;
-use many_modules::account::features::{FeatureInfo, TryCreateFeature};
+use many_modules::account::features::{spending_limits, FeatureInfo, TryCreateFeature};
+use many_modules::ledger::{MintPolicy, MintRateLimit};
 use many_types::ledger::{Symbol, TokenAmount};
+use many_types::Either;
 use std::cmp::Ordering;
 use std::collections::{BTreeMap, BTreeSet};
 use std::path::Path;
 
-#[derive(serde::Deserialize, Clone, Debug, Default)]
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug, Default)]
 pub struct MultisigFeatureArgJson {
     pub threshold: Option<u64>,
     pub timeout_in_secs: Option<u64>,
     pub execute_automatically: Option<bool>,
 }
 
-#[derive(serde::Deserialize, Clone, Debug, Default)]
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug)]
+pub struct SpendingLimitJson {
+    pub amount: TokenAmount,
+    pub window_in_secs: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fixed: Option<bool>,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug, Default)]
+pub struct SpendingPolicyJson {
+    #[serde(default)]
+    pub limits: BTreeMap<Symbol, SpendingLimitJson>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub allowlist: Option<BTreeSet<Address>>,
+}
+
+impl From<SpendingPolicyJson> for spending_limits::SpendingPolicy {
+    fn from(value: SpendingPolicyJson) -> Self {
+        Self {
+            limits: value
+                .limits
+                .into_iter()
+                .map(|(symbol, limit)| {
+                    (
+                        symbol,
+                        spending_limits::SpendingLimit {
+                            amount: limit.amount,
+                            window_in_secs: limit.window_in_secs,
+                            fixed: limit.fixed,
+                        },
+                    )
+                })
+                .collect(),
+            allowlist: value.allowlist,
+        }
+    }
+}
+
+impl From<spending_limits::SpendingPolicy> for SpendingPolicyJson {
+    fn from(value: spending_limits::SpendingPolicy) -> Self {
+        Self {
+            limits: value
+                .limits
+                .into_iter()
+                .map(|(symbol, limit)| {
+                    (
+                        symbol,
+                        SpendingLimitJson {
+                            amount: limit.amount,
+                            window_in_secs: limit.window_in_secs,
+                            fixed: limit.fixed,
+                        },
+                    )
+                })
+                .collect(),
+            allowlist: value.allowlist,
+        }
+    }
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug, Default)]
+pub struct SpendingLimitsFeatureArgJson {
+    #[serde(default)]
+    pub members: BTreeMap<Address, SpendingPolicyJson>,
+    #[serde(default)]
+    pub roles: BTreeMap<String, SpendingPolicyJson>,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug, Default)]
 pub struct FeatureJson {
     pub id: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub arg: Option<serde_json::value::Value>,
 }
 
@@ -30,6 +102,55 @@ impl FeatureJson {
                 features::ledger::AccountLedger::ID,
             )),
             features::multisig::MultisigAccountFeature::ID => self.arg_into_multisig(),
+            features::tokens::TokenAccountLedger::ID => Some(features::Feature::with_id(
+                features::tokens::TokenAccountLedger::ID,
+            )),
+            spending_limits::SpendingLimitsFeature::ID => self.arg_into_spending_limits(),
+            _ => None,
+        }
+    }
+
+    /// Converts an account feature to its JSON representation.
+    /// Returns `None` if the feature is not supported by the ledger.
+    pub fn try_from_feature(feature: &features::Feature) -> Option<Self> {
+        match feature.id() {
+            id @ (features::ledger::AccountLedger::ID
+            | features::tokens::TokenAccountLedger::ID) => Some(Self { id, arg: None }),
+            features::multisig::MultisigAccountFeature::ID => {
+                let arg = features::multisig::MultisigAccountFeature::try_create(feature)
+                    .ok()?
+                    .arg;
+                let arg = MultisigFeatureArgJson {
+                    threshold: arg.threshold,
+                    timeout_in_secs: arg.timeout_in_secs,
+                    execute_automatically: arg.execute_automatically,
+                };
+                Some(Self {
+                    id: features::multisig::MultisigAccountFeature::ID,
+                    arg: Some(serde_json::to_value(arg).ok()?),
+                })
+            }
+            spending_limits::SpendingLimitsFeature::ID => {
+                let arg = spending_limits::SpendingLimitsFeature::try_create(feature)
+                    .ok()?
+                    .arg;
+                let arg = SpendingLimitsFeatureArgJson {
+                    members: arg
+                        .members
+                        .into_iter()
+                        .map(|(id, policy)| (id, policy.into()))
+                        .collect(),
+                    roles: arg
+                        .roles
+                        .into_iter()
+                        .map(|(role, policy)| (role.to_string(), policy.into()))
+                        .collect(),
+                };
+                Some(Self {
+                    id: spending_limits::SpendingLimitsFeature::ID,
+                    arg: Some(serde_json::to_value(arg).ok()?),
+                })
+            }
             _ => None,
         }
     }
@@ -48,6 +169,34 @@ impl FeatureJson {
             .as_feature()
         })
     }
+
+    fn arg_into_spending_limits(&self) -> Option<features::Feature> {
+        self.arg.as_ref().map(|a| {
+            let a: SpendingLimitsFeatureArgJson =
+                serde_json::from_value(a.clone()).expect("Invalid Feature argument.");
+
+            spending_limits::SpendingLimitsFeature::from_arg(
+                spending_limits::SpendingLimitsFeatureArg {
+                    members: a
+                        .members
+                        .into_iter()
+                        .map(|(id, policy)| (id, policy.into()))
+                        .collect(),
+                    roles: a
+                        .roles
+                        .into_iter()
+                        .map(|(role, policy)| {
+                            (
+                                std::str::FromStr::from_str(&role).expect("Invalid role."),
+                                policy.into(),
+                            )
+                        })
+                        .collect(),
+                },
+            )
+            .as_feature()
+        })
+    }
 }
 
 impl Eq for FeatureJson {}
@@ -70,13 +219,45 @@ impl Ord for FeatureJson {
     }
 }
 
-#[derive(serde::Deserialize, Clone, Debug, Default)]
+/// The reason an account was disabled.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug, Default)]
+pub struct DisabledReasonJson {
+    pub code: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub arguments: BTreeMap<String, String>,
+}
+
+impl From<DisabledReasonJson> for Reason<u64> {
+    fn from(value: DisabledReasonJson) -> Self {
+        Reason::new(value.code, value.message, value.arguments)
+    }
+}
+
+impl From<Reason<u64>> for DisabledReasonJson {
+    fn from(value: Reason<u64>) -> Self {
+        Self {
+            code: *value.code(),
+            message: value.message().map(str::to_string),
+            arguments: value.arguments().clone(),
+        }
+    }
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug, Default)]
 pub struct AccountJson {
     pub id: Option<Address>,
     pub subresource_id: Option<u32>,
     pub description: Option<String>,
     pub roles: BTreeMap<Address, BTreeSet<String>>,
     pub features: BTreeSet<FeatureJson>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub disabled: Option<bool>,
+
+    /// The reason the account was disabled, if any. Takes precedence over `disabled`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub disabled_reason: Option<DisabledReasonJson>,
 }
 
 /// Converts the JSON Account metadata to our internal representation
@@ -104,16 +285,71 @@ impl From<AccountJson> for AccountMeta {
                 .iter()
                 .map(|v| v.try_into_feature().expect("Unsupported feature."))
                 .collect(),
+            disabled: value
+                .disabled_reason
+                .map(|reason| Either::Right(reason.into()))
+                .or(value.disabled.map(Either::Left)),
         }
     }
 }
 
-#[derive(serde::Deserialize, Clone, Debug)]
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug)]
 pub struct SymbolMetaJson {
     pub name: String,
     pub decimals: u64,
     pub owner: Option<Address>,
     pub maximum: Option<TokenAmount>,
+
+    /// The token extended info, as base64-encoded CBOR.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extended_info: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mint_policy: Option<MintPolicyJson>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub paused: Option<bool>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub frozen: Option<BTreeSet<Address>>,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug)]
+pub struct MintRateLimitJson {
+    pub amount: TokenAmount,
+    pub period_in_secs: u64,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug, Default)]
+pub struct MintPolicyJson {
+    #[serde(default)]
+    pub allowances: BTreeMap<Address, TokenAmount>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rate_limit: Option<MintRateLimitJson>,
+}
+
+impl From<MintPolicyJson> for MintPolicy {
+    fn from(value: MintPolicyJson) -> Self {
+        Self {
+            allowances: value.allowances,
+            rate_limit: value.rate_limit.map(|r| MintRateLimit {
+                amount: r.amount,
+                period_in_secs: r.period_in_secs,
+            }),
+        }
+    }
+}
+
+impl From<MintPolicy> for MintPolicyJson {
+    fn from(value: MintPolicy) -> Self {
+        Self {
+            allowances: value.allowances,
+            rate_limit: value.rate_limit.map(|r| MintRateLimitJson {
+                amount: r.amount,
+                period_in_secs: r.period_in_secs,
+            }),
+        }
+    }
 }
 
 /// Converts the JSON Symbol metadata to our internal representation
@@ -124,23 +360,53 @@ impl From<SymbolMetaJson> for SymbolMeta {
             decimals: value.decimals,
             owner: value.owner,
             maximum: value.maximum,
+            extended_info: value.extended_info.map(|e| {
+                let bytes = general_purpose::STANDARD
+                    .decode(e)
+                    .expect("Invalid base64 for extended info");
+                minicbor::decode(&bytes).expect("Invalid extended info")
+            }),
+            mint_policy: value.mint_policy.map(Into::into),
+            paused: value.paused,
+            frozen: value.frozen,
         }
     }
 }
 
 /// The initial state schema, loaded from JSON.
-#[derive(serde::Deserialize, Clone, Debug, Default)]
+/// This is also the format produced by `LedgerStorage::export_state()`.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug, Default)]
 pub struct InitialStateJson {
     pub identity: Address,
     pub initial: BTreeMap<Address, BTreeMap<String, TokenAmount>>,
     pub token_identity: Option<Address>,
     pub account_identity: Option<Address>,
     pub token_next_subresource: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub account_next_subresource: Option<u32>,
     pub symbols: BTreeMap<Address, String>,
     pub symbols_meta: Option<BTreeMap<Address, SymbolMetaJson>>,
     pub accounts: Option<Vec<AccountJson>>,
     pub id_store_seed: Option<u64>,
     pub id_store_keys: Option<BTreeMap<String, String>>,
+
+    /// The events and the event count, as base64 storage keys and values.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub events: Option<BTreeMap<String, String>>,
+
+    /// The multisig transactions, as base64 storage keys and values.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub multisig_transactions: Option<BTreeMap<String, String>>,
+
+    /// The usage of account spending limits and token mint rate limits, as base64
+    /// storage keys and values.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage: Option<BTreeMap<String, String>>,
+
+    /// The data attributes, as base64 storage keys and values.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<BTreeMap<String, String>>,
+
     pub hash: Option<String>,
 }
 
@@ -160,6 +426,12 @@ impl InitialStateJson {
         Ok(s)
     }
 
+    pub fn write<P: AsRef<Path>>(&self, path: P) -> Result<(), Box<dyn std::error::Error>> {
+        let content = serde_json::to_string_pretty(self).map_err(Box::new)?;
+        std::fs::write(path.as_ref(), content).map_err(Box::new)?;
+        Ok(())
+    }
+
     pub fn symbols(&self) -> BTreeMap<Address, String> {
         self.symbols.clone()
     }
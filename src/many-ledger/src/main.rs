@@ -1,13 +1,16 @@
 #![feature(used_with_arg)]
 
 use clap::Parser;
+use many_cli_helpers::keystore::identity_from_pem_file;
 use many_cli_helpers::CommonCliFlags;
 use many_identity::verifiers::AnonymousVerifier;
 use many_identity::{Address, Identity};
-use many_identity_dsa::{CoseKeyIdentity, CoseKeyVerifier};
+use many_identity_dsa::CoseKeyVerifier;
 use many_identity_webauthn::WebAuthnVerifier;
 use many_migration::MigrationConfig;
+use many_modules::abci_backend::ManyAbciModuleBackend;
 use many_modules::account::features::Feature;
+use many_modules::BackendLock;
 use many_modules::{abci_backend, account, data, events, idstore, ledger};
 use many_protocol::ManyUrl;
 use many_server::transport::http::HttpServer;
@@ -16,7 +19,7 @@ use many_server_cache::{RequestCacheValidator, RocksDbCacheBackend};
 use std::collections::BTreeSet;
 use std::net::SocketAddr;
 use std::path::PathBuf;
-use std::sync::{Arc, Mutex};
+use std::sync::Arc;
 use tracing::{debug, info, warn};
 
 use crate::allow_addrs::AllowAddrsModule;
@@ -26,6 +29,7 @@ use crate::idstore_webauthn::IdStoreWebAuthnModule;
 use crate::json::InitialStateJson;
 use crate::migration::MIGRATIONS;
 use crate::module::account::AccountFeatureModule;
+use crate::module::snapshot::SnapshotLock;
 use module::*;
 
 mod error;
@@ -40,6 +44,12 @@ struct Opts {
     #[clap(flatten)]
     common_flags: CommonCliFlags,
 
+    #[clap(flatten)]
+    http_flags: many_cli_helpers::HttpServerFlags,
+
+    #[clap(flatten)]
+    rate_limit_flags: many_cli_helpers::RateLimitFlags,
+
     /// The location of a PEM file for the identity of this server.
     // The field needs to be an Option for the clap derive to work properly.
     #[clap(long, required = true)]
@@ -62,6 +72,21 @@ struct Opts {
     #[clap(long, required = true)]
     persistent: Option<PathBuf>,
 
+    /// Path to a directory where the state of past blocks is kept, to serve
+    /// queries at a past height. History is disabled if unspecified.
+    #[clap(long)]
+    history: Option<PathBuf>,
+
+    /// Number of past blocks to keep in the history directory.
+    #[clap(long, default_value = "1000")]
+    history_window: u64,
+
+    /// Path to a directory where a snapshot of the last committed block is
+    /// kept, to serve queries without waiting for the block being executed.
+    /// Only used in ABCI mode. Otherwise queries wait for the running command.
+    #[clap(long)]
+    snapshot: Option<PathBuf>,
+
     /// Delete the persistent storage to start from a clean state.
     /// If this is not specified the initial state will not be used.
     #[clap(long, short)]
@@ -116,11 +141,16 @@ struct Opts {
 fn main() {
     let Opts {
         common_flags,
+        http_flags,
+        rate_limit_flags,
         pem,
         addr,
         abci,
         mut state,
         persistent,
+        history,
+        history_window,
+        snapshot,
         clean,
         migrations_config,
         allow_origin,
@@ -152,13 +182,15 @@ fn main() {
     let persistent = persistent.unwrap();
 
     if clean {
-        // Delete the persistent storage.
+        // Delete the persistent storage and its history.
         // Ignore NotFound errors.
-        match std::fs::remove_dir_all(persistent.as_path()) {
-            Ok(_) => {}
-            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
-            Err(e) => {
-                panic!("Error: {e}")
+        for path in std::iter::once(&persistent).chain(history.as_ref()) {
+            match std::fs::remove_dir_all(path.as_path()) {
+                Ok(_) => {}
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => {
+                    panic!("Error: {e}")
+                }
             }
         }
     } else if persistent.exists() {
@@ -166,8 +198,7 @@ fn main() {
         state = None;
     }
 
-    let pem = std::fs::read_to_string(pem).expect("Could not read PEM file.");
-    let key = CoseKeyIdentity::from_pem(pem).expect("Could not generate identity from PEM file.");
+    let key = identity_from_pem_file(&pem).expect("Could not generate identity from PEM file.");
     info!(address = key.address().to_string().as_str());
 
     let state: Option<InitialStateJson> =
@@ -241,7 +272,25 @@ fn main() {
     } else {
         panic!("Persistent store or staging file not found.")
     };
-    let module_impl = Arc::new(Mutex::new(module_impl));
+    let module_impl = if let Some(history) = history {
+        module_impl
+            .with_history(history, history_window)
+            .expect("Could not open the history directory.")
+    } else {
+        module_impl
+    };
+    let module_impl = SnapshotLock::new(module_impl);
+    let module_impl = match snapshot {
+        Some(path) if abci => module_impl
+            .with_snapshot(path)
+            .expect("Could not create the snapshot directory."),
+        Some(_) => {
+            warn!("Ignoring --snapshot outside of ABCI mode.");
+            module_impl
+        }
+        None => module_impl,
+    };
+    let module_impl = Arc::new(module_impl);
 
     let many = ManyServer::simple(
         "many-ledger",
@@ -256,8 +305,10 @@ fn main() {
 
     {
         let mut s = many.lock().unwrap();
-        s.add_module(ledger::LedgerModule::new(module_impl.clone()));
-        let ledger_command_module = ledger::LedgerCommandsModule::new(module_impl.clone());
+        let init = ManyAbciModuleBackend::init(&mut *module_impl.write()).unwrap();
+        s.add_query_endpoints(init.queries());
+        s.add_module(ledger::LedgerModule::with_lock(module_impl.clone()));
+        let ledger_command_module = ledger::LedgerCommandsModule::with_lock(module_impl.clone());
         if let Some(path) = allow_addrs {
             let allow_addrs: BTreeSet<Address> =
                 json5::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
@@ -268,11 +319,11 @@ fn main() {
         } else {
             s.add_module(ledger_command_module);
         }
-        s.add_module(events::EventsModule::new(module_impl.clone()));
-        s.add_module(ledger::LedgerTokensModule::new(module_impl.clone()));
-        s.add_module(ledger::LedgerMintBurnModule::new(module_impl.clone()));
+        s.add_module(events::EventsModule::with_lock(module_impl.clone()));
+        s.add_module(ledger::LedgerTokensModule::with_lock(module_impl.clone()));
+        s.add_module(ledger::LedgerMintBurnModule::with_lock(module_impl.clone()));
 
-        let idstore_module = idstore::IdStoreModule::new(module_impl.clone());
+        let idstore_module = idstore::IdStoreModule::with_lock(module_impl.clone());
         #[cfg(feature = "webauthn_testing")]
         {
             let Opts {
@@ -293,24 +344,44 @@ fn main() {
         s.add_module(idstore_module);
 
         s.add_module(AccountFeatureModule::new(
-            account::AccountModule::new(module_impl.clone()),
+            account::AccountModule::with_lock(module_impl.clone()),
             [Feature::with_id(0), Feature::with_id(1)],
         ));
-        s.add_module(account::features::multisig::AccountMultisigModule::new(
-            module_impl.clone(),
-        ));
-        s.add_module(data::DataModule::new(module_impl.clone()));
+        s.add_module(
+            account::features::multisig::AccountMultisigModule::with_lock(module_impl.clone()),
+        );
+        s.add_module(data::DataModule::with_lock(module_impl.clone()));
         if abci {
             s.set_timeout(u64::MAX);
-            s.add_module(abci_backend::AbciModule::new(module_impl));
+            s.add_module(abci_backend::AbciModule::with_lock(module_impl));
         }
 
         if let Some(p) = cache_db {
-            s.add_validator(RequestCacheValidator::new(RocksDbCacheBackend::new(p)));
+            let mut validator =
+                RequestCacheValidator::new(RocksDbCacheBackend::new(p)).with_time_fn(s.time_fn());
+            if abci {
+                // Blocks are not checked against a timeout, so requests are
+                // never forgotten (see `RequestCacheValidator::with_timeout`).
+                validator = validator.with_timeout(u64::MAX);
+            }
+            s.add_validator(validator);
+        }
+
+        if let Some(validator) = rate_limit_flags.validator() {
+            // Blocks must be executed the same way on all nodes, so the frontend
+            // limits the rate instead.
+            if abci {
+                warn!("Ignoring rate limits in ABCI mode; set them on many-abci.");
+            } else {
+                s.add_validator(validator);
+            }
         }
     }
 
-    let mut many_server = HttpServer::new(many);
+    let mut many_server = HttpServer::new(many.clone());
+    http_flags
+        .configure(&mut many_server)
+        .expect("Could not configure the HTTP server");
 
     signal_hook::flag::register(signal_hook::consts::SIGTERM, many_server.term_signal())
         .expect("Could not register signal handler");
@@ -320,5 +391,7 @@ fn main() {
         .expect("Could not register signal handler");
 
     let runtime = tokio::runtime::Runtime::new().unwrap();
-    runtime.block_on(many_server.bind(addr)).unwrap();
+    runtime
+        .block_on(http_flags.bind(&mut many_server, many, addr))
+        .unwrap();
 }
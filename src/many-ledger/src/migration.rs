@@ -7,6 +7,9 @@ pub mod block_9400;
 pub mod data;
 pub mod legacy_remove_roles;
 pub mod memo;
+pub mod mint_policy;
+pub mod spending_limits;
+pub mod token_controls;
 pub mod token_create;
 pub mod tokens;
 
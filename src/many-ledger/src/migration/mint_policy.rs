@@ -0,0 +1,12 @@
+use crate::migration::MIGRATIONS;
+use linkme::distributed_slice;
+use many_error::ManyError;
+use many_migration::InnerMigration;
+
+#[distributed_slice(MIGRATIONS)]
+pub static MINT_POLICY_MIGRATION: InnerMigration<merk::Merk, ManyError> =
+    InnerMigration::new_trigger(
+        false,
+        "Mint Policy Migration",
+        "Enables and enforces the mint policies of tokens",
+    );
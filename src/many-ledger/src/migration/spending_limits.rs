@@ -0,0 +1,12 @@
+use crate::migration::MIGRATIONS;
+use linkme::distributed_slice;
+use many_error::ManyError;
+use many_migration::InnerMigration;
+
+#[distributed_slice(MIGRATIONS)]
+pub static SPENDING_LIMITS_MIGRATION: InnerMigration<merk::Merk, ManyError> =
+    InnerMigration::new_trigger(
+        false,
+        "Spending Limits Migration",
+        "Enforces the spending limits feature of accounts",
+    );
@@ -0,0 +1,12 @@
+use crate::migration::MIGRATIONS;
+use linkme::distributed_slice;
+use many_error::ManyError;
+use many_migration::InnerMigration;
+
+#[distributed_slice(MIGRATIONS)]
+pub static TOKEN_CONTROLS_MIGRATION: InnerMigration<merk::Merk, ManyError> =
+    InnerMigration::new_trigger(
+        false,
+        "Token Controls Migration",
+        "Enables freezing addresses and pausing tokens",
+    );
@@ -19,6 +19,7 @@ mod ledger_commands;
 mod ledger_mintburn;
 mod ledger_tokens;
 mod multisig;
+pub mod snapshot;
 
 /// A simple ledger that keeps transactions in memory.
 #[derive(Debug)]
@@ -54,6 +55,11 @@ impl LedgerModuleImpl {
                 balances,
             )?
             .with_account(state.account_identity, accounts)?
+            .with_account_next_subresource(state.account_next_subresource)?
+            .with_events(state.events)?
+            .with_multisig_transactions(state.multisig_transactions)?
+            .with_usage(state.usage)?
+            .with_data(state.data)?
             .build()?;
 
         if let Some(h) = state.hash {
@@ -86,6 +92,19 @@ impl LedgerModuleImpl {
         Ok(Self { storage })
     }
 
+    /// Keep the state of the last `window` committed blocks in `path` to serve
+    /// queries at a past height.
+    pub fn with_history<P: AsRef<Path>>(self, path: P, window: u64) -> Result<Self, ManyError> {
+        Ok(Self {
+            storage: self.storage.with_history(path, window)?,
+        })
+    }
+
+    /// Export the ledger state in a format that can be loaded back with `new()`.
+    pub fn export_state(&self) -> Result<InitialStateJson, ManyError> {
+        self.storage.export_state()
+    }
+
     #[cfg(feature = "balance_testing")]
     pub fn set_balance_only_for_testing(
         &mut self,
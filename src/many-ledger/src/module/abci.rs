@@ -61,6 +61,11 @@ impl ManyAbciModuleBackend for LedgerModuleImpl {
                 ("tokens.removeExtendedInfo".to_string(), EndpointInfo { is_command : true }),
                 ("tokens.mint".to_string(), EndpointInfo { is_command : true }),
                 ("tokens.burn".to_string(), EndpointInfo { is_command : true }),
+                ("tokens.setMintPolicy".to_string(), EndpointInfo { is_command : true }),
+                ("tokens.freeze".to_string(), EndpointInfo { is_command : true }),
+                ("tokens.unfreeze".to_string(), EndpointInfo { is_command : true }),
+                ("tokens.pause".to_string(), EndpointInfo { is_command : true }),
+                ("tokens.unpause".to_string(), EndpointInfo { is_command : true }),
             ]),
         })
     }
@@ -4,11 +4,12 @@ use many_error::{ManyError, ManyErrorCode};
 use many_identity::Address;
 use many_modules::account::features::{multisig, FeatureId, FeatureInfo, TryCreateFeature};
 use many_modules::account::{Account, AccountModuleBackend, Role};
-use many_modules::{account, EmptyReturn, ManyModule, ManyModuleInfo};
+use many_modules::{account, BackendLock, EmptyReturn, ManyModule, ManyModuleInfo};
 use many_protocol::{context::Context, RequestMessage, ResponseMessage};
 use many_types::cbor::CborAny;
 use std::collections::{BTreeMap, BTreeSet};
 use std::fmt::{Debug, Formatter};
+use std::sync::Mutex;
 
 fn get_roles_for_account(account: &account::Account) -> BTreeSet<account::Role> {
     let features = account.features();
@@ -50,6 +51,12 @@ pub(crate) fn validate_features_for_account(account: &account::Account) -> Resul
         }
     }
 
+    if let Err(e) = features.get::<account::features::spending_limits::SpendingLimitsFeature>() {
+        if e.code() != ManyErrorCode::AttributeNotFound {
+            return Err(e);
+        }
+    }
+
     Ok(())
 }
 
@@ -222,28 +229,30 @@ impl AccountModuleBackend for LedgerModuleImpl {
         args: account::InfoArgs,
         context: Context,
     ) -> Result<account::InfoReturn, ManyError> {
-        self.storage
-            .get_account_even_disabled(&args.account)
-            .and_then(
-                |(
-                    account::Account {
+        let historical = args.height.map(|h| self.storage.at_height(h)).transpose()?;
+        let snapshot = historical.as_ref().map(|checkpoint| checkpoint.read());
+        let storage = snapshot.as_deref().unwrap_or(&self.storage);
+
+        storage.get_account_even_disabled(&args.account).and_then(
+            |(
+                account::Account {
+                    description,
+                    roles,
+                    features,
+                    disabled,
+                },
+                keys,
+            )| {
+                storage
+                    .prove_state(context, keys)
+                    .map(|_| account::InfoReturn {
                         description,
                         roles,
                         features,
                         disabled,
-                    },
-                    keys,
-                )| {
-                    self.storage
-                        .prove_state(context, keys)
-                        .map(|_| account::InfoReturn {
-                            description,
-                            roles,
-                            features,
-                            disabled,
-                        })
-                },
-            )
+                    })
+            },
+        )
     }
 
     fn disable(
@@ -281,14 +290,14 @@ impl AccountModuleBackend for LedgerModuleImpl {
 }
 
 /// A module for returning the features by this account.
-pub struct AccountFeatureModule<T: AccountModuleBackend> {
-    inner: account::AccountModule<T>,
+pub struct AccountFeatureModule<T: AccountModuleBackend, L = Mutex<T>> {
+    inner: account::AccountModule<T, L>,
     info: ManyModuleInfo,
 }
 
-impl<T: AccountModuleBackend> AccountFeatureModule<T> {
+impl<T: AccountModuleBackend, L: BackendLock<T>> AccountFeatureModule<T, L> {
     pub fn new(
-        inner: account::AccountModule<T>,
+        inner: account::AccountModule<T, L>,
         features: impl IntoIterator<Item = account::features::Feature>,
     ) -> Self {
         let mut info: ManyModuleInfo = inner.info().clone();
@@ -303,14 +312,14 @@ impl<T: AccountModuleBackend> AccountFeatureModule<T> {
     }
 }
 
-impl<T: AccountModuleBackend> Debug for AccountFeatureModule<T> {
+impl<T: AccountModuleBackend, L> Debug for AccountFeatureModule<T, L> {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         f.write_str("AccountFeatureModule")
     }
 }
 
 #[async_trait::async_trait]
-impl<T: AccountModuleBackend> ManyModule for AccountFeatureModule<T> {
+impl<T: AccountModuleBackend, L: BackendLock<T>> ManyModule for AccountFeatureModule<T, L> {
     fn info(&self) -> &ManyModuleInfo {
         &self.info
     }
@@ -1,24 +1,27 @@
 use coset::CoseSign1;
 use many_error::ManyError;
 use many_identity::Address;
-use many_modules::{ledger, ManyModule, ManyModuleInfo};
+use many_modules::{ledger, BackendLock, ManyModule, ManyModuleInfo};
 use many_protocol::{RequestMessage, ResponseMessage};
 use std::collections::BTreeSet;
 use std::fmt::{Debug, Formatter};
+use std::sync::Mutex;
 
-pub struct AllowAddrsModule<T: ledger::LedgerCommandsModuleBackend> {
-    pub inner: ledger::LedgerCommandsModule<T>,
+pub struct AllowAddrsModule<T: ledger::LedgerCommandsModuleBackend, L = Mutex<T>> {
+    pub inner: ledger::LedgerCommandsModule<T, L>,
     pub allow_addrs: BTreeSet<Address>,
 }
 
-impl<T: ledger::LedgerCommandsModuleBackend> Debug for AllowAddrsModule<T> {
+impl<T: ledger::LedgerCommandsModuleBackend, L> Debug for AllowAddrsModule<T, L> {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         f.write_str("AllowAddrsModule")
     }
 }
 
 #[async_trait::async_trait]
-impl<T: ledger::LedgerCommandsModuleBackend> ManyModule for AllowAddrsModule<T> {
+impl<T: ledger::LedgerCommandsModuleBackend, L: BackendLock<T>> ManyModule
+    for AllowAddrsModule<T, L>
+{
     fn info(&self) -> &ManyModuleInfo {
         self.inner.info()
     }
@@ -1,22 +1,25 @@
 use coset::CoseSign1;
 use many_error::ManyError;
-use many_modules::{idstore, ManyModule, ManyModuleInfo};
+use many_modules::{idstore, BackendLock, ManyModule, ManyModuleInfo};
 use many_protocol::{RequestMessage, ResponseMessage};
 use std::fmt::{Debug, Formatter};
+use std::sync::Mutex;
 
-pub struct IdStoreWebAuthnModule<T: idstore::IdStoreModuleBackend> {
-    pub inner: idstore::IdStoreModule<T>,
+pub struct IdStoreWebAuthnModule<T: idstore::IdStoreModuleBackend, L = Mutex<T>> {
+    pub inner: idstore::IdStoreModule<T, L>,
     pub check_webauthn: bool,
 }
 
-impl<T: idstore::IdStoreModuleBackend> Debug for IdStoreWebAuthnModule<T> {
+impl<T: idstore::IdStoreModuleBackend, L> Debug for IdStoreWebAuthnModule<T, L> {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         f.write_str("IdStoreWebAuthnModule")
     }
 }
 
 #[async_trait::async_trait]
-impl<T: idstore::IdStoreModuleBackend> ManyModule for IdStoreWebAuthnModule<T> {
+impl<T: idstore::IdStoreModuleBackend, L: BackendLock<T>> ManyModule
+    for IdStoreWebAuthnModule<T, L>
+{
     fn info(&self) -> &ManyModuleInfo {
         self.inner.info()
     }
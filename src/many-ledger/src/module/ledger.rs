@@ -41,12 +41,18 @@ impl ledger::LedgerModuleBackend for LedgerModuleImpl {
     fn balance(
         &self,
         sender: &Address,
-        ledger::BalanceArgs { account, symbols }: ledger::BalanceArgs,
+        ledger::BalanceArgs {
+            account,
+            symbols,
+            height,
+        }: ledger::BalanceArgs,
         context: Context,
     ) -> Result<ledger::BalanceReturns, ManyError> {
         let identity = account.as_ref().unwrap_or(sender);
 
-        let storage = &self.storage;
+        let historical = height.map(|h| self.storage.at_height(h)).transpose()?;
+        let snapshot = historical.as_ref().map(|checkpoint| checkpoint.read());
+        let storage = snapshot.as_deref().unwrap_or(&self.storage);
         let symbols = symbols.unwrap_or_default().0;
 
         let (balances, keys) = storage
@@ -40,7 +40,7 @@ impl ledger::LedgerCommandsModuleBackend for LedgerModuleImpl {
         }
 
         self.storage
-            .send(from, &to, &symbol, amount, memo)
+            .send(sender, from, &to, &symbol, amount, memo)
             .map(|_| EmptyReturn)
     }
 }
@@ -1,12 +1,20 @@
 use crate::error;
+use crate::migration::mint_policy::MINT_POLICY_MIGRATION;
 use crate::migration::tokens::TOKEN_MIGRATION;
 use crate::module::LedgerModuleImpl;
+use crate::storage::account::verify_acl;
 use crate::storage::ledger_tokens::verify_tokens_sender;
 use many_error::ManyError;
 use many_identity::Address;
+use many_modules::account::features::tokens::TokenAccountLedger;
+use many_modules::account::features::TryCreateFeature;
+use many_modules::account::Role;
 use many_modules::events::EventInfo;
 use many_modules::ledger;
-use many_modules::ledger::{TokenBurnArgs, TokenBurnReturns, TokenMintArgs, TokenMintReturns};
+use many_modules::ledger::{
+    TokenBurnArgs, TokenBurnReturns, TokenMintArgs, TokenMintReturns, TokenSetMintPolicyArgs,
+    TokenSetMintPolicyReturns,
+};
 use many_types::ledger::Symbol;
 use std::collections::BTreeSet;
 
@@ -35,12 +43,13 @@ impl ledger::LedgerMintBurnModuleBackend for LedgerModuleImpl {
             memo,
         } = args;
 
+        // The mint policy, if any, only restricts identities which can already mint
         self.verify_mint_burn_identity(sender, &symbol)?;
 
         check_symbol_exists(&symbol, self.storage.get_symbols()?)?;
 
         // Mint into storage
-        let _ = self.storage.mint_token(symbol, &distribution)?;
+        let _ = self.storage.mint_token(sender, symbol, &distribution)?;
 
         // Log event
         self.storage
@@ -91,6 +100,41 @@ impl ledger::LedgerMintBurnModuleBackend for LedgerModuleImpl {
             })
             .map(|_| TokenBurnReturns { distribution })
     }
+
+    fn set_mint_policy(
+        &mut self,
+        sender: &Address,
+        args: TokenSetMintPolicyArgs,
+    ) -> Result<TokenSetMintPolicyReturns, ManyError> {
+        if !self.storage.migrations().is_active(&MINT_POLICY_MIGRATION) {
+            return Err(ManyError::invalid_method_name("tokens.setMintPolicy"));
+        }
+
+        let TokenSetMintPolicyArgs { symbol, policy } = args;
+
+        check_symbol_exists(&symbol, self.storage.get_symbols()?)?;
+
+        // Only the token owner can change the mint policy
+        match self.storage.get_owner(&symbol)? {
+            (Some(owner), _) => {
+                verify_acl(
+                    &self.storage,
+                    sender,
+                    &owner,
+                    [Role::Owner],
+                    TokenAccountLedger::ID,
+                )?;
+            }
+            (None, _) => return Err(error::no_token_owner()),
+        }
+
+        self.storage.set_mint_policy(&symbol, policy.clone())?;
+
+        // Log event
+        self.storage
+            .log_event(EventInfo::TokenSetMintPolicy { symbol, policy })
+            .map(|_| TokenSetMintPolicyReturns {})
+    }
 }
 
 impl LedgerModuleImpl {
@@ -1,4 +1,5 @@
 use crate::error;
+use crate::migration::token_controls::TOKEN_CONTROLS_MIGRATION;
 use crate::migration::token_create::TOKEN_CREATE_MIGRATION;
 use crate::migration::tokens::TOKEN_MIGRATION;
 use crate::module::LedgerModuleImpl;
@@ -10,10 +11,12 @@ use many_modules::account::features::TryCreateFeature;
 use many_modules::account::Role;
 use many_modules::ledger::{
     LedgerTokensModuleBackend, TokenAddExtendedInfoArgs, TokenAddExtendedInfoReturns,
-    TokenCreateArgs, TokenCreateReturns, TokenInfoArgs, TokenInfoReturns,
-    TokenRemoveExtendedInfoArgs, TokenRemoveExtendedInfoReturns, TokenUpdateArgs,
-    TokenUpdateReturns,
+    TokenCreateArgs, TokenCreateReturns, TokenFreezeArgs, TokenFreezeReturns, TokenInfoArgs,
+    TokenInfoReturns, TokenPauseArgs, TokenPauseReturns, TokenRemoveExtendedInfoArgs,
+    TokenRemoveExtendedInfoReturns, TokenUnfreezeArgs, TokenUnfreezeReturns, TokenUnpauseArgs,
+    TokenUnpauseReturns, TokenUpdateArgs, TokenUpdateReturns,
 };
+use many_types::ledger::Symbol;
 use many_types::Either;
 
 fn check_ticker_length(ticker: &String) -> Result<(), ManyError> {
@@ -76,13 +79,17 @@ impl LedgerTokensModuleBackend for LedgerModuleImpl {
             return Err(ManyError::invalid_method_name("tokens.info"));
         }
 
+        let historical = args.height.map(|h| self.storage.at_height(h)).transpose()?;
+        let snapshot = historical.as_ref().map(|checkpoint| checkpoint.read());
+        let storage = snapshot.as_deref().unwrap_or(&self.storage);
+
         let symbol = &args.symbol;
-        if !self.storage.get_symbols()?.contains(symbol) {
+        if !storage.get_symbols()?.contains(symbol) {
             return Err(ManyError::unknown(format!(
                 "The symbol {symbol} was not found"
             )));
         }
-        self.storage.info_token(args)
+        storage.info_token(args)
     }
 
     fn update(
@@ -190,4 +197,121 @@ impl LedgerTokensModuleBackend for LedgerModuleImpl {
         let (result, _) = self.storage.remove_extended_info(args)?;
         Ok(result)
     }
+
+    fn freeze(
+        &mut self,
+        sender: &Address,
+        args: TokenFreezeArgs,
+    ) -> Result<TokenFreezeReturns, ManyError> {
+        if !self
+            .storage
+            .migrations()
+            .is_active(&TOKEN_CONTROLS_MIGRATION)
+        {
+            return Err(ManyError::invalid_method_name("tokens.freeze"));
+        }
+
+        let TokenFreezeArgs {
+            symbol,
+            address,
+            memo,
+        } = args;
+        self.verify_token_controls_sender(sender, &symbol)?;
+        self.storage
+            .freeze(symbol, address, memo)
+            .map(|_| TokenFreezeReturns {})
+    }
+
+    fn unfreeze(
+        &mut self,
+        sender: &Address,
+        args: TokenUnfreezeArgs,
+    ) -> Result<TokenUnfreezeReturns, ManyError> {
+        if !self
+            .storage
+            .migrations()
+            .is_active(&TOKEN_CONTROLS_MIGRATION)
+        {
+            return Err(ManyError::invalid_method_name("tokens.unfreeze"));
+        }
+
+        let TokenUnfreezeArgs {
+            symbol,
+            address,
+            memo,
+        } = args;
+        self.verify_token_controls_sender(sender, &symbol)?;
+        self.storage
+            .unfreeze(symbol, address, memo)
+            .map(|_| TokenUnfreezeReturns {})
+    }
+
+    fn pause(
+        &mut self,
+        sender: &Address,
+        args: TokenPauseArgs,
+    ) -> Result<TokenPauseReturns, ManyError> {
+        if !self
+            .storage
+            .migrations()
+            .is_active(&TOKEN_CONTROLS_MIGRATION)
+        {
+            return Err(ManyError::invalid_method_name("tokens.pause"));
+        }
+
+        let TokenPauseArgs { symbol, memo } = args;
+        self.verify_token_controls_sender(sender, &symbol)?;
+        self.storage
+            .pause(symbol, memo)
+            .map(|_| TokenPauseReturns {})
+    }
+
+    fn unpause(
+        &mut self,
+        sender: &Address,
+        args: TokenUnpauseArgs,
+    ) -> Result<TokenUnpauseReturns, ManyError> {
+        if !self
+            .storage
+            .migrations()
+            .is_active(&TOKEN_CONTROLS_MIGRATION)
+        {
+            return Err(ManyError::invalid_method_name("tokens.unpause"));
+        }
+
+        let TokenUnpauseArgs { symbol, memo } = args;
+        self.verify_token_controls_sender(sender, &symbol)?;
+        self.storage
+            .unpause(symbol, memo)
+            .map(|_| TokenUnpauseReturns {})
+    }
+}
+
+impl LedgerModuleImpl {
+    /// Only the token owner is allowed to freeze addresses and pause a symbol
+    fn verify_token_controls_sender(
+        &self,
+        sender: &Address,
+        symbol: &Symbol,
+    ) -> Result<(), ManyError> {
+        if !self.storage.get_symbols()?.contains(symbol) {
+            return Err(ManyError::unknown(format!(
+                "The symbol {symbol} was not found"
+            )));
+        }
+
+        match self.storage.get_owner(symbol)? {
+            (Some(owner), _) => verify_acl(
+                &self.storage,
+                sender,
+                &owner,
+                [Role::Owner],
+                TokenAccountLedger::ID,
+            )
+            .map(|_| ()),
+            (None, _) => Err(ManyError::unknown(
+                "Unable to update, this token is immutable",
+            )),
+        }
+    }
 }
@@ -0,0 +1,127 @@
+use crate::error;
+use crate::module::LedgerModuleImpl;
+use many_error::ManyError;
+use many_modules::BackendLock;
+use std::ops::{Deref, DerefMut};
+use std::path::{Path, PathBuf};
+use std::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+/// Shares the ledger between the modules of the server. Commands use the
+/// ledger itself. Queries run concurrently and, once snapshots are enabled,
+/// are served from a snapshot of the last committed block so they never wait
+/// for the commands of the block being executed.
+pub struct SnapshotLock {
+    live: RwLock<LedgerModuleImpl>,
+    snapshot: Option<Snapshot>,
+}
+
+struct Snapshot {
+    /// Directory containing the snapshot, named after its height.
+    path: PathBuf,
+    module: RwLock<LedgerModuleImpl>,
+}
+
+impl Snapshot {
+    /// Replace the snapshot if `live` committed a new block since it was taken.
+    fn refresh(&self, live: &LedgerModuleImpl) -> Result<(), ManyError> {
+        let height = live.storage.get_height()?;
+        let current = self.module.read().unwrap().storage.get_height()?;
+        if height == current {
+            return Ok(());
+        }
+
+        let storage = live.storage.snapshot(&self.path.join(height.to_string()))?;
+        let previous = std::mem::replace(
+            &mut *self.module.write().unwrap(),
+            LedgerModuleImpl { storage },
+        );
+
+        // Close the previous snapshot before deleting its files.
+        drop(previous);
+        std::fs::remove_dir_all(self.path.join(current.to_string()))
+            .map_err(error::storage_checkpoint_failed)
+    }
+}
+
+impl SnapshotLock {
+    pub fn new(module_impl: LedgerModuleImpl) -> Self {
+        Self {
+            live: RwLock::new(module_impl),
+            snapshot: None,
+        }
+    }
+
+    /// Serve the queries from a snapshot of the last committed block, kept in
+    /// the `path` directory. The snapshot is taken again after every commit,
+    /// so this is only meant for blockchain mode.
+    pub fn with_snapshot<P: AsRef<Path>>(mut self, path: P) -> Result<Self, ManyError> {
+        let path = path.as_ref().to_path_buf();
+        match std::fs::remove_dir_all(&path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                return Err(error::storage_checkpoint_failed(e));
+            }
+            _ => {}
+        }
+        std::fs::create_dir_all(&path).map_err(error::storage_checkpoint_failed)?;
+
+        let live = self.live.get_mut().unwrap();
+        let height = live.storage.get_height()?;
+        let storage = live.storage.snapshot(&path.join(height.to_string()))?;
+        self.snapshot = Some(Snapshot {
+            path,
+            module: RwLock::new(LedgerModuleImpl { storage }),
+        });
+        Ok(self)
+    }
+}
+
+/// A guard giving exclusive access to the ledger, which refreshes the snapshot
+/// when it is released.
+pub struct SnapshotWrite<'a> {
+    live: RwLockWriteGuard<'a, LedgerModuleImpl>,
+    snapshot: Option<&'a Snapshot>,
+}
+
+impl Deref for SnapshotWrite<'_> {
+    type Target = LedgerModuleImpl;
+
+    fn deref(&self) -> &LedgerModuleImpl {
+        &self.live
+    }
+}
+
+impl DerefMut for SnapshotWrite<'_> {
+    fn deref_mut(&mut self) -> &mut LedgerModuleImpl {
+        &mut self.live
+    }
+}
+
+impl Drop for SnapshotWrite<'_> {
+    fn drop(&mut self) {
+        if let Some(snapshot) = self.snapshot {
+            if let Err(e) = snapshot.refresh(&self.live) {
+                tracing::error!("Could not refresh the query snapshot: {e}");
+            }
+        }
+    }
+}
+
+impl BackendLock<LedgerModuleImpl> for SnapshotLock {
+    type Read<'a> = RwLockReadGuard<'a, LedgerModuleImpl>;
+    type Write<'a> = SnapshotWrite<'a>;
+
+    fn read(&self) -> RwLockReadGuard<'_, LedgerModuleImpl> {
+        self.snapshot
+            .as_ref()
+            .map_or(&self.live, |snapshot| &snapshot.module)
+            .read()
+            .unwrap()
+    }
+
+    fn write(&self) -> SnapshotWrite<'_> {
+        SnapshotWrite {
+            live: self.live.write().unwrap(),
+            snapshot: self.snapshot.as_ref(),
+        }
+    }
+}
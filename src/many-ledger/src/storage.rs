@@ -12,11 +12,14 @@ use many_types::Timestamp;
 use merk::Op;
 use std::collections::{BTreeMap, BTreeSet};
 use std::path::Path;
+use std::sync::Arc;
 
 mod abci;
 pub mod account;
 pub mod data;
 pub mod event;
+mod export;
+mod history;
 pub(crate) mod idstore;
 pub mod iterator;
 mod ledger;
@@ -25,13 +28,16 @@ pub mod ledger_mintburn;
 pub mod ledger_tokens;
 mod migrations;
 pub mod multisig;
+mod spending_limits;
+pub mod token_controls;
 
 pub const SYMBOLS_ROOT: &str = "/config/symbols";
 pub const IDENTITY_ROOT: &str = "/config/identity";
 pub const HEIGHT_ROOT: &str = "/height";
+pub const BALANCES_ROOT: &str = "/balances/";
 
 pub(super) fn key_for_account_balance(id: &Address, symbol: &Symbol) -> Vec<u8> {
-    format!("/balances/{id}/{symbol}").into_bytes()
+    format!("{BALANCES_ROOT}{id}/{symbol}").into_bytes()
 }
 
 pub(super) fn key_for_subresource_counter(id: &Address, token_migration_active: bool) -> Vec<u8> {
@@ -59,6 +65,11 @@ pub struct LedgerStorage {
     current_hash: Option<Vec<u8>>,
 
     migrations: LedgerMigrations,
+    migration_config: Option<MigrationConfig>,
+
+    /// Shared with the snapshots of this storage, so they can also serve
+    /// queries at a past height.
+    history: Option<Arc<history::History>>,
 }
 
 impl LedgerStorage {
@@ -153,6 +164,7 @@ impl LedgerStorage {
         // a transaction.
         let latest_tid = EventId::from(height.saturating_sub(1) << HEIGHT_EVENTID_SHIFT);
         let migrations = migration_config
+            .clone()
             .map_or_else(MigrationSet::empty, |config| {
                 LedgerMigrations::load(&MIGRATIONS, config, height)
             })
@@ -165,6 +177,8 @@ impl LedgerStorage {
             current_time: None,
             current_hash: None,
             migrations,
+            migration_config,
+            history: None,
         })
     }
 
@@ -178,6 +192,8 @@ impl LedgerStorage {
             current_time: None,
             current_hash: None,
             migrations: MigrationSet::empty().map_err(ManyError::unknown)?, // TODO: Custom error
+            migration_config: None,
+            history: None,
         })
     }
 
@@ -24,6 +24,10 @@ impl LedgerStorage {
 
         self.commit_storage().expect("Unable to commit to storage.");
 
+        // Keep a snapshot of the committed block for historical queries, if enabled.
+        self.checkpoint(height + 1)
+            .expect("Unable to create storage checkpoint.");
+
         let hash = self.persistent_store.root_hash().to_vec();
         self.current_hash = Some(hash.clone());
 
@@ -6,8 +6,8 @@ use crate::storage::multisig::{
     MULTISIG_DEFAULT_EXECUTE_AUTOMATICALLY, MULTISIG_DEFAULT_TIMEOUT_IN_SECS,
     MULTISIG_MAXIMUM_TIMEOUT_IN_SECS,
 };
-use crate::storage::{LedgerStorage, IDENTITY_ROOT};
-use many_error::ManyError;
+use crate::storage::{key_for_subresource_counter, LedgerStorage, IDENTITY_ROOT};
+use many_error::{ManyError, Reason};
 use many_identity::Address;
 use many_modules::account::features::{FeatureId, FeatureInfo, FeatureSet};
 use many_modules::account::Role;
@@ -18,6 +18,7 @@ use std::collections::{BTreeMap, BTreeSet};
 
 pub const ACCOUNT_IDENTITY_ROOT: &str = "/config/account_identity";
 pub const ACCOUNT_SUBRESOURCE_ID_ROOT: &str = "/config/account_id";
+pub const ACCOUNTS_ROOT: &str = "/accounts/";
 
 /// Internal representation of Account metadata
 #[derive(Clone, Debug)]
@@ -27,10 +28,11 @@ pub struct AccountMeta {
     pub description: Option<String>,
     pub roles: BTreeMap<Address, BTreeSet<Role>>,
     pub features: FeatureSet,
+    pub disabled: Option<Either<bool, Reason<u64>>>,
 }
 
 pub(super) fn key_for_account(id: &Address) -> Vec<u8> {
-    format!("/accounts/{id}").into_bytes()
+    format!("{ACCOUNTS_ROOT}{id}").into_bytes()
 }
 
 pub fn verify_acl(
@@ -74,7 +76,7 @@ impl LedgerStorage {
                         description: account.description.clone(),
                         roles: account.roles,
                         features: account.features,
-                        disabled: None,
+                        disabled: account.disabled,
                     },
                     false,
                 )?;
@@ -98,6 +100,32 @@ impl LedgerStorage {
         Ok(self)
     }
 
+    /// Override the next account subresource ID.
+    /// Used when restoring an exported state where the account counter is ahead of the
+    /// last created account.
+    pub fn with_account_next_subresource(mut self, next: Option<u32>) -> Result<Self, ManyError> {
+        if let Some(next) = next {
+            let identity = self
+                .persistent_store
+                .get(ACCOUNT_IDENTITY_ROOT.as_bytes())
+                .map_err(error::storage_get_failed)?
+                .map_or_else(
+                    || self.get_identity(IDENTITY_ROOT),
+                    |b| Address::from_bytes(&b),
+                )?;
+            self.persistent_store
+                .apply(&[(
+                    key_for_subresource_counter(
+                        &identity,
+                        self.migrations.is_active(&TOKEN_MIGRATION),
+                    ),
+                    Op::Put(next.to_be_bytes().to_vec()),
+                )])
+                .map_err(error::storage_apply_failed)?;
+        }
+        Ok(self)
+    }
+
     pub(crate) fn _add_account(
         &mut self,
         mut account: account::Account,
@@ -8,6 +8,7 @@ use many_types::ledger::TokenAmount;
 use merk::Op;
 use std::collections::BTreeMap;
 
+pub const DATA_ROOT: &[u8] = b"/data/";
 pub const DATA_ATTRIBUTES_KEY: &[u8] = b"/data/attributes";
 pub const DATA_INFO_KEY: &[u8] = b"/data/info";
 
@@ -0,0 +1,309 @@
+use crate::error;
+use crate::json::{AccountJson, FeatureJson, InitialStateJson, SymbolMetaJson};
+use crate::migration::tokens::TOKEN_MIGRATION;
+use crate::storage::account::{ACCOUNTS_ROOT, ACCOUNT_IDENTITY_ROOT};
+use crate::storage::data::DATA_ROOT;
+use crate::storage::event::{EVENTS_ROOT, EVENT_COUNT_ROOT};
+use crate::storage::idstore::IDSTORE_SEED_ROOT;
+use crate::storage::iterator::LedgerIterator;
+use crate::storage::ledger_mintburn::MINT_USAGE_ROOT;
+use crate::storage::ledger_tokens::{key_for_ext_info, SYMBOLS_ROOT_DASH, TOKEN_IDENTITY_ROOT};
+use crate::storage::multisig::MULTISIG_TRANSACTIONS_ROOT;
+use crate::storage::spending_limits::SPENDING_ROOT;
+use crate::storage::{LedgerStorage, BALANCES_ROOT, IDENTITY_ROOT};
+use base64::{engine::general_purpose, Engine as _};
+use many_error::ManyError;
+use many_identity::Address;
+use many_modules::account::Account;
+use many_modules::events::EventLog;
+use many_types::ledger::{TokenAmount, TokenInfo};
+use many_types::{Either, SortOrder};
+use merk::Op;
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+/// The storage prefixes of the events and the event count.
+const EVENTS_PREFIXES: &[&[u8]] = &[EVENTS_ROOT, EVENT_COUNT_ROOT];
+
+/// The storage prefixes of the multisig transactions.
+const MULTISIG_PREFIXES: &[&[u8]] = &[MULTISIG_TRANSACTIONS_ROOT];
+
+/// The storage prefixes of the spending limits and mint rate limits usage.
+const USAGE_PREFIXES: &[&[u8]] = &[SPENDING_ROOT.as_bytes(), MINT_USAGE_ROOT.as_bytes()];
+
+/// The storage prefixes of the data attributes.
+const DATA_PREFIXES: &[&[u8]] = &[DATA_ROOT];
+
+fn key_suffix<'a>(key: &'a [u8], prefix: &str) -> Result<&'a str, ManyError> {
+    std::str::from_utf8(&key[prefix.len()..]).map_err(ManyError::deserialization_error)
+}
+
+fn decode_u32(bytes: Vec<u8>) -> Result<u32, ManyError> {
+    Ok(u32::from_be_bytes(bytes.try_into().map_err(|_| {
+        ManyError::deserialization_error("Invalid subresource counter")
+    })?))
+}
+
+fn decode_u64(bytes: Vec<u8>) -> Result<u64, ManyError> {
+    Ok(u64::from_be_bytes(bytes.try_into().map_err(|_| {
+        ManyError::deserialization_error("Invalid idstore seed")
+    })?))
+}
+
+fn decode_base64(value: &str) -> Result<Vec<u8>, ManyError> {
+    general_purpose::STANDARD
+        .decode(value)
+        .map_err(ManyError::deserialization_error)
+}
+
+impl LedgerStorage {
+    /// Export the current ledger state in the initial state format.
+    ///
+    /// The export includes the identities, symbols and their metadata (including
+    /// extended info, mint policy and token controls), all balances, accounts
+    /// (roles, features and disabled status or reason), the idstore, the subresource
+    /// counters, the events, the multisig transactions, the spending limits and mint
+    /// rate limits usage and the data attributes.
+    /// Loading the result with `LedgerModuleImpl::new()` (using the same migrations)
+    /// produces a storage with the same root hash. The block height is not part of the
+    /// export.
+    pub fn export_state(&self) -> Result<InitialStateJson, ManyError> {
+        let identity = self.get_identity(IDENTITY_ROOT)?;
+        let symbols = self.get_symbols_and_tickers()?;
+
+        let mut initial: BTreeMap<Address, BTreeMap<String, TokenAmount>> = BTreeMap::new();
+        for item in LedgerIterator::all_balances(&self.persistent_store) {
+            let (key, value) = item.map_err(error::storage_get_failed)?;
+            let (id, symbol) = key_suffix(&key, BALANCES_ROOT)?
+                .split_once('/')
+                .ok_or_else(|| ManyError::deserialization_error("Invalid balance key"))?;
+            initial
+                .entry(Address::from_str(id)?)
+                .or_default()
+                .insert(symbol.to_string(), TokenAmount::from(value));
+        }
+
+        let mut symbols_meta = BTreeMap::new();
+        for item in LedgerIterator::all_symbols(&self.persistent_store, SortOrder::Ascending) {
+            let (key, value) = item.map_err(error::storage_get_failed)?;
+            let symbol = Address::from_str(key_suffix(&key, SYMBOLS_ROOT_DASH)?)?;
+            let info: TokenInfo =
+                minicbor::decode(&value).map_err(ManyError::deserialization_error)?;
+            let extended_info = self
+                .persistent_store
+                .get(&key_for_ext_info(&symbol))
+                .map_err(error::storage_get_failed)?
+                .map(|bytes| general_purpose::STANDARD.encode(bytes));
+
+            let frozen = self.get_frozen(&symbol)?;
+            symbols_meta.insert(
+                symbol,
+                SymbolMetaJson {
+                    name: info.summary.name,
+                    decimals: info.summary.decimals,
+                    owner: info.owner,
+                    maximum: info.supply.maximum,
+                    extended_info,
+                    mint_policy: self.get_mint_policy(&symbol)?.map(Into::into),
+                    paused: self.is_paused(&symbol)?.then_some(true),
+                    frozen: (!frozen.is_empty()).then_some(frozen),
+                },
+            );
+        }
+
+        // Subresource counters are keyed by identity only after the token migration.
+        let token_migration_active = self.migrations.is_active(&TOKEN_MIGRATION);
+        let token_identity = self.get_optional_identity(TOKEN_IDENTITY_ROOT)?;
+        let token_next_subresource = token_identity
+            .map(|id| self.get_raw_subresource_counter(&id, token_migration_active))
+            .transpose()?
+            .flatten();
+
+        // Same fallback as `with_account_next_subresource()`.
+        let account_identity = self.get_optional_identity(ACCOUNT_IDENTITY_ROOT)?;
+        let account_next_subresource = self.get_raw_subresource_counter(
+            &account_identity.unwrap_or(identity),
+            token_migration_active,
+        )?;
+
+        let mut accounts = Vec::new();
+        for item in LedgerIterator::all_accounts(&self.persistent_store) {
+            let (key, value) = item.map_err(error::storage_get_failed)?;
+            let id = Address::from_str(key_suffix(&key, ACCOUNTS_ROOT)?)?;
+            let account: Account =
+                minicbor::decode(&value).map_err(ManyError::deserialization_error)?;
+
+            let features = account
+                .features
+                .iter()
+                .map(|f| {
+                    FeatureJson::try_from_feature(f).ok_or_else(|| {
+                        ManyError::unknown(format!("Unsupported feature {} for {id}", f.id()))
+                    })
+                })
+                .collect::<Result<_, _>>()?;
+
+            accounts.push(AccountJson {
+                id: Some(id),
+                subresource_id: id.subresource_id(),
+                description: account.description,
+                roles: account
+                    .roles
+                    .into_iter()
+                    .map(|(addr, roles)| (addr, roles.iter().map(|r| r.to_string()).collect()))
+                    .collect(),
+                features,
+                disabled: account.disabled.as_ref().map(|d| match d {
+                    Either::Left(disabled) => *disabled,
+                    Either::Right(_) => true,
+                }),
+                disabled_reason: match account.disabled {
+                    Some(Either::Right(reason)) => Some(reason.into()),
+                    _ => None,
+                },
+            });
+        }
+        // Accounts need to be re-created in the order they were created.
+        accounts.sort_by_key(|a| a.subresource_id);
+
+        let id_store_seed = self
+            .persistent_store
+            .get(IDSTORE_SEED_ROOT)
+            .map_err(error::storage_get_failed)?
+            .map(decode_u64)
+            .transpose()?;
+
+        let mut id_store_keys = BTreeMap::new();
+        for item in LedgerIterator::all_idstore(&self.persistent_store) {
+            let (key, value) = item.map_err(error::storage_get_failed)?;
+            id_store_keys.insert(
+                general_purpose::STANDARD.encode(key),
+                general_purpose::STANDARD.encode(value),
+            );
+        }
+
+        Ok(InitialStateJson {
+            identity,
+            initial,
+            token_identity,
+            account_identity,
+            token_next_subresource,
+            account_next_subresource,
+            symbols,
+            symbols_meta: (!symbols_meta.is_empty()).then_some(symbols_meta),
+            accounts: (!accounts.is_empty()).then_some(accounts),
+            id_store_seed,
+            id_store_keys: (!id_store_keys.is_empty()).then_some(id_store_keys),
+            events: self.export_entries(EVENTS_PREFIXES)?,
+            multisig_transactions: self.export_entries(MULTISIG_PREFIXES)?,
+            usage: self.export_entries(USAGE_PREFIXES)?,
+            data: self.export_entries(DATA_PREFIXES)?,
+            hash: None,
+        })
+    }
+
+    /// Restore the exported events and event count.
+    pub fn with_events(self, entries: Option<BTreeMap<String, String>>) -> Result<Self, ManyError> {
+        self.with_entries(EVENTS_PREFIXES, entries)
+    }
+
+    /// Restore the exported multisig transactions.
+    pub fn with_multisig_transactions(
+        self,
+        entries: Option<BTreeMap<String, String>>,
+    ) -> Result<Self, ManyError> {
+        self.with_entries(MULTISIG_PREFIXES, entries)
+    }
+
+    /// Restore the exported spending limits and mint rate limits usage.
+    pub fn with_usage(self, entries: Option<BTreeMap<String, String>>) -> Result<Self, ManyError> {
+        self.with_entries(USAGE_PREFIXES, entries)
+    }
+
+    /// Restore the exported data attributes.
+    pub fn with_data(self, entries: Option<BTreeMap<String, String>>) -> Result<Self, ManyError> {
+        self.with_entries(DATA_PREFIXES, entries)
+    }
+
+    /// Restore storage entries exported with `export_entries()`. Every key needs to
+    /// start with one of `prefixes`.
+    fn with_entries(
+        mut self,
+        prefixes: &[&[u8]],
+        entries: Option<BTreeMap<String, String>>,
+    ) -> Result<Self, ManyError> {
+        let Some(entries) = entries else {
+            return Ok(self);
+        };
+
+        let mut batch = Vec::new();
+        for (key, value) in entries {
+            let key = decode_base64(&key)?;
+            if !prefixes.iter().any(|prefix| key.starts_with(prefix)) {
+                return Err(ManyError::unknown(format!(
+                    "Unexpected storage key {}",
+                    String::from_utf8_lossy(&key)
+                )));
+            }
+            batch.push((key, Op::Put(decode_base64(&value)?)));
+        }
+        batch.sort_by(|(k1, _), (k2, _)| k1.cmp(k2));
+
+        // Keep numbering events after the last imported one.
+        if let Some((_, Op::Put(value))) = batch
+            .iter()
+            .filter(|(key, _)| key.starts_with(EVENTS_ROOT))
+            .last()
+        {
+            let event: EventLog =
+                minicbor::decode(value).map_err(ManyError::deserialization_error)?;
+            self.latest_tid = event.id;
+        }
+
+        self.persistent_store
+            .apply(&batch)
+            .map_err(error::storage_apply_failed)?;
+        Ok(self)
+    }
+
+    /// All the entries of the storage under `prefixes`, as base64 keys and values.
+    fn export_entries(
+        &self,
+        prefixes: &[&[u8]],
+    ) -> Result<Option<BTreeMap<String, String>>, ManyError> {
+        let mut entries = BTreeMap::new();
+        for prefix in prefixes {
+            for item in LedgerIterator::all_prefixed(&self.persistent_store, prefix) {
+                let (key, value) = item.map_err(error::storage_get_failed)?;
+                entries.insert(
+                    general_purpose::STANDARD.encode(key),
+                    general_purpose::STANDARD.encode(value),
+                );
+            }
+        }
+        Ok((!entries.is_empty()).then_some(entries))
+    }
+
+    fn get_optional_identity(&self, identity_root: &str) -> Result<Option<Address>, ManyError> {
+        self.persistent_store
+            .get(identity_root.as_bytes())
+            .map_err(error::storage_get_failed)?
+            .map(|bytes| Address::from_bytes(&bytes))
+            .transpose()
+    }
+
+    fn get_raw_subresource_counter(
+        &self,
+        id: &Address,
+        token_migration_active: bool,
+    ) -> Result<Option<u32>, ManyError> {
+        self.persistent_store
+            .get(&super::key_for_subresource_counter(
+                id,
+                token_migration_active,
+            ))
+            .map_err(error::storage_get_failed)?
+            .map(decode_u32)
+            .transpose()
+    }
+}
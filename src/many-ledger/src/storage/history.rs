@@ -0,0 +1,238 @@
+use crate::error;
+use crate::storage::LedgerStorage;
+use lru::LruCache;
+use many_error::ManyError;
+use std::collections::BTreeMap;
+use std::num::NonZeroUsize;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, Weak};
+
+/// Maximum number of checkpoints kept open to serve queries.
+const OPEN_CHECKPOINTS: usize = 16;
+
+/// A read-only view of the storage at a past block height.
+///
+/// The storage is only reachable through [Checkpoint::read], which never gives
+/// mutable access, so nothing can be applied or committed to the snapshot.
+/// Once the height leaves the history window the checkpoint is marked as
+/// expired, and its directory is deleted when the last reference is dropped.
+pub struct Checkpoint {
+    path: PathBuf,
+    storage: Option<Mutex<LedgerStorage>>,
+    expired: AtomicBool,
+}
+
+/// A guard giving shared access to the storage of a checkpoint.
+pub struct CheckpointRead<'a>(MutexGuard<'a, LedgerStorage>);
+
+impl Deref for CheckpointRead<'_> {
+    type Target = LedgerStorage;
+
+    fn deref(&self) -> &LedgerStorage {
+        &self.0
+    }
+}
+
+impl Checkpoint {
+    fn open(path: PathBuf, storage: &LedgerStorage) -> Result<Self, ManyError> {
+        // Opened in blockchain mode, so the snapshot is never committed to.
+        let storage = LedgerStorage::load(&path, true, storage.migration_config.clone())?;
+        Ok(Self {
+            path,
+            storage: Some(Mutex::new(storage)),
+            expired: AtomicBool::new(false),
+        })
+    }
+
+    pub fn read(&self) -> CheckpointRead<'_> {
+        CheckpointRead(
+            self.storage
+                .as_ref()
+                .expect("Storage is only taken on drop")
+                .lock()
+                .unwrap(),
+        )
+    }
+}
+
+impl Drop for Checkpoint {
+    fn drop(&mut self) {
+        // Close the database before deleting its files.
+        drop(self.storage.take());
+        if self.expired.load(Ordering::Acquire) {
+            if let Err(e) = std::fs::remove_dir_all(&self.path) {
+                tracing::warn!("Could not delete checkpoint {:?}: {e}", self.path);
+            }
+        }
+    }
+}
+
+/// The checkpoints opened by queries.
+struct OpenCheckpoints {
+    /// The most recently used checkpoints, by height. A checkpoint is opened
+    /// once and shared by all the queries at its height, instead of opening
+    /// the database for every query.
+    recent: LruCache<u64, Arc<Checkpoint>>,
+
+    /// Every checkpoint that may still be referenced by a query, including
+    /// the ones evicted from `recent`.
+    live: BTreeMap<u64, Weak<Checkpoint>>,
+}
+
+/// Configuration of the historical snapshots kept by the storage.
+pub(crate) struct History {
+    /// Directory containing one checkpoint per committed block height.
+    path: PathBuf,
+
+    /// Number of block heights to keep. Older checkpoints are deleted.
+    window: u64,
+
+    open: Mutex<OpenCheckpoints>,
+}
+
+impl History {
+    fn path_for_height(&self, height: u64) -> PathBuf {
+        self.path.join(height.to_string())
+    }
+
+    /// List the heights of all the checkpoints in the history directory.
+    fn heights(&self) -> Result<Vec<u64>, ManyError> {
+        Ok(std::fs::read_dir(&self.path)
+            .map_err(error::storage_checkpoint_failed)?
+            .filter_map(|entry| entry.ok()?.file_name().to_str()?.parse::<u64>().ok())
+            .collect())
+    }
+}
+
+impl LedgerStorage {
+    /// Keep a checkpoint of the storage at every committed block for the last
+    /// `window` blocks, in the `path` directory. Checkpoints are only created
+    /// when a block is committed (i.e. in blockchain mode).
+    pub fn with_history<P: AsRef<Path>>(mut self, path: P, window: u64) -> Result<Self, ManyError> {
+        let path = path.as_ref().to_path_buf();
+        std::fs::create_dir_all(&path).map_err(error::storage_checkpoint_failed)?;
+        self.history = Some(Arc::new(History {
+            path,
+            window,
+            open: Mutex::new(OpenCheckpoints {
+                recent: LruCache::new(
+                    NonZeroUsize::new(OPEN_CHECKPOINTS).expect("Capacity is not zero"),
+                ),
+                live: BTreeMap::new(),
+            }),
+        }));
+        Ok(self)
+    }
+
+    /// Create a checkpoint for the block at `height` and delete the checkpoints
+    /// that are outside the history window. Does nothing if history is disabled.
+    ///
+    /// A checkpoint still used by a query is deleted when the query drops it.
+    pub(crate) fn checkpoint(&self, height: u64) -> Result<(), ManyError> {
+        let history = match &self.history {
+            Some(history) => history,
+            None => return Ok(()),
+        };
+
+        let path = history.path_for_height(height);
+        if !path.exists() {
+            self.persistent_store
+                .checkpoint(&path)
+                .map_err(error::storage_checkpoint_failed)?;
+        }
+
+        let mut open = history.open.lock().unwrap();
+        for h in history.heights()? {
+            if h + history.window <= height {
+                open.recent.pop(&h);
+                match open.live.remove(&h).and_then(|weak| weak.upgrade()) {
+                    Some(checkpoint) => checkpoint.expired.store(true, Ordering::Release),
+                    None => std::fs::remove_dir_all(history.path_for_height(h))
+                        .map_err(error::storage_checkpoint_failed)?,
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Create a checkpoint of the committed state in `path` and open it, to
+    /// serve queries while the storage executes the next block. The snapshot
+    /// shares the history of this storage, and is never committed to.
+    pub(crate) fn snapshot(&self, path: &Path) -> Result<LedgerStorage, ManyError> {
+        self.persistent_store
+            .checkpoint(path)
+            .map_err(error::storage_checkpoint_failed)?;
+        let mut storage = LedgerStorage::load(path, true, self.migration_config.clone())?;
+        storage.history = self.history.clone();
+        Ok(storage)
+    }
+
+    /// A read-only view of the storage as it was after the block at `height`
+    /// was committed. The current height returns the checkpoint of the latest
+    /// block. The most recently used checkpoints stay open between queries.
+    pub(crate) fn at_height(&self, height: u64) -> Result<Arc<Checkpoint>, ManyError> {
+        let history = self
+            .history
+            .as_ref()
+            .ok_or_else(|| error::height_not_available(height))?;
+        if height + history.window <= self.get_height()? {
+            return Err(error::height_not_available(height));
+        }
+
+        // Hold the lock while opening, so a checkpoint is never opened twice.
+        let mut open = history.open.lock().unwrap();
+        if let Some(checkpoint) = open.recent.get(&height) {
+            return Ok(checkpoint.clone());
+        }
+        if let Some(checkpoint) = open.live.get(&height).and_then(Weak::upgrade) {
+            open.recent.put(height, checkpoint.clone());
+            return Ok(checkpoint);
+        }
+
+        let path = history.path_for_height(height);
+        if !path.exists() {
+            return Err(error::height_not_available(height));
+        }
+        let checkpoint = Arc::new(Checkpoint::open(path, self)?);
+        open.live.retain(|_, weak| weak.strong_count() > 0);
+        open.live.insert(height, Arc::downgrade(&checkpoint));
+        open.recent.put(height, checkpoint.clone());
+        Ok(checkpoint)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::storage::LedgerStorage;
+
+    fn storage_with_history(window: u64) -> (LedgerStorage, std::path::PathBuf) {
+        let history = tempfile::tempdir().unwrap().into_path();
+        let storage = LedgerStorage::new(tempfile::tempdir().unwrap().into_path(), true)
+            .unwrap()
+            .build()
+            .unwrap()
+            .with_history(&history, window)
+            .unwrap();
+        (storage, history)
+    }
+
+    #[test]
+    fn pruned_while_in_use() {
+        let (mut storage, history) = storage_with_history(1);
+        storage.commit();
+        let height = storage.get_height().unwrap();
+
+        let checkpoint = storage.at_height(height).unwrap();
+        storage.commit();
+
+        // The checkpoint is outside the window, but a query still holds it.
+        assert!(history.join(height.to_string()).exists());
+        assert!(storage.at_height(height).is_err());
+        assert_eq!(checkpoint.read().get_height().unwrap(), height);
+
+        drop(checkpoint);
+        assert!(!history.join(height.to_string()).exists());
+    }
+}
@@ -1,6 +1,7 @@
 use crate::storage::event::{key_for_event, EVENTS_ROOT};
 use crate::storage::InnerStorage;
 use many_modules::events::EventId;
+use many_types::ledger::Symbol;
 use many_types::{CborRange, SortOrder};
 use merk::rocksdb;
 use merk::rocksdb::ReadOptions;
@@ -47,6 +48,33 @@ impl<'a> LedgerIterator<'a> {
         Self { inner }
     }
 
+    pub fn all_balances(merk: &'a InnerStorage) -> Self {
+        Self::all_prefixed(merk, crate::storage::BALANCES_ROOT.as_bytes())
+    }
+
+    pub fn all_accounts(merk: &'a InnerStorage) -> Self {
+        Self::all_prefixed(merk, crate::storage::account::ACCOUNTS_ROOT.as_bytes())
+    }
+
+    pub fn all_idstore(merk: &'a InnerStorage) -> Self {
+        Self::all_prefixed(merk, crate::storage::idstore::IDSTORE_ROOT)
+    }
+
+    pub fn all_frozen(merk: &'a InnerStorage, symbol: &Symbol) -> Self {
+        use crate::storage::token_controls::key_for_frozen_prefix;
+
+        Self::all_prefixed(merk, key_for_frozen_prefix(symbol).as_bytes())
+    }
+
+    pub(crate) fn all_prefixed(merk: &'a InnerStorage, prefix: &[u8]) -> Self {
+        let mut options = ReadOptions::default();
+        options.set_iterate_range(rocksdb::PrefixRange(prefix));
+
+        Self {
+            inner: merk.iter_opt(IteratorMode::Start, options),
+        }
+    }
+
     pub fn all_events(merk: &'a InnerStorage) -> Self {
         Self::events_scoped_by_id(merk, CborRange::default(), SortOrder::Indeterminate)
     }
@@ -1,4 +1,6 @@
 use crate::error;
+use crate::migration::spending_limits::SPENDING_LIMITS_MIGRATION;
+use crate::migration::token_controls::TOKEN_CONTROLS_MIGRATION;
 use crate::storage::{key_for_account_balance, LedgerStorage};
 use many_error::ManyError;
 use many_identity::Address;
@@ -32,8 +34,12 @@ impl LedgerStorage {
         }
     }
 
+    /// Send tokens from `from` to `to`. The `sender` is the identity that
+    /// requested the transfer, which is different from `from` when acting on
+    /// behalf of an account.
     pub fn send(
         &mut self,
+        sender: &Address,
         from: &Address,
         to: &Address,
         symbol: &Symbol,
@@ -57,6 +63,16 @@ impl LedgerStorage {
             return Err(error::insufficient_funds());
         }
 
+        if self.migrations.is_active(&TOKEN_CONTROLS_MIGRATION) {
+            self.check_token_controls(from, to, symbol)?;
+        }
+
+        let spending = if self.migrations.is_active(&SPENDING_LIMITS_MIGRATION) {
+            self.check_spending_limits(sender, from, to, symbol, &amount)?
+        } else {
+            None
+        };
+
         info!("send({} => {}, {} {})", from, to, &amount, symbol);
 
         let mut amount_to = self.get_balance(to, symbol)?;
@@ -84,6 +100,12 @@ impl LedgerStorage {
             .apply(&batch)
             .map_err(error::storage_apply_failed)?;
 
+        if let Some(entry) = spending {
+            self.persistent_store
+                .apply(&[entry])
+                .map_err(error::storage_apply_failed)?;
+        }
+
         self.log_event(EventInfo::Send {
             from: *from,
             to: *to,
@@ -1,25 +1,102 @@
 use crate::error;
+use crate::migration::mint_policy::MINT_POLICY_MIGRATION;
 use crate::storage::ledger_tokens::key_for_symbol;
 use crate::storage::{key_for_account_balance, LedgerStorage};
 use many_error::ManyError;
-use many_modules::ledger::TokenInfoArgs;
+use many_identity::Address;
+use many_modules::ledger::{MintPolicy, MintRateLimit, TokenInfoArgs};
 use many_types::ledger::{LedgerTokensAddressMap, Symbol, TokenAmount, TokenInfoSupply};
 use merk::{BatchEntry, Op};
+use minicbor::{Decode, Encode};
 use std::collections::BTreeSet;
 
+pub fn key_for_mint_policy(symbol: &Symbol) -> Vec<u8> {
+    format!("/config/mint_policy/{symbol}").into_bytes()
+}
+
+pub(crate) const MINT_USAGE_ROOT: &str = "/config/mint_usage/";
+
+pub(crate) fn key_for_mint_usage(symbol: &Symbol) -> Vec<u8> {
+    format!("{MINT_USAGE_ROOT}{symbol}").into_bytes()
+}
+
+/// The amount minted during a period of a mint rate limit.
+#[derive(Clone, Debug, Encode, Decode)]
+#[cbor(map)]
+struct MintPeriodUsage {
+    #[n(0)]
+    period: u64,
+
+    #[n(1)]
+    minted: TokenAmount,
+}
+
 impl LedgerStorage {
+    pub fn get_mint_policy(&self, symbol: &Symbol) -> Result<Option<MintPolicy>, ManyError> {
+        self.persistent_store
+            .get(&key_for_mint_policy(symbol))
+            .map_err(error::storage_get_failed)?
+            .map(|bytes| minicbor::decode(&bytes).map_err(ManyError::deserialization_error))
+            .transpose()
+    }
+
+    /// Set the mint policy of a token, or remove it if `policy` is `None`.
+    pub fn set_mint_policy(
+        &mut self,
+        symbol: &Symbol,
+        policy: Option<MintPolicy>,
+    ) -> Result<impl IntoIterator<Item = Vec<u8>>, ManyError> {
+        let key = key_for_mint_policy(symbol);
+        let op = match policy {
+            Some(policy) => {
+                Op::Put(minicbor::to_vec(policy).map_err(ManyError::serialization_error)?)
+            }
+            None => Op::Delete,
+        };
+        self.persistent_store
+            .apply(&[(key.clone(), op)])
+            .map_err(error::storage_apply_failed)?;
+
+        self.maybe_commit().map(|_| vec![key])
+    }
+
+    /// Returns the amount minted during the current period of the rate limit.
+    pub fn get_minted_in_period(
+        &self,
+        symbol: &Symbol,
+        rate_limit: &MintRateLimit,
+    ) -> Result<TokenAmount, ManyError> {
+        let period = rate_limit.period(self.now().secs());
+        Ok(self
+            .persistent_store
+            .get(&key_for_mint_usage(symbol))
+            .map_err(error::storage_get_failed)?
+            .map(|bytes| {
+                minicbor::decode::<MintPeriodUsage>(&bytes)
+                    .map_err(ManyError::deserialization_error)
+            })
+            .transpose()?
+            .filter(|usage| usage.period == period)
+            .map_or_else(TokenAmount::zero, |usage| usage.minted))
+    }
+
     pub(crate) fn get_token_supply(&self, symbol: &Symbol) -> Result<TokenInfoSupply, ManyError> {
         Ok(self
             .info_token(TokenInfoArgs {
                 symbol: *symbol,
                 extended_info: None,
+                height: None,
             })?
             .info
             .supply)
     }
 
+    /// Mint new tokens. The mint policy of the token, if any, further limits the
+    /// `sender`, which must already be allowed to mint. Its allowance is decremented
+    /// if it has one.
     pub fn mint_token(
         &mut self,
+        sender: &Address,
         symbol: Symbol,
         distribution: &LedgerTokensAddressMap,
     ) -> Result<impl IntoIterator<Item = Vec<u8>>, ManyError> {
@@ -53,11 +130,61 @@ impl LedgerStorage {
             batch.push((key, Op::Put(new_balance.to_vec())));
         }
 
+        // Enforce the mint policy, if any
+        let policy = if self.migrations.is_active(&MINT_POLICY_MIGRATION) {
+            self.get_mint_policy(&symbol)?
+        } else {
+            None
+        };
+        if let Some(mut policy) = policy {
+            let allowance = policy
+                .allowances
+                .get_mut(sender)
+                .ok_or_else(|| error::no_mint_allowance(sender, symbol))?;
+            if circulating > *allowance {
+                return Err(error::over_mint_allowance(sender, circulating, allowance));
+            }
+            *allowance -= &circulating;
+
+            let key = key_for_mint_policy(&symbol);
+            keys.push(key.clone());
+            batch.push((
+                key,
+                Op::Put(minicbor::to_vec(&policy).map_err(ManyError::serialization_error)?),
+            ));
+
+            if let Some(rate_limit) = &policy.rate_limit {
+                let mut minted = self.get_minted_in_period(&symbol, rate_limit)?;
+                minted += &circulating;
+                if minted > rate_limit.amount {
+                    return Err(error::over_mint_rate_limit(
+                        symbol,
+                        circulating,
+                        rate_limit.amount.clone(),
+                    ));
+                }
+
+                let key = key_for_mint_usage(&symbol);
+                keys.push(key.clone());
+                batch.push((
+                    key,
+                    Op::Put(
+                        minicbor::to_vec(MintPeriodUsage {
+                            period: rate_limit.period(self.now().secs()),
+                            minted,
+                        })
+                        .map_err(ManyError::serialization_error)?,
+                    ),
+                ));
+            }
+        }
+
         // Update circulating supply
         let mut info = self
             .info_token(TokenInfoArgs {
                 symbol,
                 extended_info: None,
+                height: None,
             })?
             .info;
         info.supply.circulating += &circulating;
@@ -117,6 +244,7 @@ impl LedgerStorage {
             .info_token(TokenInfoArgs {
                 symbol,
                 extended_info: None,
+                height: None,
             })?
             .info;
         info.supply.circulating -= &circulating;
@@ -1,6 +1,8 @@
 use crate::error;
 use crate::migration::tokens::TOKEN_MIGRATION;
 use crate::storage::iterator::LedgerIterator;
+use crate::storage::ledger_mintburn::key_for_mint_policy;
+use crate::storage::token_controls::token_controls_batch;
 use crate::storage::{
     key_for_account_balance, key_for_subresource_counter, LedgerStorage, IDENTITY_ROOT,
     SYMBOLS_ROOT,
@@ -11,9 +13,9 @@ use many_identity::Address;
 use many_modules::events::EventInfo;
 use many_modules::ledger::extended_info::{ExtendedInfoKey, TokenExtendedInfo};
 use many_modules::ledger::{
-    TokenAddExtendedInfoArgs, TokenAddExtendedInfoReturns, TokenCreateArgs, TokenCreateReturns,
-    TokenInfoArgs, TokenInfoReturns, TokenRemoveExtendedInfoArgs, TokenRemoveExtendedInfoReturns,
-    TokenUpdateArgs, TokenUpdateReturns,
+    MintPolicy, TokenAddExtendedInfoArgs, TokenAddExtendedInfoReturns, TokenCreateArgs,
+    TokenCreateReturns, TokenInfoArgs, TokenInfoReturns, TokenRemoveExtendedInfoArgs,
+    TokenRemoveExtendedInfoReturns, TokenUpdateArgs, TokenUpdateReturns,
 };
 use many_types::ledger::{Symbol, TokenAmount, TokenInfo, TokenInfoSummary, TokenInfoSupply};
 use many_types::{AttributeRelatedIndex, Either, SortOrder};
@@ -37,6 +39,10 @@ pub struct SymbolMeta {
     pub decimals: u64,
     pub owner: Option<Address>,
     pub maximum: Option<TokenAmount>,
+    pub extended_info: Option<TokenExtendedInfo>,
+    pub mint_policy: Option<MintPolicy>,
+    pub paused: Option<bool>,
+    pub frozen: Option<BTreeSet<Address>>,
 }
 
 pub fn verify_tokens_sender(sender: &Address, token_identity: Address) -> Result<(), ManyError> {
@@ -65,20 +71,20 @@ impl LedgerStorage {
     fn _token_info(
         symbol: Symbol,
         ticker: String,
-        meta: SymbolMeta,
+        meta: &SymbolMeta,
         total_supply: TokenAmount,
     ) -> TokenInfo {
         TokenInfo {
             symbol,
             summary: TokenInfoSummary {
-                name: meta.name,
+                name: meta.name.clone(),
                 ticker,
                 decimals: meta.decimals,
             },
             supply: TokenInfoSupply {
                 total: total_supply.clone(),
                 circulating: total_supply,
-                maximum: meta.maximum,
+                maximum: meta.maximum.clone(),
             },
             owner: meta.owner,
         }
@@ -117,12 +123,12 @@ impl LedgerStorage {
             for (k, meta) in symbols_meta.into_iter() {
                 let total_supply = total_supply[&k].clone(); // Safe
                 let ticker = symbols[&k].clone(); // Safe
-                let info = LedgerStorage::_token_info(k, ticker, meta, total_supply.clone());
+                let info = LedgerStorage::_token_info(k, ticker, &meta, total_supply.clone());
 
                 batch.push((
                     key_for_ext_info(&k),
                     Op::Put(
-                        minicbor::to_vec(TokenExtendedInfo::default())
+                        minicbor::to_vec(meta.extended_info.unwrap_or_default())
                             .map_err(ManyError::serialization_error)?,
                     ),
                 ));
@@ -130,6 +136,17 @@ impl LedgerStorage {
                     key_for_symbol(&k).into(),
                     Op::Put(minicbor::to_vec(info).map_err(ManyError::serialization_error)?),
                 ));
+                if let Some(policy) = meta.mint_policy {
+                    batch.push((
+                        key_for_mint_policy(&k),
+                        Op::Put(minicbor::to_vec(policy).map_err(ManyError::serialization_error)?),
+                    ));
+                }
+                batch.extend(token_controls_batch(
+                    &k,
+                    meta.paused.unwrap_or_default(),
+                    &meta.frozen.unwrap_or_default(),
+                ));
             }
             batch.sort_by(|(k1, _), (k2, _)| k1.cmp(k2));
             self.persistent_store
@@ -325,6 +342,7 @@ impl LedgerStorage {
         let TokenInfoArgs {
             symbol,
             extended_info,
+            ..
         } = args;
 
         // Try fetching the token info from the persistent storage
@@ -353,9 +371,22 @@ impl LedgerStorage {
         let info: TokenInfo =
             minicbor::decode(&token_info_enc).map_err(ManyError::deserialization_error)?;
 
+        let mint_policy = self.get_mint_policy(&symbol)?;
+        let minted_in_period = mint_policy
+            .as_ref()
+            .and_then(|policy| policy.rate_limit.as_ref())
+            .map(|rate_limit| self.get_minted_in_period(&symbol, rate_limit))
+            .transpose()?;
+
+        let frozen = self.get_frozen(&symbol)?;
+
         Ok(TokenInfoReturns {
             info,
             extended_info: ext_info,
+            mint_policy,
+            minted_in_period,
+            paused: Some(self.is_paused(&symbol)?),
+            frozen: (!frozen.is_empty()).then_some(frozen),
         })
     }
 
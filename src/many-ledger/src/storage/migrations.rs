@@ -11,10 +11,12 @@ impl LedgerStorage {
         // NOTE: Migrations are only applied in blockchain mode when loading an existing DB
         //       It is currently NOT possible to run new code in non-blockchain mode when loading an existing DB
         self.migrations = migration_config
+            .clone()
             .map_or_else(MigrationSet::empty, |config| {
                 LedgerMigrations::load(&MIGRATIONS, config, 0)
             })
             .map_err(ManyError::unknown)?; // TODO: Custom error
+        self.migration_config = migration_config;
 
         Ok(self)
     }
@@ -1,6 +1,7 @@
 use crate::error;
 use crate::migration::block_9400::Block9400Tx;
 use crate::migration::memo::MEMO_MIGRATION;
+use crate::migration::token_controls::TOKEN_CONTROLS_MIGRATION;
 use crate::module::account::validate_account;
 use crate::storage::event::EVENT_ID_KEY_SIZE_IN_BYTES;
 use crate::storage::LedgerStorage;
@@ -56,7 +57,7 @@ fn _execute_multisig_tx(
                 [account::Role::CanLedgerTransact, account::Role::Owner],
             )?;
 
-            ledger.send(&from, to, symbol, amount.clone(), memo.clone())?;
+            ledger.send(sender, &from, to, symbol, amount.clone(), memo.clone())?;
             minicbor::to_vec(EmptyReturn)
         }
 
@@ -290,6 +291,17 @@ impl LedgerStorage {
             [account::Role::CanMultisigSubmit, account::Role::Owner],
         )?;
 
+        // Refuse sends that could not be executed because of the token controls.
+        if let events::AccountMultisigTransaction::Send(send) = arg.transaction.as_ref() {
+            if self.migrations.is_active(&TOKEN_CONTROLS_MIGRATION) {
+                self.check_token_controls(
+                    &send.from.unwrap_or(account_id),
+                    &send.to,
+                    &send.symbol,
+                )?;
+            }
+        }
+
         let multisig_f = account
             .features
             .get::<account::features::multisig::MultisigAccountFeature>()?;
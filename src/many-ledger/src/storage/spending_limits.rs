@@ -0,0 +1,122 @@
+use crate::error;
+use crate::storage::LedgerStorage;
+use many_error::ManyError;
+use many_identity::Address;
+use many_modules::account::features::spending_limits::{errors, SpendingLimitsFeature};
+use many_modules::account::Role;
+use many_types::ledger::{Symbol, TokenAmount};
+use merk::BatchEntry;
+use merk::Op;
+use minicbor::{Decode, Encode};
+
+pub const SPENDING_ROOT: &str = "/spending/";
+
+pub(super) fn key_for_spending(account: &Address, member: &Address, symbol: &Symbol) -> Vec<u8> {
+    format!("{SPENDING_ROOT}{account}/{member}/{symbol}").into_bytes()
+}
+
+/// An amount spent by a member of an account at a given time.
+#[derive(Clone, Debug, Encode, Decode)]
+#[cbor(map)]
+struct SpendingEntry {
+    #[n(0)]
+    timestamp: u64,
+
+    #[n(1)]
+    amount: TokenAmount,
+}
+
+impl LedgerStorage {
+    fn get_spending_entries(
+        &self,
+        account: &Address,
+        member: &Address,
+        symbol: &Symbol,
+    ) -> Result<Vec<SpendingEntry>, ManyError> {
+        self.persistent_store
+            .get(&key_for_spending(account, member, symbol))
+            .map_err(error::storage_get_failed)?
+            .map_or(Ok(vec![]), |bytes| {
+                minicbor::decode(&bytes).map_err(ManyError::deserialization_error)
+            })
+    }
+
+    /// Verify that `sender` can send `amount` of `symbol` from the `from` account to `to`
+    /// according to the spending limits feature of the account.
+    ///
+    /// Returns the batch entry recording the spending if the sender has limits for this
+    /// symbol. Owners of the account and senders sending from their own address are never
+    /// limited.
+    pub(crate) fn check_spending_limits(
+        &self,
+        sender: &Address,
+        from: &Address,
+        to: &Address,
+        symbol: &Symbol,
+        amount: &TokenAmount,
+    ) -> Result<Option<BatchEntry>, ManyError> {
+        if sender == from {
+            return Ok(None);
+        }
+        let account = match self.get_account(from) {
+            Ok((account, _)) => account,
+            Err(_) => return Ok(None),
+        };
+        if account.has_role(sender, Role::Owner) {
+            return Ok(None);
+        }
+        let feature = match account.feature::<SpendingLimitsFeature>() {
+            Some(feature) => feature,
+            None => return Ok(None),
+        };
+
+        let roles = account.get_roles(sender);
+        let policies = feature.arg.policies_for(sender, &roles).collect::<Vec<_>>();
+
+        for policy in &policies {
+            if let Some(allowlist) = &policy.allowlist {
+                if !allowlist.contains(to) {
+                    return Err(errors::recipient_not_allowed(to));
+                }
+            }
+        }
+
+        let limits = policies
+            .iter()
+            .filter_map(|policy| policy.limits.get(symbol))
+            .collect::<Vec<_>>();
+        if limits.is_empty() {
+            return Ok(None);
+        }
+
+        let now = self.now().secs();
+        let mut entries = self.get_spending_entries(from, sender, symbol)?;
+        for limit in &limits {
+            let mut spent = TokenAmount::zero();
+            for entry in entries.iter().filter(|e| limit.contains(e.timestamp, now)) {
+                spent += &entry.amount;
+            }
+
+            if &spent + amount > limit.amount {
+                let remaining = if spent < limit.amount {
+                    &limit.amount - &spent
+                } else {
+                    TokenAmount::zero()
+                };
+                return Err(errors::spending_limit_exceeded(symbol, remaining));
+            }
+        }
+
+        // Only keep the entries that are still needed by one of the windows.
+        entries.retain(|e| limits.iter().any(|limit| limit.contains(e.timestamp, now)));
+        entries.push(SpendingEntry {
+            timestamp: now,
+            amount: amount.clone(),
+        });
+
+        Ok(Some((
+            key_for_spending(from, sender, symbol),
+            Op::Put(minicbor::to_vec(entries).map_err(ManyError::serialization_error)?),
+        )))
+    }
+}
@@ -0,0 +1,155 @@
+use crate::error;
+use crate::storage::iterator::LedgerIterator;
+use crate::storage::LedgerStorage;
+use many_error::ManyError;
+use many_identity::Address;
+use many_modules::events::EventInfo;
+use many_types::ledger::Symbol;
+use many_types::Memo;
+use merk::{BatchEntry, Op};
+use std::collections::BTreeSet;
+use std::str::FromStr;
+
+pub const FROZEN_ROOT: &str = "/config/frozen/";
+pub const PAUSED_ROOT: &str = "/config/paused/";
+
+/// The value stored for frozen addresses and paused symbols. Only the presence
+/// of the key matters.
+const FLAG_VALUE: [u8; 1] = [1];
+
+pub(crate) fn key_for_frozen_prefix(symbol: &Symbol) -> String {
+    format!("{FROZEN_ROOT}{symbol}/")
+}
+
+pub(crate) fn key_for_frozen(symbol: &Symbol, address: &Address) -> Vec<u8> {
+    format!("{FROZEN_ROOT}{symbol}/{address}").into_bytes()
+}
+
+pub(crate) fn key_for_paused(symbol: &Symbol) -> Vec<u8> {
+    format!("{PAUSED_ROOT}{symbol}").into_bytes()
+}
+
+/// Returns the batch entries to initialize the token controls of a symbol.
+pub(super) fn token_controls_batch(
+    symbol: &Symbol,
+    paused: bool,
+    frozen: &BTreeSet<Address>,
+) -> Vec<BatchEntry> {
+    let mut batch: Vec<BatchEntry> = frozen
+        .iter()
+        .map(|address| {
+            (
+                key_for_frozen(symbol, address),
+                Op::Put(FLAG_VALUE.to_vec()),
+            )
+        })
+        .collect();
+    if paused {
+        batch.push((key_for_paused(symbol), Op::Put(FLAG_VALUE.to_vec())));
+    }
+    batch
+}
+
+impl LedgerStorage {
+    pub fn is_paused(&self, symbol: &Symbol) -> Result<bool, ManyError> {
+        Ok(self
+            .persistent_store
+            .get(&key_for_paused(symbol))
+            .map_err(error::storage_get_failed)?
+            .is_some())
+    }
+
+    pub fn is_frozen(&self, symbol: &Symbol, address: &Address) -> Result<bool, ManyError> {
+        Ok(self
+            .persistent_store
+            .get(&key_for_frozen(symbol, address))
+            .map_err(error::storage_get_failed)?
+            .is_some())
+    }
+
+    /// Returns all the addresses whose balance of `symbol` is frozen.
+    pub fn get_frozen(&self, symbol: &Symbol) -> Result<BTreeSet<Address>, ManyError> {
+        let prefix = key_for_frozen_prefix(symbol);
+        LedgerIterator::all_frozen(&self.persistent_store, symbol)
+            .map(|item| {
+                let (key, _) = item.map_err(error::storage_get_failed)?;
+                Address::from_str(
+                    std::str::from_utf8(&key[prefix.len()..])
+                        .map_err(ManyError::deserialization_error)?,
+                )
+            })
+            .collect()
+    }
+
+    /// Verify that `symbol` can be transferred from `from` to `to`, i.e. that the
+    /// symbol isn't paused and that neither balance is frozen.
+    pub(crate) fn check_token_controls(
+        &self,
+        from: &Address,
+        to: &Address,
+        symbol: &Symbol,
+    ) -> Result<(), ManyError> {
+        if self.is_paused(symbol)? {
+            return Err(error::token_paused(symbol));
+        }
+        for address in [from, to] {
+            if self.is_frozen(symbol, address)? {
+                return Err(error::address_frozen(address, symbol));
+            }
+        }
+        Ok(())
+    }
+
+    fn apply_token_control(&mut self, key: Vec<u8>, set: bool) -> Result<(), ManyError> {
+        let op = if set {
+            Op::Put(FLAG_VALUE.to_vec())
+        } else {
+            Op::Delete
+        };
+        self.persistent_store
+            .apply(&[(key, op)])
+            .map_err(error::storage_apply_failed)
+    }
+
+    pub fn freeze(
+        &mut self,
+        symbol: Symbol,
+        address: Address,
+        memo: Option<Memo>,
+    ) -> Result<(), ManyError> {
+        self.apply_token_control(key_for_frozen(&symbol, &address), true)?;
+        self.log_event(EventInfo::TokenFreeze {
+            symbol,
+            address,
+            memo,
+        })?;
+        self.maybe_commit()
+    }
+
+    pub fn unfreeze(
+        &mut self,
+        symbol: Symbol,
+        address: Address,
+        memo: Option<Memo>,
+    ) -> Result<(), ManyError> {
+        self.apply_token_control(key_for_frozen(&symbol, &address), false)?;
+        self.log_event(EventInfo::TokenUnfreeze {
+            symbol,
+            address,
+            memo,
+        })?;
+        self.maybe_commit()
+    }
+
+    pub fn pause(&mut self, symbol: Symbol, memo: Option<Memo>) -> Result<(), ManyError> {
+        self.apply_token_control(key_for_paused(&symbol), true)?;
+        self.log_event(EventInfo::TokenPause { symbol, memo })?;
+        self.maybe_commit()
+    }
+
+    pub fn unpause(&mut self, symbol: Symbol, memo: Option<Memo>) -> Result<(), ManyError> {
+        self.apply_token_control(key_for_paused(&symbol), false)?;
+        self.log_event(EventInfo::TokenUnpause { symbol, memo })?;
+        self.maybe_commit()
+    }
+}
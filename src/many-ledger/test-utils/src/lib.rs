@@ -249,6 +249,7 @@ impl Setup {
                 BalanceArgs {
                     account: None,
                     symbols: Some(vec![symbol].into()),
+                    height: None,
                 },
                 Context::new(RequestMessage::default(), unbounded().0),
             )?
@@ -758,6 +759,7 @@ pub fn verify_balance(
         BalanceArgs {
             account: Some(id),
             symbols: Some(vec![symbol].into()),
+            height: None,
         },
         Context::new(RequestMessage::default(), unbounded().0),
     );
@@ -10,6 +10,7 @@ pub fn info(h: &Setup, symbol: Symbol) -> Result<TokenInfoReturns, ManyError> {
         TokenInfoArgs {
             symbol,
             extended_info: None,
+            height: None,
         },
     )
 }
@@ -20,6 +20,7 @@ fn account_info(
         id,
         account::InfoArgs {
             account: *account_id,
+            height: None,
         },
         Context::new(RequestMessage::default(), unbounded().0),
     );
@@ -328,6 +329,7 @@ fn disable() {
         &id,
         account::InfoArgs {
             account: account_id,
+            height: None,
         },
         Context::new(RequestMessage::default(), unbounded().0),
     );
@@ -370,6 +372,7 @@ fn add_feature() {
         &id,
         account::InfoArgs {
             account: account_id,
+            height: None,
         },
         Context::new(RequestMessage::default(), unbounded().0),
     )
@@ -398,6 +401,7 @@ fn add_feature() {
         &id,
         account::InfoArgs {
             account: account_id,
+            height: None,
         },
         Context::new(RequestMessage::default(), unbounded().0),
     )
@@ -0,0 +1,271 @@
+use itertools::Itertools;
+use many_identity::testing::identity;
+use many_identity::verifiers::AnonymousVerifier;
+use many_identity::{Address, AnonymousIdentity, Identity};
+use many_identity_dsa::ed25519::generate_random_ed25519_identity;
+use many_identity_dsa::CoseKeyVerifier;
+use many_ledger::json::InitialStateJson;
+use many_ledger::migration::data::ACCOUNT_COUNT_DATA_ATTRIBUTE;
+use many_ledger::migration::memo::MEMO_MIGRATION;
+use many_ledger::module::account::AccountFeatureModule;
+use many_ledger::module::LedgerModuleImpl;
+use many_ledger_test_utils::*;
+use many_modules::account::features::multisig::{
+    self, AccountMultisigModule, MultisigTransactionState,
+};
+use many_modules::account::features::{Feature, FeatureInfo, FeatureSet};
+use many_modules::{abci_backend, account, events, ledger};
+use many_server::simulator::{tx, Simulator, Start};
+use many_server::ManyServer;
+use many_types::ledger::TokenAmount;
+use minicbor::bytes::ByteVec;
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::{Arc, Mutex};
+
+/// Create a simulator of `n` ledger nodes, where `owner` starts with
+/// 1_000_000 MFX.
+fn simulator(
+    n: usize,
+    owner: Address,
+    migrations: impl IntoIterator<Item = impl Into<MigrationHarness>>,
+) -> Simulator {
+    let migrations = format!(
+        r#"{{ "migrations": [{}] }}"#,
+        migrations
+            .into_iter()
+            .map(|x| x.into().to_json_str())
+            .join(",")
+    );
+    let dirs = (0..n)
+        .map(|_| tempfile::tempdir().expect("Could not create a temporary dir."))
+        .collect::<Vec<_>>();
+
+    Simulator::new(n, move |i, start| {
+        let migrations = Some(serde_json::from_str(&migrations).unwrap());
+        let module_impl = match start {
+            Start::Genesis => {
+                let mut state = InitialStateJson::read("../../staging/ledger_state.json5")
+                    .or_else(|_| InitialStateJson::read("staging/ledger_state.json5"))
+                    .expect("Could not read initial state.");
+                state.hash = None;
+
+                let mut module_impl =
+                    LedgerModuleImpl::new(state, migrations, dirs[i].path(), true)?;
+                module_impl.set_balance_only_for_testing(owner, 1_000_000, *MFX_SYMBOL)?;
+                module_impl
+            }
+            Start::Restart => LedgerModuleImpl::load(migrations, dirs[i].path(), true)?,
+        };
+        Ok(server(module_impl))
+    })
+}
+
+/// Create the server of a node, with the modules of many-ledger used in these
+/// tests.
+fn server(module_impl: LedgerModuleImpl) -> Arc<Mutex<ManyServer>> {
+    let module_impl = Arc::new(Mutex::new(module_impl));
+    let many = ManyServer::simple(
+        "many-ledger",
+        AnonymousIdentity,
+        (AnonymousVerifier, CoseKeyVerifier),
+        None,
+    );
+    {
+        let mut s = many.lock().unwrap();
+        s.add_module(ledger::LedgerModule::new(module_impl.clone()));
+        s.add_module(ledger::LedgerCommandsModule::new(module_impl.clone()));
+        s.add_module(events::EventsModule::new(module_impl.clone()));
+        s.add_module(AccountFeatureModule::new(
+            account::AccountModule::new(module_impl.clone()),
+            [Feature::with_id(0), Feature::with_id(1)],
+        ));
+        s.add_module(AccountMultisigModule::new(module_impl.clone()));
+        s.add_module(abci_backend::AbciModule::new(module_impl));
+    }
+    many
+}
+
+fn send(from: &impl Identity, to: Address, amount: u64) -> coset::CoseSign1 {
+    tx(
+        from,
+        "ledger.send",
+        ledger::SendArgs {
+            from: Some(from.address()),
+            to,
+            amount: TokenAmount::from(amount),
+            symbol: *MFX_SYMBOL,
+            memo: None,
+        },
+    )
+}
+
+fn balance(simulator: &Simulator, node: usize, account: Address) -> TokenAmount {
+    let result = simulator.query(
+        node,
+        tx(
+            &AnonymousIdentity,
+            "ledger.balance",
+            ledger::BalanceArgs {
+                account: Some(account),
+                symbols: Some(vec![*MFX_SYMBOL].into()),
+                height: None,
+            },
+        ),
+    );
+    minicbor::decode::<ledger::BalanceReturns>(&result.unwrap())
+        .unwrap()
+        .balances
+        .get(&*MFX_SYMBOL)
+        .cloned()
+        .unwrap_or_default()
+}
+
+#[test]
+fn sends() {
+    let owner = generate_random_ed25519_identity();
+    let mut simulator = simulator(4, owner.address(), Vec::<MigrationHarness>::new());
+    for i in 2..10 {
+        simulator.block(vec![send(&owner, identity(i), 1_000)]);
+    }
+
+    for node in 0..4 {
+        assert_eq!(balance(&simulator, node, owner.address()), 992_000u64);
+        assert_eq!(balance(&simulator, node, identity(9)), 1_000u64);
+    }
+}
+
+#[test]
+fn failed_transactions() {
+    let owner = generate_random_ed25519_identity();
+    let other = generate_random_ed25519_identity();
+    let mut simulator = simulator(3, owner.address(), Vec::<MigrationHarness>::new());
+    let results = simulator.block(vec![
+        send(&owner, other.address(), 2_000_000),
+        send(&other, identity(3), 1),
+        // Signed by another identity than the sender.
+        tx(
+            &other,
+            "ledger.send",
+            ledger::SendArgs {
+                from: Some(owner.address()),
+                to: other.address(),
+                amount: TokenAmount::from(1u64),
+                symbol: *MFX_SYMBOL,
+                memo: None,
+            },
+        ),
+        send(&owner, other.address(), 1_000),
+    ]);
+    assert!(results[0].is_err());
+    assert!(results[1].is_err());
+    assert!(results[2].is_err());
+    assert!(results[3].is_ok());
+    assert_eq!(balance(&simulator, 2, other.address()), 1_000u64);
+}
+
+#[test]
+fn migrations_and_restarts() {
+    let owner = generate_random_ed25519_identity();
+    let mut simulator = simulator(
+        4,
+        owner.address(),
+        [(3, &ACCOUNT_COUNT_DATA_ATTRIBUTE), (5, &MEMO_MIGRATION)],
+    );
+
+    // Node 3 misses both migrations, and replays them when it starts again.
+    simulator.stop(3);
+    for i in 2..8 {
+        simulator.block(vec![send(&owner, identity(i), 1_000)]);
+
+        // Restart node 1 after every block, including the migration heights.
+        simulator.restart(1);
+    }
+    simulator.start(3);
+    simulator.empty_blocks(2);
+
+    assert_eq!(simulator.height(), 8);
+    assert_eq!(balance(&simulator, 3, owner.address()), 994_000u64);
+}
+
+#[test]
+fn accounts() {
+    let owner = generate_random_ed25519_identity();
+    let approver = generate_random_ed25519_identity();
+    let mut simulator = simulator(3, owner.address(), [(2, &MEMO_MIGRATION)]);
+
+    // Transactions need the approval of both identities.
+    let feature = multisig::MultisigAccountFeature::create(Some(2), None, None).as_feature();
+    let results = simulator.block(vec![tx(
+        &owner,
+        "account.create",
+        account::CreateArgs {
+            description: Some("Foobar".to_string()),
+            roles: Some(BTreeMap::from([(
+                approver.address(),
+                BTreeSet::from([account::Role::CanMultisigApprove]),
+            )])),
+            features: FeatureSet::from_iter([feature]),
+        },
+    )]);
+    let account_id = minicbor::decode::<account::CreateReturn>(results[0].as_ref().unwrap())
+        .unwrap()
+        .id;
+
+    simulator.block(vec![send(&owner, account_id, 10_000)]);
+    simulator.restart(0);
+    let results = simulator.block(vec![tx(
+        &owner,
+        "account.multisigSubmitTransaction",
+        multisig::SubmitTransactionArgs::send(
+            account_id,
+            identity(4),
+            *MFX_SYMBOL,
+            TokenAmount::from(5_000u64),
+            None,
+        ),
+    )]);
+    let token: ByteVec =
+        minicbor::decode::<multisig::SubmitTransactionReturn>(results[0].as_ref().unwrap())
+            .unwrap()
+            .token;
+
+    for node in 0..3 {
+        let result = simulator.query(
+            node,
+            tx(
+                &owner,
+                "account.multisigInfo",
+                multisig::InfoArgs {
+                    token: token.clone(),
+                },
+            ),
+        );
+        let info = minicbor::decode::<multisig::InfoReturn>(&result.unwrap()).unwrap();
+        assert_eq!(info.submitter, owner.address());
+        assert_eq!(info.state, MultisigTransactionState::Pending);
+        assert!(info.approvers[&owner.address()].approved);
+        assert!(!info.approvers.contains_key(&approver.address()));
+    }
+
+    simulator.restart(2);
+    let results = simulator.block(vec![
+        tx(
+            &approver,
+            "account.multisigApprove",
+            multisig::ApproveArgs {
+                token: token.clone(),
+            },
+        ),
+        tx(
+            &owner,
+            "account.multisigExecute",
+            multisig::ExecuteArgs { token },
+        ),
+    ]);
+    assert!(results.iter().all(Result::is_ok));
+
+    for node in 0..3 {
+        assert_eq!(balance(&simulator, node, account_id), 5_000u64);
+        assert_eq!(balance(&simulator, node, identity(4)), 5_000u64);
+    }
+}
@@ -0,0 +1,142 @@
+use many_identity::testing::identity;
+use many_ledger::json::{AccountJson, DisabledReasonJson, InitialStateJson};
+use many_ledger::migration::token_create::TOKEN_CREATE_MIGRATION;
+use many_ledger::migration::tokens::TOKEN_MIGRATION;
+use many_ledger::module::LedgerModuleImpl;
+use many_ledger_test_utils::{default_token_create_args, AccountType, Setup, MFX_SYMBOL};
+use many_migration::{Metadata, MigrationConfig};
+use many_modules::abci_backend::ManyAbciModuleBackend;
+use many_modules::ledger::LedgerTokensModuleBackend;
+use many_types::ledger::TokenAmount;
+use std::collections::{BTreeMap, BTreeSet};
+
+fn migration_config() -> Option<MigrationConfig> {
+    let metadata = Metadata {
+        block_height: 0,
+        upper_block_height: None,
+        disabled: false,
+        issue: None,
+        extra: Default::default(),
+    };
+    Some(
+        MigrationConfig::default()
+            .with_migration_opts(&TOKEN_MIGRATION, metadata.clone())
+            .with_migration_opts(&TOKEN_CREATE_MIGRATION, metadata),
+    )
+}
+
+fn import(state: InitialStateJson) -> LedgerModuleImpl {
+    let path = tempfile::tempdir().unwrap().into_path();
+    LedgerModuleImpl::new(state, migration_config(), path, false).unwrap()
+}
+
+fn hash(module_impl: &LedgerModuleImpl) -> Vec<u8> {
+    ManyAbciModuleBackend::info(module_impl)
+        .unwrap()
+        .hash
+        .to_vec()
+}
+
+/// Verify an untouched genesis state can be exported and re-imported with the same hash
+#[test]
+fn export_genesis() {
+    let setup = Setup::new_with_migrations(
+        false,
+        [(0, &TOKEN_MIGRATION), (0, &TOKEN_CREATE_MIGRATION)],
+        true,
+    );
+    let state = setup.module_impl.export_state().unwrap();
+    assert!(state.hash.is_none());
+
+    let module_impl = import(state);
+    assert_eq!(hash(&setup.module_impl), hash(&module_impl));
+}
+
+/// Verify a state with new tokens, balances, accounts, events and multisig transactions
+/// round-trips through the export
+#[test]
+fn export_round_trip() {
+    let mut setup = Setup::new_with_migrations(
+        false,
+        [(0, &TOKEN_MIGRATION), (0, &TOKEN_CREATE_MIGRATION)],
+        true,
+    );
+    let id = setup.id;
+    setup.set_balance(id, 1_000_000, *MFX_SYMBOL);
+    setup.send_(id, identity(5), 1_000u64);
+    setup.send_(identity(5), identity(6), 1_000u64);
+    let symbol = LedgerTokensModuleBackend::create(
+        &mut setup.module_impl,
+        &id,
+        default_token_create_args(None, Some(TokenAmount::from(1_000_000u64))),
+    )
+    .unwrap()
+    .info
+    .symbol;
+    let multisig = setup.create_account_(AccountType::Multisig);
+    setup.create_account_(AccountType::Tokens);
+    setup.multisig_send_(multisig, identity(7), 10u64);
+
+    let exported = setup.module_impl.export_state().unwrap();
+    let json = serde_json::to_string(&exported).unwrap();
+
+    // The exported JSON can be read back as an initial state.
+    let state: InitialStateJson = json5::from_str(&json).unwrap();
+    let module_impl = import(state);
+    assert_eq!(hash(&setup.module_impl), hash(&module_impl));
+
+    let reexported = module_impl.export_state().unwrap();
+    assert_eq!(
+        serde_json::to_value(&exported).unwrap(),
+        serde_json::to_value(&reexported).unwrap()
+    );
+    assert_eq!(hash(&module_impl), hash(&import(reexported.clone())));
+
+    // Zero balances are kept.
+    assert_eq!(
+        reexported.initial[&identity(5)]["mqbfbahksdwaqeenayy2gxke32hgb7aq4ao4wt745lsfs6wiaaaaqnz"],
+        TokenAmount::zero()
+    );
+    assert_eq!(
+        reexported.initial[&identity(1)][&symbol.to_string()],
+        TokenAmount::from(123u64)
+    );
+    let symbol_meta = &reexported.symbols_meta.as_ref().unwrap()[&symbol];
+    assert_eq!(symbol_meta.owner, Some(id));
+    assert!(symbol_meta.extended_info.is_some());
+    assert!(reexported
+        .accounts
+        .unwrap()
+        .iter()
+        .any(|a| a.id == Some(multisig)));
+    assert!(reexported.events.is_some());
+    assert!(reexported.multisig_transactions.is_some());
+}
+
+/// Verify the reason an account was disabled is kept
+#[test]
+fn export_disabled_reason() {
+    let setup = Setup::new_with_migrations(
+        false,
+        [(0, &TOKEN_MIGRATION), (0, &TOKEN_CREATE_MIGRATION)],
+        true,
+    );
+    let mut state = setup.module_impl.export_state().unwrap();
+    state.accounts = Some(vec![AccountJson {
+        roles: BTreeMap::from([(identity(1), BTreeSet::from(["owner".to_string()]))]),
+        disabled_reason: Some(DisabledReasonJson {
+            code: 1,
+            message: Some("Disabled by governance".to_string()),
+            arguments: BTreeMap::new(),
+        }),
+        ..Default::default()
+    }]);
+
+    let module_impl = import(state);
+    let account = &module_impl.export_state().unwrap().accounts.unwrap()[0];
+    assert_eq!(account.disabled, Some(true));
+    assert_eq!(
+        account.disabled_reason.as_ref().unwrap().message.as_deref(),
+        Some("Disabled by governance")
+    );
+}
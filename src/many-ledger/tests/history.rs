@@ -0,0 +1,157 @@
+//! Tests regarding queries at a past block height.
+use async_channel::unbounded;
+use many_error::ManyError;
+use many_identity::testing::identity;
+use many_identity::Address;
+use many_ledger::error;
+use many_ledger_test_utils::*;
+use many_modules::account;
+use many_modules::account::AccountModuleBackend;
+use many_modules::ledger::{BalanceArgs, LedgerModuleBackend};
+use many_protocol::context::Context;
+use many_protocol::RequestMessage;
+use many_types::ledger::TokenAmount;
+
+fn balance_at(setup: &Setup, account: Address, height: u64) -> Result<TokenAmount, ManyError> {
+    Ok(setup
+        .module_impl
+        .balance(
+            &account,
+            BalanceArgs {
+                account: None,
+                symbols: Some(vec![*MFX_SYMBOL].into()),
+                height: Some(height),
+            },
+            Context::new(RequestMessage::default(), unbounded().0),
+        )?
+        .balances
+        .get(&*MFX_SYMBOL)
+        .cloned()
+        .unwrap_or_default())
+}
+
+fn account_info_at(
+    setup: &Setup,
+    account: Address,
+    height: u64,
+) -> Result<account::InfoReturn, ManyError> {
+    AccountModuleBackend::info(
+        &setup.module_impl,
+        &setup.id,
+        account::InfoArgs {
+            account,
+            height: Some(height),
+        },
+        Context::new(RequestMessage::default(), unbounded().0),
+    )
+}
+
+fn setup_with_history(window: u64) -> Setup {
+    let mut setup = Setup::new(true);
+    let path = tempfile::tempdir().unwrap().into_path();
+    setup.module_impl = setup.module_impl.with_history(path, window).unwrap();
+    setup.set_balance(setup.id, 1_000_000, *MFX_SYMBOL);
+    setup
+}
+
+#[test]
+fn balance_at_height() {
+    let mut setup = setup_with_history(10);
+
+    let (h1, _) = setup.block(|setup| setup.send_(setup.id, identity(2), 100u32));
+    let (h2, _) = setup.block(|setup| setup.send_(setup.id, identity(2), 50u32));
+    let (h3, _) = setup.block(|setup| setup.send_(setup.id, identity(2), 25u32));
+
+    assert_eq!(balance_at(&setup, identity(2), h1).unwrap(), 100u32);
+    assert_eq!(balance_at(&setup, identity(2), h2).unwrap(), 150u32);
+    assert_eq!(balance_at(&setup, identity(2), h3).unwrap(), 175u32);
+    assert_eq!(setup.balance_(identity(2)), 175u32);
+}
+
+#[test]
+fn height_outside_window() {
+    let mut setup = setup_with_history(2);
+
+    let (h1, _) = setup.block(|setup| setup.send_(setup.id, identity(2), 100u32));
+    setup.block(|setup| setup.send_(setup.id, identity(2), 50u32));
+    let (h3, _) = setup.block(|setup| setup.send_(setup.id, identity(2), 25u32));
+
+    // The first block is outside the window and has been pruned.
+    let result = balance_at(&setup, identity(2), h1);
+    assert_eq!(
+        result.unwrap_err().code(),
+        error::height_not_available(h1).code()
+    );
+
+    // Future blocks are not available.
+    let result = balance_at(&setup, identity(2), h3 + 1);
+    assert_eq!(
+        result.unwrap_err().code(),
+        error::height_not_available(h3 + 1).code()
+    );
+}
+
+#[test]
+fn pruned_after_query() {
+    let mut setup = setup_with_history(2);
+
+    let (h1, _) = setup.block(|setup| setup.send_(setup.id, identity(2), 100u32));
+
+    // The second query uses the checkpoint opened by the first one.
+    assert_eq!(balance_at(&setup, identity(2), h1).unwrap(), 100u32);
+    assert_eq!(balance_at(&setup, identity(2), h1).unwrap(), 100u32);
+
+    setup.block(|setup| setup.send_(setup.id, identity(2), 50u32));
+    setup.block(|setup| setup.send_(setup.id, identity(2), 25u32));
+
+    // Pruning also closes the open checkpoint.
+    let result = balance_at(&setup, identity(2), h1);
+    assert_eq!(
+        result.unwrap_err().code(),
+        error::height_not_available(h1).code()
+    );
+}
+
+#[test]
+fn history_disabled() {
+    let mut setup = Setup::new(true);
+    setup.set_balance(setup.id, 1_000_000, *MFX_SYMBOL);
+    let (h, _) = setup.block(|setup| setup.send_(setup.id, identity(2), 100u32));
+
+    let result = balance_at(&setup, identity(2), h);
+    assert_eq!(
+        result.unwrap_err().code(),
+        error::height_not_available(h).code()
+    );
+}
+
+#[test]
+fn account_info_at_height() {
+    let mut setup = setup_with_history(10);
+
+    let (h1, _) = setup.block(|_| {});
+    let (h2, account_id) = setup.block(|setup| setup.create_account_(AccountType::Multisig));
+    let (h3, _) = setup.block(|setup| {
+        AccountModuleBackend::disable(
+            &mut setup.module_impl,
+            &setup.id,
+            account::DisableArgs {
+                account: account_id,
+            },
+        )
+        .unwrap();
+    });
+
+    assert_eq!(
+        account_info_at(&setup, account_id, h1).unwrap_err().code(),
+        account::errors::unknown_account(account_id).code()
+    );
+    assert!(account_info_at(&setup, account_id, h2)
+        .unwrap()
+        .disabled
+        .is_none());
+    assert!(account_info_at(&setup, account_id, h3)
+        .unwrap()
+        .disabled
+        .is_some());
+}
@@ -92,6 +92,7 @@ fn id_has_tokens(w: &mut MintWorld, id: SomeId, amount: u64) {
         BalanceArgs {
             account: Some(addr),
             symbols: Some(vec![w.info.symbol].into()),
+            height: None,
         },
         Context::new(RequestMessage::default(), unbounded().0),
     )
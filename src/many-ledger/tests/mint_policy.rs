@@ -0,0 +1,251 @@
+use many_error::ManyError;
+use many_identity::testing::identity;
+use many_identity::Address;
+use many_ledger::error;
+use many_ledger::migration::mint_policy::MINT_POLICY_MIGRATION;
+use many_ledger::migration::token_create::TOKEN_CREATE_MIGRATION;
+use many_ledger::migration::tokens::TOKEN_MIGRATION;
+use many_ledger_test_utils::{default_token_create_args, Setup};
+use many_modules::events::{EventFilter, EventInfo, EventKind, EventsModuleBackend, ListArgs};
+use many_modules::ledger::{
+    LedgerMintBurnModuleBackend, LedgerTokensModuleBackend, MintPolicy, MintRateLimit,
+    TokenInfoArgs, TokenInfoReturns, TokenMintArgs, TokenSetMintPolicyArgs,
+};
+use many_types::ledger::{LedgerTokensAddressMap, Symbol, TokenAmount, TokenMaybeOwner};
+use std::collections::BTreeMap;
+
+const DAY: u64 = 86400;
+
+fn setup_with_token() -> (Setup, Symbol) {
+    setup_with_token_and_migrations(true)
+}
+
+fn setup_with_token_and_migrations(mint_policy: bool) -> (Setup, Symbol) {
+    let mut migrations = vec![(0, &TOKEN_MIGRATION), (0, &TOKEN_CREATE_MIGRATION)];
+    if mint_policy {
+        migrations.push((0, &MINT_POLICY_MIGRATION));
+    }
+    let mut setup = Setup::new_with_migrations(true, migrations, true);
+    let owner = setup.id;
+    let (_, symbol) = setup.block(|setup| {
+        LedgerTokensModuleBackend::create(
+            &mut setup.module_impl,
+            &owner,
+            default_token_create_args(Some(TokenMaybeOwner::Left(owner)), None),
+        )
+        .unwrap()
+        .info
+        .symbol
+    });
+    (setup, symbol)
+}
+
+fn set_policy(
+    setup: &mut Setup,
+    sender: Address,
+    symbol: Symbol,
+    policy: Option<MintPolicy>,
+) -> Result<(), ManyError> {
+    setup
+        .block(|setup| {
+            LedgerMintBurnModuleBackend::set_mint_policy(
+                &mut setup.module_impl,
+                &sender,
+                TokenSetMintPolicyArgs { symbol, policy },
+            )
+        })
+        .1
+        .map(|_| ())
+}
+
+fn mint(setup: &mut Setup, sender: Address, symbol: Symbol, amount: u64) -> Result<(), ManyError> {
+    setup
+        .block(|setup| {
+            LedgerMintBurnModuleBackend::mint(
+                &mut setup.module_impl,
+                &sender,
+                TokenMintArgs {
+                    symbol,
+                    distribution: LedgerTokensAddressMap::from([(
+                        identity(10),
+                        TokenAmount::from(amount),
+                    )]),
+                    memo: None,
+                },
+            )
+        })
+        .1
+        .map(|_| ())
+}
+
+fn info(setup: &Setup, symbol: Symbol) -> TokenInfoReturns {
+    LedgerTokensModuleBackend::info(
+        &setup.module_impl,
+        &setup.id,
+        TokenInfoArgs {
+            symbol,
+            extended_info: None,
+            height: None,
+        },
+    )
+    .unwrap()
+}
+
+fn allowances(minter: Address, amount: u64) -> BTreeMap<Address, TokenAmount> {
+    BTreeMap::from([(minter, TokenAmount::from(amount))])
+}
+
+#[test]
+fn minter_allowance() {
+    let (mut setup, symbol) = setup_with_token();
+    let owner = setup.id;
+
+    let policy = MintPolicy {
+        allowances: allowances(owner, 100),
+        rate_limit: None,
+    };
+    set_policy(&mut setup, owner, symbol, Some(policy)).unwrap();
+
+    mint(&mut setup, owner, symbol, 60).unwrap();
+    assert_eq!(
+        mint(&mut setup, owner, symbol, 50).unwrap_err().code(),
+        error::over_mint_allowance("", "", "").code()
+    );
+    assert_eq!(
+        info(&setup, symbol).mint_policy.unwrap().allowances[&owner],
+        TokenAmount::from(40u64)
+    );
+
+    // Refill.
+    let policy = MintPolicy {
+        allowances: allowances(owner, 100),
+        rate_limit: None,
+    };
+    set_policy(&mut setup, owner, symbol, Some(policy)).unwrap();
+    mint(&mut setup, owner, symbol, 100).unwrap();
+}
+
+#[test]
+fn allowance_does_not_grant_minting() {
+    let (mut setup, symbol) = setup_with_token();
+    let owner = setup.id;
+
+    let policy = MintPolicy {
+        allowances: allowances(identity(5), 100),
+        rate_limit: None,
+    };
+    set_policy(&mut setup, owner, symbol, Some(policy)).unwrap();
+
+    assert_eq!(
+        mint(&mut setup, identity(5), symbol, 10)
+            .unwrap_err()
+            .code(),
+        error::invalid_sender().code()
+    );
+
+    // Once there is a policy, identities without an allowance cannot mint.
+    assert_eq!(
+        mint(&mut setup, owner, symbol, 10).unwrap_err().code(),
+        error::no_mint_allowance("", "").code()
+    );
+}
+
+#[test]
+fn set_policy_logs_event() {
+    let (mut setup, symbol) = setup_with_token();
+    let owner = setup.id;
+
+    let policy = MintPolicy {
+        allowances: allowances(owner, 100),
+        rate_limit: None,
+    };
+    set_policy(&mut setup, owner, symbol, Some(policy.clone())).unwrap();
+
+    let list = EventsModuleBackend::list(
+        &setup.module_impl,
+        ListArgs {
+            filter: Some(EventFilter {
+                kind: Some(vec![EventKind::TokenSetMintPolicy].into()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    assert_eq!(list.nb_events, 1);
+    assert_eq!(
+        list.events[0].content,
+        EventInfo::TokenSetMintPolicy {
+            symbol,
+            policy: Some(policy),
+        }
+    );
+}
+
+#[test]
+fn rate_limit() {
+    let (mut setup, symbol) = setup_with_token();
+    let owner = setup.id;
+
+    let policy = MintPolicy {
+        allowances: allowances(owner, 1_000_000),
+        rate_limit: Some(MintRateLimit {
+            amount: TokenAmount::from(100u64),
+            period_in_secs: DAY,
+        }),
+    };
+    set_policy(&mut setup, owner, symbol, Some(policy)).unwrap();
+
+    mint(&mut setup, owner, symbol, 60).unwrap();
+    assert_eq!(
+        mint(&mut setup, owner, symbol, 50).unwrap_err().code(),
+        error::over_mint_rate_limit("", "", "").code()
+    );
+    assert_eq!(
+        info(&setup, symbol).minted_in_period,
+        Some(TokenAmount::from(60u64))
+    );
+
+    // A new period starts.
+    setup.inc_time(DAY);
+    mint(&mut setup, owner, symbol, 100).unwrap();
+
+    // Removing the policy removes the limit.
+    set_policy(&mut setup, owner, symbol, None).unwrap();
+    mint(&mut setup, owner, symbol, 1_000).unwrap();
+    assert!(info(&setup, symbol).mint_policy.is_none());
+}
+
+#[test]
+fn requires_migration() {
+    let (mut setup, symbol) = setup_with_token_and_migrations(false);
+    let owner = setup.id;
+
+    let policy = MintPolicy {
+        allowances: allowances(owner, 100),
+        rate_limit: None,
+    };
+    assert_eq!(
+        set_policy(&mut setup, owner, symbol, Some(policy))
+            .unwrap_err()
+            .code(),
+        ManyError::invalid_method_name("").code()
+    );
+    mint(&mut setup, owner, symbol, 1_000).unwrap();
+}
+
+#[test]
+fn only_owner_sets_policy() {
+    let (mut setup, symbol) = setup_with_token();
+
+    let result = set_policy(
+        &mut setup,
+        identity(5),
+        symbol,
+        Some(MintPolicy {
+            allowances: allowances(identity(5), 1_000_000),
+            rate_limit: None,
+        }),
+    );
+    assert_eq!(result.unwrap_err().code(), error::unauthorized().code());
+}
@@ -26,6 +26,7 @@ fn account_info(
         id,
         account::InfoArgs {
             account: account_id,
+            height: None,
         },
         Context::new(RequestMessage::default(), unbounded().0),
     )
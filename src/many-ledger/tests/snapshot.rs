@@ -0,0 +1,89 @@
+//! Tests regarding queries served from a snapshot of the last committed block.
+use async_channel::unbounded;
+use many_identity::testing::identity;
+use many_identity::Address;
+use many_ledger::module::snapshot::SnapshotLock;
+use many_ledger_test_utils::*;
+use many_modules::abci_backend::{AbciBlock, ManyAbciModuleBackend};
+use many_modules::ledger::{
+    BalanceArgs, LedgerCommandsModuleBackend, LedgerModuleBackend, SendArgs,
+};
+use many_modules::BackendLock;
+use many_protocol::context::Context;
+use many_protocol::RequestMessage;
+use many_types::ledger::TokenAmount;
+
+fn balance(lock: &SnapshotLock, account: Address) -> TokenAmount {
+    lock.read()
+        .balance(
+            &account,
+            BalanceArgs {
+                account: None,
+                symbols: Some(vec![*MFX_SYMBOL].into()),
+                height: None,
+            },
+            Context::new(RequestMessage::default(), unbounded().0),
+        )
+        .unwrap()
+        .balances
+        .get(&*MFX_SYMBOL)
+        .cloned()
+        .unwrap_or_default()
+}
+
+fn send(lock: &SnapshotLock, from: Address, to: Address, amount: u32) {
+    LedgerCommandsModuleBackend::send(
+        &mut *lock.write(),
+        &from,
+        SendArgs {
+            from: Some(from),
+            to,
+            amount: amount.into(),
+            symbol: *MFX_SYMBOL,
+            memo: None,
+        },
+    )
+    .unwrap();
+}
+
+#[test]
+fn query_during_block() {
+    let mut setup = Setup::new(true);
+    setup.set_balance(setup.id, 1_000_000, *MFX_SYMBOL);
+    setup.block(|setup| setup.send_(setup.id, identity(2), 100u32));
+
+    let Setup {
+        module_impl, id, ..
+    } = setup;
+    let lock = SnapshotLock::new(module_impl)
+        .with_snapshot(tempfile::tempdir().unwrap().into_path().join("snapshot"))
+        .unwrap();
+
+    lock.write().begin_block(AbciBlock { time: None }).unwrap();
+    send(&lock, id, identity(2), 50);
+
+    // The block isn't committed, so queries still see the previous one, even
+    // while a command holds the ledger.
+    {
+        let _command = lock.write();
+        assert_eq!(balance(&lock, identity(2)), 100u32);
+    }
+
+    lock.write().end_block().unwrap();
+    lock.write().commit().unwrap();
+    assert_eq!(balance(&lock, identity(2)), 150u32);
+}
+
+#[test]
+fn without_snapshot() {
+    let mut setup = Setup::new(false);
+    setup.set_balance(setup.id, 1_000_000, *MFX_SYMBOL);
+
+    let Setup {
+        module_impl, id, ..
+    } = setup;
+    let lock = SnapshotLock::new(module_impl);
+
+    send(&lock, id, identity(2), 100);
+    assert_eq!(balance(&lock, identity(2)), 100u32);
+}
@@ -0,0 +1,184 @@
+use async_channel::unbounded;
+use many_error::ManyError;
+use many_identity::testing::identity;
+use many_identity::Address;
+use many_ledger::migration::spending_limits::SPENDING_LIMITS_MIGRATION;
+use many_ledger_test_utils::*;
+use many_modules::account;
+use many_modules::account::features::spending_limits::{
+    errors, SpendingLimit, SpendingLimitsFeature, SpendingLimitsFeatureArg, SpendingPolicy,
+};
+use many_modules::account::features::FeatureInfo;
+use many_modules::account::AccountModuleBackend;
+use many_protocol::{context::Context, RequestMessage};
+use many_types::ledger::TokenAmount;
+use std::collections::{BTreeMap, BTreeSet};
+
+const DAY: u64 = 86400;
+
+fn daily_limit(amount: u64) -> BTreeMap<Address, SpendingLimit> {
+    BTreeMap::from([(
+        *MFX_SYMBOL,
+        SpendingLimit {
+            amount: TokenAmount::from(amount),
+            window_in_secs: DAY,
+            fixed: None,
+        },
+    )])
+}
+
+/// Create a ledger account where identity(2) can transact, limited by `arg`.
+fn setup_with_limits(arg: SpendingLimitsFeatureArg) -> (Setup, Address) {
+    setup_with_limits_from(arg, 0)
+}
+
+/// Same as `setup_with_limits`, with limits enforced from block `height`.
+fn setup_with_limits_from(arg: SpendingLimitsFeatureArg, height: u64) -> (Setup, Address) {
+    let mut setup = Setup::new_with_migrations(true, [(height, &SPENDING_LIMITS_MIGRATION)], false);
+    let mut args = create_account_args(AccountType::Ledger);
+    args.features
+        .insert(SpendingLimitsFeature::from_arg(arg).as_feature());
+    let account_id = AccountModuleBackend::create(&mut setup.module_impl, &setup.id, args)
+        .unwrap()
+        .id;
+    setup.set_balance(account_id, 1_000_000, *MFX_SYMBOL);
+    (setup, account_id)
+}
+
+fn send_as(
+    setup: &mut Setup,
+    sender: Address,
+    account_id: Address,
+    to: Address,
+    amount: u64,
+) -> Result<(), ManyError> {
+    setup
+        .block(|setup| setup.send_as(sender, account_id, to, amount, *MFX_SYMBOL))
+        .1
+}
+
+#[test]
+fn role_limit() {
+    let (mut setup, account_id) = setup_with_limits(SpendingLimitsFeatureArg {
+        roles: BTreeMap::from([(
+            account::Role::CanLedgerTransact,
+            SpendingPolicy {
+                limits: daily_limit(100),
+                allowlist: None,
+            },
+        )]),
+        ..Default::default()
+    });
+
+    assert!(send_as(&mut setup, identity(2), account_id, identity(5), 60).is_ok());
+    let result = send_as(&mut setup, identity(2), account_id, identity(5), 50);
+    assert_eq!(
+        result.unwrap_err().code(),
+        errors::spending_limit_exceeded("", "").code()
+    );
+    assert!(send_as(&mut setup, identity(2), account_id, identity(5), 40).is_ok());
+    assert_eq!(setup.balance_(identity(5)), 100u32);
+
+    // Owners are not limited.
+    let owner = setup.id;
+    assert!(send_as(&mut setup, owner, account_id, identity(5), 1_000).is_ok());
+
+    // The rolling window moves forward.
+    setup.inc_time(DAY);
+    assert!(send_as(&mut setup, identity(2), account_id, identity(5), 60).is_ok());
+    assert!(send_as(&mut setup, identity(2), account_id, identity(5), 50).is_err());
+}
+
+#[test]
+fn window_end_is_exclusive() {
+    let (mut setup, account_id) = setup_with_limits(SpendingLimitsFeatureArg {
+        roles: BTreeMap::from([(
+            account::Role::CanLedgerTransact,
+            SpendingPolicy {
+                limits: daily_limit(100),
+                allowlist: None,
+            },
+        )]),
+        ..Default::default()
+    });
+
+    // Every block advances the time by one second.
+    assert!(send_as(&mut setup, identity(2), account_id, identity(5), 100).is_ok());
+    setup.inc_time(DAY - 2);
+    assert!(send_as(&mut setup, identity(2), account_id, identity(5), 1).is_err());
+
+    // Exactly one window later, the first spending does not count anymore.
+    assert!(send_as(&mut setup, identity(2), account_id, identity(5), 100).is_ok());
+}
+
+#[test]
+fn not_enforced_before_migration() {
+    let (mut setup, account_id) = setup_with_limits_from(
+        SpendingLimitsFeatureArg {
+            roles: BTreeMap::from([(
+                account::Role::CanLedgerTransact,
+                SpendingPolicy {
+                    limits: daily_limit(100),
+                    allowlist: None,
+                },
+            )]),
+            ..Default::default()
+        },
+        1_000,
+    );
+
+    assert!(send_as(&mut setup, identity(2), account_id, identity(5), 1_000).is_ok());
+    assert_eq!(setup.balance_(identity(5)), 1_000u32);
+}
+
+#[test]
+fn member_allowlist() {
+    let (mut setup, account_id) = setup_with_limits(SpendingLimitsFeatureArg {
+        members: BTreeMap::from([(
+            identity(2),
+            SpendingPolicy {
+                limits: BTreeMap::new(),
+                allowlist: Some(BTreeSet::from([identity(3)])),
+            },
+        )]),
+        ..Default::default()
+    });
+
+    let result = send_as(&mut setup, identity(2), account_id, identity(4), 1_000);
+    assert_eq!(
+        result.unwrap_err().code(),
+        errors::recipient_not_allowed("").code()
+    );
+    assert!(send_as(&mut setup, identity(2), account_id, identity(3), 1_000).is_ok());
+    assert_eq!(setup.balance_(identity(3)), 1_000u32);
+}
+
+#[test]
+fn info_exposes_limits() {
+    let arg = SpendingLimitsFeatureArg {
+        members: BTreeMap::from([(
+            identity(2),
+            SpendingPolicy {
+                limits: daily_limit(100),
+                allowlist: Some(BTreeSet::from([identity(3)])),
+            },
+        )]),
+        ..Default::default()
+    };
+    let (setup, account_id) = setup_with_limits(arg.clone());
+
+    let info = AccountModuleBackend::info(
+        &setup.module_impl,
+        &setup.id,
+        account::InfoArgs {
+            account: account_id,
+            height: None,
+        },
+        Context::new(RequestMessage::default(), unbounded().0),
+    )
+    .unwrap();
+    assert_eq!(
+        info.features.get::<SpendingLimitsFeature>().unwrap().arg,
+        arg
+    );
+}
@@ -58,6 +58,7 @@ fn load() {
             ledger::BalanceArgs {
                 account: Some(identity(5)),
                 symbols: Some(vec![identity(1000)].into()),
+                height: None,
             },
             Context::new(RequestMessage::default(), unbounded().0),
         )
@@ -110,6 +111,10 @@ fn load_symbol_meta() {
                 decimals: 9,
                 owner: None,
                 maximum: None,
+                extended_info: None,
+                mint_policy: None,
+                paused: None,
+                frozen: None,
             },
         )]);
         let initial_balance = BTreeMap::from([(
@@ -154,6 +159,7 @@ fn load_symbol_meta() {
             ledger::BalanceArgs {
                 account: Some(identity(5)),
                 symbols: Some(vec![identity(1000)].into()),
+                height: None,
             },
             Context::new(RequestMessage::default(), unbounded().0),
         )
@@ -185,6 +191,7 @@ fn load_symbol_meta() {
         TokenInfoArgs {
             symbol: identity(1000),
             extended_info: None,
+            height: None,
         },
     )
     .unwrap()
@@ -0,0 +1,236 @@
+use many_error::ManyError;
+use many_identity::testing::identity;
+use many_identity::Address;
+use many_ledger::error;
+use many_ledger::migration::token_controls::TOKEN_CONTROLS_MIGRATION;
+use many_ledger::migration::token_create::TOKEN_CREATE_MIGRATION;
+use many_ledger::migration::tokens::TOKEN_MIGRATION;
+use many_ledger_test_utils::{default_token_create_args, AccountType, Setup};
+use many_modules::events::{EventFilter, EventKind, EventsModuleBackend, ListArgs};
+use many_modules::ledger::{
+    LedgerTokensModuleBackend, TokenFreezeArgs, TokenInfoArgs, TokenPauseArgs, TokenUnfreezeArgs,
+    TokenUnpauseArgs,
+};
+use many_types::ledger::{Symbol, TokenAmount};
+use std::collections::BTreeSet;
+
+fn setup_with_token() -> (Setup, Symbol) {
+    setup_with_token_and_migrations(true)
+}
+
+fn setup_with_token_and_migrations(token_controls: bool) -> (Setup, Symbol) {
+    let mut migrations = vec![(0, &TOKEN_MIGRATION), (0, &TOKEN_CREATE_MIGRATION)];
+    if token_controls {
+        migrations.push((0, &TOKEN_CONTROLS_MIGRATION));
+    }
+    let mut setup = Setup::new_with_migrations(false, migrations, true);
+    let symbol = LedgerTokensModuleBackend::create(
+        &mut setup.module_impl,
+        &setup.id,
+        default_token_create_args(None, None),
+    )
+    .unwrap()
+    .info
+    .symbol;
+    (setup, symbol)
+}
+
+fn freeze(setup: &mut Setup, symbol: Symbol, address: Address) {
+    let id = setup.id;
+    LedgerTokensModuleBackend::freeze(
+        &mut setup.module_impl,
+        &id,
+        TokenFreezeArgs {
+            symbol,
+            address,
+            memo: None,
+        },
+    )
+    .unwrap();
+}
+
+fn event_count(setup: &Setup, kind: EventKind) -> usize {
+    EventsModuleBackend::list(
+        &setup.module_impl,
+        ListArgs {
+            filter: Some(EventFilter {
+                kind: Some(vec![kind].into()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        },
+    )
+    .unwrap()
+    .events
+    .len()
+}
+
+#[test]
+fn freeze_unfreeze() {
+    let (mut setup, symbol) = setup_with_token();
+    let id = setup.id;
+
+    freeze(&mut setup, symbol, identity(1));
+
+    // Neither sending from nor to a frozen address is possible.
+    assert_eq!(
+        setup
+            .send(identity(1), identity(2), 10u64, symbol)
+            .unwrap_err()
+            .code(),
+        error::address_frozen("", "").code()
+    );
+    assert_eq!(
+        setup
+            .send(identity(2), identity(1), 10u64, symbol)
+            .unwrap_err()
+            .code(),
+        error::address_frozen("", "").code()
+    );
+    // Other addresses are not affected.
+    setup.send(identity(2), identity(3), 10u64, symbol).unwrap();
+
+    let info = LedgerTokensModuleBackend::info(
+        &setup.module_impl,
+        &id,
+        TokenInfoArgs {
+            symbol,
+            extended_info: None,
+            height: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(info.frozen, Some(BTreeSet::from([identity(1)])));
+    assert_eq!(info.paused, Some(false));
+
+    LedgerTokensModuleBackend::unfreeze(
+        &mut setup.module_impl,
+        &id,
+        TokenUnfreezeArgs {
+            symbol,
+            address: identity(1),
+            memo: None,
+        },
+    )
+    .unwrap();
+    setup.send(identity(1), identity(2), 10u64, symbol).unwrap();
+
+    assert_eq!(event_count(&setup, EventKind::TokenFreeze), 1);
+    assert_eq!(event_count(&setup, EventKind::TokenUnfreeze), 1);
+}
+
+#[test]
+fn pause_unpause() {
+    let (mut setup, symbol) = setup_with_token();
+    let id = setup.id;
+
+    LedgerTokensModuleBackend::pause(
+        &mut setup.module_impl,
+        &id,
+        TokenPauseArgs { symbol, memo: None },
+    )
+    .unwrap();
+    assert_eq!(
+        setup
+            .send(identity(2), identity(3), 10u64, symbol)
+            .unwrap_err()
+            .code(),
+        error::token_paused("").code()
+    );
+
+    LedgerTokensModuleBackend::unpause(
+        &mut setup.module_impl,
+        &id,
+        TokenUnpauseArgs { symbol, memo: None },
+    )
+    .unwrap();
+    setup.send(identity(2), identity(3), 10u64, symbol).unwrap();
+
+    assert_eq!(event_count(&setup, EventKind::TokenPause), 1);
+    assert_eq!(event_count(&setup, EventKind::TokenUnpause), 1);
+}
+
+#[test]
+fn requires_migration() {
+    let (mut setup, symbol) = setup_with_token_and_migrations(false);
+    let id = setup.id;
+
+    let result = LedgerTokensModuleBackend::freeze(
+        &mut setup.module_impl,
+        &id,
+        TokenFreezeArgs {
+            symbol,
+            address: identity(1),
+            memo: None,
+        },
+    );
+    assert_eq!(
+        result.unwrap_err().code(),
+        ManyError::invalid_method_name("").code()
+    );
+
+    let result = LedgerTokensModuleBackend::pause(
+        &mut setup.module_impl,
+        &id,
+        TokenPauseArgs { symbol, memo: None },
+    );
+    assert_eq!(
+        result.unwrap_err().code(),
+        ManyError::invalid_method_name("").code()
+    );
+    setup.send(identity(1), identity(2), 10u64, symbol).unwrap();
+}
+
+#[test]
+fn only_owner() {
+    let (mut setup, symbol) = setup_with_token();
+
+    let result = LedgerTokensModuleBackend::freeze(
+        &mut setup.module_impl,
+        &identity(1),
+        TokenFreezeArgs {
+            symbol,
+            address: identity(2),
+            memo: None,
+        },
+    );
+    assert_eq!(result.unwrap_err().code(), error::unauthorized().code());
+
+    let result = LedgerTokensModuleBackend::pause(
+        &mut setup.module_impl,
+        &identity(1),
+        TokenPauseArgs { symbol, memo: None },
+    );
+    assert_eq!(result.unwrap_err().code(), error::unauthorized().code());
+}
+
+#[test]
+fn multisig_send_frozen() {
+    let (mut setup, symbol) = setup_with_token();
+    let account_id = setup.create_account_(AccountType::Multisig);
+    setup.send(identity(1), account_id, 100u64, symbol).unwrap();
+
+    let token = setup
+        .multisig_send(account_id, identity(4), 10u64, symbol)
+        .unwrap();
+
+    freeze(&mut setup, symbol, account_id);
+
+    // New transactions are refused and pending ones fail on execution.
+    assert_eq!(
+        setup
+            .multisig_send(account_id, identity(4), 10u64, symbol)
+            .unwrap_err()
+            .code(),
+        error::address_frozen("", "").code()
+    );
+    let response = setup.multisig_execute_(&token);
+    assert_eq!(
+        response.data.unwrap_err().code(),
+        error::address_frozen("", "").code()
+    );
+    assert_eq!(
+        setup.balance(identity(4), symbol).unwrap(),
+        TokenAmount::zero()
+    );
+}
@@ -8,6 +8,8 @@ use syn::punctuated::Punctuated;
 use syn::spanned::Spanned;
 use syn::{FnArg, Pat, PatType, ReturnType, Token, TraitItem, TraitItemFn, Type, TypePath};
 
+mod schema;
+
 #[derive(Deserialize)]
 struct ManyModuleAttributes {
     pub id: Option<u32>,
@@ -284,6 +286,48 @@ impl Endpoint {
         }
     }
 
+    /// Returns the name and description of the endpoint, from the schemas of
+    /// its argument and return types.
+    pub fn description(&self, namespace: &Option<String>, many_modules: &Ident) -> TokenStream {
+        let span = self.span;
+        let name = self.name.as_str().to_camel_case();
+        let ep = match namespace {
+            Some(ref namespace) => format!("{namespace}.{name}"),
+            None => name,
+        };
+
+        let argument = match &self.arg {
+            Some((_, ty)) => quote_spanned! { span => Some(many_types::schema_of!(#ty)) },
+            None => quote! { None },
+        };
+
+        // The success type of the `Result`.
+        let returns = match self.ret_type.as_ref() {
+            Type::Path(TypePath { path, .. }) => match &path.segments.last().unwrap().arguments {
+                syn::PathArguments::AngleBracketed(args) => match args.args.first() {
+                    Some(syn::GenericArgument::Type(ty)) => Some(ty),
+                    _ => None,
+                },
+                _ => None,
+            },
+            _ => None,
+        };
+        let returns = match returns {
+            Some(ty) => quote_spanned! { span => many_types::schema_of!(#ty) },
+            None => quote! { many_types::schema::Schema::Any },
+        };
+
+        quote_spanned! { span =>
+            (
+                #ep.to_string(),
+                #many_modules ::base::EndpointDescription {
+                    argument: #argument,
+                    returns: #returns,
+                },
+            )
+        }
+    }
+
     pub fn validate_endpoint_pat(&self, namespace: &Option<String>) -> TokenStream {
         let span = self.span;
         let name = self.name.as_str().to_camel_case();
@@ -331,7 +375,11 @@ impl Endpoint {
         }
     }
 
-    pub fn execute_endpoint_pat(&self, namespace: &Option<String>) -> TokenStream {
+    pub fn execute_endpoint_pat(
+        &self,
+        namespace: &Option<String>,
+        many_modules: &Ident,
+    ) -> TokenStream {
         let span = self.span;
         let name = self.name.as_str().to_camel_case();
         let ep = match namespace {
@@ -341,9 +389,9 @@ impl Endpoint {
         let ep_ident = &self.func;
 
         let backend_decl = if self.is_mut {
-            quote! { let mut backend = self.backend.lock().unwrap(); }
+            quote! { let mut backend = #many_modules ::BackendLock::write(&*self.backend); }
         } else {
-            quote! { let backend = self.backend.lock().unwrap(); }
+            quote! { let backend = #many_modules ::BackendLock::read(&*self.backend); }
         };
 
         let call = match (
@@ -491,6 +539,16 @@ fn many_module_impl(attr: &TokenStream, item: TokenStream) -> Result<TokenStream
         })
         .collect();
 
+    let query_strings = endpoints
+        .iter()
+        .zip(&endpoint_strings)
+        .filter(|(e, _)| !e.is_mut)
+        .map(|(_, name)| name);
+
+    let descriptions = endpoints
+        .iter()
+        .map(|e| e.description(&namespace, &many_modules));
+
     let validate_endpoint_pat = endpoints
         .iter()
         .map(|e| e.validate_endpoint_pat(&namespace));
@@ -511,7 +569,9 @@ fn many_module_impl(attr: &TokenStream, item: TokenStream) -> Result<TokenStream
         }
     };
 
-    let execute_endpoint_pat = endpoints.iter().map(|e| e.execute_endpoint_pat(&namespace));
+    let execute_endpoint_pat = endpoints
+        .iter()
+        .map(|e| e.execute_endpoint_pat(&namespace, &many_modules));
 
     let execute = quote! {
         async fn execute(
@@ -579,6 +639,8 @@ fn many_module_impl(attr: &TokenStream, item: TokenStream) -> Result<TokenStream
                         name: #struct_name .to_string(),
                         attribute: #attribute,
                         endpoints: vec![ #( #endpoint_strings .to_string() ),* ],
+                        queries: std::collections::BTreeSet::from([ #( #query_strings .to_string() ),* ]),
+                        descriptions: std::collections::BTreeMap::from([ #( #descriptions ),* ]),
                     })));
                     &*VALUE
                 }
@@ -588,11 +650,12 @@ fn many_module_impl(attr: &TokenStream, item: TokenStream) -> Result<TokenStream
         #[async_trait::async_trait]
         #trait_
 
-        #vis struct #struct_ident<T: #trait_ident> {
-            backend: std::sync::Arc<std::sync::Mutex<T>>
+        #vis struct #struct_ident<T: #trait_ident, L = std::sync::Mutex<T>> {
+            backend: std::sync::Arc<L>,
+            _backend: std::marker::PhantomData<fn() -> T>,
         }
 
-        impl<T: #trait_ident> std::fmt::Debug for #struct_ident<T> {
+        impl<T: #trait_ident, L> std::fmt::Debug for #struct_ident<T, L> {
             fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
                 f.debug_struct(#struct_name).finish()
             }
@@ -600,12 +663,26 @@ fn many_module_impl(attr: &TokenStream, item: TokenStream) -> Result<TokenStream
 
         impl<T: #trait_ident> #struct_ident<T> {
             pub fn new(backend: std::sync::Arc<std::sync::Mutex<T>>) -> Self {
-                Self { backend }
+                Self {
+                    backend,
+                    _backend: std::marker::PhantomData,
+                }
+            }
+        }
+
+        impl<T: #trait_ident, L: #many_modules ::BackendLock<T>> #struct_ident<T, L> {
+            /// Share the backend through another lock than a `Mutex`, e.g. to
+            /// serve the queries without waiting for the commands.
+            pub fn with_lock(backend: std::sync::Arc<L>) -> Self {
+                Self {
+                    backend,
+                    _backend: std::marker::PhantomData,
+                }
             }
         }
 
         #[async_trait::async_trait]
-        impl<T: #trait_ident>  #many_modules ::ManyModule for #struct_ident<T> {
+        impl<T: #trait_ident, L: #many_modules ::BackendLock<T>> #many_modules ::ManyModule for #struct_ident<T, L> {
             fn info(&self) -> & #many_modules ::ManyModuleInfo {
                 & #info_ident
             }
@@ -626,3 +703,13 @@ pub fn many_module(
         .unwrap_or_else(|e| e.to_compile_error())
         .into()
 }
+
+/// Implement `many_types::schema::CborSchema` for a type using the `minicbor`
+/// derive macros, from its `#[n(...)]` and `#[cbor(...)]` attributes.
+#[proc_macro_derive(CborSchema)]
+pub fn cbor_schema(item: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    syn::parse(item)
+        .and_then(schema::derive_cbor_schema)
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
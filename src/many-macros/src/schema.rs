@@ -0,0 +1,261 @@
+//! The `CborSchema` derive macro, which describes the encoding of types using
+//! the `minicbor` derive macros.
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::ext::IdentExt;
+use syn::spanned::Spanned;
+use syn::{Attribute, Data, DeriveInput, Fields, GenericParam, LitInt, Type};
+
+/// The `#[cbor(...)]` options that change the encoding of a type or field.
+#[derive(Default)]
+struct CborOptions {
+    map: bool,
+    array: bool,
+    transparent: bool,
+    index_only: bool,
+    skip: bool,
+    custom: bool,
+    /// Encoded with `minicbor::bytes`, as a byte string.
+    bytes: bool,
+    tag: Option<u64>,
+}
+
+impl CborOptions {
+    fn from_attributes(attrs: &[Attribute]) -> syn::Result<Self> {
+        let mut options = Self::default();
+        for attr in attrs.iter().filter(|a| a.path().is_ident("cbor")) {
+            attr.parse_nested_meta(|meta| {
+                let path = &meta.path;
+                if path.is_ident("map") {
+                    options.map = true;
+                } else if path.is_ident("array") {
+                    options.map = false;
+                    options.array = true;
+                } else if path.is_ident("transparent") {
+                    options.transparent = true;
+                } else if path.is_ident("index_only") {
+                    options.index_only = true;
+                } else if path.is_ident("skip") {
+                    options.skip = true;
+                } else if ["with", "encode_with"]
+                    .iter()
+                    .any(|name| path.is_ident(name))
+                {
+                    options.custom = true;
+                }
+
+                // Skip the value of other options.
+                if meta.input.peek(syn::Token![=]) {
+                    let value = meta.value()?.parse::<syn::Expr>()?;
+                    if path.is_ident("with") && is_str(&value, "minicbor::bytes") {
+                        options.custom = false;
+                        options.bytes = true;
+                    }
+                } else if meta.input.peek(syn::token::Paren) {
+                    let content;
+                    syn::parenthesized!(content in meta.input);
+                    if path.is_ident("tag") {
+                        options.tag = Some(content.parse::<LitInt>()?.base10_parse()?);
+                    } else {
+                        content.parse::<TokenStream>()?;
+                    }
+                }
+                Ok(())
+            })?;
+        }
+        Ok(options)
+    }
+}
+
+/// Whether an expression is the string literal `value`.
+fn is_str(expr: &syn::Expr, value: &str) -> bool {
+    match expr {
+        syn::Expr::Lit(syn::ExprLit {
+            lit: syn::Lit::Str(lit),
+            ..
+        }) => lit.value() == value,
+        _ => false,
+    }
+}
+
+/// The index of a field or variant, from its `#[n(...)]` or `#[b(...)]`
+/// attribute, or the `n(...)` or `b(...)` option of its `#[cbor(...)]`
+/// attribute.
+fn index(attrs: &[Attribute], span: proc_macro2::Span) -> syn::Result<u64> {
+    let is_index = |path: &syn::Path| path.is_ident("n") || path.is_ident("b");
+    if let Some(attr) = attrs.iter().find(|a| is_index(a.path())) {
+        return attr.parse_args::<LitInt>()?.base10_parse();
+    }
+
+    let mut index = None;
+    for attr in attrs.iter().filter(|a| a.path().is_ident("cbor")) {
+        attr.parse_nested_meta(|meta| {
+            if is_index(&meta.path) {
+                let content;
+                syn::parenthesized!(content in meta.input);
+                index = Some(content.parse::<LitInt>()?.base10_parse()?);
+            } else if meta.input.peek(syn::Token![=]) {
+                meta.value()?.parse::<syn::Expr>()?;
+            } else if meta.input.peek(syn::token::Paren) {
+                let content;
+                syn::parenthesized!(content in meta.input);
+                content.parse::<TokenStream>()?;
+            }
+            Ok(())
+        })?;
+    }
+    index.ok_or_else(|| syn::Error::new(span, "missing an `#[n(...)]` index"))
+}
+
+/// The inner type of an `Option<T>`.
+fn option_inner(ty: &Type) -> Option<&Type> {
+    let Type::Path(path) = ty else { return None };
+    let segment = path.path.segments.last()?;
+    if segment.ident != "Option" {
+        return None;
+    }
+    let syn::PathArguments::AngleBracketed(args) = &segment.arguments else {
+        return None;
+    };
+    match args.args.first()? {
+        syn::GenericArgument::Type(ty) => Some(ty),
+        _ => None,
+    }
+}
+
+/// Wrap a schema in a CBOR tag, for types or fields with `#[cbor(tag(...))]`.
+fn tagged(schema: TokenStream, tag: Option<u64>) -> TokenStream {
+    match tag {
+        Some(tag) => quote! { many_types::schema::Schema::tag(#tag, #schema) },
+        None => schema,
+    }
+}
+
+/// The schema of the fields of a struct or variant.
+fn fields_schema(fields: &Fields, options: &CborOptions) -> syn::Result<TokenStream> {
+    let mut described = vec![];
+    for (i, field) in fields.iter().enumerate() {
+        let field_options = CborOptions::from_attributes(&field.attrs)?;
+        if field_options.skip {
+            continue;
+        }
+        let key = index(&field.attrs, field.span())?;
+        let name = field
+            .ident
+            .as_ref()
+            .map_or_else(|| i.to_string(), |ident| ident.unraw().to_string());
+        described.push((key, name, &field.ty, field_options));
+    }
+    described.sort_by_key(|(key, ..)| *key);
+
+    let schema = |ty: &Type, field_options: &CborOptions| {
+        let schema = if field_options.custom {
+            quote! { many_types::schema::Schema::Any }
+        } else if field_options.bytes {
+            // `Option<Vec<u8>>` fields of arrays can be `null`.
+            match option_inner(ty) {
+                Some(_) => quote! {
+                    many_types::schema::Schema::Choice(vec![
+                        many_types::schema::Schema::Bytes,
+                        many_types::schema::Schema::Null,
+                    ])
+                },
+                None => quote! { many_types::schema::Schema::Bytes },
+            }
+        } else {
+            quote! { <#ty as many_types::schema::CborSchema>::schema() }
+        };
+        tagged(schema, field_options.tag)
+    };
+
+    if options.transparent {
+        return match described.as_slice() {
+            [(_, _, ty, field_options)] => Ok(schema(ty, field_options)),
+            _ => Err(syn::Error::new(
+                fields.span(),
+                "transparent types must have exactly one field",
+            )),
+        };
+    }
+
+    if options.map {
+        // `None` values are omitted from maps.
+        let fields = described.iter().map(|(key, name, ty, field_options)| {
+            let (ty, optional) = match option_inner(ty) {
+                Some(inner) => (inner, quote! { .optional() }),
+                None => (*ty, quote! {}),
+            };
+            let schema = schema(ty, field_options);
+            quote! { many_types::schema::Field::new(#key, #name, #schema) #optional }
+        });
+        Ok(quote! { many_types::schema::Schema::Struct(vec![ #( #fields ),* ]) })
+    } else {
+        // Missing indices are encoded as `null`.
+        let mut items = vec![];
+        for (key, _, ty, field_options) in &described {
+            while (items.len() as u64) < *key {
+                items.push(quote! { many_types::schema::Schema::Null });
+            }
+            items.push(schema(ty, field_options));
+        }
+        Ok(quote! { many_types::schema::Schema::Tuple(vec![ #( #items ),* ]) })
+    }
+}
+
+pub(crate) fn derive_cbor_schema(input: DeriveInput) -> syn::Result<TokenStream> {
+    let options = CborOptions::from_attributes(&input.attrs)?;
+    let ident = &input.ident;
+    let name = ident.to_string();
+
+    let body = match &input.data {
+        Data::Struct(data) => tagged(fields_schema(&data.fields, &options)?, options.tag),
+        Data::Enum(data) => {
+            let mut variants = vec![];
+            for variant in &data.variants {
+                let index = index(&variant.attrs, variant.span())?;
+                if options.index_only {
+                    variants.push(quote! { many_types::schema::Schema::Literal(#index) });
+                } else {
+                    let mut variant_options = CborOptions::from_attributes(&variant.attrs)?;
+                    // Variants use the encoding of the enum, unless they override it.
+                    variant_options.map |= options.map && !variant_options.array;
+                    let fields = tagged(
+                        fields_schema(&variant.fields, &variant_options)?,
+                        variant_options.tag,
+                    );
+                    variants.push(quote! {
+                        many_types::schema::Schema::Tuple(vec![
+                            many_types::schema::Schema::Literal(#index),
+                            #fields,
+                        ])
+                    });
+                }
+            }
+            quote! { many_types::schema::Schema::Choice(vec![ #( #variants ),* ]) }
+        }
+        Data::Union(data) => {
+            return Err(syn::Error::new(
+                data.union_token.span(),
+                "unions are not supported",
+            ))
+        }
+    };
+
+    let mut generics = input.generics.clone();
+    for param in &mut generics.params {
+        if let GenericParam::Type(param) = param {
+            param
+                .bounds
+                .push(syn::parse_quote!(many_types::schema::CborSchema));
+        }
+    }
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics many_types::schema::CborSchema for #ident #ty_generics #where_clause {
+            fn schema() -> many_types::schema::Schema {
+                many_types::schema::Schema::recursive(#name, || #body)
+            }
+        }
+    })
+}
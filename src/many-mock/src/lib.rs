@@ -1,57 +1,106 @@
 use std::collections::BTreeMap;
-use std::fmt;
-
-use serde::{de::Visitor, Deserialize, Deserializer, Serialize};
+use std::path::Path;
 
+pub mod record;
+mod rule;
 pub mod server;
 
-pub type MockEntries = BTreeMap<String, Vec<u8>>;
-
-#[derive(Serialize, Deserialize, Debug)]
-struct MockEntriesWrapper {
-    #[serde(deserialize_with = "deserialize_entries", flatten)]
-    entries: MockEntries,
-}
+pub use rule::{MockError, MockRule};
 
-fn deserialize_entries<'de, D>(d: D) -> Result<MockEntries, D::Error>
-where
-    D: Deserializer<'de>,
-{
-    struct MockEntriesVisitor;
-    impl<'de> Visitor<'de> for MockEntriesVisitor {
-        type Value = BTreeMap<String, Vec<u8>>;
-
-        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-            formatter.write_str("A map from string to hex code")
-        }
+/// The rules of each method, by method name.
+pub type MockEntries = BTreeMap<String, Vec<MockRule>>;
 
-        fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
-        where
-            A: serde::de::MapAccess<'de>,
-        {
-            let mut result = BTreeMap::new();
-            while let Some((key, value)) = map.next_entry::<String, String>()? {
-                let value_data = cbor_diag::parse_diag(value).map_err(|e| {
-                    serde::de::Error::custom(format!("Deserialization error: {e:?}"))
-                })?;
-                let value = value_data.to_bytes();
-                result.insert(key, value);
-            }
-            Ok(result)
+/// Parse the rules of a method, which are either a CBOR diagnostic string
+/// (the response to all requests), a table (one rule) or an array of tables.
+fn parse_rules(method: &str, value: toml::Value) -> Result<Vec<MockRule>, String> {
+    let rules = match value {
+        toml::Value::String(response) => {
+            let response = cbor_diag::parse_diag(response)
+                .map_err(|e| format!("Deserialization error: {e:?}"))?;
+            vec![MockRule::response(response)]
         }
-    }
-
-    d.deserialize_map(MockEntriesVisitor)
+        toml::Value::Array(rules) => rules
+            .into_iter()
+            .map(|rule| rule.try_into::<MockRule>().map_err(|e| e.to_string()))
+            .collect::<Result<_, _>>()?,
+        rule => vec![rule.try_into::<MockRule>().map_err(|e| e.to_string())?],
+    };
+    rules
+        .iter()
+        .try_for_each(MockRule::validate)
+        .map_err(|e| format!("Invalid mock entry for {method:?}: {e}"))?;
+    Ok(rules)
 }
 
 /// Reads and parses the mockfile provided by the mockfile_arg parameter, or from a default path
 pub fn parse_mockfile(mockfile_arg: &str) -> Result<MockEntries, String> {
-    let path = std::path::Path::new(mockfile_arg);
+    let path = Path::new(mockfile_arg);
     if !path.exists() {
         return Err(format!("File {path:?} does not exist"));
     }
     let contents = std::fs::read_to_string(path).map_err(|_| "Error reading file".to_string())?;
-    let parsed: MockEntriesWrapper = toml::from_str(&contents)
+    let parsed: BTreeMap<String, toml::Value> = toml::from_str(&contents)
         .map_err(|e| format!("Invalid mockfile, parse errors: {:?}", e.to_string()))?;
-    Ok(parsed.entries)
+    parsed
+        .into_iter()
+        .map(|(method, value)| Ok((method.clone(), parse_rules(&method, value)?)))
+        .collect()
+}
+
+/// Write mock entries to a mockfile, as arrays of rules.
+pub fn write_mockfile(path: impl AsRef<Path>, entries: &MockEntries) -> Result<(), String> {
+    let contents = toml::to_string(entries).map_err(|e| e.to_string())?;
+    std::fs::write(path, contents).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rules() {
+        let path = std::env::temp_dir().join("many-mock-rules.toml");
+        std::fs::write(
+            &path,
+            r#"
+                simplefield = '"hello"'
+
+                [["ledger.balance"]]
+                args = '{0: "maa"}'
+                call = 2
+                error = { code = -1, message = "Failed {reason}.", arguments = { reason = "x" } }
+
+                [["ledger.balance"]]
+                response = '{0: {}}'
+                async = true
+                delay_ms = 10
+            "#,
+        )
+        .unwrap();
+        let entries = parse_mockfile(path.to_str().unwrap()).unwrap();
+        assert_eq!(
+            entries["simplefield"][0]
+                .response
+                .as_ref()
+                .unwrap()
+                .to_diag(),
+            r#""hello""#
+        );
+        let rules = &entries["ledger.balance"];
+        assert_eq!(rules.len(), 2);
+        assert_eq!(rules[0].call, Some(2));
+        assert_eq!(
+            many_error::ManyError::from(rules[0].error.as_ref().unwrap()).to_string(),
+            "Failed x."
+        );
+        assert!(rules[1].is_async);
+
+        // Written mockfiles can be read back.
+        write_mockfile(&path, &entries).unwrap();
+        assert_eq!(parse_mockfile(path.to_str().unwrap()).unwrap(), entries);
+
+        std::fs::write(&path, "x = { response = '1', error = { code = 1 } }").unwrap();
+        assert!(parse_mockfile(path.to_str().unwrap()).is_err());
+        std::fs::remove_file(path).unwrap();
+    }
 }
@@ -0,0 +1,96 @@
+//! A proxy to a MANY server, which records the requests it forwards and their
+//! responses as a mockfile.
+use async_trait::async_trait;
+use coset::CoseSign1;
+use many_protocol::{RequestMessage, ResponseMessage};
+use many_server::transport::LowLevelManyRequestHandler;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tracing::warn;
+
+use crate::{write_mockfile, MockEntries, MockError, MockRule};
+
+#[derive(Debug)]
+pub struct MockRecorder {
+    upstream: String,
+    path: PathBuf,
+    entries: Mutex<MockEntries>,
+}
+
+impl MockRecorder {
+    /// Forward requests to the server at `upstream`, and write the recorded
+    /// rules to the mockfile at `path` after each request.
+    pub fn new(upstream: impl ToString, path: impl Into<PathBuf>) -> Self {
+        Self {
+            upstream: upstream.to_string(),
+            path: path.into(),
+            entries: Default::default(),
+        }
+    }
+
+    /// The rule answering a request with a response.
+    fn rule(request: &RequestMessage, response: &ResponseMessage) -> Result<MockRule, String> {
+        let args = if request.data.is_empty() {
+            None
+        } else {
+            Some(cbor_diag::parse_bytes(&request.data).map_err(|e| format!("{e:?}"))?)
+        };
+        let (response, error) = match &response.data {
+            Ok(data) if data.is_empty() => (None, None),
+            Ok(data) => (
+                Some(cbor_diag::parse_bytes(data).map_err(|e| format!("{e:?}"))?),
+                None,
+            ),
+            Err(error) => (None, Some(MockError::from(error))),
+        };
+        Ok(MockRule {
+            args,
+            response,
+            error,
+            ..Default::default()
+        })
+    }
+
+    fn record(&self, request: &CoseSign1, response: &CoseSign1) -> Result<(), String> {
+        let request = request
+            .payload
+            .as_ref()
+            .ok_or("Empty request envelope")
+            .and_then(|payload| {
+                RequestMessage::from_bytes(payload).map_err(|_| "Invalid request")
+            })?;
+        let response = response
+            .payload
+            .as_ref()
+            .ok_or("Empty response envelope")
+            .and_then(|payload| {
+                ResponseMessage::from_bytes(payload).map_err(|_| "Invalid response")
+            })?;
+        if many_client::client::wait::async_token(&response).is_some() {
+            return Err(format!("{} returned an async token", request.method));
+        }
+        let rule = Self::rule(&request, &response)?;
+
+        let mut entries = self.entries.lock().unwrap();
+        let rules = entries.entry(request.method).or_default();
+        // Identical requests are recorded once, with their first response.
+        if rules.iter().any(|r| r.args == rule.args) {
+            return Ok(());
+        }
+        rules.push(rule);
+        write_mockfile(&self.path, &entries)
+    }
+}
+
+#[async_trait]
+impl LowLevelManyRequestHandler for MockRecorder {
+    async fn execute(&self, envelope: CoseSign1) -> Result<CoseSign1, String> {
+        let response = many_client::client::send_envelope(&self.upstream, envelope.clone())
+            .await
+            .map_err(|e| e.to_string())?;
+        if let Err(e) = self.record(&envelope, &response) {
+            warn!("Could not record the request: {e}");
+        }
+        Ok(response)
+    }
+}
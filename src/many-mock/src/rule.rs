@@ -0,0 +1,243 @@
+use cbor_diag::DataItem;
+use many_error::{ManyError, ManyErrorCode};
+use many_identity::Address;
+use many_protocol::RequestMessage;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// (De)serialize CBOR values as CBOR diagnostic notation.
+mod diag {
+    use cbor_diag::DataItem;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(item: &Option<DataItem>, s: S) -> Result<S::Ok, S::Error> {
+        match item {
+            Some(item) => s.serialize_str(&item.to_diag()),
+            None => s.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Option<DataItem>, D::Error> {
+        Option::<String>::deserialize(d)?
+            .map(|text| {
+                cbor_diag::parse_diag(text)
+                    .map_err(|e| serde::de::Error::custom(format!("Deserialization error: {e:?}")))
+            })
+            .transpose()
+    }
+}
+
+/// An error response of a mock rule.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct MockError {
+    pub code: i64,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub arguments: BTreeMap<String, String>,
+}
+
+impl From<&MockError> for ManyError {
+    fn from(error: &MockError) -> Self {
+        ManyError::new(
+            ManyErrorCode::from(error.code),
+            error.message.clone(),
+            error.arguments.clone(),
+        )
+    }
+}
+
+impl From<&ManyError> for MockError {
+    fn from(error: &ManyError) -> Self {
+        Self {
+            code: error.code().into(),
+            message: error.message().map(ToString::to_string),
+            arguments: error.arguments().clone(),
+        }
+    }
+}
+
+/// A response of the mock server to the requests of a method. The rules of a
+/// method are tried in order, and the first one matching the request is used.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct MockRule {
+    /// Only match requests whose argument matches this value. Maps match if
+    /// they contain the entries of the pattern; other values must be equal.
+    #[serde(default, skip_serializing_if = "Option::is_none", with = "diag")]
+    pub args: Option<DataItem>,
+
+    /// Only match requests from this sender.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub from: Option<Address>,
+
+    /// Only match the nth call of the method, starting at 1.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub call: Option<u64>,
+
+    /// The value returned. Rules without response nor error return no data.
+    #[serde(default, skip_serializing_if = "Option::is_none", with = "diag")]
+    pub response: Option<DataItem>,
+
+    /// The error returned, instead of a value.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<MockError>,
+
+    /// How long to wait before responding, in milliseconds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delay_ms: Option<u64>,
+
+    /// Respond with an async token, whose result is the response (or error)
+    /// of the rule.
+    #[serde(default, rename = "async", skip_serializing_if = "std::ops::Not::not")]
+    pub is_async: bool,
+}
+
+impl MockRule {
+    /// A rule always returning this value.
+    pub fn response(response: DataItem) -> Self {
+        Self {
+            response: Some(response),
+            ..Default::default()
+        }
+    }
+
+    pub(crate) fn validate(&self) -> Result<(), String> {
+        match (&self.response, &self.error) {
+            (Some(_), Some(_)) => Err("A rule cannot have both a response and an error".into()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Whether this rule applies to the `call`th call of a method.
+    pub(crate) fn matches(&self, request: &RequestMessage, call: u64) -> bool {
+        if self.call.map_or(false, |c| c != call) {
+            return false;
+        }
+        if self
+            .from
+            .map_or(false, |from| request.from.unwrap_or_default() != from)
+        {
+            return false;
+        }
+        match &self.args {
+            None => true,
+            Some(pattern) => {
+                // Requests without argument match a `null` pattern.
+                let args = if request.data.is_empty() {
+                    Ok(DataItem::Simple(cbor_diag::Simple::NULL))
+                } else {
+                    cbor_diag::parse_bytes(&request.data)
+                };
+                args.map_or(false, |args| value_matches(pattern, &args))
+            }
+        }
+    }
+
+    /// The CBOR data or error returned by this rule.
+    pub(crate) fn result(&self) -> Result<Vec<u8>, ManyError> {
+        match (&self.error, &self.response) {
+            (Some(error), _) => Err(error.into()),
+            (None, Some(response)) => Ok(response.to_bytes()),
+            (None, None) => Ok(vec![]),
+        }
+    }
+}
+
+/// The content of a byte string or text string, which can be of indefinite
+/// length.
+fn string_bytes(item: &DataItem) -> Option<Vec<u8>> {
+    match item {
+        DataItem::ByteString(s) => Some(s.data.clone()),
+        DataItem::IndefiniteByteString(s) => Some(s.iter().flat_map(|s| s.data.clone()).collect()),
+        DataItem::TextString(s) => Some(s.data.clone().into_bytes()),
+        DataItem::IndefiniteTextString(s) => Some(s.iter().flat_map(|s| s.data.bytes()).collect()),
+        _ => None,
+    }
+}
+
+/// Whether a CBOR value matches a pattern, regardless of encoding widths.
+fn value_matches(pattern: &DataItem, value: &DataItem) -> bool {
+    use DataItem::*;
+
+    match (pattern, value) {
+        (Integer { value: a, .. }, Integer { value: b, .. }) => a == b,
+        (Negative { value: a, .. }, Negative { value: b, .. }) => a == b,
+        (ByteString(_) | IndefiniteByteString(_), ByteString(_) | IndefiniteByteString(_))
+        | (TextString(_) | IndefiniteTextString(_), TextString(_) | IndefiniteTextString(_)) => {
+            string_bytes(pattern) == string_bytes(value)
+        }
+        (Array { data: a, .. }, Array { data: b, .. }) => {
+            a.len() == b.len() && a.iter().zip(b).all(|(a, b)| value_matches(a, b))
+        }
+        (Map { data: a, .. }, Map { data: b, .. }) => a.iter().all(|(key, pattern)| {
+            b.iter()
+                .any(|(k, v)| value_matches(key, k) && value_matches(pattern, v))
+        }),
+        (
+            Tag {
+                tag: a, value: x, ..
+            },
+            Tag {
+                tag: b, value: y, ..
+            },
+        ) => a == b && value_matches(x, y),
+        (Float { value: a, .. }, Float { value: b, .. }) => a == b,
+        (Simple(a), Simple(b)) => a == b,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(data: &str) -> RequestMessage {
+        RequestMessage {
+            data: cbor_diag::parse_diag(data).unwrap().to_bytes(),
+            ..Default::default()
+        }
+    }
+
+    fn rule(args: &str) -> MockRule {
+        MockRule {
+            args: Some(cbor_diag::parse_diag(args).unwrap()),
+            ..MockRule::response(DataItem::Simple(cbor_diag::Simple::NULL))
+        }
+    }
+
+    #[test]
+    fn args() {
+        let request = request(r#"{0: "a", 1: [1_1, -2], 2: h'01'}"#);
+        assert!(rule("{0: \"a\"}").matches(&request, 1));
+        assert!(rule("{1: [1, -2], 2: h'01'}").matches(&request, 1));
+        assert!(!rule("{0: \"b\"}").matches(&request, 1));
+        assert!(!rule("{3: 0}").matches(&request, 1));
+        assert!(!rule("[1]").matches(&request, 1));
+        assert!(rule("null").matches(&RequestMessage::default(), 1));
+    }
+
+    #[test]
+    fn call_and_sender() {
+        let request = RequestMessage {
+            from: Some(Address::anonymous()),
+            ..request("null")
+        };
+        let rule = MockRule {
+            call: Some(2),
+            from: Some(Address::anonymous()),
+            ..rule("null")
+        };
+        assert!(!rule.matches(&request, 1));
+        assert!(rule.matches(&request, 2));
+
+        let rule = MockRule {
+            from: Some(Address::illegal()),
+            ..rule
+        };
+        assert!(!rule.matches(&request, 2));
+    }
+}
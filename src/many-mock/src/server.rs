@@ -6,17 +6,31 @@ use many_identity::Identity;
 use many_identity_dsa::CoseKeyVerifier;
 use many_identity_webauthn::WebAuthnVerifier;
 use many_modules::base;
-use many_protocol::{ManyUrl, ResponseMessage};
+use many_modules::r#async::attributes::AsyncAttribute;
+use many_modules::r#async::{AsyncToken, StatusArgs, StatusReturn};
+use many_protocol::{ManyUrl, RequestMessage, ResponseMessage};
 use many_server::transport::LowLevelManyRequestHandler;
+use std::collections::{BTreeMap, BTreeSet};
 use std::fmt::Debug;
+use std::sync::Mutex;
+use std::time::Duration;
 
 use crate::MockEntries;
 
+/// The method used to read the results of async tokens, unless it is mocked.
+const ASYNC_STATUS: &str = "async.status";
+
 #[derive(Debug)]
 pub struct ManyMockServer<I: Identity> {
     mock_entries: MockEntries,
     identity: I,
     verifier: (AnonymousVerifier, CoseKeyVerifier, WebAuthnVerifier),
+
+    /// The number of calls of each method.
+    calls: Mutex<BTreeMap<String, u64>>,
+
+    /// The response envelopes of async tokens.
+    async_results: Mutex<BTreeMap<Vec<u8>, CoseSign1>>,
 }
 
 impl<I: Identity> ManyMockServer<I> {
@@ -35,33 +49,101 @@ impl<I: Identity> ManyMockServer<I> {
             mock_entries,
             identity,
             verifier,
+            calls: Default::default(),
+            async_results: Default::default(),
         }
     }
+
+    fn has_async_rules(&self) -> bool {
+        self.mock_entries
+            .values()
+            .flatten()
+            .any(|rule| rule.is_async)
+    }
+
+    fn respond(&self, response: ResponseMessage) -> Result<CoseSign1, String> {
+        many_protocol::encode_cose_sign1_from_response(response, &self.identity)
+            .map_err(|e| e.to_string())
+    }
+
+    fn async_status(&self, message: &RequestMessage) -> Result<CoseSign1, String> {
+        let data = minicbor::decode::<StatusArgs>(&message.data)
+            .map_err(ManyError::deserialization_error)
+            .and_then(|args| {
+                let status = match self.async_results.lock().unwrap().get(args.token.as_ref()) {
+                    Some(response) => StatusReturn::Done {
+                        response: Box::new(response.clone()),
+                    },
+                    None => StatusReturn::Unknown,
+                };
+                minicbor::to_vec(status).map_err(|e| ManyError::serialization_error(e.to_string()))
+            });
+        self.respond(ResponseMessage::from_request(
+            message,
+            &self.identity.address(),
+            data,
+        ))
+    }
 }
 
 #[async_trait]
 impl<I: Identity + Debug + Send + Sync> LowLevelManyRequestHandler for ManyMockServer<I> {
     async fn execute(&self, envelope: CoseSign1) -> Result<CoseSign1, String> {
         let request = many_protocol::decode_request_from_cose_sign1(&envelope, &self.verifier);
-        let id = &self.identity;
+        let address = self.identity.address();
 
         let message = request.map_err(|_| "Error processing the request".to_string())?;
-        let response = self
-            .mock_entries
-            .get(&message.method)
-            .ok_or_else(|| "No mock entry for that".to_string())?;
-        let response = ResponseMessage {
-            from: id.address(),
-            data: Ok(response.clone()),
-            ..Default::default()
+        let rules = match self.mock_entries.get(&message.method) {
+            Some(rules) => rules,
+            None if message.method == ASYNC_STATUS => return self.async_status(&message),
+            None => return Err("No mock entry for that".to_string()),
         };
-        many_protocol::encode_cose_sign1_from_response(response, id).map_err(|e| e.to_string())
+
+        let call = {
+            let mut calls = self.calls.lock().unwrap();
+            let call = calls.entry(message.method.clone()).or_default();
+            *call += 1;
+            *call
+        };
+        let rule = match rules.iter().find(|rule| rule.matches(&message, call)) {
+            Some(rule) => rule,
+            None => {
+                return self.respond(ResponseMessage::from_request(
+                    &message,
+                    &address,
+                    Err(ManyError::unknown("No mock rule matches the request.")),
+                ))
+            }
+        };
+
+        if let Some(delay) = rule.delay_ms {
+            tokio::time::sleep(Duration::from_millis(delay)).await;
+        }
+        let response = ResponseMessage::from_request(&message, &address, rule.result());
+        if !rule.is_async {
+            return self.respond(response);
+        }
+
+        let token = {
+            let mut results = self.async_results.lock().unwrap();
+            let token = (results.len() as u64).to_be_bytes().to_vec();
+            results.insert(token.clone(), self.respond(response)?);
+            token
+        };
+        self.respond(
+            ResponseMessage::from_request(&message, &address, Ok(vec![]))
+                .with_attribute(AsyncAttribute::new(AsyncToken::from(token)).into()),
+        )
     }
 }
 
 impl<I: Identity> base::BaseModuleBackend for ManyMockServer<I> {
     fn endpoints(&self) -> Result<base::Endpoints, ManyError> {
-        Ok(base::Endpoints(self.mock_entries.keys().cloned().collect()))
+        let mut endpoints: BTreeSet<String> = self.mock_entries.keys().cloned().collect();
+        if self.has_async_rules() {
+            endpoints.insert(ASYNC_STATUS.to_string());
+        }
+        Ok(base::Endpoints(endpoints))
     }
 
     fn status(&self) -> Result<base::Status, ManyError> {
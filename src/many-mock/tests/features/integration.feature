@@ -8,3 +8,21 @@ Scenario: The server should answer with a JSON
 Scenario: The server should answer with a string
   Given I request "simplefield"
   Then it should be "hello"
+
+Scenario: The server should match the argument
+  Given I request "ledger.balance" with {"account": "maa"}
+  Then "balances" should be {"maa": 1}
+
+Scenario: The server should answer with an error
+  Given I request "ledger.balance" with {"account": "mqbfbahksdwaqeenayy2gxke32hgb7aq4ao4wt745lsfs6wiaaaaqnz"}
+  Then it should fail with code 10000 and message "Unknown account x."
+
+Scenario: The server should answer depending on the number of calls
+  Given I request "counter"
+  Then it should be 1
+  Given I request "counter"
+  Then it should be "many"
+
+Scenario: The server should answer with an async token
+  Given I request "ledger.send"
+  Then it should be {}
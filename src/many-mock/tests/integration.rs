@@ -7,6 +7,7 @@ use std::{
 use ciborium::value::Value;
 use cucumber::{given, then, World};
 use many_client::ManyClient;
+use many_error::ManyError;
 use many_identity::{AcceptAllVerifier, Address, AnonymousIdentity};
 use many_mock::server::ManyMockServer;
 use many_server::{transport::http::HttpServer, ManyServer};
@@ -17,6 +18,7 @@ struct MockWorld {
     finish_server: Arc<AtomicBool>,
     client: ManyClient<AnonymousIdentity>,
     response: Option<Value>,
+    error: Option<ManyError>,
 }
 
 impl Drop for MockWorld {
@@ -58,17 +60,35 @@ impl MockWorld {
             finish_server,
             client,
             response: None,
+            error: None,
         }
     }
 }
 
-#[given(regex = r#"I request "(.*)""#)]
+async fn request(w: &mut MockWorld, method: String, argument: Vec<u8>) {
+    let result = w.client.call_raw(method, &argument).await.unwrap();
+    let result = w.client.wait_response(result).await.unwrap();
+    match result.data {
+        Ok(bytes) => {
+            let response: Value = ciborium::de::from_reader(bytes.as_slice())
+                .expect("Should have parsed to a cbor value");
+            w.response = Some(response);
+        }
+        Err(e) => w.error = Some(e),
+    }
+}
+
+#[given(regex = r#"^I request "([^"]*)"$"#)]
 async fn make_request(w: &mut MockWorld, method: String) {
-    let result = w.client.call(method, ()).await.unwrap();
-    let bytes = result.data.expect("Should have a Vec<u8>");
-    let response: Value =
-        ciborium::de::from_reader(bytes.as_slice()).expect("Should have parsed to a cbor value");
-    w.response = Some(response);
+    request(w, method, minicbor::to_vec(()).unwrap()).await;
+}
+
+#[given(regex = r#"^I request "([^"]*)" with (.*)$"#)]
+async fn make_request_with(w: &mut MockWorld, method: String, argument: String) {
+    let argument: Value = serde_json::from_str(&argument).unwrap();
+    let mut bytes = vec![];
+    ciborium::ser::into_writer(&argument, &mut bytes).unwrap();
+    request(w, method, bytes).await;
 }
 
 #[allow(clippy::needless_pass_by_ref_mut)]
@@ -94,6 +114,14 @@ async fn field_value(w: &mut MockWorld, field_name: String, value: String) {
     assert_eq!(object[&field_name], json_value);
 }
 
+#[allow(clippy::needless_pass_by_ref_mut)]
+#[then(regex = r#"it should fail with code (-?\d+) and message "(.*)""#)]
+async fn error(w: &mut MockWorld, code: i64, message: String) {
+    let error = w.error.as_ref().expect("Should have failed");
+    assert_eq!(i64::from(error.code()), code);
+    assert_eq!(error.to_string(), message);
+}
+
 #[tokio::main]
 async fn main() {
     // Support both Cargo and Bazel paths
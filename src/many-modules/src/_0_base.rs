@@ -5,6 +5,7 @@ use many_identity::Address;
 use many_macros::many_module;
 use many_types::attributes::AttributeSet;
 use many_types::cbor::CborAny;
+use many_types::schema::{CborSchema, Field, Schema};
 use minicbor::data::Type;
 use minicbor::encode::{Error, Write};
 use minicbor::{Decode, Decoder, Encode, Encoder};
@@ -14,10 +15,27 @@ use many_error::ManyError;
 #[cfg(test)]
 use mockall::{automock, predicate::*};
 
-#[derive(Clone, Debug, Decode, Encode)]
+#[derive(Clone, Debug, Decode, Encode, CborSchema)]
 #[cbor(transparent)]
 pub struct Endpoints(#[n(0)] pub BTreeSet<String>);
 
+/// The schemas of the argument and return value of an endpoint.
+#[derive(Clone, Debug, Decode, Encode, CborSchema, Eq, PartialEq)]
+#[cbor(map)]
+pub struct EndpointDescription {
+    /// The schema of the argument, if the endpoint takes one.
+    #[n(0)]
+    pub argument: Option<Schema>,
+
+    #[n(1)]
+    pub returns: Schema,
+}
+
+/// The descriptions of endpoints, by method name.
+#[derive(Clone, Debug, Default, Decode, Encode, CborSchema)]
+#[cbor(transparent)]
+pub struct Descriptions(#[n(0)] pub BTreeMap<String, EndpointDescription>);
+
 // TODO: Move this in it's own file, like other modules
 pub type HeartbeatReturn = EmptyReturn;
 
@@ -83,6 +101,23 @@ impl<C> Encode<C> for Status {
     }
 }
 
+impl CborSchema for Status {
+    fn schema() -> Schema {
+        Schema::named(
+            "Status",
+            Schema::Struct(vec![
+                Field::new(0, "version", Schema::Uint),
+                Field::new(1, "name", Schema::Text),
+                Field::new(2, "public_key", Schema::Bytes).optional(),
+                Field::new(3, "identity", Address::schema()),
+                Field::new(4, "attributes", AttributeSet::schema()),
+                Field::new(5, "server_version", Schema::Text).optional(),
+                Field::new(7, "timeout", Schema::Uint).optional(),
+            ]),
+        )
+    }
+}
+
 impl<'b, C> Decode<'b, C> for Status {
     fn decode(d: &mut Decoder<'b>, _: &mut C) -> Result<Self, minicbor::decode::Error> {
         let mut builder = StatusBuilder::default();
@@ -143,6 +178,11 @@ pub trait BaseModuleBackend: Send {
         Ok(HeartbeatReturn {})
     }
     fn status(&self) -> Result<Status, ManyError>;
+
+    /// Servers that cannot describe their endpoints return no descriptions.
+    fn describe(&self) -> Result<Descriptions, ManyError> {
+        Ok(Descriptions::default())
+    }
 }
 
 #[cfg(test)]
@@ -216,6 +256,35 @@ mod tests {
         assert_eq!(endpoints.0, results.0);
     }
 
+    #[test]
+    fn describe() {
+        assert_eq!(
+            BaseModuleInfo.descriptions["status"],
+            EndpointDescription {
+                argument: None,
+                returns: Status::schema(),
+            }
+        );
+
+        let mut mock = MockBaseModuleBackend::new();
+        let descriptions = Descriptions(BTreeMap::from([(
+            "endpoints".to_string(),
+            BaseModuleInfo.descriptions["endpoints"].clone(),
+        )]));
+        mock.expect_describe()
+            .times(1)
+            .return_const(Ok(descriptions.clone()));
+        let module = super::BaseModule::new(Arc::new(Mutex::new(mock)));
+        let results: Descriptions =
+            minicbor::decode(&call_module(1, &module, "describe", "null").unwrap()).unwrap();
+
+        assert_eq!(descriptions.0, results.0);
+        assert_eq!(
+            results.0["endpoints"].returns,
+            Schema::named("Endpoints", Schema::array(Schema::Text))
+        );
+    }
+
     #[test]
     fn heartbeat() {
         let mut mock = MockBaseModuleBackend::new();
@@ -1,6 +1,7 @@
 use crate::EmptyReturn;
 use crate::ManyError;
 use many_macros::many_module;
+use many_types::schema::CborSchema;
 use minicbor::bytes::ByteVec;
 use minicbor::{Decode, Encode};
 use std::collections::BTreeMap;
@@ -8,14 +9,14 @@ use std::collections::BTreeMap;
 #[cfg(test)]
 use mockall::{automock, predicate::*};
 
-#[derive(Clone, Debug, Encode, Decode, Eq, PartialEq)]
+#[derive(Clone, Debug, Encode, Decode, CborSchema, Eq, PartialEq)]
 #[cbor(map)]
 pub struct EndpointInfo {
     #[n(0)]
     pub is_command: bool,
 }
 
-#[derive(Clone, Debug, Encode, Decode, Eq, PartialEq)]
+#[derive(Clone, Debug, Encode, Decode, CborSchema, Eq, PartialEq)]
 #[cbor(map)]
 pub struct AbciInit {
     /// List the methods supported by this module. For performance reason, this list will be
@@ -28,7 +29,17 @@ pub struct AbciInit {
     pub endpoints: BTreeMap<String, EndpointInfo>,
 }
 
-#[derive(Clone, Debug, Encode, Decode, Eq, PartialEq)]
+impl AbciInit {
+    /// The endpoints that don't change the state.
+    pub fn queries(&self) -> impl Iterator<Item = &str> {
+        self.endpoints
+            .iter()
+            .filter(|(_, info)| !info.is_command)
+            .map(|(name, _)| name.as_str())
+    }
+}
+
+#[derive(Clone, Debug, Encode, Decode, CborSchema, Eq, PartialEq)]
 #[cbor(map)]
 pub struct AbciInfo {
     #[n(0)]
@@ -38,14 +49,14 @@ pub struct AbciInfo {
     pub hash: ByteVec,
 }
 
-#[derive(Clone, Debug, Encode, Decode, Eq, PartialEq)]
+#[derive(Clone, Debug, Encode, Decode, CborSchema, Eq, PartialEq)]
 #[cbor(map)]
 pub struct AbciBlock {
     #[n(0)]
     pub time: Option<u64>,
 }
 
-#[derive(Clone, Debug, Encode, Decode, Eq, PartialEq)]
+#[derive(Clone, Debug, Encode, Decode, CborSchema, Eq, PartialEq)]
 #[cbor(map)]
 pub struct AbciCommitInfo {
     #[n(0)]
@@ -3,17 +3,18 @@ use super::{
     PublicKey,
 };
 use many_identity::Address;
+use many_types::schema::CborSchema;
 use minicbor::{Decode, Encode};
 
-#[derive(Clone, Debug, Encode, Decode, Eq, PartialEq)]
+#[derive(Clone, Debug, Encode, Decode, CborSchema, Eq, PartialEq)]
 #[cbor(map)]
 pub struct GetFromRecallPhraseArgs(#[n(0)] pub RecallPhrase);
 
-#[derive(Clone, Debug, Encode, Decode, Eq, PartialEq)]
+#[derive(Clone, Debug, Encode, Decode, CborSchema, Eq, PartialEq)]
 #[cbor(map)]
 pub struct GetFromAddressArgs(#[n(0)] pub Address);
 
-#[derive(Clone, Debug, Encode, Decode)]
+#[derive(Clone, Debug, Encode, Decode, CborSchema)]
 #[cbor(map)]
 pub struct GetReturns {
     #[n(0)]
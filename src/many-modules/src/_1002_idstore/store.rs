@@ -1,8 +1,9 @@
 use super::types::{CredentialId, PublicKey, RecallPhrase};
 use many_identity::Address;
+use many_types::schema::CborSchema;
 use minicbor::{Decode, Encode};
 
-#[derive(Clone, Debug, Encode, Decode, Eq, PartialEq)]
+#[derive(Clone, Debug, Encode, Decode, CborSchema, Eq, PartialEq)]
 #[cbor(map)]
 pub struct StoreArgs {
     #[n(0)]
@@ -15,6 +16,6 @@ pub struct StoreArgs {
     pub public_key: PublicKey,
 }
 
-#[derive(Clone, Debug, Encode, Decode)]
+#[derive(Clone, Debug, Encode, Decode, CborSchema)]
 #[cbor(map)]
 pub struct StoreReturns(#[n(0)] pub RecallPhrase);
@@ -1,11 +1,12 @@
+use many_types::schema::CborSchema;
 use minicbor::{bytes::ByteVec, Decode, Encode};
 
 pub type RecallPhrase = Vec<String>;
 
-#[derive(Clone, Debug, Encode, Decode, Eq, PartialEq)]
+#[derive(Clone, Debug, Encode, Decode, CborSchema, Eq, PartialEq)]
 #[cbor(transparent)]
 pub struct CredentialId(#[n(0)] pub ByteVec);
 
-#[derive(Clone, Debug, Encode, Decode, Eq, PartialEq)]
+#[derive(Clone, Debug, Encode, Decode, CborSchema, Eq, PartialEq)]
 #[cbor(transparent)]
 pub struct PublicKey(#[n(0)] pub ByteVec);
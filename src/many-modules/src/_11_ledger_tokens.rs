@@ -1,9 +1,11 @@
 use crate::EmptyReturn;
+use crate::_12_ledger_mintburn::MintPolicy;
 use many_error::ManyError;
 use many_identity::Address;
 use many_macros::many_module;
 use many_types::{cbor_type_decl, ledger, AttributeRelatedIndex, Memo};
 use minicbor::{Decode, Encode};
+use std::collections::BTreeSet;
 
 pub mod extended_info;
 
@@ -24,11 +26,16 @@ cbor_type_decl!(
     pub struct TokenInfoArgs {
         0 => symbol: ledger::Symbol,
         1 => extended_info: Option<Vec<AttributeRelatedIndex>>, // TODO: This thing should be of at least length 1
+        2 => height: Option<u64>,
     }
 
     pub struct TokenInfoReturns {
         0 => info: ledger::TokenInfo,
         1 => extended_info: extended_info::TokenExtendedInfo,
+        2 => mint_policy: Option<MintPolicy>,
+        3 => minted_in_period: Option<ledger::TokenAmount>,
+        4 => paused: Option<bool>,
+        5 => frozen: Option<BTreeSet<Address>>,
     }
 
     pub struct TokenUpdateArgs {
@@ -51,11 +58,37 @@ cbor_type_decl!(
         1 => extended_info: Vec<AttributeRelatedIndex>, // TODO: This thing should be of at least length 1
         2 => memo: Option<Memo>,
     }
+
+    pub struct TokenFreezeArgs {
+        0 => symbol: ledger::Symbol,
+        1 => address: Address,
+        2 => memo: Option<Memo>,
+    }
+
+    pub struct TokenUnfreezeArgs {
+        0 => symbol: ledger::Symbol,
+        1 => address: Address,
+        2 => memo: Option<Memo>,
+    }
+
+    pub struct TokenPauseArgs {
+        0 => symbol: ledger::Symbol,
+        1 => memo: Option<Memo>,
+    }
+
+    pub struct TokenUnpauseArgs {
+        0 => symbol: ledger::Symbol,
+        1 => memo: Option<Memo>,
+    }
 );
 
 pub type TokenUpdateReturns = EmptyReturn;
 pub type TokenAddExtendedInfoReturns = EmptyReturn;
 pub type TokenRemoveExtendedInfoReturns = EmptyReturn;
+pub type TokenFreezeReturns = EmptyReturn;
+pub type TokenUnfreezeReturns = EmptyReturn;
+pub type TokenPauseReturns = EmptyReturn;
+pub type TokenUnpauseReturns = EmptyReturn;
 
 #[many_module(name = LedgerTokensModule, id = 11, namespace = tokens, many_modules_crate = crate)]
 #[cfg_attr(test, mockall::automock)]
@@ -89,6 +122,34 @@ pub trait LedgerTokensModuleBackend: Send {
         sender: &Address,
         args: TokenRemoveExtendedInfoArgs,
     ) -> Result<TokenRemoveExtendedInfoReturns, ManyError>;
+
+    #[many(deny_anonymous)]
+    fn freeze(
+        &mut self,
+        sender: &Address,
+        args: TokenFreezeArgs,
+    ) -> Result<TokenFreezeReturns, ManyError>;
+
+    #[many(deny_anonymous)]
+    fn unfreeze(
+        &mut self,
+        sender: &Address,
+        args: TokenUnfreezeArgs,
+    ) -> Result<TokenUnfreezeReturns, ManyError>;
+
+    #[many(deny_anonymous)]
+    fn pause(
+        &mut self,
+        sender: &Address,
+        args: TokenPauseArgs,
+    ) -> Result<TokenPauseReturns, ManyError>;
+
+    #[many(deny_anonymous)]
+    fn unpause(
+        &mut self,
+        sender: &Address,
+        args: TokenUnpauseArgs,
+    ) -> Result<TokenUnpauseReturns, ManyError>;
 }
 
 #[cfg(test)]
@@ -226,4 +287,48 @@ mod tests {
 
         assert_eq!(rm_ext_info_returns, TokenRemoveExtendedInfoReturns {});
     }
+
+    #[test]
+    fn freeze() {
+        let mut mock = MockLedgerTokensModuleBackend::new();
+        let data = TokenFreezeArgs {
+            symbol: Default::default(),
+            address: identity(2),
+            memo: None,
+        };
+        mock.expect_freeze()
+            .with(eq(identity(1)), eq(data.clone()))
+            .times(1)
+            .returning(|_, _| Ok(TokenFreezeReturns {}));
+        let module = super::LedgerTokensModule::new(Arc::new(Mutex::new(mock)));
+
+        let freeze_returns: TokenFreezeReturns = minicbor::decode(
+            &call_module_cbor(1, &module, "tokens.freeze", minicbor::to_vec(data).unwrap())
+                .unwrap(),
+        )
+        .unwrap();
+
+        assert_eq!(freeze_returns, TokenFreezeReturns {});
+    }
+
+    #[test]
+    fn pause() {
+        let mut mock = MockLedgerTokensModuleBackend::new();
+        let data = TokenPauseArgs {
+            symbol: Default::default(),
+            memo: None,
+        };
+        mock.expect_pause()
+            .with(eq(identity(1)), eq(data.clone()))
+            .times(1)
+            .returning(|_, _| Ok(TokenPauseReturns {}));
+        let module = super::LedgerTokensModule::new(Arc::new(Mutex::new(mock)));
+
+        let pause_returns: TokenPauseReturns = minicbor::decode(
+            &call_module_cbor(1, &module, "tokens.pause", minicbor::to_vec(data).unwrap()).unwrap(),
+        )
+        .unwrap();
+
+        assert_eq!(pause_returns, TokenPauseReturns {});
+    }
 }
@@ -1,4 +1,5 @@
 use many_error::ManyError;
+use many_types::schema::{CborSchema, Schema};
 use many_types::{AttributeRelatedIndex, Memo};
 use minicbor::encode::{Error, Write};
 use minicbor::{decode, Decode, Decoder, Encode, Encoder};
@@ -209,6 +210,15 @@ impl Default for TokenExtendedInfo {
     }
 }
 
+impl CborSchema for TokenExtendedInfo {
+    fn schema() -> Schema {
+        Schema::named(
+            "TokenExtendedInfo",
+            Schema::map(AttributeRelatedIndex::schema(), Schema::Any),
+        )
+    }
+}
+
 impl<C> Encode<C> for TokenExtendedInfo {
     fn encode<W: Write>(&self, e: &mut Encoder<W>, ctx: &mut C) -> Result<(), Error<W::Error>> {
         e.map(self.inner.len() as u64)?;
@@ -2,8 +2,46 @@ use crate::EmptyReturn;
 use many_error::ManyError;
 use many_identity::Address;
 use many_macros::many_module;
+use many_types::schema::CborSchema;
 use many_types::{cbor_type_decl, ledger, Memo};
 use minicbor::{Decode, Encode};
+use std::collections::BTreeMap;
+
+/// Limits on the amounts that can be minted for a token.
+#[derive(Clone, Debug, Default, Decode, Encode, CborSchema, Eq, PartialEq)]
+#[cbor(map)]
+pub struct MintPolicy {
+    /// Remaining amount each minter can mint. Once a token has a policy, an
+    /// identity without an allowance cannot mint it. Allowances do not grant the
+    /// right to mint; they only limit identities which can already mint the
+    /// token. Every mint decrements the allowance of its minter. The token owner
+    /// refills allowances by setting a new policy.
+    #[n(0)]
+    pub allowances: BTreeMap<Address, ledger::TokenAmount>,
+
+    /// Maximum amount that can be minted, by anyone, within a period of block time.
+    #[n(1)]
+    pub rate_limit: Option<MintRateLimit>,
+}
+
+#[derive(Clone, Debug, Decode, Encode, CborSchema, Eq, PartialEq)]
+#[cbor(map)]
+pub struct MintRateLimit {
+    #[n(0)]
+    pub amount: ledger::TokenAmount,
+
+    /// Length of a period, in seconds. Periods are aligned on multiples of this
+    /// value since the UNIX epoch.
+    #[n(1)]
+    pub period_in_secs: u64,
+}
+
+impl MintRateLimit {
+    /// Returns the index of the period containing the `now` timestamp (in seconds).
+    pub fn period(&self, now: u64) -> u64 {
+        now / self.period_in_secs.max(1)
+    }
+}
 
 cbor_type_decl!(
     pub struct TokenMintArgs {
@@ -22,9 +60,15 @@ cbor_type_decl!(
     pub struct TokenBurnReturns {
         0 => distribution: ledger::LedgerTokensAddressMap,
     }
+
+    pub struct TokenSetMintPolicyArgs {
+        0 => symbol: ledger::Symbol,
+        1 => policy: Option<MintPolicy>,
+    }
 );
 
 pub type TokenMintReturns = EmptyReturn;
+pub type TokenSetMintPolicyReturns = EmptyReturn;
 
 #[many_module(name = LedgerMintBurnModule, id = 12, namespace = tokens, many_modules_crate = crate)]
 #[cfg_attr(test, mockall::automock)]
@@ -39,6 +83,14 @@ pub trait LedgerMintBurnModuleBackend: Send {
         sender: &Address,
         args: TokenBurnArgs,
     ) -> Result<TokenBurnReturns, ManyError>;
+
+    /// Set or remove (if `policy` is `None`) the mint policy of a token.
+    #[many(deny_anonymous)]
+    fn set_mint_policy(
+        &mut self,
+        sender: &Address,
+        args: TokenSetMintPolicyArgs,
+    ) -> Result<TokenSetMintPolicyReturns, ManyError>;
 }
 
 #[cfg(test)]
@@ -100,4 +152,37 @@ mod tests {
             }
         );
     }
+
+    #[test]
+    fn set_mint_policy() {
+        let mut mock = MockLedgerMintBurnModuleBackend::new();
+        let data = TokenSetMintPolicyArgs {
+            symbol: Default::default(),
+            policy: Some(MintPolicy {
+                allowances: BTreeMap::from([(identity(2), ledger::TokenAmount::from(100u64))]),
+                rate_limit: Some(MintRateLimit {
+                    amount: ledger::TokenAmount::from(1000u64),
+                    period_in_secs: 86400,
+                }),
+            }),
+        };
+        mock.expect_set_mint_policy()
+            .with(eq(identity(1)), eq(data.clone()))
+            .times(1)
+            .returning(|_, _| Ok(TokenSetMintPolicyReturns {}));
+        let module = super::LedgerMintBurnModule::new(Arc::new(Mutex::new(mock)));
+
+        let returns: TokenSetMintPolicyReturns = minicbor::decode(
+            &call_module_cbor(
+                1,
+                &module,
+                "tokens.setMintPolicy",
+                minicbor::to_vec(data).unwrap(),
+            )
+            .unwrap(),
+        )
+        .unwrap();
+
+        assert_eq!(returns, TokenSetMintPolicyReturns {});
+    }
 }
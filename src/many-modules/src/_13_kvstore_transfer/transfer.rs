@@ -1,11 +1,12 @@
 use crate::events::AddressContainer;
 use crate::EmptyReturn;
 use many_identity::Address;
+use many_types::schema::CborSchema;
 use minicbor::bytes::ByteVec;
 use minicbor::{Decode, Encode};
 use std::collections::BTreeSet;
 
-#[derive(Clone, Debug, Encode, Decode, Eq, PartialEq)]
+#[derive(Clone, Debug, Encode, Decode, CborSchema, Eq, PartialEq)]
 #[cbor(map)]
 pub struct TransferArgs {
     #[n(0)]
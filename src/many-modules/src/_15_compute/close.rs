@@ -1,9 +1,10 @@
 use crate::EmptyReturn;
+use many_types::schema::CborSchema;
 use minicbor::{Decode, Encode};
 
 pub type CloseReturns = EmptyReturn;
 
-#[derive(Clone, Decode, Encode)]
+#[derive(Clone, Decode, Encode, CborSchema)]
 #[cbor(map)]
 pub struct CloseArgs {
     #[n(0)]
@@ -1,7 +1,8 @@
 use many_types::compute::{ByteUnits, DeploymentMeta, Region};
+use many_types::schema::CborSchema;
 use minicbor::{Decode, Encode};
 
-#[derive(Clone, Debug, Decode, Encode)]
+#[derive(Clone, Debug, Decode, Encode, CborSchema)]
 #[cbor(map)]
 pub struct DeployArgs {
     #[n(0)]
@@ -22,6 +23,6 @@ pub struct DeployArgs {
     pub region: Region,
 }
 
-#[derive(Clone, Decode, Encode)]
+#[derive(Clone, Decode, Encode, CborSchema)]
 #[cbor(transparent)]
 pub struct DeployReturns(#[n(0)] pub DeploymentMeta);
@@ -1,8 +1,9 @@
+use many_types::schema::CborSchema;
 use many_types::web::{WebDeploymentFilter, WebDeploymentInfo};
 use many_types::SortOrder;
 use minicbor::{Decode, Encode};
 
-#[derive(Clone, Debug, Encode, Decode, PartialEq, Eq)]
+#[derive(Clone, Debug, Encode, Decode, CborSchema, PartialEq, Eq)]
 #[cbor(map)]
 pub struct ListArgs {
     #[n(0)]
@@ -18,7 +19,7 @@ pub struct ListArgs {
     pub page: Option<usize>,
 }
 
-#[derive(Clone, Debug, Encode, Decode, PartialEq, Eq)]
+#[derive(Clone, Debug, Encode, Decode, CborSchema, PartialEq, Eq)]
 #[cbor(map)]
 pub struct ListReturns {
     #[n(0)]
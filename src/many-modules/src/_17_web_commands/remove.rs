@@ -1,9 +1,10 @@
 use crate::EmptyReturn;
 use many_identity::Address;
+use many_types::schema::CborSchema;
 use many_types::Memo;
 use minicbor::{Decode, Encode};
 
-#[derive(Clone, Debug, Encode, Decode, PartialEq, Eq)]
+#[derive(Clone, Debug, Encode, Decode, CborSchema, PartialEq, Eq)]
 #[cbor(map)]
 pub struct RemoveArgs {
     #[n(0)]
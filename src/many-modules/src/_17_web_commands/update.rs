@@ -1,9 +1,10 @@
 use many_identity::Address;
+use many_types::schema::CborSchema;
 use many_types::web::{WebDeploymentInfo, WebDeploymentSource};
 use many_types::Memo;
 use minicbor::{Decode, Encode};
 
-#[derive(Clone, Debug, Decode, Encode, PartialEq, Eq)]
+#[derive(Clone, Debug, Decode, Encode, CborSchema, PartialEq, Eq)]
 #[cbor(map)]
 pub struct UpdateArgs {
     #[n(0)]
@@ -25,7 +26,7 @@ pub struct UpdateArgs {
     pub domain: Option<String>,
 }
 
-#[derive(Clone, Debug, Decode, Encode, PartialEq, Eq)]
+#[derive(Clone, Debug, Decode, Encode, CborSchema, PartialEq, Eq)]
 #[cbor(map)]
 pub struct UpdateReturns {
     #[n(0)]
@@ -96,6 +96,7 @@ mod tests {
         let data = BalanceArgs {
             account: None,
             symbols: Some(VecOrSingle::from(vec![*SYMBOL])),
+            height: None,
         };
         let mut mock = MockLedgerModuleBackend::new();
         mock.expect_balance()
@@ -1,9 +1,10 @@
 use many_identity::Address;
+use many_types::schema::CborSchema;
 use many_types::{ledger, VecOrSingle};
 use minicbor::{Decode, Encode};
 use std::collections::BTreeMap;
 
-#[derive(Clone, Debug, Encode, Decode, Eq, PartialEq)]
+#[derive(Clone, Debug, Encode, Decode, CborSchema, Eq, PartialEq)]
 #[cbor(map)]
 pub struct BalanceArgs {
     #[n(0)]
@@ -11,9 +12,13 @@ pub struct BalanceArgs {
 
     #[n(1)]
     pub symbols: Option<VecOrSingle<ledger::Symbol>>,
+
+    /// Query the balances at a past block height instead of the latest state.
+    #[n(2)]
+    pub height: Option<u64>,
 }
 
-#[derive(Clone, Encode, Decode)]
+#[derive(Clone, Encode, Decode, CborSchema)]
 #[cbor(map)]
 pub struct BalanceReturns {
     #[n(0)]
@@ -1,12 +1,13 @@
 use crate::EmptyArg;
 use many_types::ledger;
+use many_types::schema::CborSchema;
 use minicbor::bytes::ByteVec;
 use minicbor::{Decode, Encode};
 use std::collections::BTreeMap;
 
 pub type InfoArgs = EmptyArg;
 
-#[derive(Clone, Debug, Decode, Encode)]
+#[derive(Clone, Debug, Decode, Encode, CborSchema)]
 #[cbor(map)]
 pub struct InfoReturns {
     // TODO: remove this. Kept for backward compatibility.
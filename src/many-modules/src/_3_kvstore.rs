@@ -3,6 +3,7 @@ use crate::kvstore::list::ListReturns;
 use many_error::ManyError;
 use many_identity::Address;
 use many_macros::many_module;
+use many_types::schema::{CborSchema, Schema};
 use minicbor::{decode, encode};
 
 #[cfg(test)]
@@ -62,6 +63,19 @@ impl std::str::FromStr for KeyFilterType {
     }
 }
 
+impl CborSchema for KeyFilterType {
+    fn schema() -> Schema {
+        Schema::named(
+            "KeyFilterType",
+            Schema::Choice(vec![
+                Schema::Tuple(vec![Schema::Literal(0), Address::schema()]),
+                Schema::Tuple(vec![Schema::Literal(1), Address::schema()]),
+                Schema::Tuple(vec![Schema::Literal(2), Schema::Bool]),
+            ]),
+        )
+    }
+}
+
 impl<C> minicbor::Encode<C> for KeyFilterType {
     fn encode<W: encode::Write>(
         &self,
@@ -1,14 +1,15 @@
+use many_types::schema::CborSchema;
 use minicbor::bytes::ByteVec;
 use minicbor::{Decode, Encode};
 
-#[derive(Clone, Debug, Encode, Decode, Eq, PartialEq)]
+#[derive(Clone, Debug, Encode, Decode, CborSchema, Eq, PartialEq)]
 #[cbor(map)]
 pub struct GetArgs {
     #[n(0)]
     pub key: ByteVec,
 }
 
-#[derive(Clone, Debug, Encode, Decode)]
+#[derive(Clone, Debug, Encode, Decode, CborSchema)]
 #[cbor(map)]
 pub struct GetReturns {
     #[n(0)]
@@ -1,10 +1,11 @@
 use crate::EmptyArg;
+use many_types::schema::CborSchema;
 use minicbor::bytes::ByteVec;
 use minicbor::{Decode, Encode};
 
 pub type InfoArg = EmptyArg;
 
-#[derive(Clone, Decode, Encode)]
+#[derive(Clone, Decode, Encode, CborSchema)]
 #[cbor(map)]
 pub struct InfoReturns {
     #[n(0)]
@@ -1,9 +1,10 @@
 use crate::kvstore::KeyFilterType;
+use many_types::schema::CborSchema;
 use many_types::SortOrder;
 use minicbor::bytes::ByteVec;
 use minicbor::{Decode, Encode};
 
-#[derive(Clone, Decode, Encode)]
+#[derive(Clone, Decode, Encode, CborSchema)]
 #[cbor(map)]
 pub struct ListArgs {
     #[n(0)]
@@ -16,7 +17,7 @@ pub struct ListArgs {
     pub filter: Option<Vec<KeyFilterType>>,
 }
 
-#[derive(Clone, Decode, Encode)]
+#[derive(Clone, Decode, Encode, CborSchema)]
 #[cbor(map)]
 pub struct ListReturns {
     #[n(0)]
@@ -1,17 +1,18 @@
 use many_error::Reason;
 use many_identity::Address;
+use many_types::schema::CborSchema;
 use many_types::Either;
 use minicbor::bytes::ByteVec;
 use minicbor::{Decode, Encode};
 
-#[derive(Clone, Debug, Encode, Decode, Eq, PartialEq)]
+#[derive(Clone, Debug, Encode, Decode, CborSchema, Eq, PartialEq)]
 #[cbor(map)]
 pub struct QueryArgs {
     #[n(0)]
     pub key: ByteVec,
 }
 
-#[derive(Clone, Debug, Encode, Decode)]
+#[derive(Clone, Debug, Encode, Decode, CborSchema)]
 #[cbor(map)]
 pub struct QueryReturns {
     #[n(0)]
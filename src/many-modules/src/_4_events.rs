@@ -8,6 +8,7 @@ use many_protocol::ResponseMessage;
 use many_types::ledger;
 use many_types::ledger::{Symbol, TokenAmount};
 use many_types::legacy::{DataLegacy, MemoLegacy};
+use many_types::schema::{CborSchema, Field, Schema};
 use many_types::{AttributeRelatedIndex, CborRange, Either, Memo, Timestamp, VecOrSingle};
 use minicbor::bytes::ByteVec;
 use minicbor::{encode, Decode, Decoder, Encode, Encoder};
@@ -120,6 +121,12 @@ impl<C> Encode<C> for EventId {
     }
 }
 
+impl CborSchema for EventId {
+    fn schema() -> Schema {
+        Schema::named("EventId", Schema::Bytes)
+    }
+}
+
 impl<'b, C> Decode<'b, C> for EventId {
     fn decode(d: &mut Decoder<'b>, _: &mut C) -> Result<Self, minicbor::decode::Error> {
         Ok(EventId(ByteVec::from(d.bytes()?.to_vec())))
@@ -173,6 +180,22 @@ impl<C> Encode<C> for EventFilter {
     }
 }
 
+impl CborSchema for EventFilter {
+    fn schema() -> Schema {
+        // Attribute specific filters have array keys, which are not described.
+        Schema::named(
+            "EventFilter",
+            Schema::Struct(vec![
+                Field::new(0, "account", Option::<VecOrSingle<Address>>::schema()).optional(),
+                Field::new(1, "kind", Option::<VecOrSingle<EventKind>>::schema()).optional(),
+                Field::new(2, "symbol", Option::<VecOrSingle<Address>>::schema()).optional(),
+                Field::new(3, "id_range", Option::<CborRange<EventId>>::schema()).optional(),
+                Field::new(4, "date_range", Option::<CborRange<Timestamp>>::schema()).optional(),
+            ]),
+        )
+    }
+}
+
 impl<'b, C> Decode<'b, C> for EventFilter {
     fn decode(d: &mut Decoder<'b>, _: &mut C) -> Result<Self, minicbor::decode::Error> {
         use minicbor::decode::Error;
@@ -385,6 +408,12 @@ macro_rules! define_event_kind {
             }
         }
 
+        impl CborSchema for EventKind {
+            fn schema() -> Schema {
+                Schema::named("EventKind", AttributeRelatedIndex::schema())
+            }
+        }
+
         impl<'b, C> Decode<'b, C> for EventKind {
             fn decode(d: &mut Decoder<'b>, _: &mut C) -> Result<Self, minicbor::decode::Error> {
                 TryFrom::try_from(d.decode::<AttributeRelatedIndex>()?)
@@ -525,6 +554,23 @@ macro_rules! encode_event_info_field {
     };
 }
 
+macro_rules! event_info_field_schema {
+    (@inner $idx: literal $name: ident $type: ty []) => {
+        Field::new($idx, stringify!($name), <$type as CborSchema>::schema())
+    };
+    // Memos are omitted when empty.
+    (@inner $idx: literal $name: ident $type: ty [ memo $( $tail: ident )* ]) => {
+        Field::new($idx, stringify!($name), <$type as CborSchema>::schema()).optional()
+    };
+    (@inner $idx: literal $name: ident $type: ty [ $head: ident $( $tail: ident )* ]) => {
+        event_info_field_schema!(@inner $idx $name $type [ $( $tail )* ])
+    };
+
+    ($idx: literal $name: ident $type: ty $([ $( $tag: ident )* ])?) => {
+        event_info_field_schema!(@inner $idx $name $type [ $( $( $tag )* )? ])
+    };
+}
+
 macro_rules! encode_event_info_unpack_decode {
     (@inner $name: ident $idx: literal []) => {
         $name.ok_or(minicbor::decode::Error::missing_value($idx))
@@ -564,6 +610,23 @@ macro_rules! encode_event_info {
             }
         }
 
+        impl CborSchema for EventInfo {
+            fn schema() -> Schema {
+                Schema::named(
+                    "EventInfo",
+                    Schema::Choice(vec![ $(
+                        Schema::named(
+                            concat!(stringify!($sname), "Event"),
+                            Schema::Struct(vec![
+                                Field::new(0, "kind", EventKind::schema()),
+                                $( event_info_field_schema!( $idx $name $type $([ $( $tag )* ])? ), )*
+                            ]),
+                        ),
+                    )* ]),
+                )
+            }
+        }
+
         impl<'b, C> Decode<'b, C> for EventInfo {
             fn decode(d: &mut Decoder<'b>, _: &mut C) -> Result<Self, minicbor::decode::Error> {
                 let mut len = d.map()?.ok_or(minicbor::decode::Error::message(
@@ -663,6 +726,20 @@ macro_rules! define_multisig_event {
             }
         }
 
+        impl CborSchema for AccountMultisigTransaction {
+            fn schema() -> Schema {
+                // Transactions can submit multisig transactions.
+                Schema::recursive("AccountMultisigTransaction", || {
+                    Schema::Choice(vec![
+                        $( $( Schema::Struct(vec![
+                            Field::new(0, "kind", EventKind::schema()),
+                            Field::new(1, "argument", <$arg as CborSchema>::schema()),
+                        ]), )? )*
+                    ])
+                })
+            }
+        }
+
         impl<'b, C> Decode<'b, C> for AccountMultisigTransaction {
             fn decode(d: &mut Decoder<'b>, _: &mut C) -> Result<Self, minicbor::decode::Error> {
                 let len = d.map()?.ok_or(minicbor::decode::Error::message(
@@ -821,6 +898,24 @@ define_event! {
         2     | extended_info:          Vec<AttributeRelatedIndex>,
         3     | memo:                   Option<Memo>                           [ memo ],
     },
+    [11, 4]     TokenFreeze (module::ledger::TokenFreezeArgs) {
+        1     | symbol:                 Address                                [ id ],
+        2     | address:                Address                                [ id ],
+        3     | memo:                   Option<Memo>                           [ memo ],
+    },
+    [11, 5]     TokenUnfreeze (module::ledger::TokenUnfreezeArgs) {
+        1     | symbol:                 Address                                [ id ],
+        2     | address:                Address                                [ id ],
+        3     | memo:                   Option<Memo>                           [ memo ],
+    },
+    [11, 6]     TokenPause (module::ledger::TokenPauseArgs) {
+        1     | symbol:                 Address                                [ id ],
+        2     | memo:                   Option<Memo>                           [ memo ],
+    },
+    [11, 7]     TokenUnpause (module::ledger::TokenUnpauseArgs) {
+        1     | symbol:                 Address                                [ id ],
+        2     | memo:                   Option<Memo>                           [ memo ],
+    },
     [12, 0]     TokenMint (module::ledger::TokenMintArgs) {
         1     | symbol:                 Address                                [ id ],
         2     | distribution:           ledger::LedgerTokensAddressMap         [ id ],
@@ -831,6 +926,10 @@ define_event! {
         2     | distribution:           ledger::LedgerTokensAddressMap         [ id ],
         3     | memo:                   Option<Memo>                           [ memo ],
     },
+    [12, 2]     TokenSetMintPolicy (module::ledger::TokenSetMintPolicyArgs) {
+        1     | symbol:                 Address                                [ id ],
+        2     | policy:                 Option<module::ledger::MintPolicy>,
+    },
     [13, 0]     KvStoreTransfer (module::kvstore::TransferArgs [ addresses ]) {
         1     | key:                    ByteVec,
         2     | owner:                  Address                                [ id ],
@@ -860,7 +959,7 @@ define_event! {
 }
 
 /// An Event that happened on the server and that is part of the log.
-#[derive(Debug, Encode, Decode)]
+#[derive(Debug, Encode, Decode, CborSchema)]
 #[cbor(map)]
 pub struct EventLog {
     #[n(0)]
@@ -1115,6 +1214,21 @@ mod test {
                 memo: None,
             },
             [i0, i1, i2],
+        );
+        check(
+            EventInfo::TokenFreeze {
+                symbol: i0,
+                address: i1,
+                memo: None,
+            },
+            [i0, i1],
+        );
+        check(
+            EventInfo::TokenPause {
+                symbol: i0,
+                memo: None,
+            },
+            [i0],
         )
     }
 
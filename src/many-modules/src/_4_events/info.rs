@@ -1,10 +1,11 @@
 use crate::events::EventKind;
 use crate::EmptyArg;
+use many_types::schema::CborSchema;
 use minicbor::{Decode, Encode};
 
 pub type InfoArgs = EmptyArg;
 
-#[derive(Decode, Encode)]
+#[derive(Decode, Encode, CborSchema)]
 #[cbor(map)]
 pub struct InfoReturn {
     #[n(0)]
@@ -1,11 +1,12 @@
 use std::collections::BTreeMap;
 
+use many_types::schema::CborSchema;
 use many_types::VecOrSingle;
 use minicbor::{Decode, Encode};
 
 use crate::data::{DataIndex, DataInfo};
 
-#[derive(Clone, Encode, Decode)]
+#[derive(Clone, Encode, Decode, CborSchema)]
 pub struct DataGetInfoArgs {
     #[n(0)]
     pub indices: VecOrSingle<DataIndex>,
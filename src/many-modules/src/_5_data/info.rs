@@ -1,9 +1,10 @@
 use crate::{data::DataIndex, EmptyArg};
+use many_types::schema::CborSchema;
 use minicbor::{Decode, Encode};
 
 pub type DataInfoArgs = EmptyArg;
 
-#[derive(Clone, Decode, Encode)]
+#[derive(Clone, Decode, Encode, CborSchema)]
 pub struct DataInfoReturns {
     #[n(0)]
     pub indices: Vec<DataIndex>,
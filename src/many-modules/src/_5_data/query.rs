@@ -1,11 +1,12 @@
 use std::collections::BTreeMap;
 
+use many_types::schema::CborSchema;
 use many_types::VecOrSingle;
 use minicbor::{Decode, Encode};
 
 use crate::data::{DataIndex, DataValue};
 
-#[derive(Clone, Encode, Decode)]
+#[derive(Clone, Encode, Decode, CborSchema)]
 pub struct DataQueryArgs {
     #[n(0)]
     pub indices: VecOrSingle<DataIndex>,
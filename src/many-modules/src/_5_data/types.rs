@@ -1,10 +1,11 @@
+use many_types::schema::{CborSchema, Schema};
 use many_types::AttributeRelatedIndex;
 use minicbor::{Decode, Encode};
 use num_bigint::BigInt;
 
 pub type DataIndex = AttributeRelatedIndex;
 
-#[derive(Clone, Debug, Decode, Encode, Eq, PartialEq)]
+#[derive(Clone, Debug, Decode, Encode, CborSchema, Eq, PartialEq)]
 pub enum DataType {
     #[n(0)]
     Counter,
@@ -12,7 +13,7 @@ pub enum DataType {
     Gauge,
 }
 
-#[derive(Clone, Decode, Encode, Debug)]
+#[derive(Clone, Decode, Encode, CborSchema, Debug)]
 pub enum DataValue {
     #[n(0)]
     Counter(#[n(0)] DataValueTypeCounter),
@@ -43,6 +44,23 @@ pub enum DataValueTypeGauge {
     BigInt(#[cbor(n(0), decode_with = "decode_bigint", encode_with = "encode_bigint")] BigInt),
 }
 
+impl CborSchema for DataValueTypeGauge {
+    fn schema() -> Schema {
+        let variant = |index, schema| {
+            Schema::Tuple(vec![Schema::Literal(index), Schema::Tuple(vec![schema])])
+        };
+        Schema::named(
+            "DataValueTypeGauge",
+            Schema::Choice(vec![
+                variant(0, Schema::Int),
+                variant(1, Schema::Float),
+                // Big integers are encoded as arrays of bytes.
+                variant(2, Schema::array(Schema::Uint)),
+            ]),
+        )
+    }
+}
+
 impl TryFrom<DataValueTypeGauge> for BigInt {
     type Error = String;
 
@@ -74,7 +92,7 @@ fn encode_bigint<C, W: minicbor::encode::Write>(
     Ok(())
 }
 
-#[derive(Clone, Debug, Encode, Decode, Eq, PartialEq)]
+#[derive(Clone, Debug, Encode, Decode, CborSchema, Eq, PartialEq)]
 pub struct DataInfo {
     #[n(0)]
     pub r#type: DataType,
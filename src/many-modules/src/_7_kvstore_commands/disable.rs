@@ -1,10 +1,11 @@
 use crate::EmptyReturn;
 use many_error::Reason;
 use many_identity::Address;
+use many_types::schema::CborSchema;
 use minicbor::bytes::ByteVec;
 use minicbor::{Decode, Encode};
 
-#[derive(Clone, Debug, Encode, Decode, Eq, PartialEq)]
+#[derive(Clone, Debug, Encode, Decode, CborSchema, Eq, PartialEq)]
 #[cbor(map)]
 pub struct DisableArgs {
     #[n(0)]
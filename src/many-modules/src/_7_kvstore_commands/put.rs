@@ -1,5 +1,6 @@
 use crate::EmptyReturn;
 use many_identity::Address;
+use many_types::schema::CborSchema;
 use minicbor::bytes::ByteVec;
 use minicbor::data::Type;
 use minicbor::{Decode, Encode};
@@ -9,7 +10,7 @@ use minicbor::{Decode, Encode};
 const KVSTORE_KEY_MAX_SIZE: usize = 254;
 const KVSTORE_VALUE_MAX_SIZE: usize = 524288; // 512KiB
 
-#[derive(Clone, Debug, Encode, Decode, Eq, PartialEq)]
+#[derive(Clone, Debug, Encode, Decode, CborSchema, Eq, PartialEq)]
 #[cbor(map)]
 pub struct PutArgs {
     #[n(0)]
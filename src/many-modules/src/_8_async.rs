@@ -1,8 +1,9 @@
 use crate::ResponseMessage;
 use coset::{CborSerializable, CoseSign1};
-use many_error::ManyError;
+use many_error::{define_attribute_many_error, ManyError};
 use many_identity::Address;
 use many_macros::many_module;
+use many_types::schema::{CborSchema, Field, Schema};
 use minicbor::data::Type;
 use minicbor::encode::{Error, Write};
 use minicbor::{Decode, Decoder, Encode, Encoder};
@@ -10,6 +11,14 @@ use minicbor::{Decode, Decoder, Encode, Encoder};
 #[cfg(test)]
 use mockall::{automock, predicate::*};
 
+define_attribute_many_error!(
+    attribute 8 => {
+        1: pub fn token_expired(token) => "Async token {token} expired before its result was read.",
+        2: pub fn wait_timed_out(token) => "Timed out waiting for the result of async token {token}.",
+        3: pub fn wait_cancelled(token) => "Cancelled waiting for the result of async token {token}.",
+    }
+);
+
 /// An AsyncToken which is returned when the server does not have an immediate
 /// response.
 #[derive(Clone, Eq, PartialEq)]
@@ -41,6 +50,12 @@ impl<'b, C> Decode<'b, C> for AsyncToken {
     }
 }
 
+impl CborSchema for AsyncToken {
+    fn schema() -> Schema {
+        Schema::named("AsyncToken", Schema::Bytes)
+    }
+}
+
 impl From<Vec<u8>> for AsyncToken {
     fn from(v: Vec<u8>) -> Self {
         Self(v)
@@ -111,7 +126,7 @@ pub mod attributes {
     }
 }
 
-#[derive(Debug, Clone, Encode, Decode, Eq, PartialEq)]
+#[derive(Debug, Clone, Encode, Decode, CborSchema, Eq, PartialEq)]
 #[cbor(map)]
 pub struct StatusArgs {
     #[n(0)]
@@ -152,6 +167,23 @@ impl StatusReturn {
     }
 }
 
+impl CborSchema for StatusReturn {
+    fn schema() -> Schema {
+        Schema::named(
+            "StatusReturn",
+            Schema::Struct(vec![
+                Field::new(
+                    0,
+                    "kind",
+                    Schema::Choice((0..=4).map(Schema::Literal).collect()),
+                ),
+                // A `CoseSign1` envelope of a `ResponseMessage`, when done.
+                Field::new(1, "response", Schema::Bytes).optional(),
+            ]),
+        )
+    }
+}
+
 impl<C> Encode<C> for StatusReturn {
     fn encode<W: Write>(&self, e: &mut Encoder<W>, _: &mut C) -> Result<(), Error<W::Error>> {
         if let StatusReturn::Done { response } = self {
@@ -4,6 +4,7 @@ use many_error::{ManyError, Reason};
 use many_identity::Address;
 use many_macros::many_module;
 use many_protocol::context::Context;
+use many_types::schema::{CborSchema, Schema};
 use many_types::{Either, VecOrSingle};
 use minicbor::{decode, encode, Decode, Decoder, Encode, Encoder};
 use std::collections::{BTreeMap, BTreeSet};
@@ -60,6 +61,12 @@ impl<C> Encode<C> for Role {
     }
 }
 
+impl CborSchema for Role {
+    fn schema() -> Schema {
+        Schema::named("Role", Schema::Text)
+    }
+}
+
 impl<'b, C> Decode<'b, C> for Role {
     fn decode(d: &mut Decoder<'b>, _: &mut C) -> Result<Self, decode::Error> {
         let role = d.str()?;
@@ -285,7 +292,7 @@ impl Account {
     }
 }
 
-#[derive(Clone, Debug, Encode, Decode, Eq, PartialEq)]
+#[derive(Clone, Debug, Encode, Decode, CborSchema, Eq, PartialEq)]
 #[cbor(map)]
 pub struct CreateArgs {
     #[n(0)]
@@ -304,14 +311,14 @@ impl AddressContainer for CreateArgs {
     }
 }
 
-#[derive(Clone, Debug, Encode, Decode, Eq, PartialEq)]
+#[derive(Clone, Debug, Encode, Decode, CborSchema, Eq, PartialEq)]
 #[cbor(map)]
 pub struct CreateReturn {
     #[n(0)]
     pub id: Address,
 }
 
-#[derive(Clone, Debug, Encode, Decode, Eq, PartialEq)]
+#[derive(Clone, Debug, Encode, Decode, CborSchema, Eq, PartialEq)]
 #[cbor(map)]
 pub struct SetDescriptionArgs {
     #[n(0)]
@@ -329,21 +336,21 @@ impl AddressContainer for SetDescriptionArgs {
 
 pub type SetDescriptionReturn = EmptyReturn;
 
-#[derive(Clone, Debug, Encode, Decode, Eq, PartialEq)]
+#[derive(Clone, Debug, Encode, Decode, CborSchema, Eq, PartialEq)]
 #[cbor(map)]
 pub struct ListRolesArgs {
     #[n(0)]
     pub account: Address,
 }
 
-#[derive(Clone, Encode, Decode)]
+#[derive(Clone, Encode, Decode, CborSchema)]
 #[cbor(map)]
 pub struct ListRolesReturn {
     #[n(0)]
     pub roles: BTreeSet<Role>,
 }
 
-#[derive(Clone, Debug, Encode, Decode, Eq, PartialEq)]
+#[derive(Clone, Debug, Encode, Decode, CborSchema, Eq, PartialEq)]
 #[cbor(map)]
 pub struct GetRolesArgs {
     #[n(0)]
@@ -353,14 +360,14 @@ pub struct GetRolesArgs {
     pub identities: VecOrSingle<Address>,
 }
 
-#[derive(Clone, Debug, Encode, Decode)]
+#[derive(Clone, Debug, Encode, Decode, CborSchema)]
 #[cbor(map)]
 pub struct GetRolesReturn {
     #[n(0)]
     pub roles: AddressRoleMap,
 }
 
-#[derive(Clone, Debug, Encode, Decode, Eq, PartialEq)]
+#[derive(Clone, Debug, Encode, Decode, CborSchema, Eq, PartialEq)]
 #[cbor(map)]
 pub struct AddRolesArgs {
     #[n(0)]
@@ -380,7 +387,7 @@ impl AddressContainer for AddRolesArgs {
 
 pub type AddRolesReturn = EmptyReturn;
 
-#[derive(Clone, Debug, Encode, Decode, Eq, PartialEq)]
+#[derive(Clone, Debug, Encode, Decode, CborSchema, Eq, PartialEq)]
 #[cbor(map)]
 pub struct RemoveRolesArgs {
     #[n(0)]
@@ -400,14 +407,18 @@ impl AddressContainer for RemoveRolesArgs {
 
 pub type RemoveRolesReturn = EmptyReturn;
 
-#[derive(Clone, Debug, Encode, Decode, Eq, PartialEq)]
+#[derive(Clone, Debug, Encode, Decode, CborSchema, Eq, PartialEq)]
 #[cbor(map)]
 pub struct InfoArgs {
     #[n(0)]
     pub account: Address,
+
+    /// Query the account at a past block height instead of the latest state.
+    #[n(1)]
+    pub height: Option<u64>,
 }
 
-#[derive(Clone, Debug, Encode, Decode)]
+#[derive(Clone, Debug, Encode, Decode, CborSchema)]
 #[cbor(map)]
 pub struct InfoReturn {
     #[n(0)]
@@ -423,7 +434,7 @@ pub struct InfoReturn {
     pub disabled: Option<Either<bool, Reason<u64>>>,
 }
 
-#[derive(Clone, Debug, Encode, Decode, Eq, PartialEq)]
+#[derive(Clone, Debug, Encode, Decode, CborSchema, Eq, PartialEq)]
 #[cbor(map)]
 pub struct DisableArgs {
     #[n(0)]
@@ -438,7 +449,7 @@ impl AddressContainer for DisableArgs {
 
 pub type DisableReturn = EmptyReturn;
 
-#[derive(Clone, Debug, Encode, Decode, Eq, PartialEq)]
+#[derive(Clone, Debug, Encode, Decode, CborSchema, Eq, PartialEq)]
 #[cbor(map)]
 pub struct AddFeaturesArgs {
     #[n(0)]
@@ -2,18 +2,20 @@ use crate::account::Role;
 use crate::Attribute;
 use many_error::ManyError;
 use many_types::cbor::CborAny;
+use many_types::schema::CborSchema;
 use minicbor::{Decode, Encode};
 use std::collections::BTreeSet;
 
 pub mod kvstore;
 pub mod ledger;
 pub mod multisig;
+pub mod spending_limits;
 pub mod tokens;
 
 pub type FeatureId = u32;
 
 /// An Account Feature.
-#[derive(Encode, Decode, Clone, Debug, Ord, PartialOrd, Eq, PartialEq)]
+#[derive(Encode, Decode, CborSchema, Clone, Debug, Ord, PartialOrd, Eq, PartialEq)]
 #[repr(transparent)]
 #[cbor(transparent)]
 pub struct Feature(#[n(0)] Attribute);
@@ -54,7 +56,7 @@ impl Feature {
 /// assert!(feature_set.get_feature(1).is_some());
 /// assert!(feature_set.get_feature(2).is_none());
 /// ```
-#[derive(Encode, Decode, Clone, Debug, Default, Ord, PartialOrd, Eq, PartialEq)]
+#[derive(Encode, Decode, CborSchema, Clone, Debug, Default, Ord, PartialOrd, Eq, PartialEq)]
 #[cbor(transparent)]
 pub struct FeatureSet(#[n(0)] BTreeSet<Feature>);
 
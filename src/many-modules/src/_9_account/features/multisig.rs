@@ -10,6 +10,7 @@ use many_protocol::ResponseMessage;
 use many_types::cbor::CborAny;
 use many_types::ledger::TokenAmount;
 use many_types::legacy::{DataLegacy, MemoLegacy};
+use many_types::schema::{CborSchema, Schema};
 use many_types::{legacy, Memo, Timestamp};
 use minicbor::bytes::ByteVec;
 use minicbor::{decode, encode, Decode, Decoder, Encode, Encoder};
@@ -28,7 +29,7 @@ pub mod errors {
     );
 }
 
-#[derive(Default, Clone, Debug, Encode, Decode)]
+#[derive(Default, Clone, Debug, Encode, Decode, CborSchema)]
 #[cbor(map)]
 pub struct MultisigAccountFeatureArg {
     #[n(0)]
@@ -122,7 +123,7 @@ impl super::FeatureInfo for MultisigAccountFeature {
     }
 }
 
-#[derive(Clone, Debug, Encode, Decode, Eq, PartialEq)]
+#[derive(Clone, Debug, Encode, Decode, CborSchema, Eq, PartialEq)]
 #[cbor(map)]
 pub struct SubmitTransactionArgs {
     #[n(0)]
@@ -185,21 +186,21 @@ impl AddressContainer for SubmitTransactionArgs {
     }
 }
 
-#[derive(Clone, Debug, Encode, Decode)]
+#[derive(Clone, Debug, Encode, Decode, CborSchema)]
 #[cbor(map)]
 pub struct SubmitTransactionReturn {
     #[n(0)]
     pub token: ByteVec,
 }
 
-#[derive(Clone, Debug, Encode, Decode, Eq, PartialEq)]
+#[derive(Clone, Debug, Encode, Decode, CborSchema, Eq, PartialEq)]
 #[cbor(map)]
 pub struct InfoArgs {
     #[n(0)]
     pub token: ByteVec,
 }
 
-#[derive(Clone, Debug, Default, Encode, Decode, Eq, PartialEq)]
+#[derive(Clone, Debug, Default, Encode, Decode, CborSchema, Eq, PartialEq)]
 #[cbor(map)]
 pub struct ApproverInfo {
     #[n(0)]
@@ -233,6 +234,15 @@ impl<C> Encode<C> for MultisigTransactionState {
     }
 }
 
+impl CborSchema for MultisigTransactionState {
+    fn schema() -> Schema {
+        Schema::named(
+            "MultisigTransactionState",
+            Schema::Choice((0..=4).map(Schema::Literal).collect()),
+        )
+    }
+}
+
 impl<'b, C> Decode<'b, C> for MultisigTransactionState {
     fn decode(d: &mut Decoder<'b>, _: &mut C) -> Result<Self, decode::Error> {
         match d.u32()? {
@@ -246,7 +256,7 @@ impl<'b, C> Decode<'b, C> for MultisigTransactionState {
     }
 }
 
-#[derive(Debug, Clone, Encode, Decode)]
+#[derive(Debug, Clone, Encode, Decode, CborSchema)]
 #[cbor(map)]
 pub struct InfoReturn {
     #[n(0)]
@@ -280,7 +290,7 @@ pub struct InfoReturn {
     pub memo: Option<Memo>,
 }
 
-#[derive(Clone, Debug, Encode, Decode, Eq, PartialEq)]
+#[derive(Clone, Debug, Encode, Decode, CborSchema, Eq, PartialEq)]
 #[cbor(map)]
 pub struct SetDefaultsArgs {
     #[n(0)]
@@ -304,7 +314,7 @@ impl AddressContainer for SetDefaultsArgs {
 
 pub type SetDefaultsReturn = EmptyReturn;
 
-#[derive(Clone, Debug, Encode, Decode, Eq, PartialEq)]
+#[derive(Clone, Debug, Encode, Decode, CborSchema, Eq, PartialEq)]
 #[cbor(map)]
 pub struct ApproveArgs {
     #[n(0)]
@@ -313,7 +323,7 @@ pub struct ApproveArgs {
 
 pub type ApproveReturn = EmptyReturn;
 
-#[derive(Clone, Debug, Encode, Decode, Eq, PartialEq)]
+#[derive(Clone, Debug, Encode, Decode, CborSchema, Eq, PartialEq)]
 #[cbor(map)]
 pub struct RevokeArgs {
     #[n(0)]
@@ -322,14 +332,14 @@ pub struct RevokeArgs {
 
 pub type RevokeReturn = EmptyReturn;
 
-#[derive(Clone, Debug, Encode, Decode, Eq, PartialEq)]
+#[derive(Clone, Debug, Encode, Decode, CborSchema, Eq, PartialEq)]
 #[cbor(map)]
 pub struct ExecuteArgs {
     #[n(0)]
     pub token: ByteVec,
 }
 
-#[derive(Clone, Debug, Encode, Decode, Eq, PartialEq)]
+#[derive(Clone, Debug, Encode, Decode, CborSchema, Eq, PartialEq)]
 #[cbor(map)]
 pub struct WithdrawArgs {
     #[n(0)]
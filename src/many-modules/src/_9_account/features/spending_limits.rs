@@ -0,0 +1,196 @@
+use crate::account::features::{Feature, FeatureId, TryCreateFeature};
+use crate::account::Role;
+use many_error::ManyError;
+use many_identity::Address;
+use many_types::cbor::CborAny;
+use many_types::ledger::{Symbol, TokenAmount};
+use minicbor::{Decode, Encode};
+use std::collections::{BTreeMap, BTreeSet};
+
+pub mod errors {
+    use many_error::define_attribute_many_error;
+    define_attribute_many_error!(
+        attribute 9 => {
+            200: pub fn spending_limit_exceeded(symbol, remaining)
+                => "Spending limit exceeded for symbol {symbol}. Remaining: {remaining}.",
+            201: pub fn recipient_not_allowed(to) => "Recipient {to} is not in the spending allowlist.",
+        }
+    );
+}
+
+/// A maximum amount that can be spent within a time window.
+#[derive(Clone, Debug, Encode, Decode, Eq, PartialEq)]
+#[cbor(map)]
+pub struct SpendingLimit {
+    #[n(0)]
+    pub amount: TokenAmount,
+
+    /// Length of the window, in seconds. A daily limit uses 86400.
+    #[n(1)]
+    pub window_in_secs: u64,
+
+    /// If true, windows are aligned on multiples of `window_in_secs` since the
+    /// UNIX epoch (e.g. UTC days). Otherwise the window is rolling and ends now.
+    #[n(2)]
+    pub fixed: Option<bool>,
+}
+
+impl SpendingLimit {
+    /// Returns the first second (inclusive) of the window containing `now`.
+    pub fn window_start(&self, now: u64) -> u64 {
+        if self.fixed.unwrap_or(false) {
+            now - now % self.window_in_secs.max(1)
+        } else {
+            (now + 1).saturating_sub(self.window_in_secs.max(1))
+        }
+    }
+
+    /// Returns the end (exclusive) of the window containing `now`. Windows
+    /// are exactly `window_in_secs` long.
+    pub fn window_end(&self, now: u64) -> u64 {
+        self.window_start(now) + self.window_in_secs.max(1)
+    }
+
+    /// Whether a spending at `timestamp` counts in the window containing `now`.
+    pub fn contains(&self, timestamp: u64, now: u64) -> bool {
+        self.window_start(now) <= timestamp && timestamp < self.window_end(now)
+    }
+}
+
+/// Limits per symbol and an optional recipient allowlist.
+#[derive(Clone, Debug, Default, Encode, Decode, Eq, PartialEq)]
+#[cbor(map)]
+pub struct SpendingPolicy {
+    #[n(0)]
+    pub limits: BTreeMap<Symbol, SpendingLimit>,
+
+    /// If set, funds can only be sent to these addresses.
+    #[n(1)]
+    pub allowlist: Option<BTreeSet<Address>>,
+}
+
+#[derive(Clone, Debug, Default, Encode, Decode, Eq, PartialEq)]
+#[cbor(map)]
+pub struct SpendingLimitsFeatureArg {
+    /// Policies for specific members of the account.
+    #[n(0)]
+    pub members: BTreeMap<Address, SpendingPolicy>,
+
+    /// Policies applying to every member having the role.
+    #[n(1)]
+    pub roles: BTreeMap<Role, SpendingPolicy>,
+}
+
+impl SpendingLimitsFeatureArg {
+    /// Returns all the policies applying to a member with the given roles.
+    pub fn policies_for<'a>(
+        &'a self,
+        member: &Address,
+        roles: &'a BTreeSet<Role>,
+    ) -> impl Iterator<Item = &'a SpendingPolicy> {
+        self.members
+            .get(member)
+            .into_iter()
+            .chain(roles.iter().filter_map(|role| self.roles.get(role)))
+    }
+}
+
+/// Constrains how much members of an account can send, and to whom.
+/// Owners of the account are not limited.
+#[derive(Clone, Debug, Default)]
+pub struct SpendingLimitsFeature {
+    pub arg: SpendingLimitsFeatureArg,
+}
+
+impl SpendingLimitsFeature {
+    pub fn from_arg(arg: SpendingLimitsFeatureArg) -> Self {
+        Self { arg }
+    }
+}
+
+impl TryCreateFeature for SpendingLimitsFeature {
+    const ID: FeatureId = 4;
+
+    fn try_create(f: &Feature) -> Result<Self, ManyError> {
+        match f.arguments().as_slice() {
+            [argument] => {
+                let bytes = minicbor::to_vec(argument).map_err(ManyError::serialization_error)?;
+                let arg = minicbor::decode(&bytes)
+                    .map_err(|_| ManyError::invalid_attribute_arguments())?;
+                Ok(Self { arg })
+            }
+            _ => Err(ManyError::invalid_attribute_arguments()),
+        }
+    }
+}
+
+impl super::FeatureInfo for SpendingLimitsFeature {
+    fn as_feature(&self) -> Feature {
+        let bytes = minicbor::to_vec(&self.arg).expect("Unable to encode spending limits.");
+        let argument: CborAny =
+            minicbor::decode(&bytes).expect("Unable to decode spending limits.");
+
+        Feature::with_id(Self::ID).with_argument(argument)
+    }
+
+    fn roles() -> BTreeSet<Role> {
+        BTreeSet::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::account::features::FeatureInfo;
+    use many_identity::testing::identity;
+
+    #[test]
+    fn feature_round_trip() {
+        let arg = SpendingLimitsFeatureArg {
+            members: BTreeMap::from([(
+                identity(1),
+                SpendingPolicy {
+                    limits: BTreeMap::from([(
+                        identity(100),
+                        SpendingLimit {
+                            amount: TokenAmount::from(1_000u64),
+                            window_in_secs: 86400,
+                            fixed: Some(true),
+                        },
+                    )]),
+                    allowlist: Some(BTreeSet::from([identity(2)])),
+                },
+            )]),
+            roles: BTreeMap::from([(Role::CanLedgerTransact, SpendingPolicy::default())]),
+        };
+
+        let feature = SpendingLimitsFeature::from_arg(arg.clone()).as_feature();
+        assert_eq!(
+            SpendingLimitsFeature::try_create(&feature).unwrap().arg,
+            arg
+        );
+    }
+
+    #[test]
+    fn window_start() {
+        let limit = SpendingLimit {
+            amount: TokenAmount::from(1u64),
+            window_in_secs: 100,
+            fixed: None,
+        };
+        assert_eq!(limit.window_start(1_050), 951);
+        assert_eq!(limit.window_start(50), 0);
+        assert!(limit.contains(951, 1_050));
+        assert!(limit.contains(1_050, 1_050));
+        assert!(!limit.contains(950, 1_050));
+
+        let limit = SpendingLimit {
+            fixed: Some(true),
+            ..limit
+        };
+        assert_eq!(limit.window_start(1_050), 1_000);
+        assert_eq!(limit.window_end(1_050), 1_100);
+        assert!(limit.contains(1_000, 1_050));
+        assert!(!limit.contains(999, 1_050));
+    }
+}
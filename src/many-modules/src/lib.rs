@@ -5,9 +5,13 @@ use async_trait::async_trait;
 use many_error::ManyError;
 use many_protocol::{RequestMessage, ResponseMessage};
 use many_types::attributes::Attribute;
+use many_types::schema::{CborSchema, Schema};
 use minicbor::encode::{Error, Write};
 use minicbor::{Decoder, Encoder};
+use std::collections::{BTreeMap, BTreeSet};
 use std::fmt::Debug;
+use std::ops::{Deref, DerefMut};
+use std::sync::{Mutex, MutexGuard, RwLock, RwLockReadGuard, RwLockWriteGuard};
 
 macro_rules! reexport_module {
     ( $( $rename: ident: $name: ident $(+ $more: ident)*; )* ) => {
@@ -62,6 +66,12 @@ impl<'b, C> minicbor::Decode<'b, C> for EmptyReturn {
     }
 }
 
+impl CborSchema for EmptyReturn {
+    fn schema() -> Schema {
+        Schema::Null
+    }
+}
+
 #[derive(Debug, Eq, PartialEq)]
 pub struct EmptyArg;
 
@@ -81,6 +91,12 @@ impl<'b, C> minicbor::Decode<'b, C> for EmptyArg {
     }
 }
 
+impl CborSchema for EmptyArg {
+    fn schema() -> Schema {
+        Schema::Null
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct ManyModuleInfo {
     /// Returns the name of this module, for logs and metering.
@@ -91,6 +107,13 @@ pub struct ManyModuleInfo {
 
     /// The endpoints that this module exports.
     pub endpoints: Vec<String>,
+
+    /// The endpoints that do not change the state of the server, i.e. whose
+    /// backend method takes `&self`.
+    pub queries: BTreeSet<String>,
+
+    /// The schemas of the arguments and return values of the endpoints.
+    pub descriptions: BTreeMap<String, base::EndpointDescription>,
 }
 
 /// A module ran by an many-server server.
@@ -114,6 +137,76 @@ pub trait ManyModule: Sync + Send + Debug {
     async fn execute(&self, message: RequestMessage) -> Result<ResponseMessage, ManyError>;
 }
 
+/// How a module shares its backend between requests. Query endpoints (whose
+/// backend method takes `&self`) go through `read()` and commands through
+/// `write()`, so a backend can serve queries without waiting for commands.
+pub trait BackendLock<T>: Send + Sync {
+    type Read<'a>: Deref<Target = T>
+    where
+        Self: 'a;
+    type Write<'a>: DerefMut<Target = T>
+    where
+        Self: 'a;
+
+    fn read(&self) -> Self::Read<'_>;
+    fn write(&self) -> Self::Write<'_>;
+}
+
+/// Queries and commands all wait for each other.
+impl<T: Send> BackendLock<T> for Mutex<T> {
+    type Read<'a> = MutexGuard<'a, T> where T: 'a;
+    type Write<'a> = MutexGuard<'a, T> where T: 'a;
+
+    fn read(&self) -> MutexGuard<'_, T> {
+        self.lock().unwrap()
+    }
+
+    fn write(&self) -> MutexGuard<'_, T> {
+        self.lock().unwrap()
+    }
+}
+
+/// Queries run concurrently with each other, but wait for commands.
+impl<T: Send + Sync> BackendLock<T> for RwLock<T> {
+    type Read<'a> = RwLockReadGuard<'a, T> where T: 'a;
+    type Write<'a> = RwLockWriteGuard<'a, T> where T: 'a;
+
+    fn read(&self) -> RwLockReadGuard<'_, T> {
+        RwLock::read(self).unwrap()
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, T> {
+        RwLock::write(self).unwrap()
+    }
+}
+
+/// The information of the modules defined in this crate.
+pub fn modules() -> Vec<&'static ManyModuleInfo> {
+    let infos: [&'static ManyModuleInfo; 20] = [
+        &base::BaseModuleInfo,
+        &blockchain::BlockchainModuleInfo,
+        &ledger::LedgerModuleInfo,
+        &ledger::LedgerCommandsModuleInfo,
+        &ledger::LedgerTokensModuleInfo,
+        &ledger::LedgerMintBurnModuleInfo,
+        &kvstore::KvStoreModuleInfo,
+        &kvstore::KvStoreCommandsModuleInfo,
+        &kvstore::KvStoreTransferModuleInfo,
+        &events::EventsModuleInfo,
+        &data::DataModuleInfo,
+        &r#async::AsyncModuleInfo,
+        &account::AccountModuleInfo,
+        &account::features::multisig::AccountMultisigModuleInfo,
+        &compute::ComputeModuleInfo,
+        &web::WebModuleInfo,
+        &web::WebCommandsModuleInfo,
+        &abci_backend::AbciModuleInfo,
+        &abci_frontend::AbciFrontendModuleInfo,
+        &idstore::IdStoreModuleInfo,
+    ];
+    infos.into()
+}
+
 #[cfg(test)]
 pub(crate) mod testutils {
     use crate::ManyModule;
@@ -166,3 +259,39 @@ pub(crate) mod testutils {
         response.data
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn described() {
+        for info in modules() {
+            assert_eq!(
+                info.descriptions.len(),
+                info.endpoints.len(),
+                "{}",
+                info.name
+            );
+            for (endpoint, description) in &info.descriptions {
+                let schemas = description.argument.iter().chain([&description.returns]);
+                for schema in schemas {
+                    assert_ne!(schema, &Schema::Any, "{endpoint}");
+                    assert!(schema.undescribed().is_empty(), "{endpoint}: {schema:?}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn queries() {
+        let queries: BTreeSet<&str> = modules()
+            .into_iter()
+            .flat_map(|info| info.queries.iter().map(String::as_str))
+            .collect();
+        assert!(queries.contains("ledger.balance"));
+        assert!(queries.contains("status"));
+        assert!(!queries.contains("ledger.send"));
+        assert!(!queries.contains("account.multisigSubmitTransaction"));
+    }
+}
@@ -0,0 +1,297 @@
+//! Delegation certificates let an identity (the delegator) authorize another
+//! identity (the delegate) to sign requests on its behalf, for a limited time
+//! and optionally for a limited set of methods.
+//!
+//! Certificates are COSE_Sign1 envelopes signed by the delegator. They travel
+//! in the [DELEGATION] attribute of a request, ordered from the delegator that
+//! the request acts as, down to the identity that signed the request.
+use coset::{CborSerializable, CoseSign1, CoseSign1Builder};
+use many_error::ManyError;
+use many_identity::{Address, Identity, Verifier};
+use many_types::attributes::{Attribute, AttributeSet, TryFromAttributeSet};
+use many_types::cbor::CborAny;
+use many_types::Timestamp;
+use minicbor::{Decode, Encode};
+use std::collections::BTreeSet;
+
+pub const DELEGATION: Attribute = Attribute::id(4);
+
+/// The maximum number of certificates in a delegation chain.
+pub const MAXIMUM_CHAIN_LENGTH: usize = 8;
+
+#[derive(Clone, Debug, Encode, Decode, Eq, PartialEq)]
+#[cbor(map)]
+pub struct DelegationCertificate {
+    #[n(0)]
+    pub delegator: Address,
+
+    #[n(1)]
+    pub delegate: Address,
+
+    #[n(2)]
+    pub expiration: Timestamp,
+
+    /// If set, the delegate can only call these methods.
+    #[n(3)]
+    pub methods: Option<BTreeSet<String>>,
+}
+
+impl DelegationCertificate {
+    pub fn new(delegator: Address, delegate: Address, expiration: Timestamp) -> Self {
+        Self {
+            delegator,
+            delegate,
+            expiration,
+            methods: None,
+        }
+    }
+
+    pub fn with_methods(mut self, methods: impl IntoIterator<Item = String>) -> Self {
+        self.methods = Some(methods.into_iter().collect());
+        self
+    }
+
+    /// Sign this certificate. The identity must be the delegator.
+    pub fn sign(&self, identity: &impl Identity) -> Result<CoseSign1, ManyError> {
+        if !identity.address().matches(&self.delegator) {
+            return Err(ManyError::invalid_delegation(
+                "the certificate must be signed by its delegator",
+            ));
+        }
+        let payload =
+            minicbor::to_vec(self).map_err(|e| ManyError::serialization_error(e.to_string()))?;
+        identity.sign_1(CoseSign1Builder::default().payload(payload).build())
+    }
+
+    /// Verify the signature of a certificate envelope and decode it. The envelope
+    /// must have been signed by the delegator.
+    pub fn decode_and_verify(
+        envelope: &CoseSign1,
+        verifier: &impl Verifier,
+    ) -> Result<Self, ManyError> {
+        let signer = verifier.verify_1(envelope)?;
+        let payload = envelope
+            .payload
+            .as_ref()
+            .ok_or_else(ManyError::empty_envelope)?;
+        let certificate: Self = minicbor::decode(payload)
+            .map_err(|e| ManyError::deserialization_error(e.to_string()))?;
+
+        if signer.is_anonymous() || !signer.matches(&certificate.delegator) {
+            return Err(ManyError::invalid_delegation(
+                "the certificate was not signed by its delegator",
+            ));
+        }
+        if certificate.delegate.is_anonymous() || certificate.delegate.is_illegal() {
+            return Err(ManyError::invalid_delegation(
+                "cannot delegate to the anonymous or illegal identity",
+            ));
+        }
+        Ok(certificate)
+    }
+
+    pub fn is_expired(&self, now: Timestamp) -> bool {
+        self.expiration <= now
+    }
+
+    pub fn allows(&self, method: &str) -> bool {
+        self.methods
+            .as_ref()
+            .map_or(true, |methods| methods.contains(method))
+    }
+}
+
+/// A chain of signed delegation certificates.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DelegationAttribute {
+    pub certificates: Vec<CoseSign1>,
+}
+
+impl DelegationAttribute {
+    pub fn new(certificates: Vec<CoseSign1>) -> Self {
+        Self { certificates }
+    }
+
+    /// Returns the delegator at the root of the chain, without verifying
+    /// signatures. This is the address a client should put in the `from`
+    /// field of its requests; servers must use [Self::verify] instead.
+    pub fn delegator(&self) -> Result<Address, ManyError> {
+        let envelope = self
+            .certificates
+            .first()
+            .ok_or_else(|| ManyError::invalid_delegation("the chain is empty"))?;
+        let payload = envelope
+            .payload
+            .as_ref()
+            .ok_or_else(ManyError::empty_envelope)?;
+        let certificate: DelegationCertificate = minicbor::decode(payload)
+            .map_err(|e| ManyError::deserialization_error(e.to_string()))?;
+        Ok(certificate.delegator)
+    }
+
+    /// Verify the signatures of the certificates, and that they form a chain
+    /// from the delegator at its root down to `signer`, and return the
+    /// delegator. The expiration and methods of the certificates are not
+    /// checked, as they depend on the clock of the server (see [Self::resolve]).
+    pub fn verify(&self, signer: &Address, verifier: &impl Verifier) -> Result<Address, ManyError> {
+        let certificates = self.verified_certificates(signer, verifier)?;
+        Ok(certificates[0].delegator)
+    }
+
+    /// Validate the whole chain and return the address the request acts as.
+    ///
+    /// Every certificate must be signed by its delegator, delegate to the
+    /// delegator of the next certificate, not be expired at `now` and allow
+    /// `method`. The delegate of the last certificate must be `signer`.
+    pub fn resolve(
+        &self,
+        signer: &Address,
+        method: &str,
+        now: Timestamp,
+        verifier: &impl Verifier,
+    ) -> Result<Address, ManyError> {
+        let certificates = self.verified_certificates(signer, verifier)?;
+        for certificate in &certificates {
+            if certificate.is_expired(now) {
+                return Err(ManyError::delegation_expired(certificate.delegator));
+            }
+            if !certificate.allows(method) {
+                return Err(ManyError::method_not_delegated(method));
+            }
+        }
+        Ok(certificates[0].delegator)
+    }
+
+    /// Decode the certificates, verifying their signatures and links. The
+    /// result is never empty.
+    fn verified_certificates(
+        &self,
+        signer: &Address,
+        verifier: &impl Verifier,
+    ) -> Result<Vec<DelegationCertificate>, ManyError> {
+        if self.certificates.is_empty() {
+            return Err(ManyError::invalid_delegation("the chain is empty"));
+        }
+        if self.certificates.len() > MAXIMUM_CHAIN_LENGTH {
+            return Err(ManyError::invalid_delegation(format!(
+                "the chain is longer than {MAXIMUM_CHAIN_LENGTH} certificates"
+            )));
+        }
+
+        let certificates = self
+            .certificates
+            .iter()
+            .map(|envelope| DelegationCertificate::decode_and_verify(envelope, verifier))
+            .collect::<Result<Vec<_>, _>>()?;
+        for pair in certificates.windows(2) {
+            if !pair[0].delegate.matches(&pair[1].delegator) {
+                return Err(ManyError::invalid_delegation(
+                    "the certificates do not form a chain",
+                ));
+            }
+        }
+
+        match certificates.last() {
+            Some(last) if last.delegate.matches(signer) => Ok(certificates),
+            _ => Err(ManyError::invalid_delegation(
+                "the request was not signed by the last delegate",
+            )),
+        }
+    }
+}
+
+impl From<DelegationAttribute> for Attribute {
+    fn from(a: DelegationAttribute) -> Attribute {
+        Attribute::new(
+            DELEGATION.id,
+            a.certificates
+                .into_iter()
+                .map(|envelope| CborAny::Bytes(envelope.to_vec().unwrap()))
+                .collect(),
+        )
+    }
+}
+
+impl TryFrom<Attribute> for DelegationAttribute {
+    type Error = ManyError;
+
+    fn try_from(value: Attribute) -> Result<Self, Self::Error> {
+        if value.id != DELEGATION.id {
+            return Err(ManyError::invalid_attribute_id(value.id));
+        }
+
+        let certificates = value
+            .into_arguments()
+            .into_iter()
+            .map(|argument| match argument {
+                CborAny::Bytes(bytes) => CoseSign1::from_slice(&bytes)
+                    .map_err(|e| ManyError::deserialization_error(e.to_string())),
+                _ => Err(ManyError::invalid_attribute_arguments()),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self { certificates })
+    }
+}
+
+impl TryFromAttributeSet for DelegationAttribute {
+    fn try_from_set(set: &AttributeSet) -> Result<Self, ManyError> {
+        match set.get_attribute(DELEGATION.id) {
+            Some(attr) => DelegationAttribute::try_from(attr.clone()),
+            None => Err(ManyError::attribute_not_found(DELEGATION.id.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use many_identity::testing::identity;
+    use many_identity::AnonymousIdentity;
+
+    #[test]
+    fn attribute_roundtrip() {
+        let certificate = DelegationCertificate::new(
+            Address::anonymous(),
+            identity(1),
+            Timestamp::new(1_000).unwrap(),
+        )
+        .with_methods(["status".to_string()]);
+        let envelope = certificate.sign(&AnonymousIdentity).unwrap();
+        let attribute = DelegationAttribute::new(vec![envelope]);
+
+        let decoded = DelegationAttribute::try_from(Attribute::from(attribute.clone())).unwrap();
+        assert_eq!(Attribute::from(decoded.clone()), Attribute::from(attribute));
+        assert_eq!(decoded.delegator().unwrap(), Address::anonymous());
+    }
+
+    #[test]
+    fn sign_requires_delegator() {
+        let certificate =
+            DelegationCertificate::new(identity(1), identity(2), Timestamp::new(1_000).unwrap());
+        assert_eq!(
+            certificate.sign(&AnonymousIdentity).unwrap_err().code(),
+            ManyError::invalid_delegation("").code()
+        );
+    }
+
+    #[test]
+    fn empty_chain() {
+        struct AnonymousVerifier;
+        impl Verifier for AnonymousVerifier {
+            fn verify_1(&self, _envelope: &CoseSign1) -> Result<Address, ManyError> {
+                Ok(Address::anonymous())
+            }
+        }
+
+        let attribute = DelegationAttribute::new(vec![]);
+        assert!(attribute.delegator().is_err());
+        assert!(attribute
+            .resolve(
+                &identity(1),
+                "status",
+                Timestamp::new(0).unwrap(),
+                &AnonymousVerifier
+            )
+            .is_err());
+    }
+}
@@ -2,11 +2,14 @@ use coset::CoseSign1;
 use coset::CoseSign1Builder;
 use many_error::ManyError;
 use many_identity::{Address, Identity, Verifier};
+use many_types::Timestamp;
 
 pub mod context;
+pub mod delegation;
 pub mod request;
 pub mod response;
 
+pub use delegation::{DelegationAttribute, DelegationCertificate};
 pub use request::{RequestMessage, RequestMessageBuilder};
 pub use response::{ResponseMessage, ResponseMessageBuilder};
 
@@ -22,8 +25,20 @@ pub fn decode_request_from_cose_sign1(
         return Err(ManyError::invalid_from_identity());
     }
 
-    // Check the `from` field.
     let message: RequestMessage = envelope.try_into()?;
+
+    // If the request carries delegation certificates, it acts as the delegator
+    // at the root of the chain, once the signatures and links of the chain are
+    // verified. The expiration and methods of the certificates are validated by
+    // servers against their own clock (see many-server's `DelegationValidator`).
+    let from_id = if message.attributes.has_id(delegation::DELEGATION.id) {
+        let chain: delegation::DelegationAttribute = message.attributes.get()?;
+        chain.verify(&from_id, verifier)?
+    } else {
+        from_id
+    };
+
+    // Check the `from` field.
     let message_from = message.from.unwrap_or_default();
     if !from_id.matches(&message_from) || message_from.is_illegal() {
         Err(ManyError::invalid_from_identity())
@@ -76,6 +91,42 @@ pub fn encode_cose_sign1_from_request(
     }
 }
 
+/// Sign a request with `identity`. Servers reject requests whose timestamp is
+/// too old, so unless `keep_timestamp` is set the request is timestamped now.
+///
+/// The request must be from the identity or, if it carries delegation
+/// certificates, from the delegator at the root of the chain. Anonymous
+/// requests are signed as coming from that address.
+pub fn sign_request(
+    mut request: RequestMessage,
+    identity: &impl Identity,
+    keep_timestamp: bool,
+) -> Result<CoseSign1, ManyError> {
+    let address = identity.address();
+    let sender = if request.attributes.has_id(delegation::DELEGATION.id) {
+        let chain: DelegationAttribute = request.attributes.get()?;
+        chain.delegator()?
+    } else {
+        address
+    };
+
+    match request.from {
+        Some(from) if !from.is_anonymous() => {
+            if !sender.matches(&from) {
+                return Err(ManyError::unknown(format!(
+                    "The request is from {from}, it cannot be signed by {address}."
+                )));
+            }
+        }
+        _ => request.from = Some(sender),
+    }
+    if !keep_timestamp || request.timestamp.is_none() {
+        request.timestamp = Some(Timestamp::now());
+    }
+
+    encode_cose_sign1_from_request(request, identity)
+}
+
 #[test]
 fn encode_illegal() {
     let message = RequestMessage {
@@ -117,3 +168,54 @@ fn decode_illegal() {
         encode_cose_sign1_from_request(message, &many_identity::AnonymousIdentity).unwrap();
     assert!(decode_request_from_cose_sign1(&envelope, &IllegalVerifier).is_err());
 }
+
+#[test]
+fn sign_request_delegated() {
+    use many_identity::testing::identity;
+
+    /// Signs as its address, without an actual signature.
+    struct Unsigned(Address);
+    impl Identity for Unsigned {
+        fn address(&self) -> Address {
+            self.0
+        }
+        fn public_key(&self) -> Option<coset::CoseKey> {
+            None
+        }
+        fn sign_1(&self, envelope: CoseSign1) -> Result<CoseSign1, ManyError> {
+            Ok(envelope)
+        }
+    }
+
+    let delegate = Unsigned(identity(2));
+    let request = RequestMessage {
+        from: Some(identity(1)),
+        ..Default::default()
+    };
+    assert!(sign_request(request.clone(), &delegate, false).is_err());
+
+    // The request acts as the delegator, identity(1).
+    let certificate =
+        DelegationCertificate::new(identity(1), identity(2), Timestamp::new(1_000).unwrap())
+            .sign(&Unsigned(identity(1)))
+            .unwrap();
+    let request = RequestMessage {
+        attributes: [DelegationAttribute::new(vec![certificate]).into()]
+            .into_iter()
+            .collect(),
+        ..request
+    };
+    let envelope = sign_request(request.clone(), &delegate, false).unwrap();
+    let signed = RequestMessage::try_from(&envelope).unwrap();
+    assert_eq!(signed.from, Some(identity(1)));
+    assert!(signed.timestamp.is_some());
+
+    // Anonymous delegated requests are from the delegator.
+    let request = RequestMessage {
+        from: None,
+        ..request
+    };
+    let envelope = sign_request(request, &delegate, false).unwrap();
+    let signed = RequestMessage::try_from(&envelope).unwrap();
+    assert_eq!(signed.from, Some(identity(1)));
+}
@@ -1,9 +1,10 @@
 use crate::RequestMessage;
 use coset::CoseSign1;
 use derive_builder::Builder;
-use many_error::ManyError;
+use many_error::{ManyError, Reason};
 use many_identity::{Address, Verifier};
 use many_types::attributes::{Attribute, AttributeSet};
+use many_types::schema::{CborSchema, Field, Schema};
 use many_types::Timestamp;
 use minicbor::data::{Tag, Type};
 use minicbor::encode::{Error, Write};
@@ -175,6 +176,40 @@ impl<C> Encode<C> for ResponseMessage {
     }
 }
 
+impl CborSchema for ResponseMessage {
+    fn schema() -> Schema {
+        let key = |key: ResponseMessageCborKey| key as u64;
+        Schema::named(
+            "ResponseMessage",
+            Schema::tag(
+                10002,
+                Schema::Struct(vec![
+                    Field::new(key(ResponseMessageCborKey::From), "from", Address::schema())
+                        .optional(),
+                    Field::new(key(ResponseMessageCborKey::To), "to", Address::schema()).optional(),
+                    Field::new(
+                        key(ResponseMessageCborKey::Result),
+                        "result",
+                        Schema::Choice(vec![Schema::Bytes, Reason::<i64>::schema()]),
+                    ),
+                    Field::new(
+                        key(ResponseMessageCborKey::Timestamp),
+                        "timestamp",
+                        Timestamp::schema(),
+                    ),
+                    Field::new(key(ResponseMessageCborKey::Id), "id", Schema::Uint).optional(),
+                    Field::new(
+                        key(ResponseMessageCborKey::Attributes),
+                        "attributes",
+                        AttributeSet::schema(),
+                    )
+                    .optional(),
+                ]),
+            ),
+        )
+    }
+}
+
 impl<'b, C> Decode<'b, C> for ResponseMessage {
     fn decode(d: &mut Decoder<'b>, _: &mut C) -> Result<Self, minicbor::decode::Error> {
         if d.tag()? != Tag::Unassigned(10002) {
@@ -1,39 +1,55 @@
 use coset::CoseSign1;
+use lru::LruCache;
 use many_error::ManyError;
-use many_protocol::ResponseMessage;
+use many_protocol::{RequestMessage, ResponseMessage};
+use many_server::server::{TimeFn, MANYSERVER_DEFAULT_TIMEOUT};
 use many_server::RequestValidator;
 use sha2::Digest;
+use std::num::NonZeroUsize;
 use std::path::Path;
 use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 /// Implement this trait to provide a cache backend for the cache validator.
 pub trait RequestCacheBackend: Send + Sync {
-    /// Returns true if the request was cached.
-    fn has(&self, request: &[u8]) -> bool;
+    /// Returns true if the request was cached and has not expired at `now`.
+    fn has(&self, request: &[u8], now: SystemTime) -> bool;
 
-    /// Add the request to the cache. This cannot fail.
-    fn put(&mut self, request: &[u8]);
+    /// Add the request to the cache. It can be forgotten after it expires, as its
+    /// timestamp is then out of the range accepted by the server. This cannot
+    /// fail.
+    fn put(&mut self, request: &[u8], expires: SystemTime);
+
+    /// Forget requests that expired before `now`. `now` comes from the clock
+    /// of the server, which never goes back.
+    fn prune(&mut self, _now: SystemTime) {}
 }
 
 impl RequestCacheBackend for () {
-    fn has(&self, _request: &[u8]) -> bool {
+    fn has(&self, _request: &[u8], _now: SystemTime) -> bool {
         false
     }
-    fn put(&mut self, _request: &[u8]) {}
+    fn put(&mut self, _request: &[u8], _expires: SystemTime) {}
 }
 
 impl<T: RequestCacheBackend + ?Sized> RequestCacheBackend for Arc<RwLock<T>> {
-    fn has(&self, request: &[u8]) -> bool {
-        self.read().unwrap().has(request)
+    fn has(&self, request: &[u8], now: SystemTime) -> bool {
+        self.read().unwrap().has(request, now)
+    }
+
+    fn put(&mut self, request: &[u8], expires: SystemTime) {
+        self.write().unwrap().put(request, expires)
     }
 
-    fn put(&mut self, request: &[u8]) {
-        self.write().unwrap().put(request)
+    fn prune(&mut self, now: SystemTime) {
+        self.write().unwrap().prune(now)
     }
 }
 
 pub struct RequestCacheValidator<T: RequestCacheBackend> {
     backend: T,
+    timeout: Duration,
+    time_fn: TimeFn,
 }
 
 unsafe impl<T: RequestCacheBackend + Send> Send for RequestCacheValidator<T> {}
@@ -41,7 +57,29 @@ unsafe impl<T: RequestCacheBackend + Sync> Sync for RequestCacheValidator<T> {}
 
 impl<T: RequestCacheBackend> RequestCacheValidator<T> {
     pub fn new(backend: T) -> Self {
-        Self { backend }
+        Self {
+            backend,
+            timeout: Duration::from_secs(MANYSERVER_DEFAULT_TIMEOUT),
+            time_fn: Arc::new(|| Ok(SystemTime::now())),
+        }
+    }
+
+    /// Set the timeout used by the server to validate timestamps, in seconds.
+    /// Requests are kept in the cache until their timestamp is out of range.
+    ///
+    /// Servers that do not validate timestamps, like the backends of many-abci,
+    /// must use `u64::MAX`: a request can then be replayed at any time, and
+    /// only the cache rejects it, so it is never forgotten.
+    pub fn with_timeout(mut self, timeout_in_secs: u64) -> Self {
+        self.timeout = Duration::from_secs(timeout_in_secs);
+        self
+    }
+
+    /// Set the clock of the server (see `ManyServer::time_fn`), used to expire
+    /// requests. It defaults to the system clock.
+    pub fn with_time_fn(mut self, time_fn: TimeFn) -> Self {
+        self.time_fn = time_fn;
+        self
     }
 }
 
@@ -55,7 +93,7 @@ impl<T: RequestCacheBackend> RequestValidator for RequestCacheValidator<T> {
         hasher.update(payload);
         let hash = hasher.finalize();
 
-        if self.backend.has(hash.as_ref()) {
+        if self.backend.has(hash.as_ref(), (self.time_fn)()?) {
             Err(ManyError::duplicated_message())
         } else {
             Ok(())
@@ -74,31 +112,139 @@ impl<T: RequestCacheBackend> RequestValidator for RequestCacheValidator<T> {
         let mut hasher = sha2::Sha512::default();
         hasher.update(payload);
         let hash = hasher.finalize();
-        self.backend.put(hash.as_ref());
+
+        // Messages without a valid timestamp were stamped by the server.
+        let now = (self.time_fn)()?;
+        let timestamp = RequestMessage::from_bytes(payload)
+            .ok()
+            .and_then(|message| message.timestamp)
+            .and_then(|timestamp| timestamp.as_system_time().ok())
+            .unwrap_or(now);
+        // Servers without timeout (e.g. ABCI backends) keep requests for a
+        // very long time instead.
+        let expires = timestamp
+            .checked_add(self.timeout)
+            .unwrap_or(timestamp + Duration::from_secs(u32::MAX as u64));
+        self.backend.put(hash.as_ref(), expires);
+        self.backend.prune(now);
         Ok(())
     }
 }
 
+/// An in-memory cache of a bounded number of requests. When it is full, the
+/// least recently added request is forgotten, so it should be large enough to
+/// hold all the requests of a timeout window.
+pub struct LruCacheBackend {
+    cache: LruCache<Vec<u8>, SystemTime>,
+}
+
+impl LruCacheBackend {
+    pub fn new(capacity: NonZeroUsize) -> Self {
+        Self {
+            cache: LruCache::new(capacity),
+        }
+    }
+}
+
+impl RequestCacheBackend for LruCacheBackend {
+    fn has(&self, request: &[u8], now: SystemTime) -> bool {
+        self.cache
+            .peek(request)
+            .map_or(false, |expires| *expires > now)
+    }
+
+    fn put(&mut self, request: &[u8], expires: SystemTime) {
+        self.cache.put(request.to_vec(), expires);
+    }
+}
+
+/// Column family of the requests, with their expiration time.
+const REQUESTS_CF: &str = "requests";
+
+/// Column family indexing the requests by expiration time, to prune them.
+const EXPIRATIONS_CF: &str = "expirations";
+
+/// How often expired requests are pruned.
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60);
+
+fn expiration_secs(expires: SystemTime) -> [u8; 8] {
+    expires
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+        .to_be_bytes()
+}
+
+/// A persistent cache, which prunes expired requests.
+///
+/// Requests cached by previous versions have no expiration time, and are in
+/// the default column family. Their age is unknown, so they are never pruned.
 pub struct RocksDbCacheBackend {
     db: rocksdb::DB,
+    last_prune: Instant,
 }
 
 impl RocksDbCacheBackend {
     pub fn new(path: impl AsRef<Path>) -> Self {
-        let db = rocksdb::DB::open_default(path).unwrap();
-        Self { db }
+        let mut opts = rocksdb::Options::default();
+        opts.create_if_missing(true);
+        opts.create_missing_column_families(true);
+        let db = rocksdb::DB::open_cf(&opts, path, [REQUESTS_CF, EXPIRATIONS_CF]).unwrap();
+        Self {
+            db,
+            last_prune: Instant::now(),
+        }
+    }
+
+    fn requests(&self) -> &rocksdb::ColumnFamily {
+        self.db.cf_handle(REQUESTS_CF).unwrap()
+    }
+
+    fn expirations(&self) -> &rocksdb::ColumnFamily {
+        self.db.cf_handle(EXPIRATIONS_CF).unwrap()
+    }
+
+    /// Remove the requests that expired before `now`.
+    fn prune_expired(&mut self, now: SystemTime) {
+        let now = expiration_secs(now);
+        let mut batch = rocksdb::WriteBatch::default();
+        for item in self
+            .db
+            .iterator_cf(self.expirations(), rocksdb::IteratorMode::Start)
+        {
+            let (key, _) = item.unwrap();
+            if key[..8] >= now[..] {
+                break;
+            }
+            batch.delete_cf(self.requests(), &key[8..]);
+        }
+        batch.delete_range_cf(self.expirations(), [0u8; 8], now);
+        self.db.write(batch).unwrap();
     }
 }
 
 impl RequestCacheBackend for RocksDbCacheBackend {
-    fn has(&self, key: &[u8]) -> bool {
-        self.db.get(key).unwrap().is_some()
+    fn has(&self, key: &[u8], now: SystemTime) -> bool {
+        match self.db.get_cf(self.requests(), key).unwrap() {
+            Some(expires) => expires.as_slice() >= &expiration_secs(now)[..],
+            None => self.db.get(key).unwrap().is_some(),
+        }
     }
-    fn put(&mut self, key: &[u8]) {
+    fn put(&mut self, key: &[u8], expires: SystemTime) {
+        let expires = expiration_secs(expires);
         let mut batch = rocksdb::WriteBatch::default();
-        batch.put(key, b"");
+        batch.put_cf(self.requests(), key, expires);
+        batch.put_cf(self.expirations(), [&expires[..], key].concat(), b"");
         self.db.write(batch).unwrap();
     }
+
+    /// Pruning only removes requests that `has` already reports as expired, so
+    /// how often it runs does not change which requests are duplicates.
+    fn prune(&mut self, now: SystemTime) {
+        if self.last_prune.elapsed() >= PRUNE_INTERVAL {
+            self.prune_expired(now);
+            self.last_prune = Instant::now();
+        }
+    }
 }
 
 #[derive(Clone)]
@@ -115,10 +261,107 @@ impl SharedRocksDbCacheBackend {
 }
 
 impl RequestCacheBackend for SharedRocksDbCacheBackend {
-    fn has(&self, key: &[u8]) -> bool {
-        self.inner.read().unwrap().has(key)
+    fn has(&self, key: &[u8], now: SystemTime) -> bool {
+        self.inner.read().unwrap().has(key, now)
+    }
+    fn put(&mut self, key: &[u8], expires: SystemTime) {
+        self.inner.write().unwrap().put(key, expires)
     }
-    fn put(&mut self, key: &[u8]) {
-        self.inner.write().unwrap().put(key)
+    fn prune(&mut self, now: SystemTime) {
+        self.inner.write().unwrap().prune(now)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn in_secs(secs: u64) -> SystemTime {
+        SystemTime::now() + Duration::from_secs(secs)
+    }
+
+    #[test]
+    fn lru() {
+        let now = SystemTime::now();
+        let mut cache = LruCacheBackend::new(NonZeroUsize::new(2).unwrap());
+        cache.put(b"1", in_secs(60));
+        cache.put(b"2", now - Duration::from_secs(1));
+        assert!(cache.has(b"1", now));
+        assert!(!cache.has(b"2", now));
+
+        cache.put(b"3", in_secs(60));
+        assert!(!cache.has(b"1", now));
+        assert!(cache.has(b"3", now));
+    }
+
+    #[test]
+    fn rocksdb_prune() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut cache = RocksDbCacheBackend::new(dir.path());
+        let now = SystemTime::now();
+        cache.put(b"1", in_secs(60));
+        cache.put(b"2", in_secs(120));
+        assert!(cache.has(b"1", now));
+        assert!(cache.has(b"2", now));
+        assert!(!cache.has(b"3", now));
+        assert!(!cache.has(b"1", in_secs(90)));
+
+        cache.prune_expired(in_secs(90));
+        assert!(!cache.has(b"1", now));
+        assert!(cache.has(b"2", now));
+        assert!(cache.db.get_cf(cache.requests(), b"1").unwrap().is_none());
+
+        // Entries are persisted.
+        drop(cache);
+        let cache = RocksDbCacheBackend::new(dir.path());
+        assert!(cache.has(b"2", now));
+    }
+
+    #[test]
+    fn rocksdb_legacy() {
+        let dir = tempfile::tempdir().unwrap();
+        {
+            let db = rocksdb::DB::open_default(dir.path()).unwrap();
+            db.put(b"1", b"").unwrap();
+        }
+
+        // Their age is unknown, so they are kept.
+        let mut cache = RocksDbCacheBackend::new(dir.path());
+        assert!(cache.has(b"1", SystemTime::now()));
+        cache.prune_expired(in_secs(u32::MAX as u64));
+        assert!(cache.has(b"1", in_secs(u32::MAX as u64)));
+    }
+
+    #[test]
+    fn server_clock() {
+        let now = SystemTime::now();
+        let time = Arc::new(RwLock::new(now));
+        let mut validator =
+            RequestCacheValidator::new(LruCacheBackend::new(NonZeroUsize::new(10).unwrap()))
+                .with_time_fn({
+                    let time = time.clone();
+                    Arc::new(move || Ok(*time.read().unwrap()))
+                });
+
+        let envelope = CoseSign1 {
+            payload: Some(
+                many_protocol::RequestMessageBuilder::default()
+                    .method("status".to_string())
+                    .timestamp(many_types::Timestamp::from_system_time(now).unwrap())
+                    .build()
+                    .unwrap()
+                    .to_bytes()
+                    .unwrap(),
+            ),
+            ..Default::default()
+        };
+        validator
+            .message_executed(&envelope, &ResponseMessage::default())
+            .unwrap();
+        assert!(validator.validate_envelope(&envelope).is_err());
+
+        // The request expires with the clock of the server, not the system's.
+        *time.write().unwrap() = now + Duration::from_secs(MANYSERVER_DEFAULT_TIMEOUT + 1);
+        assert!(validator.validate_envelope(&envelope).is_ok());
     }
 }
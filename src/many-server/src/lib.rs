@@ -1,4 +1,6 @@
 pub mod server;
+#[cfg(feature = "testing")]
+pub mod simulator;
 pub mod transport;
 pub mod validator;
 
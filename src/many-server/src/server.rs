@@ -1,4 +1,5 @@
 use crate::transport::LowLevelManyRequestHandler;
+use crate::validator::DelegationValidator;
 use crate::RequestValidator;
 use async_trait::async_trait;
 use coset::{CoseKey, CoseSign1};
@@ -34,9 +35,19 @@ pub struct ManyServer {
     timeout: u64,
     fallback: Option<Arc<dyn ManyServerFallback + Send + 'static>>,
 
-    time_fn: Option<Arc<dyn Fn() -> Result<SystemTime, ManyError> + Send + Sync>>,
+    /// Endpoints that can be executed concurrently with any other request.
+    query_endpoints: BTreeSet<String>,
+
+    /// Held while executing a request that is not a query, from its validation
+    /// to `message_executed()`.
+    command_lock: Arc<tokio::sync::Mutex<()>>,
+
+    time_fn: Option<TimeFn>,
 }
 
+/// A function returning the current time of a server.
+pub type TimeFn = Arc<dyn Fn() -> Result<SystemTime, ManyError> + Send + Sync>;
+
 impl ManyServer {
     /// Create a test server. This should never be used in prod.
     #[cfg(feature = "testing")]
@@ -61,6 +72,7 @@ impl ManyServer {
             let mut s2 = s.lock().unwrap();
             s2.version = version;
             s2.add_module(base::BaseModule::new(s.clone()));
+            s2.add_query_endpoints(["describe", "endpoints", "heartbeat", "status"]);
         }
 
         s
@@ -82,6 +94,8 @@ impl ManyServer {
             timeout: MANYSERVER_DEFAULT_TIMEOUT,
             fallback: None,
             method_cache: Default::default(),
+            query_endpoints: Default::default(),
+            command_lock: Default::default(),
             version: None,
             time_fn: None,
         }))
@@ -98,6 +112,14 @@ impl ManyServer {
         self.time_fn = Some(Arc::new(time_fn));
     }
 
+    /// Returns the function the server uses to get the current time, so
+    /// validators can share its clock. Set it with `set_time_fn` first.
+    pub fn time_fn(&self) -> TimeFn {
+        self.time_fn
+            .clone()
+            .unwrap_or_else(|| Arc::new(|| Ok(SystemTime::now())))
+    }
+
     pub fn set_fallback_module<M>(&mut self, module: M) -> &mut Self
     where
         M: LowLevelManyRequestHandler + base::BaseModuleBackend + 'static,
@@ -106,6 +128,30 @@ impl ManyServer {
         self
     }
 
+    /// Mark endpoints as queries, which don't change the state. The queries of
+    /// the modules are added with them. Queries can be executed concurrently. Other requests are executed one at a time, so
+    /// validators see them in the order they were executed.
+    pub fn add_query_endpoints<S: ToString>(
+        &mut self,
+        endpoints: impl IntoIterator<Item = S>,
+    ) -> &mut Self {
+        self.query_endpoints
+            .extend(endpoints.into_iter().map(|e| e.to_string()));
+        self
+    }
+
+    /// Whether the envelope contains a query. The envelope is not verified; this
+    /// is only used for scheduling.
+    fn is_query(&self, envelope: &CoseSign1) -> bool {
+        envelope
+            .payload
+            .as_ref()
+            .and_then(|payload| RequestMessage::from_bytes(payload).ok())
+            .map_or(false, |message| {
+                self.query_endpoints.contains(&message.method)
+            })
+    }
+
     pub fn add_validator(
         &mut self,
         validator: impl RequestValidator + Send + 'static,
@@ -123,6 +169,7 @@ impl ManyServer {
         let ManyModuleInfo {
             attribute,
             endpoints,
+            queries,
             ..
         } = info;
 
@@ -153,10 +200,20 @@ impl ManyServer {
         for e in endpoints {
             self.method_cache.insert(e.clone());
         }
+        self.query_endpoints.extend(queries.iter().cloned());
         self.modules.push(Arc::new(module));
         self
     }
 
+    /// Remove all modules and the fallback, releasing their backends. The base
+    /// module of simple servers refers to the server, which is otherwise never
+    /// dropped.
+    pub fn clear_modules(&mut self) {
+        self.modules.clear();
+        self.method_cache.clear();
+        self.fallback = None;
+    }
+
     pub fn validate_id(&self, message: &RequestMessage) -> Result<(), ManyError> {
         let to = &message.to;
 
@@ -199,6 +256,18 @@ impl base::BaseModuleBackend for ManyServer {
         Ok(base::Endpoints(endpoints))
     }
 
+    fn describe(&self) -> Result<base::Descriptions, ManyError> {
+        let mut descriptions = match &self.fallback {
+            Some(fb) => fb.describe()?.0,
+            None => BTreeMap::new(),
+        };
+        for module in &self.modules {
+            descriptions.extend(module.info().descriptions.clone());
+        }
+
+        Ok(base::Descriptions(descriptions))
+    }
+
     fn status(&self) -> Result<base::Status, ManyError> {
         let mut attributes: BTreeSet<Attribute> = self
             .modules
@@ -259,6 +328,15 @@ impl base::BaseModuleBackend for ManyServer {
 #[async_trait]
 impl LowLevelManyRequestHandler for Arc<Mutex<ManyServer>> {
     async fn execute(&self, envelope: CoseSign1) -> Result<CoseSign1, String> {
+        let command_lock = {
+            let this = self.lock().unwrap();
+            (!this.is_query(&envelope)).then(|| this.command_lock.clone())
+        };
+        let _command_guard = match command_lock {
+            Some(lock) => Some(lock.lock_owned().await),
+            None => None,
+        };
+
         let request = {
             let this = self.lock().unwrap();
             {
@@ -286,6 +364,8 @@ impl LowLevelManyRequestHandler for Arc<Mutex<ManyServer>> {
                     .as_ref()
                     .map_or_else(|| Ok(SystemTime::now()), |f| f())?;
 
+                DelegationValidator::new(&this.identity_verifier, now)
+                    .validate_envelope(&envelope)?;
                 this.validator.borrow().validate_request(&message)?;
                 message.validate_time(now, this.timeout)?;
 
@@ -356,15 +436,18 @@ impl LowLevelManyRequestHandler for Arc<Mutex<ManyServer>> {
 mod tests {
     use semver::{BuildMetadata, Prerelease, Version};
     use std::sync::atomic::{AtomicBool, Ordering};
-    use std::sync::RwLock;
+    use std::sync::{Barrier, MutexGuard, RwLock, RwLockReadGuard};
     use std::time::Duration;
 
     use super::*;
     use many_identity::{AcceptAllVerifier, Address, AnonymousIdentity};
     use many_identity_dsa::ed25519::generate_random_ed25519_identity;
+    use many_macros::many_module;
     use many_modules::base::Status;
+    use many_modules::BackendLock;
     use many_protocol::{
-        decode_response_from_cose_sign1, encode_cose_sign1_from_request, RequestMessageBuilder,
+        decode_response_from_cose_sign1, encode_cose_sign1_from_request, DelegationAttribute,
+        DelegationCertificate, RequestMessageBuilder,
     };
     use many_types::Timestamp;
     use proptest::prelude::*;
@@ -593,6 +676,88 @@ mod tests {
         assert!(response.data.is_err());
     }
 
+    #[test]
+    fn delegation() {
+        let delegator = generate_random_ed25519_identity();
+        let delegate = generate_random_ed25519_identity();
+        let server = ManyServer::simple(
+            "test-many-server",
+            AnonymousIdentity,
+            many_identity_dsa::CoseKeyVerifier,
+            None,
+        );
+        let now = Timestamp::now();
+
+        let send = |certificate: DelegationCertificate, method: &str| {
+            let envelope = certificate.sign(&delegator).unwrap();
+            let request: RequestMessage = RequestMessageBuilder::default()
+                .from(delegator.address())
+                .method(method.to_string())
+                .data("null".as_bytes().to_vec())
+                .timestamp(now)
+                .build()
+                .unwrap()
+                .with_attribute(DelegationAttribute::new(vec![envelope]).into());
+            let envelope = encode_cose_sign1_from_request(request, &delegate).unwrap();
+            let response_e = smol::block_on(server.execute(envelope)).unwrap();
+            decode_response_from_cose_sign1(&response_e, None, &AcceptAllVerifier).unwrap()
+        };
+        let expiration = Timestamp::new(now.secs() + 3600).unwrap();
+
+        // The request acts as the delegator.
+        let response = send(
+            DelegationCertificate::new(delegator.address(), delegate.address(), expiration),
+            "status",
+        );
+        assert!(response.data.is_ok());
+        assert_eq!(response.to, Some(delegator.address()));
+
+        // Expired certificates are refused.
+        let response = send(
+            DelegationCertificate::new(delegator.address(), delegate.address(), now),
+            "status",
+        );
+        assert_eq!(
+            response.data.unwrap_err().code(),
+            ManyError::delegation_expired("").code()
+        );
+
+        // Methods outside of the allowlist are refused.
+        let certificate =
+            DelegationCertificate::new(delegator.address(), delegate.address(), expiration)
+                .with_methods(["status".to_string()]);
+        assert!(send(certificate.clone(), "status").data.is_ok());
+        assert_eq!(
+            send(certificate, "endpoints").data.unwrap_err().code(),
+            ManyError::method_not_delegated("").code()
+        );
+
+        // The certificate must delegate to the signer of the request.
+        let other = generate_random_ed25519_identity();
+        let response = send(
+            DelegationCertificate::new(delegator.address(), other.address(), expiration),
+            "status",
+        );
+        assert_eq!(
+            response.data.unwrap_err().code(),
+            ManyError::invalid_delegation("").code()
+        );
+
+        // Expiration uses the clock of the server, not the request timestamp.
+        server
+            .lock()
+            .unwrap()
+            .set_time_fn(move || (now + 120).as_system_time());
+        let response = send(
+            DelegationCertificate::new(delegator.address(), delegate.address(), now + 60),
+            "status",
+        );
+        assert_eq!(
+            response.data.unwrap_err().code(),
+            ManyError::delegation_expired("").code()
+        );
+    }
+
     #[test]
     fn server_validates_request() {
         fn create_request(timestamp: SystemTime, nonce: u8) -> CoseSign1 {
@@ -637,4 +802,127 @@ mod tests {
             decode_response_from_cose_sign1(&response_e, None, &AcceptAllVerifier).unwrap();
         assert!(response.data.is_err());
     }
+
+    #[many_module(name = StoreModule, namespace = store)]
+    pub trait StoreModuleBackend: Send {
+        fn set(&mut self, value: u64) -> Result<u64, ManyError>;
+        fn get(&self) -> Result<u64, ManyError>;
+    }
+
+    /// A value whose command waits on a barrier twice, to be held while it runs.
+    #[derive(Clone)]
+    struct Store {
+        value: u64,
+        gate: Arc<Barrier>,
+    }
+
+    impl StoreModuleBackend for Store {
+        fn set(&mut self, value: u64) -> Result<u64, ManyError> {
+            self.gate.wait();
+            self.value = value;
+            self.gate.wait();
+            Ok(value)
+        }
+
+        fn get(&self) -> Result<u64, ManyError> {
+            Ok(self.value)
+        }
+    }
+
+    /// Serves the queries from a copy of the store, taken after every command.
+    struct SnapshotLock {
+        live: Mutex<Store>,
+        snapshot: RwLock<Store>,
+    }
+
+    struct SnapshotWrite<'a> {
+        live: MutexGuard<'a, Store>,
+        snapshot: &'a RwLock<Store>,
+    }
+
+    impl std::ops::Deref for SnapshotWrite<'_> {
+        type Target = Store;
+
+        fn deref(&self) -> &Store {
+            &self.live
+        }
+    }
+
+    impl std::ops::DerefMut for SnapshotWrite<'_> {
+        fn deref_mut(&mut self) -> &mut Store {
+            &mut self.live
+        }
+    }
+
+    impl Drop for SnapshotWrite<'_> {
+        fn drop(&mut self) {
+            *self.snapshot.write().unwrap() = self.live.clone();
+        }
+    }
+
+    impl BackendLock<Store> for SnapshotLock {
+        type Read<'a> = RwLockReadGuard<'a, Store>;
+        type Write<'a> = SnapshotWrite<'a>;
+
+        fn read(&self) -> RwLockReadGuard<'_, Store> {
+            self.snapshot.read().unwrap()
+        }
+
+        fn write(&self) -> SnapshotWrite<'_> {
+            SnapshotWrite {
+                live: self.live.lock().unwrap(),
+                snapshot: &self.snapshot,
+            }
+        }
+    }
+
+    #[test]
+    fn query_during_slow_command() {
+        fn create_request(method: &str, data: Vec<u8>) -> CoseSign1 {
+            let request: RequestMessage = RequestMessageBuilder::default()
+                .method(method.to_string())
+                .timestamp(Timestamp::now())
+                .data(data)
+                .build()
+                .unwrap();
+            encode_cose_sign1_from_request(request, &AnonymousIdentity).unwrap()
+        }
+        fn get(server: &Arc<Mutex<ManyServer>>) -> u64 {
+            let response_e =
+                smol::block_on(server.execute(create_request("store.get", vec![]))).unwrap();
+            let response =
+                decode_response_from_cose_sign1(&response_e, None, &AcceptAllVerifier).unwrap();
+            minicbor::decode(&response.data.unwrap()).unwrap()
+        }
+
+        let gate = Arc::new(Barrier::new(2));
+        let store = Store {
+            value: 0,
+            gate: gate.clone(),
+        };
+        let server = ManyServer::test(AnonymousIdentity);
+        server
+            .lock()
+            .unwrap()
+            .add_module(StoreModule::with_lock(Arc::new(SnapshotLock {
+                live: Mutex::new(store.clone()),
+                snapshot: RwLock::new(store),
+            })));
+
+        let command = std::thread::spawn({
+            let server = server.clone();
+            move || {
+                let request = create_request("store.set", minicbor::to_vec(1u64).unwrap());
+                smol::block_on(server.execute(request)).unwrap()
+            }
+        });
+
+        // The command is running and holds the store.
+        gate.wait();
+        assert_eq!(get(&server), 0);
+        gate.wait();
+
+        command.join().unwrap();
+        assert_eq!(get(&server), 1);
+    }
 }
@@ -0,0 +1,464 @@
+//! An in-process simulation of a blockchain network, to test that a backend is
+//! deterministic.
+//!
+//! The simulator runs the servers of several nodes of the same backend, and
+//! executes every block on each of them the way the ABCI frontend does: it
+//! calls `abci.beginBlock`, delivers the signed request envelopes of the
+//! block's transactions, then calls `abci.endBlock` and `abci.commit`. It
+//! panics as soon as the nodes disagree on the response to a transaction or on
+//! the app hash of a block.
+//!
+//! Nodes can be restarted, or stopped and started again later, in which case
+//! they replay the blocks they missed. Migrations are configured when creating
+//! the backends, and run at their block height on all nodes.
+use crate::transport::LowLevelManyRequestHandler;
+use crate::ManyServer;
+use coset::CoseSign1;
+use many_error::ManyError;
+use many_identity::{Address, AnonymousIdentity, Identity};
+use many_modules::abci_backend::{AbciBlock, AbciCommitInfo, AbciInfo, AbciInit};
+use many_modules::EmptyReturn;
+use many_protocol::{encode_cose_sign1_from_request, RequestMessage, ResponseMessage};
+use many_types::Timestamp;
+use minicbor::bytes::ByteVec;
+use std::sync::{Arc, Mutex};
+
+/// How the server of a node is created.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Start {
+    /// Create a new backend, with its initial state.
+    Genesis,
+
+    /// Load the backend from the storage of a previous run of the node.
+    Restart,
+}
+
+/// Create a request envelope calling `method` with `argument`, signed by
+/// `identity`.
+pub fn tx(
+    identity: &impl Identity,
+    method: &str,
+    argument: impl minicbor::Encode<()>,
+) -> CoseSign1 {
+    let message = RequestMessage {
+        from: Some(identity.address()),
+        method: method.to_string(),
+        data: minicbor::to_vec(argument).expect("Could not encode the argument"),
+        timestamp: Some(Timestamp::now()),
+        ..Default::default()
+    };
+    encode_cose_sign1_from_request(message, identity).expect("Could not sign the request")
+}
+
+struct Block {
+    time: u64,
+    transactions: Vec<CoseSign1>,
+}
+
+type Factory = Box<dyn FnMut(usize, Start) -> Result<Arc<Mutex<ManyServer>>, ManyError>>;
+
+pub struct Simulator {
+    factory: Factory,
+
+    /// The server of each node, or `None` if the node is stopped.
+    nodes: Vec<Option<Arc<Mutex<ManyServer>>>>,
+
+    /// The blocks executed, and the app hash after each of them.
+    blocks: Vec<(Block, ByteVec)>,
+
+    /// The height of the first block executed by the simulator.
+    start_height: u64,
+
+    time: u64,
+
+    runtime: tokio::runtime::Runtime,
+}
+
+impl Simulator {
+    /// Create `n` nodes with `factory`, which receives the index of the node
+    /// and whether it is a new node or a restart, and returns its server. The
+    /// server must serve the ABCI module of the backend, and backends of a node
+    /// must use the same storage across restarts.
+    pub fn new(
+        n: usize,
+        factory: impl FnMut(usize, Start) -> Result<Arc<Mutex<ManyServer>>, ManyError> + 'static,
+    ) -> Self {
+        assert!(n > 0, "The simulator needs at least one node.");
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .expect("Could not create a runtime");
+
+        let mut simulator = Self {
+            factory: Box::new(factory),
+            nodes: vec![],
+            blocks: vec![],
+            start_height: 0,
+            time: 1_000_000,
+            runtime,
+        };
+        for i in 0..n {
+            let server = simulator.create(i, Start::Genesis);
+            simulator
+                .call::<EmptyReturn>(&server, "abci.initChain", ())
+                .expect("Could not init chain");
+            simulator.nodes.push(Some(server));
+        }
+        let (height, _) = simulator.check_info();
+        simulator.start_height = height + 1;
+        simulator
+    }
+
+    /// Set the time of the next block, in seconds since the epoch. Each block
+    /// is one second after the previous one otherwise.
+    pub fn set_time(&mut self, time: u64) {
+        self.time = time;
+    }
+
+    /// The height of the last block committed.
+    pub fn height(&self) -> u64 {
+        self.start_height + self.blocks.len() as u64 - 1
+    }
+
+    /// The app hash of the last block committed.
+    pub fn hash(&self) -> Option<&ByteVec> {
+        self.blocks.last().map(|(_, hash)| hash)
+    }
+
+    /// Send a request envelope to a running node outside of a block, e.g. a
+    /// query, and return its result.
+    pub fn query(&self, i: usize, envelope: CoseSign1) -> Result<Vec<u8>, ManyError> {
+        let server = self.nodes[i].as_ref().expect("Node is stopped");
+        self.send(server, envelope).data
+    }
+
+    /// Execute a block with these transactions on all running nodes, and
+    /// return the results of the transactions.
+    pub fn block(&mut self, transactions: Vec<CoseSign1>) -> Vec<Result<Vec<u8>, ManyError>> {
+        let block = Block {
+            time: self.time,
+            transactions,
+        };
+        self.time += 1;
+        let height = self.height() + 1;
+
+        let mut expected: Option<(usize, Vec<ResponseMessage>, ByteVec)> = None;
+        for (i, node) in self.nodes.iter().enumerate() {
+            let Some(server) = node else {
+                continue;
+            };
+            let (responses, hash) = self.execute(server, &block);
+            match &expected {
+                None => expected = Some((i, responses, hash)),
+                Some((j, expected_responses, expected_hash)) => {
+                    assert_eq!(
+                        &responses, expected_responses,
+                        "Node {i} and node {j} have different results at height {height}."
+                    );
+                    assert_eq!(
+                        &hash, expected_hash,
+                        "Node {i} and node {j} have different app hashes at height {height}."
+                    );
+                }
+            }
+        }
+
+        let (_, responses, hash) = expected.expect("All nodes are stopped");
+        self.blocks.push((block, hash));
+        self.check_info();
+        responses.into_iter().map(|r| r.data).collect()
+    }
+
+    /// Execute `n` blocks without transactions.
+    pub fn empty_blocks(&mut self, n: u64) {
+        for _ in 0..n {
+            self.block(vec![]);
+        }
+    }
+
+    /// Restart a node, and check that it loads the same state.
+    pub fn restart(&mut self, i: usize) {
+        self.stop(i);
+        self.start(i);
+    }
+
+    /// Stop a node. Blocks are executed without it until it is started again.
+    pub fn stop(&mut self, i: usize) {
+        if let Some(server) = self.nodes[i].take() {
+            // Release the backend, so the node can load its storage again.
+            server.lock().unwrap().clear_modules();
+        }
+    }
+
+    /// Start a stopped node, replaying the blocks it missed.
+    pub fn start(&mut self, i: usize) {
+        assert!(self.nodes[i].is_none(), "Node {i} is already running.");
+        let server = self.create(i, Start::Restart);
+
+        let mut height = self.info(&server).height;
+        assert!(
+            height + 1 >= self.start_height && height <= self.height(),
+            "Node {i} restarted at height {height}, which the simulator did not execute."
+        );
+        while height < self.height() {
+            let (block, expected_hash) = &self.blocks[(height + 1 - self.start_height) as usize];
+            let (_, hash) = self.execute(&server, block);
+            height += 1;
+            assert_eq!(
+                &hash, expected_hash,
+                "Node {i} has a different app hash when replaying height {height}."
+            );
+        }
+
+        self.nodes[i] = Some(server);
+        self.check_info();
+    }
+
+    /// Create the server of a node, and initialize it like the ABCI frontend
+    /// does.
+    fn create(&mut self, i: usize, start: Start) -> Arc<Mutex<ManyServer>> {
+        let server = (self.factory)(i, start).expect("Could not create node");
+        // Transactions are executed long after they were signed, like in the
+        // ABCI mode of servers.
+        server.lock().unwrap().set_timeout(u64::MAX);
+        self.call::<AbciInit>(&server, "abci.init", ())
+            .expect("Could not init node");
+        server
+    }
+
+    /// Execute a block on a node, and return the responses to its transactions
+    /// and the app hash.
+    fn execute(
+        &self,
+        server: &Arc<Mutex<ManyServer>>,
+        block: &Block,
+    ) -> (Vec<ResponseMessage>, ByteVec) {
+        let begin = AbciBlock {
+            time: Some(block.time),
+        };
+        self.call::<EmptyReturn>(server, "abci.beginBlock", begin)
+            .expect("Could not begin block");
+        let responses = block
+            .transactions
+            .iter()
+            .map(|envelope| self.send(server, envelope.clone()))
+            .collect();
+        self.call::<EmptyReturn>(server, "abci.endBlock", ())
+            .expect("Could not end block");
+        let hash = self
+            .call::<AbciCommitInfo>(server, "abci.commit", ())
+            .expect("Could not commit block")
+            .hash;
+        (responses, hash)
+    }
+
+    /// Send a request envelope to a server, and return its response without
+    /// the fields that can differ between nodes, like the ABCI frontend does.
+    fn send(&self, server: &Arc<Mutex<ManyServer>>, envelope: CoseSign1) -> ResponseMessage {
+        let envelope = self
+            .runtime
+            .block_on(server.execute(envelope))
+            .expect("Could not execute the request");
+        let payload = envelope.payload.expect("Empty response envelope");
+        let mut response = ResponseMessage::from_bytes(&payload).expect("Invalid response");
+        response.from = Address::anonymous();
+        response.version = None;
+        response.timestamp = None;
+        response
+    }
+
+    /// Call an endpoint of a server anonymously, like the ABCI frontend does.
+    fn call<R: for<'b> minicbor::Decode<'b, ()>>(
+        &self,
+        server: &Arc<Mutex<ManyServer>>,
+        method: &str,
+        argument: impl minicbor::Encode<()>,
+    ) -> Result<R, ManyError> {
+        let data = self
+            .send(server, tx(&AnonymousIdentity, method, argument))
+            .data?;
+        minicbor::decode(&data).map_err(ManyError::deserialization_error)
+    }
+
+    fn info(&self, server: &Arc<Mutex<ManyServer>>) -> AbciInfo {
+        self.call(server, "abci.info", ())
+            .expect("Could not get info")
+    }
+
+    /// Check that all running nodes report the same height and app hash, and
+    /// return them.
+    fn check_info(&self) -> (u64, ByteVec) {
+        let mut infos = self
+            .nodes
+            .iter()
+            .enumerate()
+            .filter_map(|(i, node)| node.as_ref().map(|server| (i, self.info(server))));
+        let (first, expected) = infos.next().expect("All nodes are stopped");
+        for (i, info) in infos {
+            assert_eq!(
+                info, expected,
+                "Node {i} and node {first} report different heights or app hashes."
+            );
+        }
+        if let Some(hash) = self.hash() {
+            assert_eq!(expected.height, self.height(), "Unexpected height.");
+            assert_eq!(&expected.hash, hash, "Unexpected app hash.");
+        }
+        (expected.height, expected.hash)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use many_identity::verifiers::AnonymousVerifier;
+    use many_identity_dsa::ed25519::generate_random_ed25519_identity;
+    use many_identity_dsa::CoseKeyVerifier;
+    use many_macros::many_module;
+    use many_modules::abci_backend::{AbciModule, InitChainReturn, ManyAbciModuleBackend};
+    use std::collections::BTreeMap;
+
+    #[many_module(name = CounterModule, namespace = counter)]
+    pub trait CounterModuleBackend: Send {
+        fn add(&mut self, value: u64) -> Result<u64, ManyError>;
+        fn sum(&self) -> Result<u64, ManyError>;
+    }
+
+    /// A backend whose state is the sum of the values added to it, and which
+    /// persists it in a shared map when committing.
+    #[derive(Debug)]
+    struct Counter {
+        id: usize,
+        storage: Arc<Mutex<BTreeMap<usize, (u64, u64)>>>,
+        height: u64,
+        sum: u64,
+        broken: bool,
+    }
+
+    impl CounterModuleBackend for Counter {
+        fn add(&mut self, value: u64) -> Result<u64, ManyError> {
+            // A broken node adds more the second time it executes a block.
+            self.sum += if self.broken && self.height > 0 {
+                value + 1
+            } else {
+                value
+            };
+            Ok(self.sum)
+        }
+
+        fn sum(&self) -> Result<u64, ManyError> {
+            Ok(self.sum)
+        }
+    }
+
+    impl ManyAbciModuleBackend for Counter {
+        fn init(&mut self) -> Result<AbciInit, ManyError> {
+            Ok(AbciInit {
+                endpoints: BTreeMap::new(),
+            })
+        }
+
+        fn init_chain(&mut self) -> Result<InitChainReturn, ManyError> {
+            Ok(EmptyReturn)
+        }
+
+        fn info(&self) -> Result<AbciInfo, ManyError> {
+            Ok(AbciInfo {
+                height: self.height,
+                hash: self.sum.to_be_bytes().to_vec().into(),
+            })
+        }
+
+        fn commit(&mut self) -> Result<AbciCommitInfo, ManyError> {
+            self.height += 1;
+            self.storage
+                .lock()
+                .unwrap()
+                .insert(self.id, (self.height, self.sum));
+            Ok(AbciCommitInfo {
+                retain_height: 0,
+                hash: self.sum.to_be_bytes().to_vec().into(),
+            })
+        }
+    }
+
+    fn simulator(broken: Option<usize>) -> Simulator {
+        let storage = Arc::new(Mutex::new(BTreeMap::new()));
+        Simulator::new(3, move |id, start| {
+            let (height, sum) = match start {
+                Start::Genesis => (0, 0),
+                Start::Restart => storage.lock().unwrap()[&id],
+            };
+            let counter = Arc::new(Mutex::new(Counter {
+                id,
+                storage: storage.clone(),
+                height,
+                sum,
+                broken: broken == Some(id),
+            }));
+
+            let server = ManyServer::simple(
+                "counter",
+                AnonymousIdentity,
+                (AnonymousVerifier, CoseKeyVerifier),
+                None,
+            );
+            {
+                let mut s = server.lock().unwrap();
+                s.add_module(CounterModule::new(counter.clone()));
+                s.add_module(AbciModule::new(counter));
+            }
+            Ok(server)
+        })
+    }
+
+    fn add(value: u64) -> CoseSign1 {
+        tx(&AnonymousIdentity, "counter.add", value)
+    }
+
+    fn sum(simulator: &Simulator, i: usize) -> u64 {
+        let query = tx(&AnonymousIdentity, "counter.sum", ());
+        minicbor::decode(&simulator.query(i, query).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn deterministic() {
+        let mut simulator = simulator(None);
+        let identity = generate_random_ed25519_identity();
+        let results = simulator.block(vec![add(1), tx(&identity, "counter.add", 2u64)]);
+        assert_eq!(results, vec![Ok(vec![1]), Ok(vec![3])]);
+        assert_eq!(simulator.height(), 1);
+
+        simulator.restart(1);
+        simulator.stop(2);
+        simulator.block(vec![add(3)]);
+        simulator.empty_blocks(2);
+        simulator.start(2);
+        assert_eq!(simulator.height(), 4);
+        assert_eq!(sum(&simulator, 2), 6);
+    }
+
+    #[test]
+    fn invalid_transactions() {
+        let mut simulator = simulator(None);
+        let identity = generate_random_ed25519_identity();
+        let mut tampered = tx(&identity, "counter.add", 1u64);
+        tampered.payload = tx(&identity, "counter.add", 100u64).payload;
+
+        let results = simulator.block(vec![
+            tx(&AnonymousIdentity, "counter.unknown", ()),
+            tx(&AnonymousIdentity, "counter.add", "one"),
+            tampered,
+        ]);
+        assert!(results.iter().all(Result::is_err));
+        assert_eq!(sum(&simulator, 0), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "have different results at height 2")]
+    fn non_deterministic() {
+        let mut simulator = simulator(Some(1));
+        simulator.block(vec![add(1)]);
+        simulator.block(vec![add(1)]);
+    }
+}
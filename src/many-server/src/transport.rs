@@ -1,16 +1,51 @@
 use async_trait::async_trait;
-use coset::CoseSign1;
+use coset::{CoseSign1, TaggedCborSerializable};
 use many_error::ManyError;
 use many_protocol::{RequestMessage, ResponseMessage};
 use std::fmt::Debug;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
 
 pub mod http;
+pub mod tcp;
+pub mod tls;
+pub mod websocket;
+
+/// Maximum of 5MB per message on persistent connections, as for HTTP requests.
+pub const MAX_MESSAGE_LEN: usize = 1024 * 1024 * 5;
 
 #[async_trait]
 pub trait LowLevelManyRequestHandler: Send + Sync + Debug {
     async fn execute(&self, envelope: CoseSign1) -> Result<CoseSign1, String>;
 }
 
+/// Decode a tagged request envelope, execute it and encode the response.
+pub(crate) async fn execute_bytes<E: LowLevelManyRequestHandler + ?Sized>(
+    executor: &E,
+    bytes: &[u8],
+) -> Result<Vec<u8>, String> {
+    tracing::debug!("request  len={}", bytes.len());
+    tracing::trace!("request  {}", hex::encode(bytes));
+
+    let envelope = CoseSign1::from_tagged_slice(bytes).map_err(|e| e.to_string())?;
+    let bytes = executor
+        .execute(envelope)
+        .await?
+        .to_tagged_vec()
+        .map_err(|e| e.to_string())?;
+
+    tracing::debug!("response len={}", bytes.len());
+    tracing::trace!("response {}", hex::encode(&bytes));
+    Ok(bytes)
+}
+
+/// Resolves once the term signal is set.
+pub(crate) async fn term_signal_set(term_signal: &AtomicBool) {
+    while !term_signal.load(Ordering::Relaxed) {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+}
+
 /// A simpler version of the [ManyRequestHandler] which only deals with methods and payloads.
 #[async_trait]
 pub trait SimpleRequestHandler: Send + Sync + Debug {
@@ -1,78 +1,236 @@
-use crate::transport::LowLevelManyRequestHandler;
+use crate::transport::{term_signal_set, LowLevelManyRequestHandler};
 use anyhow::anyhow;
 use coset::{CoseSign1, TaggedCborSerializable};
-use std::fmt::Debug;
+use hyper::service::service_fn;
+use std::convert::Infallible;
+use std::fmt::{Debug, Formatter};
 use std::io::Cursor;
 use std::net::ToSocketAddrs;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
 use tiny_http::{Request, Response};
-use tracing::info;
+use tokio::net::TcpListener;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tokio_rustls::TlsAcceptor;
+use tracing::{info, warn};
 
 /// Maximum of 5MB per HTTP request.
 const READ_BUFFER_LEN: usize = 1024 * 1024 * 5;
 
-#[derive(Debug)]
+/// Default time to wait for in-flight requests to complete after the term signal.
+pub const DEFAULT_DRAIN_TIMEOUT: Duration = Duration::from_secs(30);
+
 pub struct HttpServer<E: LowLevelManyRequestHandler> {
-    executor: E,
+    executor: Arc<E>,
     term_signal: Arc<AtomicBool>,
+    workers: usize,
+    request_timeout: Option<Duration>,
+    drain_timeout: Duration,
+    tls: Option<Arc<rustls::ServerConfig>>,
+}
+
+impl<E: LowLevelManyRequestHandler> Debug for HttpServer<E> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HttpServer")
+            .field("executor", &self.executor)
+            .field("term_signal", &self.term_signal)
+            .field("workers", &self.workers)
+            .field("request_timeout", &self.request_timeout)
+            .field("drain_timeout", &self.drain_timeout)
+            .field("tls", &self.tls.is_some())
+            .finish()
+    }
+}
+
+fn empty_response(status: u16) -> Response<Cursor<Vec<u8>>> {
+    Response::empty(status).with_data(Cursor::new(vec![]), Some(0))
 }
 
-impl<E: LowLevelManyRequestHandler> HttpServer<E> {
+impl<E: LowLevelManyRequestHandler + 'static> HttpServer<E> {
     pub fn new(executor: E) -> Self {
         Self {
-            executor,
+            executor: Arc::new(executor),
             term_signal: Arc::new(AtomicBool::new(false)),
+            workers: 1,
+            request_timeout: None,
+            drain_timeout: DEFAULT_DRAIN_TIMEOUT,
+            tls: None,
         }
     }
 
-    async fn handle_request(&self, request: &mut Request) -> Response<std::io::Cursor<Vec<u8>>> {
-        match request.body_length() {
-            Some(x) if x > READ_BUFFER_LEN => {
-                // This is a transport error, and as such an HTTP error.
-                // Return a "413: Content Too Large" error.
-                tracing::error!("413: Content Too Large : {x} bytes");
-                return Response::empty(413u16).with_data(Cursor::new(vec![]), Some(0));
-            }
-            _ => {}
-        }
+    /// Set the maximum number of requests executed concurrently. Defaults to 1.
+    ///
+    /// The executor is responsible for ordering requests that depend on each
+    /// other; a [crate::ManyServer] only runs its query endpoints concurrently.
+    pub fn set_workers(&mut self, workers: usize) {
+        self.workers = workers.max(1);
+    }
 
-        let mut v = Vec::new();
-        let _ = request.as_reader().read_to_end(&mut v);
+    /// Respond with "504: Gateway Timeout" to requests that take longer than the
+    /// timeout. The request still runs to completion, so its result is not lost
+    /// for the executor.
+    pub fn set_request_timeout(&mut self, timeout: Duration) {
+        self.request_timeout = Some(timeout);
+    }
 
-        let bytes = &v;
+    /// Set the time to wait for in-flight requests to complete after the term
+    /// signal is set.
+    pub fn set_drain_timeout(&mut self, timeout: Duration) {
+        self.drain_timeout = timeout;
+    }
+
+    /// Serve HTTPS instead of HTTP. See [crate::transport::tls::server_config].
+    pub fn set_tls(&mut self, config: Arc<rustls::ServerConfig>) {
+        self.tls = Some(config);
+    }
 
+    /// Execute a request body. Returns the response body, or the HTTP status of
+    /// the error.
+    async fn handle_request(
+        executor: Arc<E>,
+        bytes: Vec<u8>,
+        request_timeout: Option<Duration>,
+        permit: Arc<OwnedSemaphorePermit>,
+    ) -> Result<Vec<u8>, u16> {
         tracing::debug!("request  len={}", bytes.len());
-        tracing::trace!("request  {}", hex::encode(bytes));
+        tracing::trace!("request  {}", hex::encode(&bytes));
 
-        let envelope = match CoseSign1::from_tagged_slice(bytes) {
+        let envelope = match CoseSign1::from_tagged_slice(&bytes) {
             Ok(cs) => cs,
             Err(e) => {
                 tracing::error!(
                     r#"Error decoding envelope. Error description="{}""#,
                     e.to_string()
                 );
-                return Response::empty(500u16).with_data(Cursor::new(vec![]), Some(0));
+                return Err(500);
             }
         };
 
-        let response = self
-            .executor
-            .execute(envelope)
-            .await
-            .and_then(|r| r.to_tagged_vec().map_err(|e| e.to_string()));
+        // Execute in its own task, so that a timed out request still completes. It
+        // holds a worker until then, so it is waited for when draining.
+        let execution = tokio::spawn(async move {
+            let response = executor.execute(envelope).await;
+            drop(permit);
+            response
+        });
+        let response = match request_timeout {
+            Some(timeout) => match tokio::time::timeout(timeout, execution).await {
+                Ok(response) => response,
+                Err(_) => {
+                    tracing::error!("504: Gateway Timeout");
+                    return Err(504);
+                }
+            },
+            None => execution.await,
+        };
+
         let bytes = match response {
+            Ok(response) => response.and_then(|r| r.to_tagged_vec().map_err(|e| e.to_string())),
+            Err(e) => Err(e.to_string()),
+        };
+        let bytes = match bytes {
             Ok(bytes) => bytes,
             Err(e) => {
                 tracing::error!(r#"Error getting response. Error description="{}""#, e);
-                return Response::empty(500u16).with_data(Cursor::new(vec![]), Some(0));
+                return Err(500);
             }
         };
         tracing::debug!("response len={}", bytes.len());
         tracing::trace!("response {}", hex::encode(&bytes));
 
-        Response::from_data(bytes)
+        Ok(bytes)
+    }
+
+    /// Read the body of a request, or return an error response.
+    fn read_body(request: &mut Request) -> Result<Vec<u8>, Response<Cursor<Vec<u8>>>> {
+        match request.body_length() {
+            Some(x) if x > READ_BUFFER_LEN => {
+                // This is a transport error, and as such an HTTP error.
+                // Return a "413: Content Too Large" error.
+                tracing::error!("413: Content Too Large : {x} bytes");
+                return Err(empty_response(413));
+            }
+            _ => {}
+        }
+
+        let mut v = Vec::new();
+        let _ = request.as_reader().read_to_end(&mut v);
+        Ok(v)
+    }
+
+    async fn serve_request(
+        executor: Arc<E>,
+        mut request: Request,
+        request_timeout: Option<Duration>,
+        permit: OwnedSemaphorePermit,
+    ) {
+        let permit = Arc::new(permit);
+        // Reading and writing to the connection are blocking.
+        let read = tokio::task::spawn_blocking(move || {
+            let body = Self::read_body(&mut request);
+            (request, body)
+        })
+        .await;
+        let Ok((request, body)) = read else {
+            return;
+        };
+
+        let response = match body {
+            Ok(bytes) => {
+                match Self::handle_request(executor, bytes, request_timeout, Arc::clone(&permit))
+                    .await
+                {
+                    Ok(bytes) => Response::from_data(bytes),
+                    Err(status) => empty_response(status),
+                }
+            }
+            Err(response) => response,
+        };
+
+        // If there's a transport error (e.g. connection closed) on the response itself,
+        // we don't actually care and just continue waiting for the next request.
+        let _ = tokio::task::spawn_blocking(move || request.respond(response)).await;
+        drop(permit);
+    }
+
+    /// Serve a request received over TLS.
+    async fn serve_tls_request(
+        executor: Arc<E>,
+        request: hyper::Request<hyper::Body>,
+        request_timeout: Option<Duration>,
+        semaphore: Arc<Semaphore>,
+    ) -> Result<hyper::Response<hyper::Body>, Infallible> {
+        let status_response = |status: u16| {
+            let mut response = hyper::Response::new(hyper::Body::empty());
+            *response.status_mut() =
+                hyper::StatusCode::from_u16(status).expect("Invalid status code");
+            Ok(response)
+        };
+
+        match hyper::body::HttpBody::size_hint(request.body()).upper() {
+            Some(x) if x as usize > READ_BUFFER_LEN => {
+                tracing::error!("413: Content Too Large : {x} bytes");
+                return status_response(413);
+            }
+            _ => {}
+        }
+        let Ok(permit) = semaphore.acquire_owned().await else {
+            return status_response(503);
+        };
+        let bytes = match hyper::body::to_bytes(request.into_body()).await {
+            Ok(bytes) if bytes.len() > READ_BUFFER_LEN => {
+                tracing::error!("413: Content Too Large : {} bytes", bytes.len());
+                return status_response(413);
+            }
+            Ok(bytes) => bytes.to_vec(),
+            Err(_) => return status_response(400),
+        };
+
+        match Self::handle_request(executor, bytes, request_timeout, Arc::new(permit)).await {
+            Ok(bytes) => Ok(hyper::Response::new(hyper::Body::from(bytes))),
+            Err(status) => status_response(status),
+        }
     }
 
     /// Returns a mutable reference to an atomic bool. Set the bool to true to kill
@@ -82,24 +240,216 @@ impl<E: LowLevelManyRequestHandler> HttpServer<E> {
     }
 
     pub async fn bind<A: ToSocketAddrs>(&self, addr: A) -> Result<(), anyhow::Error> {
-        let server = tiny_http::Server::http(addr).map_err(|e| anyhow!("{}", e))?;
+        // Each request in flight holds a permit.
+        let permits = u32::try_from(self.workers)?;
+        let semaphore = Arc::new(Semaphore::new(self.workers));
+
+        match &self.tls {
+            Some(tls) => {
+                let listener = std::net::TcpListener::bind(addr)?;
+                listener.set_nonblocking(true)?;
+                self.serve_tls(
+                    TcpListener::from_std(listener)?,
+                    Arc::clone(tls),
+                    Arc::clone(&semaphore),
+                )
+                .await?;
+            }
+            None => {
+                let server = tiny_http::Server::http(addr).map_err(|e| anyhow!("{}", e))?;
+                self.serve(server, Arc::clone(&semaphore)).await?;
+            }
+        }
+        info!("Server shutting down gracefully...");
+
+        // Wait for the requests in flight.
+        match tokio::time::timeout(self.drain_timeout, semaphore.acquire_many(permits)).await {
+            Ok(_) => {}
+            Err(_) => warn!("Some requests did not complete before shutting down."),
+        }
+
+        Ok(())
+    }
+
+    async fn serve(
+        &self,
+        server: tiny_http::Server,
+        semaphore: Arc<Semaphore>,
+    ) -> Result<(), anyhow::Error> {
+        let server = Arc::new(server);
 
         loop {
-            if let Some(mut request) = server.recv_timeout(Duration::from_millis(100))? {
-                let response = self.handle_request(&mut request).await;
+            // Wait for a free worker before accepting the next request.
+            let permit = Arc::clone(&semaphore).acquire_owned().await?;
+
+            let receiver = Arc::clone(&server);
+            let received = tokio::task::spawn_blocking(move || {
+                receiver.recv_timeout(Duration::from_millis(100))
+            })
+            .await??;
 
-                // If there's a transport error (e.g. connection closed) on the response itself,
-                // we don't actually care and just continue waiting for the next request.
-                let _ = request.respond(response);
+            if let Some(request) = received {
+                let executor = Arc::clone(&self.executor);
+                let request_timeout = self.request_timeout;
+                tokio::spawn(Self::serve_request(
+                    executor,
+                    request,
+                    request_timeout,
+                    permit,
+                ));
             }
 
             // Check for the term signal and break out.
             if self.term_signal.load(Ordering::Relaxed) {
-                info!("Server shutting down gracefully...");
-                break;
+                return Ok(());
             }
         }
+    }
 
-        Ok(())
+    async fn serve_tls(
+        &self,
+        listener: TcpListener,
+        tls: Arc<rustls::ServerConfig>,
+        semaphore: Arc<Semaphore>,
+    ) -> Result<(), anyhow::Error> {
+        let acceptor = TlsAcceptor::from(tls);
+
+        loop {
+            let (stream, peer) = tokio::select! {
+                accepted = listener.accept() => accepted?,
+                _ = term_signal_set(&self.term_signal) => return Ok(()),
+            };
+
+            let acceptor = acceptor.clone();
+            let executor = Arc::clone(&self.executor);
+            let request_timeout = self.request_timeout;
+            let semaphore = Arc::clone(&semaphore);
+            let term_signal = Arc::clone(&self.term_signal);
+            tokio::spawn(async move {
+                let stream = match acceptor.accept(stream).await {
+                    Ok(stream) => stream,
+                    Err(e) => {
+                        tracing::debug!("TLS handshake with {peer} failed: {e}");
+                        return;
+                    }
+                };
+
+                let service = service_fn(move |request| {
+                    Self::serve_tls_request(
+                        Arc::clone(&executor),
+                        request,
+                        request_timeout,
+                        Arc::clone(&semaphore),
+                    )
+                });
+                let connection = hyper::server::conn::Http::new()
+                    .http1_only(true)
+                    .serve_connection(stream, service);
+                tokio::pin!(connection);
+
+                // Stop keeping the connection alive once the term signal is set.
+                let result = tokio::select! {
+                    result = &mut connection => result,
+                    _ = term_signal_set(&term_signal) => {
+                        connection.as_mut().graceful_shutdown();
+                        connection.await
+                    }
+                };
+                if let Err(e) = result {
+                    tracing::debug!("HTTPS connection from {peer} closed: {e}");
+                }
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use coset::CoseSign1Builder;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::{SocketAddr, TcpListener, TcpStream};
+    use std::sync::atomic::AtomicUsize;
+
+    /// Sleeps for the duration in the payload (in milliseconds), then returns the
+    /// envelope as the response.
+    #[derive(Debug, Default)]
+    struct SleepExecutor {
+        in_flight: AtomicUsize,
+        max_in_flight: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl LowLevelManyRequestHandler for Arc<SleepExecutor> {
+        async fn execute(&self, envelope: CoseSign1) -> Result<CoseSign1, String> {
+            let in_flight = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            self.max_in_flight.fetch_max(in_flight, Ordering::SeqCst);
+
+            let millis = envelope.payload.as_ref().map_or(0, |p| p[0]);
+            tokio::time::sleep(Duration::from_millis(millis as u64 * 10)).await;
+
+            self.in_flight.fetch_sub(1, Ordering::SeqCst);
+            Ok(envelope)
+        }
+    }
+
+    /// Send a request, and return the HTTP status of the response.
+    fn post(addr: SocketAddr, payload: u8) -> u16 {
+        let body = CoseSign1Builder::new()
+            .payload(vec![payload])
+            .build()
+            .to_tagged_vec()
+            .unwrap();
+        let mut stream = TcpStream::connect(addr).unwrap();
+        write!(
+            stream,
+            "POST / HTTP/1.1\r\nHost: {addr}\r\nConnection: close\r\nContent-Length: {}\r\n\r\n",
+            body.len()
+        )
+        .unwrap();
+        stream.write_all(&body).unwrap();
+
+        let mut response = String::new();
+        BufReader::new(stream).read_line(&mut response).unwrap();
+        response.split(' ').nth(1).unwrap().parse().unwrap()
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn concurrent_requests() {
+        let addr = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let executor = Arc::new(SleepExecutor::default());
+        let mut server = HttpServer::new(Arc::clone(&executor));
+        server.set_workers(4);
+        server.set_request_timeout(Duration::from_millis(500));
+        let term_signal = server.term_signal();
+        let handle = tokio::spawn(async move { server.bind(addr).await });
+
+        let statuses = tokio::task::spawn_blocking(move || {
+            let requests = (0..4)
+                .map(|_| std::thread::spawn(move || post(addr, 20)))
+                .collect::<Vec<_>>();
+            let mut statuses = requests
+                .into_iter()
+                .map(|r| r.join().unwrap())
+                .collect::<Vec<_>>();
+
+            // This one times out.
+            statuses.push(post(addr, 100));
+            statuses
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(statuses, vec![200, 200, 200, 200, 504]);
+        assert!(executor.max_in_flight.load(Ordering::SeqCst) > 1);
+
+        // The timed out request is drained before shutting down.
+        term_signal.store(true, Ordering::Relaxed);
+        handle.await.unwrap().unwrap();
+        assert_eq!(executor.in_flight.load(Ordering::SeqCst), 0);
     }
 }
@@ -0,0 +1,172 @@
+//! A raw TCP transport. Every message is a tagged COSE_Sign1 envelope prefixed by
+//! its length, as a 32 bits big endian integer. A connection can be used for any
+//! number of requests, and responses are sent in the order of the requests. An
+//! empty response signals a transport error.
+use crate::transport::{
+    execute_bytes, term_signal_set, LowLevelManyRequestHandler, MAX_MESSAGE_LEN,
+};
+use std::fmt::Debug;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream, ToSocketAddrs};
+use tracing::info;
+
+/// Read a frame. Returns `None` if the connection was closed before the frame.
+pub async fn read_frame<R: AsyncRead + Unpin>(reader: &mut R) -> std::io::Result<Option<Vec<u8>>> {
+    let mut len = [0u8; 4];
+    match reader.read_exact(&mut len).await {
+        Ok(_) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e),
+    }
+
+    let len = u32::from_be_bytes(len) as usize;
+    if len > MAX_MESSAGE_LEN {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("Content Too Large : {len} bytes"),
+        ));
+    }
+
+    let mut bytes = vec![0u8; len];
+    reader.read_exact(&mut bytes).await?;
+    Ok(Some(bytes))
+}
+
+/// Write a frame.
+pub async fn write_frame<W: AsyncWrite + Unpin>(
+    writer: &mut W,
+    bytes: &[u8],
+) -> std::io::Result<()> {
+    let len = u32::try_from(bytes.len())
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?;
+    writer.write_all(&len.to_be_bytes()).await?;
+    writer.write_all(bytes).await?;
+    writer.flush().await
+}
+
+#[derive(Debug)]
+pub struct TcpServer<E: LowLevelManyRequestHandler> {
+    executor: Arc<E>,
+    term_signal: Arc<AtomicBool>,
+}
+
+impl<E: LowLevelManyRequestHandler + 'static> TcpServer<E> {
+    pub fn new(executor: E) -> Self {
+        Self {
+            executor: Arc::new(executor),
+            term_signal: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Returns a mutable reference to an atomic bool. Set the bool to true to kill
+    /// the server.
+    pub fn term_signal(&mut self) -> Arc<AtomicBool> {
+        Arc::clone(&self.term_signal)
+    }
+
+    /// Use the term signal of another server, so both stop at the same time.
+    pub fn set_term_signal(&mut self, term_signal: Arc<AtomicBool>) {
+        self.term_signal = term_signal;
+    }
+
+    async fn handle_connection(
+        executor: Arc<E>,
+        mut stream: TcpStream,
+        term_signal: Arc<AtomicBool>,
+    ) -> std::io::Result<()> {
+        loop {
+            let bytes = tokio::select! {
+                frame = read_frame(&mut stream) => match frame? {
+                    Some(bytes) => bytes,
+                    None => return Ok(()),
+                },
+                _ = term_signal_set(&term_signal) => return Ok(()),
+            };
+
+            let response = execute_bytes(executor.as_ref(), &bytes)
+                .await
+                .unwrap_or_else(|e| {
+                    tracing::error!(r#"Error executing request. Error description="{}""#, e);
+                    vec![]
+                });
+            write_frame(&mut stream, &response).await?;
+        }
+    }
+
+    pub async fn bind<A: ToSocketAddrs>(&self, addr: A) -> Result<(), anyhow::Error> {
+        self.serve(TcpListener::bind(addr).await?).await
+    }
+
+    /// Accept connections on an already bound listener.
+    pub async fn serve(&self, listener: TcpListener) -> Result<(), anyhow::Error> {
+        loop {
+            let (stream, peer) = tokio::select! {
+                accepted = listener.accept() => accepted?,
+                _ = term_signal_set(&self.term_signal) => break,
+            };
+            tracing::debug!("TCP connection from {peer}");
+            stream.set_nodelay(true)?;
+
+            let executor = Arc::clone(&self.executor);
+            let term_signal = Arc::clone(&self.term_signal);
+            tokio::spawn(async move {
+                if let Err(e) = Self::handle_connection(executor, stream, term_signal).await {
+                    tracing::debug!("TCP connection from {peer} closed: {e}");
+                }
+            });
+        }
+
+        info!("Server shutting down gracefully...");
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use coset::{CoseSign1, CoseSign1Builder, TaggedCborSerializable};
+
+    /// Returns the request envelope as the response.
+    #[derive(Debug)]
+    struct EchoExecutor;
+
+    #[async_trait]
+    impl LowLevelManyRequestHandler for EchoExecutor {
+        async fn execute(&self, envelope: CoseSign1) -> Result<CoseSign1, String> {
+            Ok(envelope)
+        }
+    }
+
+    #[tokio::test]
+    async fn persistent_connection() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let mut server = TcpServer::new(EchoExecutor);
+        let term_signal = server.term_signal();
+        let handle = tokio::spawn(async move { server.serve(listener).await });
+
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        for i in 0..3u8 {
+            let envelope = CoseSign1Builder::new().payload(vec![i]).build();
+            write_frame(&mut stream, &envelope.clone().to_tagged_vec().unwrap())
+                .await
+                .unwrap();
+            let response = read_frame(&mut stream).await.unwrap().unwrap();
+            assert_eq!(
+                CoseSign1::from_tagged_slice(&response).unwrap().payload,
+                envelope.payload
+            );
+        }
+
+        // Invalid envelopes get an empty response, and the connection stays open.
+        write_frame(&mut stream, b"invalid").await.unwrap();
+        assert_eq!(read_frame(&mut stream).await.unwrap(), Some(vec![]));
+
+        term_signal.store(true, std::sync::atomic::Ordering::Relaxed);
+        handle.await.unwrap().unwrap();
+        assert_eq!(read_frame(&mut stream).await.unwrap(), None);
+    }
+}
@@ -0,0 +1,35 @@
+//! TLS configuration for the server transports, from PEM encoded certificates and
+//! keys.
+use rustls::server::AllowAnyAuthenticatedClient;
+use rustls::{RootCertStore, ServerConfig};
+use std::sync::Arc;
+
+pub use many_types::tls::{certificates_from_pem, private_key_from_pem};
+
+/// Create a server configuration from a certificate chain and its private key.
+/// If `client_ca` is set, clients must present a certificate signed by one of
+/// its certificates (mutual TLS).
+pub fn server_config(
+    certificate: &[u8],
+    key: &[u8],
+    client_ca: Option<&[u8]>,
+) -> Result<Arc<ServerConfig>, anyhow::Error> {
+    let builder = ServerConfig::builder().with_safe_defaults();
+    let builder = match client_ca {
+        Some(client_ca) => {
+            let mut roots = RootCertStore::empty();
+            for certificate in certificates_from_pem(client_ca)? {
+                roots.add(&certificate)?;
+            }
+            builder.with_client_cert_verifier(AllowAnyAuthenticatedClient::new(roots).boxed())
+        }
+        None => builder.with_no_client_auth(),
+    };
+
+    let mut config = builder.with_single_cert(
+        certificates_from_pem(certificate)?,
+        private_key_from_pem(key)?,
+    )?;
+    config.alpn_protocols = vec![b"http/1.1".to_vec()];
+    Ok(Arc::new(config))
+}
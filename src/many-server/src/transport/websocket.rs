@@ -0,0 +1,150 @@
+//! A WebSocket transport. Every binary message is a tagged COSE_Sign1 envelope.
+//! A connection can be used for any number of requests, and responses are sent
+//! in the order of the requests. An empty response signals a transport error.
+use crate::transport::{
+    execute_bytes, term_signal_set, LowLevelManyRequestHandler, MAX_MESSAGE_LEN,
+};
+use futures_util::{SinkExt, StreamExt};
+use std::fmt::Debug;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use tokio::net::{TcpListener, TcpStream, ToSocketAddrs};
+use tokio_tungstenite::tungstenite::protocol::WebSocketConfig;
+use tokio_tungstenite::tungstenite::Message;
+use tracing::info;
+
+#[derive(Debug)]
+pub struct WebSocketServer<E: LowLevelManyRequestHandler> {
+    executor: Arc<E>,
+    term_signal: Arc<AtomicBool>,
+}
+
+impl<E: LowLevelManyRequestHandler + 'static> WebSocketServer<E> {
+    pub fn new(executor: E) -> Self {
+        Self {
+            executor: Arc::new(executor),
+            term_signal: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Returns a mutable reference to an atomic bool. Set the bool to true to kill
+    /// the server.
+    pub fn term_signal(&mut self) -> Arc<AtomicBool> {
+        Arc::clone(&self.term_signal)
+    }
+
+    /// Use the term signal of another server, so both stop at the same time.
+    pub fn set_term_signal(&mut self, term_signal: Arc<AtomicBool>) {
+        self.term_signal = term_signal;
+    }
+
+    async fn handle_connection(
+        executor: Arc<E>,
+        stream: TcpStream,
+        term_signal: Arc<AtomicBool>,
+    ) -> Result<(), tokio_tungstenite::tungstenite::Error> {
+        let config = WebSocketConfig {
+            max_message_size: Some(MAX_MESSAGE_LEN),
+            ..Default::default()
+        };
+        let mut ws = tokio_tungstenite::accept_async_with_config(stream, Some(config)).await?;
+
+        loop {
+            let message = tokio::select! {
+                message = ws.next() => match message {
+                    Some(message) => message?,
+                    None => return Ok(()),
+                },
+                _ = term_signal_set(&term_signal) => return ws.close(None).await,
+            };
+
+            let response = match message {
+                Message::Binary(bytes) => execute_bytes(executor.as_ref(), &bytes).await,
+                Message::Close(_) => return Ok(()),
+                // Pings are answered by the library.
+                Message::Ping(_) | Message::Pong(_) | Message::Frame(_) => continue,
+                Message::Text(_) => Err("Text messages are not supported".to_string()),
+            };
+            let bytes = response.unwrap_or_else(|e| {
+                tracing::error!(r#"Error executing request. Error description="{}""#, e);
+                vec![]
+            });
+            ws.send(Message::Binary(bytes)).await?;
+        }
+    }
+
+    pub async fn bind<A: ToSocketAddrs>(&self, addr: A) -> Result<(), anyhow::Error> {
+        self.serve(TcpListener::bind(addr).await?).await
+    }
+
+    /// Accept connections on an already bound listener.
+    pub async fn serve(&self, listener: TcpListener) -> Result<(), anyhow::Error> {
+        loop {
+            let (stream, peer) = tokio::select! {
+                accepted = listener.accept() => accepted?,
+                _ = term_signal_set(&self.term_signal) => break,
+            };
+            tracing::debug!("WebSocket connection from {peer}");
+            stream.set_nodelay(true)?;
+
+            let executor = Arc::clone(&self.executor);
+            let term_signal = Arc::clone(&self.term_signal);
+            tokio::spawn(async move {
+                if let Err(e) = Self::handle_connection(executor, stream, term_signal).await {
+                    tracing::debug!("WebSocket connection from {peer} closed: {e}");
+                }
+            });
+        }
+
+        info!("Server shutting down gracefully...");
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use coset::{CoseSign1, CoseSign1Builder, TaggedCborSerializable};
+
+    /// Returns the request envelope as the response.
+    #[derive(Debug)]
+    struct EchoExecutor;
+
+    #[async_trait]
+    impl LowLevelManyRequestHandler for EchoExecutor {
+        async fn execute(&self, envelope: CoseSign1) -> Result<CoseSign1, String> {
+            Ok(envelope)
+        }
+    }
+
+    #[tokio::test]
+    async fn persistent_connection() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let mut server = WebSocketServer::new(EchoExecutor);
+        let term_signal = server.term_signal();
+        let handle = tokio::spawn(async move { server.serve(listener).await });
+
+        let (mut ws, _) = tokio_tungstenite::connect_async(format!("ws://{addr}"))
+            .await
+            .unwrap();
+        for i in 0..3u8 {
+            let envelope = CoseSign1Builder::new().payload(vec![i]).build();
+            ws.send(Message::Binary(envelope.clone().to_tagged_vec().unwrap()))
+                .await
+                .unwrap();
+            let response = ws.next().await.unwrap().unwrap().into_data();
+            assert_eq!(
+                CoseSign1::from_tagged_slice(&response).unwrap().payload,
+                envelope.payload
+            );
+        }
+
+        ws.send(Message::Text("hello".to_string())).await.unwrap();
+        assert!(ws.next().await.unwrap().unwrap().into_data().is_empty());
+
+        term_signal.store(true, std::sync::atomic::Ordering::Relaxed);
+        handle.await.unwrap().unwrap();
+    }
+}
@@ -2,6 +2,12 @@ use coset::CoseSign1;
 use many_error::ManyError;
 use many_protocol::{RequestMessage, ResponseMessage};
 
+pub mod delegation;
+pub mod rate_limit;
+
+pub use delegation::DelegationValidator;
+pub use rate_limit::RateLimitValidator;
+
 /// A trait for transforming a request.
 pub trait RequestValidator {
     /// Validate the envelope, prior to executing the message.
@@ -0,0 +1,153 @@
+//! A validator of the delegation certificates carried by requests.
+use crate::RequestValidator;
+use coset::CoseSign1;
+use many_error::ManyError;
+use many_identity::Verifier;
+use many_protocol::delegation::{DelegationAttribute, DELEGATION};
+use many_protocol::RequestMessage;
+use many_types::Timestamp;
+use std::time::SystemTime;
+
+/// Validates the chain of delegation certificates of requests acting on behalf
+/// of another identity.
+///
+/// Certificates expire according to the clock of the server, not the timestamp
+/// of the request, so a delegate cannot use an expired certificate by
+/// backdating its requests. The server runs this validator on every request;
+/// the signatures and links of the chain are already verified when decoding the
+/// request (see [DelegationAttribute::verify]).
+pub struct DelegationValidator<'a, V: Verifier> {
+    verifier: &'a V,
+    now: SystemTime,
+}
+
+impl<'a, V: Verifier> DelegationValidator<'a, V> {
+    pub fn new(verifier: &'a V, now: SystemTime) -> Self {
+        Self { verifier, now }
+    }
+}
+
+impl<V: Verifier> RequestValidator for DelegationValidator<'_, V> {
+    fn validate_envelope(&self, envelope: &CoseSign1) -> Result<(), ManyError> {
+        let payload = envelope
+            .payload
+            .as_ref()
+            .ok_or_else(ManyError::empty_envelope)?;
+        let request =
+            RequestMessage::from_bytes(payload).map_err(ManyError::deserialization_error)?;
+        if !request.attributes.has_id(DELEGATION.id) {
+            return Ok(());
+        }
+
+        let chain: DelegationAttribute = request.attributes.get()?;
+        let signer = self.verifier.verify_1(envelope)?;
+        let root = chain.resolve(
+            &signer,
+            &request.method,
+            Timestamp::from_system_time(self.now)?,
+            self.verifier,
+        )?;
+        if !root.matches(&request.from()) {
+            return Err(ManyError::invalid_from_identity());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use many_identity::Identity;
+    use many_identity_dsa::ed25519::generate_random_ed25519_identity;
+    use many_protocol::{
+        encode_cose_sign1_from_request, DelegationCertificate, RequestMessageBuilder,
+    };
+    use std::time::Duration;
+
+    #[test]
+    fn expiration_uses_server_clock() {
+        let delegator = generate_random_ed25519_identity();
+        let delegate = generate_random_ed25519_identity();
+        let expiration = Timestamp::new(1_000_000).unwrap();
+        let certificate =
+            DelegationCertificate::new(delegator.address(), delegate.address(), expiration)
+                .sign(&delegator)
+                .unwrap();
+
+        // The request claims to be older than the expiration.
+        let request: RequestMessage = RequestMessageBuilder::default()
+            .from(delegator.address())
+            .method("status".to_string())
+            .timestamp(Timestamp::new(1).unwrap())
+            .build()
+            .unwrap()
+            .with_attribute(DelegationAttribute::new(vec![certificate]).into());
+        let envelope = encode_cose_sign1_from_request(request, &delegate).unwrap();
+
+        let before = SystemTime::UNIX_EPOCH + Duration::from_secs(expiration.secs() - 1);
+        let after = SystemTime::UNIX_EPOCH + Duration::from_secs(expiration.secs());
+        let verifier = many_identity_dsa::CoseKeyVerifier;
+        assert!(DelegationValidator::new(&verifier, before)
+            .validate_envelope(&envelope)
+            .is_ok());
+        assert_eq!(
+            DelegationValidator::new(&verifier, after)
+                .validate_envelope(&envelope)
+                .unwrap_err()
+                .code(),
+            ManyError::delegation_expired("").code()
+        );
+    }
+
+    #[test]
+    fn forged_chain() {
+        let victim = generate_random_ed25519_identity();
+        let attacker = generate_random_ed25519_identity();
+
+        // The attacker signs a certificate in the name of the victim.
+        let mut certificate = DelegationCertificate::new(
+            attacker.address(),
+            attacker.address(),
+            Timestamp::new(1_000_000).unwrap(),
+        )
+        .sign(&attacker)
+        .unwrap();
+        let forged = DelegationCertificate::new(
+            victim.address(),
+            attacker.address(),
+            Timestamp::new(1_000_000).unwrap(),
+        );
+        certificate.payload = Some(minicbor::to_vec(forged).unwrap());
+
+        let request: RequestMessage = RequestMessageBuilder::default()
+            .from(victim.address())
+            .method("status".to_string())
+            .build()
+            .unwrap()
+            .with_attribute(DelegationAttribute::new(vec![certificate]).into());
+        let envelope = encode_cose_sign1_from_request(request, &attacker).unwrap();
+
+        assert!(many_protocol::decode_request_from_cose_sign1(
+            &envelope,
+            &many_identity_dsa::CoseKeyVerifier
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn without_certificates() {
+        let identity = generate_random_ed25519_identity();
+        let request: RequestMessage = RequestMessageBuilder::default()
+            .from(identity.address())
+            .method("status".to_string())
+            .build()
+            .unwrap();
+        let envelope = encode_cose_sign1_from_request(request, &identity).unwrap();
+
+        assert!(
+            DelegationValidator::new(&many_identity_dsa::CoseKeyVerifier, SystemTime::now())
+                .validate_envelope(&envelope)
+                .is_ok()
+        );
+    }
+}
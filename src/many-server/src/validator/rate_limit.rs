@@ -0,0 +1,309 @@
+//! A validator that limits the rate of requests of each sender, using token
+//! buckets.
+use crate::RequestValidator;
+use many_error::ManyError;
+use many_identity::Address;
+use many_protocol::RequestMessage;
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Number of buckets kept before forgetting the ones that are full.
+const PRUNE_THRESHOLD: usize = 10_000;
+
+/// Minimum time between two prunes of the buckets.
+const PRUNE_INTERVAL: Duration = Duration::from_secs(10);
+
+/// A token bucket limit. A bucket holds up to `burst` requests, and refills at
+/// `per_second` requests per second.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Limit {
+    burst: u32,
+    per_second: f64,
+}
+
+impl Limit {
+    pub fn new(burst: u32, per_second: f64) -> Self {
+        Self { burst, per_second }
+    }
+}
+
+impl FromStr for Limit {
+    type Err = String;
+
+    /// Parse a limit as `BURST:PER_SECOND`, e.g. `10:0.5`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (burst, per_second) = s
+            .split_once(':')
+            .ok_or_else(|| format!("Invalid rate limit '{s}', expected BURST:PER_SECOND."))?;
+        let burst = burst.parse().map_err(|e| format!("Invalid burst: {e}"))?;
+        if burst == 0 {
+            return Err("The burst must be positive.".to_string());
+        }
+        let per_second: f64 = per_second
+            .parse()
+            .map_err(|e| format!("Invalid rate: {e}"))?;
+        if !per_second.is_finite() || per_second <= 0. {
+            return Err("The rate must be positive.".to_string());
+        }
+        Ok(Self::new(burst, per_second))
+    }
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    fn full(limit: &Limit, now: Instant) -> Self {
+        Self {
+            tokens: limit.burst as f64,
+            updated: now,
+        }
+    }
+
+    fn refill(&mut self, limit: &Limit, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * limit.per_second).min(limit.burst as f64);
+        self.updated = now;
+    }
+
+    /// The time until the bucket has a token.
+    fn wait(&self, limit: &Limit) -> Duration {
+        if self.tokens >= 1. {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64((1. - self.tokens) / limit.per_second)
+        }
+    }
+}
+
+/// The buckets of all senders, and when they were last pruned.
+#[derive(Debug, Default)]
+struct Buckets {
+    buckets: BTreeMap<(Address, Option<String>), Bucket>,
+    pruned: Option<Instant>,
+}
+
+/// Limits the rate of requests of each sender, and of each sender for each
+/// method. All anonymous requests share their buckets, and have a separate
+/// limit.
+///
+/// Limits are checked after the signature of the request is verified, so a
+/// sender cannot use the buckets of another one.
+#[derive(Debug, Default)]
+pub struct RateLimitValidator {
+    sender: Option<Limit>,
+    anonymous: Option<Limit>,
+    methods: BTreeMap<String, Limit>,
+    buckets: Mutex<Buckets>,
+}
+
+impl RateLimitValidator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Limit the requests of each (non-anonymous) sender, for all methods.
+    pub fn with_sender_limit(mut self, limit: Limit) -> Self {
+        self.sender = Some(limit);
+        self
+    }
+
+    /// Limit the requests of all anonymous senders, for all methods.
+    pub fn with_anonymous_limit(mut self, limit: Limit) -> Self {
+        self.anonymous = Some(limit);
+        self
+    }
+
+    /// Limit the requests of each sender to a method.
+    pub fn with_method_limit(mut self, method: impl ToString, limit: Limit) -> Self {
+        self.methods.insert(method.to_string(), limit);
+        self
+    }
+
+    /// Forget the buckets that are full, at most once per [PRUNE_INTERVAL] and
+    /// only when there are more than [PRUNE_THRESHOLD] of them. A full bucket is
+    /// the same as no bucket.
+    fn prune(&self, buckets: &mut Buckets, now: Instant) {
+        if buckets.buckets.len() <= PRUNE_THRESHOLD
+            || buckets.pruned.map_or(false, |pruned| {
+                now.saturating_duration_since(pruned) < PRUNE_INTERVAL
+            })
+        {
+            return;
+        }
+        buckets.pruned = Some(now);
+        buckets.buckets.retain(|(from, method), bucket| {
+            let limit = match method {
+                Some(method) => self.methods.get(method),
+                None if from.is_anonymous() => self.anonymous.as_ref(),
+                None => self.sender.as_ref(),
+            };
+            limit.map_or(false, |limit| {
+                bucket.refill(limit, now);
+                bucket.tokens < limit.burst as f64
+            })
+        });
+    }
+
+    fn check(&self, from: Address, method: &str, now: Instant) -> Result<(), ManyError> {
+        let sender = if from.is_anonymous() {
+            self.anonymous
+        } else {
+            self.sender
+        };
+        let limits = [
+            sender.map(|limit| (None, limit)),
+            self.methods
+                .get(method)
+                .map(|limit| (Some(method.to_string()), *limit)),
+        ];
+
+        let mut buckets = self.buckets.lock().unwrap();
+        self.prune(&mut buckets, now);
+        let buckets = &mut buckets.buckets;
+
+        // Only take tokens if all the buckets have one.
+        let mut wait = Duration::ZERO;
+        for (method, limit) in limits.iter().flatten() {
+            let bucket = buckets
+                .entry((from, method.clone()))
+                .or_insert_with(|| Bucket::full(limit, now));
+            bucket.refill(limit, now);
+            wait = wait.max(bucket.wait(limit));
+        }
+        if wait > Duration::ZERO {
+            // Round up, so retrying after the delay succeeds.
+            return Err(ManyError::rate_limited(wait.as_micros().div_ceil(1000)));
+        }
+
+        for (method, _) in limits.iter().flatten() {
+            if let Some(bucket) = buckets.get_mut(&(from, method.clone())) {
+                bucket.tokens -= 1.;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl RequestValidator for RateLimitValidator {
+    fn validate_request(&self, request: &RequestMessage) -> Result<(), ManyError> {
+        self.check(request.from(), &request.method, Instant::now())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use many_error::ManyErrorCode;
+    use many_identity::testing::identity;
+
+    #[test]
+    fn parse() {
+        assert_eq!("10:0.5".parse(), Ok(Limit::new(10, 0.5)));
+        assert!("10".parse::<Limit>().is_err());
+        assert!("10:0".parse::<Limit>().is_err());
+        assert!("0:1".parse::<Limit>().is_err());
+        assert!("a:1".parse::<Limit>().is_err());
+    }
+
+    #[test]
+    fn sender() {
+        let validator = RateLimitValidator::new().with_sender_limit(Limit::new(2, 1.));
+        let now = Instant::now();
+
+        assert!(validator.check(identity(1), "status", now).is_ok());
+        assert!(validator.check(identity(1), "ledger.info", now).is_ok());
+        let err = validator.check(identity(1), "status", now).unwrap_err();
+        assert_eq!(err.code(), ManyErrorCode::RateLimited);
+        assert_eq!(err.retry_after(), Some(Duration::from_secs(1)));
+
+        // Other senders have their own bucket.
+        assert!(validator.check(identity(2), "status", now).is_ok());
+        // Anonymous senders are not limited.
+        for _ in 0..10 {
+            assert!(validator.check(Address::anonymous(), "status", now).is_ok());
+        }
+
+        // Refills over time.
+        let later = now + Duration::from_millis(500);
+        assert_eq!(
+            validator
+                .check(identity(1), "status", later)
+                .unwrap_err()
+                .retry_after(),
+            Some(Duration::from_millis(500))
+        );
+        assert!(validator
+            .check(identity(1), "status", now + Duration::from_secs(1))
+            .is_ok());
+    }
+
+    #[test]
+    fn anonymous() {
+        let validator = RateLimitValidator::new()
+            .with_sender_limit(Limit::new(10, 1.))
+            .with_anonymous_limit(Limit::new(1, 0.1));
+        let now = Instant::now();
+
+        assert!(validator.check(Address::anonymous(), "status", now).is_ok());
+        assert_eq!(
+            validator
+                .check(Address::anonymous(), "status", now)
+                .unwrap_err()
+                .retry_after(),
+            Some(Duration::from_secs(10))
+        );
+        assert!(validator.check(identity(1), "status", now).is_ok());
+    }
+
+    #[test]
+    fn method() {
+        let validator = RateLimitValidator::new()
+            .with_sender_limit(Limit::new(2, 1.))
+            .with_method_limit("ledger.send", Limit::new(1, 0.5));
+        let now = Instant::now();
+
+        assert!(validator.check(identity(1), "ledger.send", now).is_ok());
+        assert_eq!(
+            validator
+                .check(identity(1), "ledger.send", now)
+                .unwrap_err()
+                .retry_after(),
+            Some(Duration::from_secs(2))
+        );
+        // The rejected request did not take a token from the sender bucket.
+        assert!(validator.check(identity(1), "ledger.info", now).is_ok());
+        assert!(validator.check(identity(1), "ledger.info", now).is_err());
+    }
+
+    #[test]
+    fn prune() {
+        let validator = RateLimitValidator::new().with_sender_limit(Limit::new(1, 1.));
+        let now = Instant::now();
+        let len = || validator.buckets.lock().unwrap().buckets.len();
+
+        for i in 0..=PRUNE_THRESHOLD as u32 {
+            assert!(validator.check(identity(i), "status", now).is_ok());
+        }
+        assert_eq!(len(), PRUNE_THRESHOLD + 1);
+
+        // The buckets are pruned once, when they are not full yet.
+        assert!(validator.check(identity(0), "status", now).is_err());
+        assert_eq!(len(), PRUNE_THRESHOLD + 1);
+
+        // They are full after a second, but are not pruned again before the
+        // interval elapsed.
+        let later = now + Duration::from_secs(1);
+        assert!(validator.check(identity(0), "status", later).is_ok());
+        assert_eq!(len(), PRUNE_THRESHOLD + 1);
+
+        let later = now + PRUNE_INTERVAL;
+        assert!(validator.check(identity(0), "status", later).is_ok());
+        assert_eq!(len(), 1);
+    }
+}
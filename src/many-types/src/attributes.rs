@@ -1,4 +1,5 @@
 use crate::cbor::CborAny;
+use crate::schema::{CborSchema, Schema};
 use many_error::ManyError;
 use minicbor::data::Type;
 use minicbor::encode::{Error, Write};
@@ -10,7 +11,7 @@ use std::fmt::Debug;
 /// An attribute ID.
 pub type AttributeId = u32;
 
-#[derive(Encode, Decode, Clone, Debug, Default, Ord, PartialOrd, Eq, PartialEq)]
+#[derive(Encode, Decode, CborSchema, Clone, Debug, Default, Ord, PartialOrd, Eq, PartialEq)]
 #[cbor(transparent)]
 pub struct AttributeSet(#[n(0)] BTreeSet<Attribute>);
 
@@ -145,6 +146,15 @@ impl<C> Encode<C> for Attribute {
     }
 }
 
+impl CborSchema for Attribute {
+    fn schema() -> Schema {
+        Schema::named(
+            "Attribute",
+            Schema::Choice(vec![Schema::Uint, Schema::array(Schema::Any)]),
+        )
+    }
+}
+
 impl<'d, C> Decode<'d, C> for Attribute {
     fn decode(d: &mut Decoder<'d>, _: &mut C) -> Result<Self, minicbor::decode::Error> {
         match d.datatype()? {
@@ -1,3 +1,4 @@
+use crate::schema::{CborSchema, Field, Schema};
 use crate::{CborRange, Timestamp};
 use minicbor::encode::{Error, Write};
 use minicbor::{decode, Decode, Decoder, Encode, Encoder};
@@ -22,6 +23,18 @@ impl<C> Encode<C> for SingleBlockQuery {
     }
 }
 
+impl CborSchema for SingleBlockQuery {
+    fn schema() -> Schema {
+        Schema::named(
+            "SingleBlockQuery",
+            Schema::Choice(vec![
+                Schema::Struct(vec![Field::new(0, "hash", Schema::Bytes)]),
+                Schema::Struct(vec![Field::new(1, "height", Schema::Uint)]),
+            ]),
+        )
+    }
+}
+
 impl<'d, C> Decode<'d, C> for SingleBlockQuery {
     fn decode(d: &mut Decoder<'d>, _: &mut C) -> Result<Self, decode::Error> {
         let mut indefinite = false;
@@ -50,7 +63,7 @@ impl<'d, C> Decode<'d, C> for SingleBlockQuery {
     }
 }
 
-#[derive(Clone, Debug, Decode, Encode, Eq, PartialEq)]
+#[derive(Clone, Debug, Decode, Encode, CborSchema, Eq, PartialEq)]
 #[cbor(map)]
 pub struct BlockIdentifier {
     #[cbor(n(0), with = "minicbor::bytes")]
@@ -70,14 +83,14 @@ impl BlockIdentifier {
     }
 }
 
-#[derive(Debug, Clone, Decode, Encode, PartialEq, Eq)]
+#[derive(Debug, Clone, Decode, Encode, CborSchema, PartialEq, Eq)]
 #[cbor(map)]
 pub struct TransactionIdentifier {
     #[cbor(n(0), with = "minicbor::bytes")]
     pub hash: Vec<u8>,
 }
 
-#[derive(Debug, Clone, Decode, Encode, PartialEq, Eq)]
+#[derive(Debug, Clone, Decode, Encode, CborSchema, PartialEq, Eq)]
 #[cbor(map)]
 pub struct Transaction {
     #[n(0)]
@@ -90,7 +103,7 @@ pub struct Transaction {
     pub response: Option<Vec<u8>>,
 }
 
-#[derive(Debug, Clone, Decode, Encode, PartialEq, Eq)]
+#[derive(Debug, Clone, Decode, Encode, CborSchema, PartialEq, Eq)]
 #[cbor(map)]
 pub struct Block {
     #[n(0)]
@@ -134,6 +147,15 @@ impl<C> Encode<C> for SingleTransactionQuery {
     }
 }
 
+impl CborSchema for SingleTransactionQuery {
+    fn schema() -> Schema {
+        Schema::named(
+            "SingleTransactionQuery",
+            Schema::Struct(vec![Field::new(0, "hash", Schema::Bytes)]),
+        )
+    }
+}
+
 impl<'d, C> Decode<'d, C> for SingleTransactionQuery {
     fn decode(d: &mut Decoder<'d>, _: &mut C) -> Result<Self, decode::Error> {
         let mut indefinite = false;
@@ -184,6 +206,22 @@ impl<C> Encode<C> for RangeBlockQuery {
     }
 }
 
+impl CborSchema for RangeBlockQuery {
+    fn schema() -> Schema {
+        Schema::named(
+            "RangeBlockQuery",
+            Schema::Choice(vec![
+                Schema::Struct(vec![Field::new(1, "height", CborRange::<u64>::schema())]),
+                Schema::Struct(vec![Field::new(
+                    2,
+                    "time",
+                    CborRange::<Timestamp>::schema(),
+                )]),
+            ]),
+        )
+    }
+}
+
 impl<'d, C> Decode<'d, C> for RangeBlockQuery {
     fn decode(d: &mut Decoder<'d>, _: &mut C) -> Result<Self, decode::Error> {
         let mut indefinite = false;
@@ -1,10 +1,11 @@
+use crate::schema::{CborSchema, Schema};
 use minicbor::encode::Write;
 use minicbor::{decode, encode, Decode, Decoder, Encode, Encoder};
 use serde::{de, Deserialize, Deserializer, Serialize};
 use std::collections::HashMap;
 use strum::Display;
 
-#[derive(Clone, Decode, Display, Debug, Encode, Eq, PartialEq)]
+#[derive(Clone, Decode, Display, Debug, Encode, CborSchema, Eq, PartialEq)]
 #[cbor(index_only)]
 pub enum ComputeStatus {
     #[n(0)]
@@ -13,7 +14,7 @@ pub enum ComputeStatus {
     Closed,
 }
 
-#[derive(Clone, Debug, Decode, Display, Encode, Eq, PartialEq)]
+#[derive(Clone, Debug, Decode, Display, Encode, CborSchema, Eq, PartialEq)]
 #[strum(serialize_all = "PascalCase")]
 #[cbor(index_only)]
 pub enum ByteUnits {
@@ -43,7 +44,7 @@ pub enum ByteUnits {
     EI,
 }
 
-#[derive(Clone, Debug, Decode, Display, Encode, Eq, PartialEq)]
+#[derive(Clone, Debug, Decode, Display, Encode, CborSchema, Eq, PartialEq)]
 #[strum(serialize_all = "kebab-case")]
 #[cbor(index_only)]
 pub enum Region {
@@ -78,6 +79,14 @@ impl<C> Encode<C> for ComputeListFilter {
     }
 }
 
+impl CborSchema for ComputeListFilter {
+    fn schema() -> Schema {
+        // The status of `Status` is encoded as a second value after the index,
+        // which cannot be described.
+        Schema::Any
+    }
+}
+
 impl<'b, C> Decode<'b, C> for ComputeListFilter {
     fn decode(d: &mut Decoder<'b>, _: &mut C) -> Result<Self, decode::Error> {
         match d.u8()? {
@@ -91,7 +100,7 @@ impl<'b, C> Decode<'b, C> for ComputeListFilter {
     }
 }
 
-#[derive(Clone, Debug, Decode, Encode, PartialEq)]
+#[derive(Clone, Debug, Decode, Encode, CborSchema, PartialEq)]
 #[cbor(map)]
 pub struct DeploymentInfo {
     #[n(0)]
@@ -104,7 +113,7 @@ pub struct DeploymentInfo {
     pub price: f64,
 }
 
-#[derive(Clone, Debug, Decode, Encode, PartialEq)]
+#[derive(Clone, Debug, Decode, Encode, CborSchema, PartialEq)]
 #[cbor(map)]
 pub struct DeploymentMeta {
     #[n(0)]
@@ -142,6 +151,15 @@ impl<C> Encode<C> for ServiceProtocol {
     }
 }
 
+impl CborSchema for ServiceProtocol {
+    fn schema() -> Schema {
+        Schema::named(
+            "ServiceProtocol",
+            Schema::Choice(vec![Schema::Literal(0), Schema::Literal(1)]),
+        )
+    }
+}
+
 impl<'b, C> Decode<'b, C> for ServiceProtocol {
     fn decode(d: &mut Decoder<'b>, _: &mut C) -> Result<Self, decode::Error> {
         Ok(match d.u8()? {
@@ -153,7 +171,7 @@ impl<'b, C> Decode<'b, C> for ServiceProtocol {
 }
 
 // Converted from Akash Go code
-#[derive(Clone, Debug, Decode, Encode, Eq, PartialEq)]
+#[derive(Clone, Debug, Decode, Encode, CborSchema, Eq, PartialEq)]
 #[cbor(map)]
 pub struct ProviderInfo {
     #[n(0)]
@@ -167,7 +185,7 @@ pub struct ProviderInfo {
 }
 
 // Converted from Akash Go code
-#[derive(Clone, Debug, Decode, Encode, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Debug, Decode, Encode, CborSchema, Eq, PartialEq, Serialize, Deserialize)]
 #[cbor(map)]
 pub struct ServiceStatus {
     #[n(0)]
@@ -192,7 +210,7 @@ pub struct ServiceStatus {
 }
 
 // Converted from Akash Go code
-#[derive(Clone, Debug, Decode, Encode, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Debug, Decode, Encode, CborSchema, Eq, PartialEq, Serialize, Deserialize)]
 #[cbor(map)]
 pub struct ForwardedPortStatus {
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -213,7 +231,7 @@ pub struct ForwardedPortStatus {
 }
 
 // Converted from Akash Go code
-#[derive(Clone, Debug, Decode, Encode, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Debug, Decode, Encode, CborSchema, Eq, PartialEq, Serialize, Deserialize)]
 #[cbor(map)]
 pub struct LeasedIPStatus {
     #[n(0)]
@@ -227,7 +245,7 @@ pub struct LeasedIPStatus {
 }
 
 // Converted from Akash Go code
-#[derive(Clone, Debug, Decode, Encode, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Debug, Decode, Encode, CborSchema, Eq, PartialEq, Serialize, Deserialize)]
 #[cbor(map)]
 pub struct LeaseStatus {
     #[n(0)]
@@ -7,6 +7,9 @@ use std::fmt::{Debug, Formatter};
 use std::ops::{Bound, RangeBounds, Shl};
 use std::str::FromStr;
 
+// Allows the `CborSchema` derive macro to be used in this crate.
+extern crate self as many_types;
+
 pub mod attributes;
 pub mod blockchain;
 pub mod cbor;
@@ -18,6 +21,9 @@ pub mod identity {
 pub mod ledger;
 pub mod memo;
 pub mod proof;
+pub mod schema;
+#[cfg(feature = "tls")]
+pub mod tls;
 pub mod web;
 
 use attributes::AttributeId;
@@ -47,7 +53,7 @@ macro_rules! cbor_type_decl {
         )*
     ) => {
         $(
-            #[derive(Clone, Debug, Decode, Encode, Eq, PartialEq)]
+            #[derive(Clone, Debug, Decode, Encode, Eq, PartialEq, $crate::schema::CborSchema)]
             #[cfg_attr(feature = "cucumber", derive(Default))]
             #[cbor(map)]
             $vis struct $name {
@@ -162,6 +168,7 @@ where
 pub struct Timestamp(u64);
 
 impl Timestamp {
+    #[cfg(not(all(feature = "wasm", target_arch = "wasm32")))]
     pub fn now() -> Self {
         Self::new(
             std::time::SystemTime::now()
@@ -172,6 +179,12 @@ impl Timestamp {
         .expect("Time flew all around")
     }
 
+    /// The system clock is not available in browsers, use the JavaScript one.
+    #[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+    pub fn now() -> Self {
+        Self::new((js_sys::Date::now() / 1000.) as u64).expect("Time flew all around")
+    }
+
     pub const fn new(secs: u64) -> Result<Self, ManyError> {
         Ok(Self(secs))
     }
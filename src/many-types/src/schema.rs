@@ -0,0 +1,884 @@
+//! Machine-readable descriptions of the CBOR encoding of types, used to
+//! describe the arguments and return values of endpoints. A schema can be
+//! rendered as CDDL, or used to validate a CBOR value.
+use crate::cbor::{CborAny, CborNull};
+use crate::ledger::TokenAmount;
+use crate::legacy::{DataLegacy, MemoLegacy};
+use crate::{AttributeRelatedIndex, CborRange, Either, Memo, SortOrder, Timestamp, VecOrSingle};
+use many_error::Reason;
+use many_identity::Address;
+use minicbor::bytes::{ByteArray, ByteVec};
+use minicbor::data::Type;
+use minicbor::{Decode, Decoder, Encode};
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt::Write;
+use std::marker::PhantomData;
+
+pub use many_macros::CborSchema;
+
+/// The schema of a CBOR value.
+#[derive(Clone, Debug, Decode, Encode, CborSchema, Eq, PartialEq)]
+pub enum Schema {
+    /// Any value.
+    #[n(0)]
+    Any,
+    #[n(1)]
+    Null,
+    #[n(2)]
+    Bool,
+    #[n(3)]
+    Uint,
+    /// A positive or negative integer.
+    #[n(4)]
+    Int,
+    #[n(5)]
+    Float,
+    #[n(6)]
+    Bytes,
+    #[n(7)]
+    Text,
+    /// A specific unsigned integer, e.g. the index of an enum variant.
+    #[n(8)]
+    Literal(#[n(0)] u64),
+    /// An array of values of the same schema.
+    #[n(9)]
+    Array(#[n(0)] Box<Schema>),
+    /// A map with keys and values of the same schemas.
+    #[n(10)]
+    Map(#[n(0)] Box<Schema>, #[n(1)] Box<Schema>),
+    /// An array of a fixed number of values.
+    #[n(11)]
+    Tuple(#[n(0)] Vec<Schema>),
+    /// A map of fields with integer keys.
+    #[n(12)]
+    Struct(#[n(0)] Vec<Field>),
+    /// One of several schemas.
+    #[n(13)]
+    Choice(#[n(0)] Vec<Schema>),
+    #[n(14)]
+    Tag(#[n(0)] u64, #[n(1)] Box<Schema>),
+    /// A named type. The schema of types that cannot be described is `Any`.
+    #[n(15)]
+    Named(#[n(0)] String, #[n(1)] Box<Schema>),
+    /// A named type described by an enclosing [`Schema::Named`], for
+    /// recursive types.
+    #[n(16)]
+    Ref(#[n(0)] String),
+}
+
+/// A field of a [`Schema::Struct`].
+#[derive(Clone, Debug, Decode, Encode, CborSchema, Eq, PartialEq)]
+#[cbor(map)]
+pub struct Field {
+    #[n(0)]
+    pub key: u64,
+
+    #[n(1)]
+    pub name: String,
+
+    #[n(2)]
+    pub schema: Schema,
+
+    /// Whether the field can be omitted.
+    #[n(3)]
+    pub optional: bool,
+}
+
+impl Field {
+    pub fn new(key: u64, name: impl ToString, schema: Schema) -> Self {
+        Self {
+            key,
+            name: name.to_string(),
+            schema,
+            optional: false,
+        }
+    }
+
+    pub fn optional(self) -> Self {
+        Self {
+            optional: true,
+            ..self
+        }
+    }
+}
+
+/// Implemented by types that can describe their CBOR encoding. Use
+/// `#[derive(CborSchema)]` for types using the `minicbor` derive macros.
+pub trait CborSchema {
+    fn schema() -> Schema;
+}
+
+impl Schema {
+    pub fn named(name: impl ToString, schema: Schema) -> Self {
+        Self::Named(name.to_string(), Box::new(schema))
+    }
+
+    pub fn array(schema: Schema) -> Self {
+        Self::Array(Box::new(schema))
+    }
+
+    pub fn map(key: Schema, value: Schema) -> Self {
+        Self::Map(Box::new(key), Box::new(value))
+    }
+
+    pub fn tag(tag: u64, schema: Schema) -> Self {
+        Self::Tag(tag, Box::new(schema))
+    }
+
+    /// The schema of a named type that can contain itself. Nested uses of
+    /// the type refer to the outer schema instead of describing it again.
+    pub fn recursive(name: &str, schema: impl FnOnce() -> Schema) -> Self {
+        thread_local! {
+            static DESCRIBING: RefCell<BTreeSet<String>> = const { RefCell::new(BTreeSet::new()) };
+        }
+
+        if !DESCRIBING.with(|names| names.borrow_mut().insert(name.to_string())) {
+            return Self::Ref(name.to_string());
+        }
+        let schema = schema();
+        DESCRIBING.with(|names| names.borrow_mut().remove(name));
+        Self::named(name, schema)
+    }
+
+    /// The names of the types in this schema that could not be described,
+    /// i.e. that do not implement [`CborSchema`].
+    pub fn undescribed(&self) -> BTreeSet<&str> {
+        let mut names = BTreeSet::new();
+        self.collect_undescribed(&mut names);
+        names
+    }
+
+    fn collect_undescribed<'a>(&'a self, names: &mut BTreeSet<&'a str>) {
+        match self {
+            Self::Named(name, schema) if **schema == Self::Any => {
+                names.insert(name);
+            }
+            Self::Array(schema) | Self::Tag(_, schema) | Self::Named(_, schema) => {
+                schema.collect_undescribed(names)
+            }
+            Self::Map(key, value) => {
+                key.collect_undescribed(names);
+                value.collect_undescribed(names);
+            }
+            Self::Tuple(schemas) | Self::Choice(schemas) => schemas
+                .iter()
+                .for_each(|schema| schema.collect_undescribed(names)),
+            Self::Struct(fields) => fields
+                .iter()
+                .for_each(|field| field.schema.collect_undescribed(names)),
+            _ => {}
+        }
+    }
+
+    /// Whether `null` is a valid value of this schema.
+    fn is_nullable(&self) -> bool {
+        match self {
+            Self::Any | Self::Null => true,
+            Self::Choice(choices) => choices.iter().any(Schema::is_nullable),
+            Self::Named(_, schema) => schema.is_nullable(),
+            _ => false,
+        }
+    }
+
+    /// CDDL rules describing this schema, starting with a rule named `name`.
+    /// Named types are described in their own rule.
+    pub fn to_cddl(&self, name: &str) -> String {
+        let mut rules = vec![];
+        let root = self.cddl_expr(&mut rules);
+        let mut cddl = format!("{name} = {root}\n");
+        for (name, rule) in rules {
+            let _ = writeln!(cddl, "{name} = {rule}");
+        }
+        cddl
+    }
+
+    fn cddl_expr(&self, rules: &mut Vec<(String, String)>) -> String {
+        match self {
+            Self::Any => "any".to_string(),
+            Self::Null => "null".to_string(),
+            Self::Bool => "bool".to_string(),
+            Self::Uint => "uint".to_string(),
+            Self::Int => "int".to_string(),
+            Self::Float => "float".to_string(),
+            Self::Bytes => "bstr".to_string(),
+            Self::Text => "tstr".to_string(),
+            Self::Literal(value) => value.to_string(),
+            Self::Array(schema) => format!("[* {}]", schema.cddl_expr(rules)),
+            Self::Map(key, value) => {
+                format!(
+                    "{{* {} => {}}}",
+                    key.cddl_expr(rules),
+                    value.cddl_expr(rules)
+                )
+            }
+            Self::Tuple(schemas) => format!(
+                "[{}]",
+                schemas
+                    .iter()
+                    .map(|schema| schema.cddl_expr(rules))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            Self::Struct(fields) if fields.is_empty() => "{}".to_string(),
+            Self::Struct(fields) => {
+                let mut expr = "{\n".to_string();
+                for field in fields {
+                    let _ = writeln!(
+                        expr,
+                        "    {}{} => {},  ; {}",
+                        if field.optional { "? " } else { "" },
+                        field.key,
+                        field.schema.cddl_expr(rules),
+                        field.name
+                    );
+                }
+                expr.push('}');
+                expr
+            }
+            Self::Choice(schemas) => schemas
+                .iter()
+                .map(|schema| schema.cddl_expr(rules))
+                .collect::<Vec<_>>()
+                .join(" / "),
+            Self::Tag(tag, schema) => format!("#6.{tag}({})", schema.cddl_expr(rules)),
+            Self::Named(name, schema) => {
+                let name = rule_name(name);
+                if !rules.iter().any(|(n, _)| n == &name) {
+                    // Reserve the rule first, so rules are listed in order of use.
+                    let index = rules.len();
+                    rules.push((name.clone(), String::new()));
+                    rules[index].1 = schema.cddl_expr(rules);
+                }
+                name
+            }
+            Self::Ref(name) => rule_name(name),
+        }
+    }
+
+    /// Check that `bytes` is a CBOR value of this schema. Unknown fields of
+    /// structs are accepted.
+    pub fn validate(&self, bytes: &[u8]) -> Result<(), String> {
+        let mut d = Decoder::new(bytes);
+        self.check(&mut d, &mut BTreeMap::new())
+            .map_err(|e| e.to_string())?;
+        if d.position() != bytes.len() {
+            return Err("Unexpected data after the value.".to_string());
+        }
+        Ok(())
+    }
+
+    /// Check the value at the position of the decoder. `named` contains the
+    /// enclosing named types, to check references to them.
+    fn check<'a>(
+        &'a self,
+        d: &mut Decoder,
+        named: &mut BTreeMap<&'a str, &'a Schema>,
+    ) -> Result<(), minicbor::decode::Error> {
+        use minicbor::decode::Error;
+
+        let datatype = d.datatype()?;
+        let position = d.position();
+        let mismatch = |expected: &str| {
+            Err(Error::message(format!(
+                "Expected {expected}, found {datatype:?} at position {position}."
+            )))
+        };
+
+        match self {
+            Self::Any => d.skip(),
+            Self::Null => match datatype {
+                Type::Null | Type::Undefined => d.skip(),
+                _ => mismatch("null"),
+            },
+            Self::Bool => d.bool().map(|_| ()),
+            Self::Uint => match datatype {
+                Type::U8 | Type::U16 | Type::U32 | Type::U64 => d.skip(),
+                _ => mismatch("an unsigned integer"),
+            },
+            Self::Int => match datatype {
+                Type::U8 | Type::U16 | Type::U32 | Type::U64 => d.skip(),
+                Type::I8 | Type::I16 | Type::I32 | Type::I64 | Type::Int => d.skip(),
+                _ => mismatch("an integer"),
+            },
+            Self::Float => match datatype {
+                Type::F16 | Type::F32 | Type::F64 => d.skip(),
+                _ => mismatch("a float"),
+            },
+            Self::Bytes => match datatype {
+                Type::Bytes | Type::BytesIndef => d.skip(),
+                _ => mismatch("a byte string"),
+            },
+            Self::Text => match datatype {
+                Type::String | Type::StringIndef => d.skip(),
+                _ => mismatch("a text string"),
+            },
+            Self::Literal(value) => match d.u64() {
+                Ok(x) if x == *value => Ok(()),
+                _ => mismatch(&value.to_string()),
+            },
+            Self::Array(schema) => {
+                let len = d.array()?;
+                check_items(d, len, |d, _| schema.check(d, named))
+            }
+            Self::Map(key, value) => {
+                let len = d.map()?;
+                check_items(d, len, |d, _| {
+                    key.check(d, named)?;
+                    value.check(d, named)
+                })
+            }
+            Self::Tuple(schemas) => {
+                let len = d.array()?;
+                let mut count = 0;
+                check_items(d, len, |d, i| {
+                    count += 1;
+                    match schemas.get(i) {
+                        Some(schema) => schema.check(d, named),
+                        None => Err(Error::message(format!(
+                            "Expected an array of {} values.",
+                            schemas.len()
+                        ))),
+                    }
+                })?;
+                // Trailing nullable values can be omitted.
+                match schemas[count.min(schemas.len())..]
+                    .iter()
+                    .all(Schema::is_nullable)
+                {
+                    true => Ok(()),
+                    false => Err(Error::message(format!(
+                        "Expected an array of {} values.",
+                        schemas.len()
+                    ))),
+                }
+            }
+            Self::Struct(fields) => {
+                let len = d.map()?;
+                let mut found = BTreeSet::new();
+                check_items(d, len, |d, _| {
+                    let field = match d.datatype()? {
+                        Type::U8 | Type::U16 | Type::U32 | Type::U64 => {
+                            let key = d.u64()?;
+                            found.insert(key);
+                            fields.iter().find(|f| f.key == key)
+                        }
+                        _ => {
+                            d.skip()?;
+                            None
+                        }
+                    };
+                    match field {
+                        Some(field) => field.schema.check(d, named).map_err(|e| {
+                            Error::message(format!("Invalid field '{}': {e}", field.name))
+                        }),
+                        None => d.skip(),
+                    }
+                })?;
+                match fields
+                    .iter()
+                    .find(|f| !f.optional && !found.contains(&f.key))
+                {
+                    Some(field) => Err(Error::message(format!(
+                        "Missing field '{}' ({}).",
+                        field.name, field.key
+                    ))),
+                    None => Ok(()),
+                }
+            }
+            Self::Choice(schemas) => {
+                for schema in schemas {
+                    let mut attempt = d.clone();
+                    if schema.check(&mut attempt, named).is_ok() {
+                        d.set_position(attempt.position());
+                        return Ok(());
+                    }
+                }
+                mismatch(&format!("one of {}", self.cddl_expr(&mut vec![])))
+            }
+            Self::Tag(tag, schema) => match datatype {
+                Type::Tag if tag_number(d)? == *tag => schema.check(d, named),
+                _ => mismatch(&format!("tag {tag}")),
+            },
+            Self::Named(name, schema) => {
+                named.insert(name, schema);
+                schema
+                    .check(d, named)
+                    .map_err(|e| Error::message(format!("Invalid {name}: {e}")))
+            }
+            Self::Ref(name) => match named.get(name.as_str()).copied() {
+                Some(schema) => schema
+                    .check(d, named)
+                    .map_err(|e| Error::message(format!("Invalid {name}: {e}"))),
+                None => Err(Error::message(format!("Unknown type {name}."))),
+            },
+        }
+    }
+}
+
+/// Check the items of an array or map of length `len` (or indefinite length).
+fn check_items<'b>(
+    d: &mut Decoder<'b>,
+    len: Option<u64>,
+    mut check: impl FnMut(&mut Decoder<'b>, usize) -> Result<(), minicbor::decode::Error>,
+) -> Result<(), minicbor::decode::Error> {
+    match len {
+        Some(len) => (0..len as usize).try_for_each(|i| check(d, i)),
+        None => {
+            let mut i = 0;
+            while d.datatype()? != Type::Break {
+                check(d, i)?;
+                i += 1;
+            }
+            d.skip()
+        }
+    }
+}
+
+/// Read the number of the tag at the position of the decoder.
+fn tag_number(d: &mut Decoder) -> Result<u64, minicbor::decode::Error> {
+    let start = d.position();
+    let input = &d.input()[start..];
+    // The header of a tag has the same encoding as an unsigned integer,
+    // with major type 6.
+    let mut header = input[..input.len().min(9)].to_vec();
+    header[0] &= 0x1f;
+    let mut header = Decoder::new(&header);
+    let tag = header.u64()?;
+    d.set_position(start + header.position());
+    Ok(tag)
+}
+
+/// The name of a CDDL rule for a Rust type name, e.g. `token-info` for
+/// `TokenInfo`.
+fn rule_name(name: &str) -> String {
+    let mut rule = String::new();
+    for (i, c) in name.chars().enumerate() {
+        if c.is_ascii_uppercase() {
+            if i > 0 && !rule.ends_with('-') {
+                rule.push('-');
+            }
+            rule.push(c.to_ascii_lowercase());
+        } else if c.is_ascii_alphanumeric() {
+            rule.push(c);
+        } else if !rule.is_empty() && !rule.ends_with('-') {
+            rule.push('-');
+        }
+    }
+    rule.trim_end_matches('-').to_string()
+}
+
+/// The schema of a type if it implements [`CborSchema`], or a named `Any`
+/// otherwise.
+#[macro_export]
+macro_rules! schema_of {
+    ($ty: ty) => {{
+        #[allow(unused_imports)]
+        use $crate::schema::{DescribedSchema as _, UndescribedSchema as _};
+        (&$crate::schema::Probe::<$ty>::new()).probe_schema(stringify!($ty))
+    }};
+}
+
+/// Used by [`schema_of!`] to fall back to `Any` for types that do not
+/// implement [`CborSchema`].
+#[doc(hidden)]
+pub struct Probe<T: ?Sized>(PhantomData<T>);
+
+impl<T: ?Sized> Probe<T> {
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        Self(PhantomData)
+    }
+}
+
+#[doc(hidden)]
+pub trait DescribedSchema {
+    fn probe_schema(&self, name: &str) -> Schema;
+}
+
+impl<T: CborSchema + ?Sized> DescribedSchema for Probe<T> {
+    fn probe_schema(&self, _name: &str) -> Schema {
+        T::schema()
+    }
+}
+
+#[doc(hidden)]
+pub trait UndescribedSchema {
+    fn probe_schema(&self, name: &str) -> Schema;
+}
+
+impl<T: ?Sized> UndescribedSchema for &Probe<T> {
+    fn probe_schema(&self, name: &str) -> Schema {
+        Schema::named(name.replace(' ', ""), Schema::Any)
+    }
+}
+
+macro_rules! primitive_schema {
+    ( $( $schema: ident => $( $ty: ty ),+; )* ) => {
+        $( $(
+            impl CborSchema for $ty {
+                fn schema() -> Schema {
+                    Schema::$schema
+                }
+            }
+        )+ )*
+    };
+}
+
+primitive_schema!(
+    Null => ();
+    Bool => bool;
+    Uint => u8, u16, u32, u64, usize;
+    Int => i8, i16, i32, i64, isize;
+    Float => f32, f64;
+    Bytes => ByteVec;
+    Text => String, str;
+);
+
+impl<const N: usize> CborSchema for ByteArray<N> {
+    fn schema() -> Schema {
+        Schema::Bytes
+    }
+}
+
+impl<T: CborSchema + ?Sized> CborSchema for &T {
+    fn schema() -> Schema {
+        T::schema()
+    }
+}
+
+impl<T: CborSchema + ?Sized> CborSchema for Box<T> {
+    fn schema() -> Schema {
+        T::schema()
+    }
+}
+
+impl<T: CborSchema> CborSchema for Option<T> {
+    fn schema() -> Schema {
+        Schema::Choice(vec![T::schema(), Schema::Null])
+    }
+}
+
+impl<T: CborSchema> CborSchema for Vec<T> {
+    fn schema() -> Schema {
+        Schema::array(T::schema())
+    }
+}
+
+impl<T: CborSchema> CborSchema for BTreeSet<T> {
+    fn schema() -> Schema {
+        Schema::array(T::schema())
+    }
+}
+
+impl<K: CborSchema, V: CborSchema> CborSchema for BTreeMap<K, V> {
+    fn schema() -> Schema {
+        Schema::map(K::schema(), V::schema())
+    }
+}
+
+impl<K: CborSchema, V: CborSchema> CborSchema for HashMap<K, V> {
+    fn schema() -> Schema {
+        Schema::map(K::schema(), V::schema())
+    }
+}
+
+impl CborSchema for CborAny {
+    fn schema() -> Schema {
+        Schema::Any
+    }
+}
+
+impl CborSchema for CborNull {
+    fn schema() -> Schema {
+        Schema::Null
+    }
+}
+
+impl<L: CborSchema, R: CborSchema> CborSchema for Either<L, R> {
+    fn schema() -> Schema {
+        Schema::Choice(vec![L::schema(), R::schema()])
+    }
+}
+
+impl<T: CborSchema> CborSchema for Reason<T> {
+    fn schema() -> Schema {
+        Schema::named(
+            "Reason",
+            Schema::Struct(vec![
+                Field::new(0, "code", T::schema()),
+                Field::new(1, "message", Schema::Text).optional(),
+                Field::new(2, "arguments", Schema::map(Schema::Text, Schema::Text)).optional(),
+            ]),
+        )
+    }
+}
+
+impl CborSchema for Address {
+    fn schema() -> Schema {
+        Schema::named("Address", Schema::tag(10000, Schema::Bytes))
+    }
+}
+
+impl CborSchema for Timestamp {
+    fn schema() -> Schema {
+        Schema::named("Timestamp", Schema::tag(1, Schema::Uint))
+    }
+}
+
+impl CborSchema for TokenAmount {
+    fn schema() -> Schema {
+        Schema::named(
+            "TokenAmount",
+            Schema::Choice(vec![Schema::Uint, Schema::tag(2, Schema::Bytes)]),
+        )
+    }
+}
+
+impl CborSchema for SortOrder {
+    fn schema() -> Schema {
+        Schema::named(
+            "SortOrder",
+            Schema::Choice(vec![
+                Schema::Literal(0),
+                Schema::Literal(1),
+                Schema::Literal(2),
+            ]),
+        )
+    }
+}
+
+impl<const M: usize> CborSchema for Memo<M> {
+    fn schema() -> Schema {
+        Schema::named(
+            "Memo",
+            Schema::array(Schema::Choice(vec![Schema::Text, Schema::Bytes])),
+        )
+    }
+}
+
+impl<S: AsRef<str>> CborSchema for MemoLegacy<S> {
+    fn schema() -> Schema {
+        Schema::named("MemoLegacy", Schema::Text)
+    }
+}
+
+impl CborSchema for DataLegacy {
+    fn schema() -> Schema {
+        Schema::named("DataLegacy", Schema::Bytes)
+    }
+}
+
+impl<T: CborSchema> CborSchema for VecOrSingle<T> {
+    fn schema() -> Schema {
+        Schema::Choice(vec![T::schema(), Schema::array(T::schema())])
+    }
+}
+
+impl CborSchema for AttributeRelatedIndex {
+    fn schema() -> Schema {
+        Schema::named(
+            "AttributeRelatedIndex",
+            Schema::Choice(vec![
+                Schema::Uint,
+                Schema::Tuple(vec![Schema::Uint, Schema::Any]),
+            ]),
+        )
+    }
+}
+
+impl<T: CborSchema> CborSchema for CborRange<T> {
+    fn schema() -> Schema {
+        let bound = Schema::Choice(vec![
+            Schema::Tuple(vec![Schema::Literal(0), T::schema()]),
+            Schema::Tuple(vec![Schema::Literal(1), T::schema()]),
+            Schema::Tuple(vec![]),
+        ]);
+        Schema::named(
+            "CborRange",
+            Schema::Struct(vec![
+                Field::new(0, "start", bound.clone()).optional(),
+                Field::new(1, "end", bound).optional(),
+            ]),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(CborSchema, Decode, Encode)]
+    #[cbor(map)]
+    struct Args {
+        #[n(0)]
+        account: Option<Address>,
+
+        #[n(1)]
+        amount: TokenAmount,
+
+        #[n(3)]
+        memo: Vec<String>,
+    }
+
+    #[derive(CborSchema, Decode, Encode)]
+    #[cbor(index_only)]
+    enum Kind {
+        #[n(0)]
+        A,
+        #[n(2)]
+        B,
+    }
+
+    #[derive(CborSchema, Decode, Encode)]
+    #[cbor(map)]
+    struct Hashes {
+        #[cbor(n(0), with = "minicbor::bytes")]
+        hash: Vec<u8>,
+
+        #[cbor(n(1), with = "minicbor::bytes")]
+        parent: Option<Vec<u8>>,
+    }
+
+    #[derive(CborSchema, Decode, Encode)]
+    #[cbor(map)]
+    enum Filter {
+        #[n(0)]
+        Owner(#[n(0)] Address),
+
+        #[n(1)]
+        #[cbor(array)]
+        Range(#[n(0)] u64, #[n(1)] u64),
+    }
+
+    #[derive(CborSchema, Decode, Encode)]
+    struct Pair(#[n(0)] Kind, #[n(1)] Option<u32>);
+
+    #[test]
+    fn derive() {
+        assert_eq!(
+            Args::schema(),
+            Schema::named(
+                "Args",
+                Schema::Struct(vec![
+                    Field::new(0, "account", Address::schema()).optional(),
+                    Field::new(1, "amount", TokenAmount::schema()),
+                    Field::new(3, "memo", Schema::array(Schema::Text)),
+                ])
+            )
+        );
+        assert_eq!(
+            Kind::schema(),
+            Schema::named(
+                "Kind",
+                Schema::Choice(vec![Schema::Literal(0), Schema::Literal(2)])
+            )
+        );
+        assert_eq!(
+            Hashes::schema(),
+            Schema::named(
+                "Hashes",
+                Schema::Struct(vec![
+                    Field::new(0, "hash", Schema::Bytes),
+                    Field::new(1, "parent", Schema::Bytes).optional(),
+                ])
+            )
+        );
+        assert_eq!(
+            Filter::schema(),
+            Schema::named(
+                "Filter",
+                Schema::Choice(vec![
+                    Schema::Tuple(vec![
+                        Schema::Literal(0),
+                        Schema::Struct(vec![Field::new(0, "0", Address::schema())]),
+                    ]),
+                    Schema::Tuple(vec![
+                        Schema::Literal(1),
+                        Schema::Tuple(vec![Schema::Uint, Schema::Uint]),
+                    ]),
+                ])
+            )
+        );
+        assert_eq!(
+            Pair::schema(),
+            Schema::named(
+                "Pair",
+                Schema::Tuple(vec![Kind::schema(), Option::<u32>::schema()])
+            )
+        );
+    }
+
+    #[test]
+    fn fallback() {
+        struct Unknown;
+        assert_eq!(
+            crate::schema_of!(Unknown),
+            Schema::named("Unknown", Schema::Any)
+        );
+        assert_eq!(crate::schema_of!(Vec<u8>), Schema::array(Schema::Uint));
+        assert_eq!(
+            Schema::array(crate::schema_of!(Unknown)).undescribed(),
+            BTreeSet::from(["Unknown"])
+        );
+        assert!(Args::schema().undescribed().is_empty());
+    }
+
+    /// A type containing itself.
+    #[derive(CborSchema, Decode, Encode)]
+    #[cbor(transparent)]
+    struct Tree(#[n(0)] Vec<Tree>);
+
+    #[test]
+    fn recursive() {
+        let schema = Tree::schema();
+        assert_eq!(
+            schema,
+            Schema::named("Tree", Schema::array(Schema::Ref("Tree".to_string())))
+        );
+        assert_eq!(schema.to_cddl("root"), "root = tree\ntree = [* tree]\n");
+
+        let valid = |diag: &str| schema.validate(&cbor_diag::parse_diag(diag).unwrap().to_bytes());
+        assert!(valid("[[], [[]]]").is_ok());
+        assert!(valid("[[1]]").is_err());
+
+        assert!(Schema::schema().undescribed().is_empty());
+    }
+
+    #[test]
+    fn cddl() {
+        assert_eq!(
+            Args::schema().to_cddl("send-args"),
+            "send-args = args\n\
+             args = {\n    \
+                 ? 0 => address,  ; account\n    \
+                 1 => token-amount,  ; amount\n    \
+                 3 => [* tstr],  ; memo\n\
+             }\n\
+             address = #6.10000(bstr)\n\
+             token-amount = uint / #6.2(bstr)\n"
+        );
+    }
+
+    #[test]
+    fn validate() {
+        let schema = Args::schema();
+        let valid = |diag: &str| schema.validate(&cbor_diag::parse_diag(diag).unwrap().to_bytes());
+
+        assert!(valid(r#"{ 1: 10, 3: ["a", "b"] }"#).is_ok());
+        assert!(valid(r#"{ 0: 10000(h'00'), 1: 2(h'0100'), 3: [], 4: "unknown" }"#).is_ok());
+        assert!(valid(r#"{ 3: [] }"#).unwrap_err().contains("amount"));
+        assert!(valid(r#"{ 1: "10", 3: [] }"#)
+            .unwrap_err()
+            .contains("amount"));
+        assert!(valid(r#"{ 0: h'00', 1: 10, 3: [] }"#).is_err());
+        assert!(valid(r#"[10]"#).is_err());
+
+        let pair = Pair::schema();
+        let valid = |diag: &str| pair.validate(&cbor_diag::parse_diag(diag).unwrap().to_bytes());
+        assert!(valid("[2, 1]").is_ok());
+        assert!(valid("[0]").is_ok());
+        assert!(valid("[1, 1]").is_err());
+        assert!(valid("[0, 1, 2]").is_err());
+    }
+}
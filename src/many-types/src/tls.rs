@@ -0,0 +1,29 @@
+//! Reading PEM encoded certificates and private keys, for TLS configurations.
+use many_error::ManyError;
+use rustls::{Certificate, PrivateKey};
+
+/// Read all the certificates of a PEM bundle.
+pub fn certificates_from_pem(pem: &[u8]) -> Result<Vec<Certificate>, ManyError> {
+    let certificates = rustls_pemfile::certs(&mut &pem[..])
+        .map_err(ManyError::unknown)?
+        .into_iter()
+        .map(Certificate)
+        .collect::<Vec<_>>();
+    if certificates.is_empty() {
+        return Err(ManyError::unknown("No certificate found in PEM."));
+    }
+    Ok(certificates)
+}
+
+/// Read the first private key (PKCS#8, PKCS#1 or SEC1) of a PEM file.
+pub fn private_key_from_pem(pem: &[u8]) -> Result<PrivateKey, ManyError> {
+    for item in rustls_pemfile::read_all(&mut &pem[..]).map_err(ManyError::unknown)? {
+        match item {
+            rustls_pemfile::Item::PKCS8Key(key)
+            | rustls_pemfile::Item::RSAKey(key)
+            | rustls_pemfile::Item::ECKey(key) => return Ok(PrivateKey(key)),
+            _ => {}
+        }
+    }
+    Err(ManyError::unknown("No private key found in PEM."))
+}
@@ -1,3 +1,4 @@
+use crate::schema::CborSchema;
 use many_error::ManyError;
 use many_identity::Address;
 use minicbor::bytes::ByteVec;
@@ -5,7 +6,7 @@ use minicbor::{Decode, Encode};
 use std::str::FromStr;
 use strum::Display;
 
-#[derive(Clone, Debug, Decode, Display, Encode, Eq, PartialEq)]
+#[derive(Clone, Debug, Decode, Display, Encode, CborSchema, Eq, PartialEq)]
 #[cbor(map)]
 pub enum WebDeploymentFilter {
     #[n(0)]
@@ -27,7 +28,7 @@ impl FromStr for WebDeploymentFilter {
     }
 }
 
-#[derive(Clone, Debug, Eq, PartialEq, Encode, Decode)]
+#[derive(Clone, Debug, Eq, PartialEq, Encode, Decode, CborSchema)]
 #[cbor(map)]
 pub struct WebDeploymentInfo {
     #[n(0)]
@@ -46,7 +47,7 @@ pub struct WebDeploymentInfo {
     pub domain: Option<String>,
 }
 
-#[derive(Clone, Debug, Encode, Decode, Display, Eq, PartialEq)]
+#[derive(Clone, Debug, Encode, Decode, CborSchema, Display, Eq, PartialEq)]
 #[cbor(map)]
 pub enum WebDeploymentSource {
     #[n(0)]
@@ -0,0 +1,71 @@
+use crate::js_error;
+use std::str::FromStr;
+use wasm_bindgen::prelude::*;
+
+/// A MANY address, e.g. `maffbahksdwaqeenayy2gxke32hgb7aq4ao4wt745lsfs6wijp`.
+#[wasm_bindgen]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Address(pub(crate) many_identity::Address);
+
+#[wasm_bindgen]
+impl Address {
+    /// Parse the textual form of an address.
+    #[wasm_bindgen(constructor)]
+    pub fn new(address: &str) -> Result<Address, JsError> {
+        many_identity::Address::from_str(address)
+            .map(Self)
+            .map_err(js_error)
+    }
+
+    pub fn anonymous() -> Address {
+        Self(many_identity::Address::anonymous())
+    }
+
+    #[wasm_bindgen(js_name = fromBytes)]
+    pub fn from_bytes(bytes: &[u8]) -> Result<Address, JsError> {
+        many_identity::Address::from_bytes(bytes)
+            .map(Self)
+            .map_err(js_error)
+    }
+
+    #[wasm_bindgen(js_name = toBytes)]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.0.to_vec()
+    }
+
+    #[wasm_bindgen(js_name = toString)]
+    pub fn to_string_js(&self) -> String {
+        self.0.to_string()
+    }
+
+    #[wasm_bindgen(js_name = isAnonymous)]
+    pub fn is_anonymous(&self) -> bool {
+        self.0.is_anonymous()
+    }
+
+    #[wasm_bindgen(js_name = isPublicKey)]
+    pub fn is_public_key(&self) -> bool {
+        self.0.is_public_key()
+    }
+
+    #[wasm_bindgen(js_name = subresourceId)]
+    pub fn subresource_id(&self) -> Option<u32> {
+        self.0.subresource_id()
+    }
+
+    /// The address of a subresource of this address' public key.
+    #[wasm_bindgen(js_name = withSubresourceId)]
+    pub fn with_subresource_id(&self, id: u32) -> Result<Address, JsError> {
+        self.0.with_subresource_id(id).map(Self).map_err(js_error)
+    }
+
+    /// Whether both addresses belong to the same public key, ignoring their
+    /// subresource IDs.
+    pub fn matches(&self, other: &Address) -> bool {
+        self.0.matches(&other.0)
+    }
+
+    pub fn equals(&self, other: &Address) -> bool {
+        self == other
+    }
+}
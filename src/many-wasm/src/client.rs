@@ -0,0 +1,171 @@
+use crate::address::Address;
+use crate::identity::Identity;
+use crate::{object, promise};
+use coset::{CborSerializable, CoseSign1, TaggedCborSerializable};
+use js_sys::{Array, Promise, Uint8Array};
+use many_client::client::ledger::{BalanceArgs, LedgerClient, SendArgs, TokenAmount};
+use many_client::ManyClient;
+use many_error::ManyError;
+use many_identity::verifiers::AnonymousVerifier;
+use many_identity::AnonymousIdentity;
+use many_identity_dsa::CoseKeyVerifier;
+use many_protocol::ResponseMessage;
+use num_bigint::BigUint;
+use std::str::FromStr;
+use wasm_bindgen::prelude::*;
+
+type Inner = ManyClient<Box<dyn many_identity::Identity>>;
+
+/// A client of a MANY server, using the `fetch` API of the browser. Methods
+/// return promises, and wait for the final response of async requests.
+#[wasm_bindgen]
+#[derive(Clone)]
+pub struct Client(Inner);
+
+#[wasm_bindgen]
+impl Client {
+    /// Create a client of the server at `url`, whose address is `to`, which
+    /// signs requests with `identity`.
+    #[wasm_bindgen(constructor)]
+    pub fn new(url: &str, to: &Address, identity: &Identity) -> Result<Client, JsError> {
+        Inner::new(url, to.0, Box::new(identity.inner.clone()))
+            .map(Self)
+            .map_err(|e| JsError::new(&e))
+    }
+
+    /// Create a client which sends anonymous requests.
+    pub fn anonymous(url: &str, to: &Address) -> Result<Client, JsError> {
+        Inner::new(url, to.0, Box::new(AnonymousIdentity))
+            .map(Self)
+            .map_err(|e| JsError::new(&e))
+    }
+
+    /// Call a method with a CBOR encoded argument, and resolve to the CBOR
+    /// encoded result.
+    pub fn call(&self, method: String, argument: Vec<u8>) -> Promise {
+        let client = self.0.clone();
+        promise(async move {
+            let response = client.call_raw(method, &argument).await?;
+            let data = client.wait_response(response).await?.data?;
+            Ok(Uint8Array::from(data.as_slice()).into())
+        })
+    }
+
+    /// Send an envelope signed with `Identity.sign` or `Identity.signRequest`,
+    /// and resolve to the CBOR encoded result.
+    #[wasm_bindgen(js_name = sendEnvelope)]
+    pub fn send_envelope(&self, envelope: Vec<u8>) -> Promise {
+        let client = self.0.clone();
+        promise(async move {
+            let envelope = CoseSign1::from_tagged_slice(&envelope)
+                .or_else(|_| CoseSign1::from_slice(&envelope))
+                .map_err(|e| ManyError::deserialization_error(e.to_string()))?;
+            let response = client.send_envelope(envelope).await?;
+            let response = ResponseMessage::decode_and_verify(
+                &response,
+                &(AnonymousVerifier, CoseKeyVerifier),
+            )?;
+            let data = client.wait_response(response).await?.data?;
+            Ok(Uint8Array::from(data.as_slice()).into())
+        })
+    }
+
+    /// Resolve to the status of the server, as an object with its `name`,
+    /// `version`, `address`, `serverVersion`, `timeout` and `attributes`.
+    pub fn status(&self) -> Promise {
+        let client = self.0.clone();
+        promise(async move {
+            let status = client.status().await?;
+            let attributes = status
+                .attributes
+                .iter()
+                .map(|attribute| JsValue::from(attribute.id))
+                .collect::<Array>();
+            Ok(object([
+                ("name", status.name.into()),
+                ("version", status.version.into()),
+                ("address", Address(status.identity).into()),
+                (
+                    "serverVersion",
+                    status.server_version.map_or(JsValue::NULL, JsValue::from),
+                ),
+                (
+                    "timeout",
+                    status
+                        .timeout
+                        .map_or(JsValue::NULL, |t| JsValue::from(t as f64)),
+                ),
+                ("attributes", attributes.into()),
+            ]))
+        })
+    }
+
+    /// Resolve to the tokens of the ledger, as an object mapping their symbol
+    /// to their `name`, `ticker` and `decimals`.
+    #[wasm_bindgen(js_name = ledgerInfo)]
+    pub fn ledger_info(&self) -> Promise {
+        let client = LedgerClient::new(self.0.clone());
+        promise(async move {
+            let info = client.info().await?;
+            Ok(object(info.tokens.into_iter().map(|(symbol, token)| {
+                (
+                    symbol.to_string(),
+                    object([
+                        ("name", token.name.into()),
+                        ("ticker", token.ticker.into()),
+                        ("decimals", (token.decimals as f64).into()),
+                    ]),
+                )
+            })))
+        })
+    }
+
+    /// Resolve to the balances of `account`, as an object mapping symbols to
+    /// amounts in decimal strings.
+    #[wasm_bindgen(js_name = ledgerBalance)]
+    pub fn ledger_balance(&self, account: &Address) -> Promise {
+        let client = LedgerClient::new(self.0.clone());
+        let account = account.0;
+        promise(async move {
+            let balances = client
+                .balance(BalanceArgs {
+                    account: Some(account),
+                    symbols: None,
+                    height: None,
+                })
+                .await?
+                .balances;
+            Ok(object(balances.into_iter().map(|(symbol, amount)| {
+                (symbol.to_string(), amount.to_string().into())
+            })))
+        })
+    }
+
+    /// Send `amount` (a decimal string) of the token `symbol` from the
+    /// identity of the client to `to`.
+    #[wasm_bindgen(js_name = ledgerSend)]
+    pub fn ledger_send(
+        &self,
+        to: &Address,
+        amount: String,
+        symbol: &Address,
+        memo: Option<String>,
+    ) -> Promise {
+        let client = LedgerClient::new(self.0.clone());
+        let (to, symbol) = (to.0, symbol.0);
+        promise(async move {
+            let amount = BigUint::from_str(&amount)
+                .map_err(|e| ManyError::unknown(format!("Invalid amount: {e}")))?;
+            client
+                .send(SendArgs {
+                    from: None,
+                    to,
+                    amount: TokenAmount::from(amount),
+                    symbol,
+                    memo: memo.map(TryInto::try_into).transpose()?,
+                })
+                .await?;
+            Ok(JsValue::UNDEFINED)
+        })
+    }
+}
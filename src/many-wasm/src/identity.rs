@@ -0,0 +1,190 @@
+use crate::address::Address;
+use crate::js_error;
+use coset::TaggedCborSerializable;
+use many_error::ManyError;
+use many_identity::Identity as _;
+use many_identity_dsa::hd::{self, DerivationPath, HdKeyAlgorithm, HdSeed};
+use many_identity_dsa::{encryption, CoseKeyIdentity};
+use many_protocol::{RequestMessage, RequestMessageBuilder};
+use many_types::Timestamp;
+use std::str::FromStr;
+use wasm_bindgen::prelude::*;
+
+/// Generate a BIP-39 mnemonic of 12, 15, 18, 21 or 24 words.
+#[wasm_bindgen(js_name = generateMnemonic)]
+pub fn generate_mnemonic(word_count: usize) -> Result<String, JsError> {
+    hd::generate_mnemonic(word_count).map_err(js_error)
+}
+
+/// A private key, which signs requests. Wallets store it with `toEncryptedPem`
+/// and load it back with `fromEncryptedPem`.
+#[wasm_bindgen]
+#[derive(Clone)]
+pub struct Identity {
+    pem: String,
+    pub(crate) inner: CoseKeyIdentity,
+}
+
+#[wasm_bindgen]
+impl Identity {
+    /// Generate a new random Ed25519 key.
+    pub fn generate() -> Result<Identity, JsError> {
+        many_identity_dsa::ed25519::generate_ed25519_pem()
+            .and_then(Self::from_pem_string)
+            .map_err(js_error)
+    }
+
+    /// Load a PKCS#8 PEM private key.
+    #[wasm_bindgen(js_name = fromPem)]
+    pub fn from_pem(pem: String) -> Result<Identity, JsError> {
+        Self::from_pem_string(pem).map_err(js_error)
+    }
+
+    /// Load a password-encrypted PKCS#8 PEM private key.
+    #[wasm_bindgen(js_name = fromEncryptedPem)]
+    pub fn from_encrypted_pem(pem: &str, password: &str) -> Result<Identity, JsError> {
+        encryption::decrypt_pem(pem, password)
+            .and_then(Self::from_pem_string)
+            .map_err(js_error)
+    }
+
+    /// Derive the key at `path` (by default `m/0'`) from a mnemonic. The
+    /// algorithm is `ed25519` or `ecdsa`.
+    #[wasm_bindgen(js_name = fromMnemonic)]
+    pub fn from_mnemonic(
+        phrase: &str,
+        passphrase: &str,
+        algorithm: &str,
+        path: Option<String>,
+    ) -> Result<Identity, JsError> {
+        let algorithm = HdKeyAlgorithm::from_str(algorithm).map_err(js_error)?;
+        let path = DerivationPath::from_str(path.as_deref().unwrap_or(hd::DEFAULT_DERIVATION_PATH))
+            .map_err(js_error)?;
+        HdSeed::from_mnemonic(phrase, passphrase)
+            .and_then(|seed| seed.pem(algorithm, &path))
+            .and_then(Self::from_pem_string)
+            .map_err(js_error)
+    }
+
+    /// The PKCS#8 PEM of the private key, unencrypted.
+    #[wasm_bindgen(js_name = toPem)]
+    pub fn to_pem(&self) -> String {
+        self.pem.clone()
+    }
+
+    /// The PKCS#8 PEM of the private key, encrypted with a password.
+    #[wasm_bindgen(js_name = toEncryptedPem)]
+    pub fn to_encrypted_pem(&self, password: &str) -> Result<String, JsError> {
+        encryption::encrypt_pem(&self.pem, password).map_err(js_error)
+    }
+
+    pub fn address(&self) -> Address {
+        Address(self.inner.address())
+    }
+
+    /// The CBOR encoded COSE public key.
+    #[wasm_bindgen(js_name = publicKey)]
+    pub fn public_key(&self) -> Result<Vec<u8>, JsError> {
+        let key = self
+            .inner
+            .public_key()
+            .ok_or_else(|| JsError::new("The identity has no public key."))?;
+        coset::CborSerializable::to_vec(key).map_err(|e| JsError::new(&e.to_string()))
+    }
+
+    /// Sign a CBOR encoded request, e.g. one created by `many request create`,
+    /// and return the tagged COSE envelope. Servers reject requests whose
+    /// timestamp is too old, so unless `keepTimestamp` is set the request is
+    /// timestamped now.
+    #[wasm_bindgen(js_name = signRequest)]
+    pub fn sign_request(&self, request: &[u8], keep_timestamp: bool) -> Result<Vec<u8>, JsError> {
+        let request = RequestMessage::from_bytes(request).map_err(|e| JsError::new(&e))?;
+        self.sign_request_message(request, keep_timestamp)
+            .map_err(js_error)
+    }
+
+    /// Create and sign a request to `to`, with a CBOR encoded argument, and
+    /// return the tagged COSE envelope.
+    pub fn sign(
+        &self,
+        to: &Address,
+        method: String,
+        argument: Vec<u8>,
+    ) -> Result<Vec<u8>, JsError> {
+        self.request(to.0, method, argument)
+            .and_then(|request| self.sign_request_message(request, true))
+            .map_err(js_error)
+    }
+}
+
+impl Identity {
+    fn from_pem_string(pem: String) -> Result<Self, ManyError> {
+        let inner = CoseKeyIdentity::from_pem(&pem)?;
+        Ok(Self { pem, inner })
+    }
+
+    /// Create a request from this identity, with a random nonce.
+    fn request(
+        &self,
+        to: many_identity::Address,
+        method: String,
+        argument: Vec<u8>,
+    ) -> Result<RequestMessage, ManyError> {
+        let mut nonce = [0u8; 16];
+        rand::RngCore::fill_bytes(&mut rand::thread_rng(), &mut nonce);
+
+        RequestMessageBuilder::default()
+            .version(1)
+            .from(self.inner.address())
+            .to(to)
+            .method(method)
+            .data(argument)
+            .nonce(nonce.to_vec())
+            .timestamp(Timestamp::now())
+            .build()
+            .map_err(|e| ManyError::unknown(format!("Could not build request: {e}")))
+    }
+
+    /// Sign a request, as coming from this identity if it is anonymous.
+    fn sign_request_message(
+        &self,
+        request: RequestMessage,
+        keep_timestamp: bool,
+    ) -> Result<Vec<u8>, ManyError> {
+        many_protocol::sign_request(request, &self.inner, keep_timestamp)?
+            .to_tagged_vec()
+            .map_err(|e| ManyError::serialization_error(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use coset::CoseSign1;
+    use many_identity::verifiers::AnonymousVerifier;
+    use many_identity_dsa::CoseKeyVerifier;
+
+    #[test]
+    fn sign() {
+        let pem = many_identity_dsa::ed25519::generate_ed25519_pem().unwrap();
+        let identity = Identity::from_pem_string(pem).unwrap();
+        let request = identity
+            .request(
+                many_identity::Address::anonymous(),
+                "ledger.info".to_string(),
+                vec![0xA0],
+            )
+            .unwrap();
+        let envelope = identity.sign_request_message(request, true).unwrap();
+
+        let envelope = CoseSign1::from_tagged_slice(&envelope).unwrap();
+        let request = many_protocol::decode_request_from_cose_sign1(
+            &envelope,
+            &(AnonymousVerifier, CoseKeyVerifier),
+        )
+        .unwrap();
+        assert_eq!(request.from, Some(identity.inner.address()));
+        assert_eq!(request.method, "ledger.info");
+        assert_eq!(request.data, vec![0xA0]);
+    }
+}
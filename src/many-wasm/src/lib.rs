@@ -0,0 +1,38 @@
+//! JavaScript bindings of the MANY identities and client, for browser wallets.
+//!
+//! Build with `wasm-pack build --target web src/many-wasm`. Addresses and
+//! identities are classes; binary values (CBOR arguments and responses, COSE
+//! envelopes) are `Uint8Array`s; token amounts are decimal strings, as they
+//! can exceed the range of JavaScript numbers.
+use many_error::ManyError;
+use std::future::Future;
+use wasm_bindgen::prelude::*;
+
+mod address;
+mod client;
+mod identity;
+
+pub use address::Address;
+pub use client::Client;
+pub use identity::{generate_mnemonic, Identity};
+
+fn js_error(error: ManyError) -> JsError {
+    JsError::new(&error.to_string())
+}
+
+/// Create a plain JavaScript object from its entries.
+fn object<K: AsRef<str>>(entries: impl IntoIterator<Item = (K, JsValue)>) -> JsValue {
+    let object = js_sys::Object::new();
+    for (key, value) in entries {
+        js_sys::Reflect::set(&object, &key.as_ref().into(), &value).expect("Object is extensible");
+    }
+    object.into()
+}
+
+/// Run a future in the event loop of the browser, and return a promise of its
+/// result.
+fn promise(future: impl Future<Output = Result<JsValue, ManyError>> + 'static) -> js_sys::Promise {
+    wasm_bindgen_futures::future_to_promise(
+        async move { future.await.map_err(|e| js_error(e).into()) },
+    )
+}
@@ -0,0 +1,58 @@
+//! Smoke tests of the bindings, run in a browser with `wasm-pack test
+//! --headless --firefox src/many-wasm`.
+#![cfg(target_arch = "wasm32")]
+use coset::{CoseSign1, TaggedCborSerializable};
+use many_identity::verifiers::AnonymousVerifier;
+use many_identity_dsa::CoseKeyVerifier;
+use many_protocol::decode_request_from_cose_sign1;
+use many_wasm::{Address, Client, Identity};
+use wasm_bindgen_futures::JsFuture;
+use wasm_bindgen_test::*;
+
+wasm_bindgen_test_configure!(run_in_browser);
+
+/// The CBOR encoding of `null`.
+const NULL: u8 = 0xf6;
+
+fn address_of(address: &Address) -> many_identity::Address {
+    many_identity::Address::from_bytes(&address.to_bytes()).unwrap()
+}
+
+#[wasm_bindgen_test]
+fn address() {
+    let identity = Identity::generate().unwrap();
+    let address = identity.address();
+    assert!(address.is_public_key());
+
+    let parsed = Address::new(&address.to_string_js()).unwrap();
+    assert!(parsed.equals(&address));
+    assert!(Address::from_bytes(&address.to_bytes())
+        .unwrap()
+        .equals(&address));
+    assert!(Address::new("not an address").is_err());
+}
+
+#[wasm_bindgen_test]
+fn sign() {
+    let identity = Identity::generate().unwrap();
+    let envelope = identity
+        .sign(&Address::anonymous(), "ledger.info".to_string(), vec![NULL])
+        .unwrap();
+
+    let envelope = CoseSign1::from_tagged_slice(&envelope).unwrap();
+    let request =
+        decode_request_from_cose_sign1(&envelope, &(AnonymousVerifier, CoseKeyVerifier)).unwrap();
+    assert_eq!(request.from, Some(address_of(&identity.address())));
+    assert_eq!(request.method, "ledger.info");
+    assert_eq!(request.data, vec![NULL]);
+    assert!(request.timestamp.is_some());
+}
+
+#[wasm_bindgen_test]
+async fn call() {
+    // Nothing listens on this port, so the call rejects its promise.
+    let identity = Identity::generate().unwrap();
+    let client = Client::new("http://127.0.0.1:1/", &Address::anonymous(), &identity).unwrap();
+    let result = JsFuture::from(client.call("ledger.info".to_string(), vec![NULL])).await;
+    assert!(result.is_err());
+}
@@ -1,9 +1,11 @@
 use clap::Parser;
+use many_cli_helpers::keystore::identity_from_pem_file;
 use many_cli_helpers::CommonCliFlags;
 use many_identity::verifiers::AnonymousVerifier;
 use many_identity::{Address, Identity};
-use many_identity_dsa::{CoseKeyIdentity, CoseKeyVerifier};
+use many_identity_dsa::CoseKeyVerifier;
 use many_identity_webauthn::WebAuthnVerifier;
+use many_modules::abci_backend::ManyAbciModuleBackend;
 use many_modules::{abci_backend, events, kvstore, web};
 use many_protocol::ManyUrl;
 use many_server::transport::http::HttpServer;
@@ -24,6 +26,9 @@ struct Opts {
     #[clap(flatten)]
     common_flags: CommonCliFlags,
 
+    #[clap(flatten)]
+    http_flags: many_cli_helpers::HttpServerFlags,
+
     /// The location of a PEM file for the identity of this server.
     // The field needs to be an Option for the clap derive to work properly.
     #[clap(long, required = true)]
@@ -76,6 +81,7 @@ struct Opts {
 fn main() {
     let Opts {
         common_flags,
+        http_flags,
         pem,
         addr,
         abci,
@@ -119,8 +125,7 @@ fn main() {
         state = None;
     }
 
-    let pem = std::fs::read_to_string(pem).expect("Could not read PEM file.");
-    let key = CoseKeyIdentity::from_pem(pem).expect("Could not generate identity from PEM file.");
+    let key = identity_from_pem_file(&pem).expect("Could not generate identity from PEM file.");
     info!(address = key.address().to_string().as_str());
 
     let state = state.map(|state| {
@@ -161,6 +166,8 @@ fn main() {
 
     {
         let mut s = many.lock().unwrap();
+        let init = ManyAbciModuleBackend::init(&mut *module.lock().unwrap()).unwrap();
+        s.add_query_endpoints(init.queries());
         let web_commands_module = web::WebCommandsModule::new(module.clone());
         if let Some(path) = allow_addrs {
             let allow_addrs: BTreeSet<Address> =
@@ -183,10 +190,20 @@ fn main() {
         }
 
         if let Some(p) = cache_db {
-            s.add_validator(RequestCacheValidator::new(RocksDbCacheBackend::new(p)));
+            let mut validator =
+                RequestCacheValidator::new(RocksDbCacheBackend::new(p)).with_time_fn(s.time_fn());
+            if abci {
+                // Blocks are not checked against a timeout, so requests are
+                // never forgotten (see `RequestCacheValidator::with_timeout`).
+                validator = validator.with_timeout(u64::MAX);
+            }
+            s.add_validator(validator);
         }
     }
-    let mut many_server = HttpServer::new(many);
+    let mut many_server = HttpServer::new(many.clone());
+    http_flags
+        .configure(&mut many_server)
+        .expect("Could not configure the HTTP server");
 
     signal_hook::flag::register(signal_hook::consts::SIGTERM, many_server.term_signal())
         .expect("Could not register signal handler");
@@ -196,5 +213,7 @@ fn main() {
         .expect("Could not register signal handler");
 
     let runtime = tokio::runtime::Runtime::new().unwrap();
-    runtime.block_on(many_server.bind(addr)).unwrap();
+    runtime
+        .block_on(http_flags.bind(&mut many_server, many, addr))
+        .unwrap();
 }
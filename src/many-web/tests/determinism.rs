@@ -0,0 +1,183 @@
+use many_identity::verifiers::AnonymousVerifier;
+use many_identity::{AnonymousIdentity, Identity};
+use many_identity_dsa::ed25519::generate_random_ed25519_identity;
+use many_identity_dsa::CoseKeyVerifier;
+use many_modules::kvstore::{GetArgs, GetReturns};
+use many_modules::web::{DeployArgs, ListArgs, ListReturns, RemoveArgs};
+use many_modules::{abci_backend, events, kvstore, web};
+use many_server::simulator::{tx, Simulator, Start};
+use many_server::ManyServer;
+use many_types::web::WebDeploymentSource;
+use many_web::module::{InitialStateJson, WebModuleImpl};
+use many_web::storage::HTTP_ROOT;
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+
+/// Create a simulator of `n` web nodes.
+fn simulator(n: usize) -> Simulator {
+    let dirs = (0..n)
+        .map(|_| tempfile::tempdir().expect("Could not create a temporary dir."))
+        .collect::<Vec<_>>();
+
+    Simulator::new(n, move |i, start| {
+        let module_impl = match start {
+            Start::Genesis => {
+                WebModuleImpl::new(InitialStateJson::default(), dirs[i].path(), true)?
+            }
+            Start::Restart => WebModuleImpl::load(dirs[i].path(), true)?,
+        };
+        Ok(server(module_impl))
+    })
+}
+
+/// Create the server of a node, with the modules of many-web.
+fn server(module_impl: WebModuleImpl) -> Arc<Mutex<ManyServer>> {
+    let module_impl = Arc::new(Mutex::new(module_impl));
+    let many = ManyServer::simple(
+        "many-web",
+        AnonymousIdentity,
+        (AnonymousVerifier, CoseKeyVerifier),
+        None,
+    );
+    {
+        let mut s = many.lock().unwrap();
+        s.add_module(web::WebCommandsModule::new(module_impl.clone()));
+        s.add_module(web::WebModule::new(module_impl.clone()));
+        s.add_module(kvstore::KvStoreModule::new(module_impl.clone()));
+        s.add_module(events::EventsModule::new(module_impl.clone()));
+        s.add_module(abci_backend::AbciModule::new(module_impl));
+    }
+    many
+}
+
+/// A zip archive of a website with a single `index.html` file.
+fn archive(index: &str) -> WebDeploymentSource {
+    let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+    zip.start_file("index.html", zip::write::FileOptions::default())
+        .unwrap();
+    zip.write_all(index.as_bytes()).unwrap();
+    WebDeploymentSource::Archive(zip.finish().unwrap().into_inner().into())
+}
+
+fn deploy(from: &impl Identity, site_name: &str, index: &str) -> coset::CoseSign1 {
+    tx(
+        from,
+        "web.deploy",
+        DeployArgs {
+            owner: None,
+            site_name: site_name.to_string(),
+            site_description: None,
+            source: archive(index),
+            memo: None,
+            domain: None,
+        },
+    )
+}
+
+fn index(
+    simulator: &Simulator,
+    node: usize,
+    owner: &impl Identity,
+    site_name: &str,
+) -> Option<Vec<u8>> {
+    let key = format!("{HTTP_ROOT}/{}/{site_name}/index.html", owner.address());
+    let data = simulator
+        .query(
+            node,
+            tx(
+                &AnonymousIdentity,
+                "kvstore.get",
+                GetArgs {
+                    key: key.into_bytes().into(),
+                },
+            ),
+        )
+        .unwrap();
+    minicbor::decode::<GetReturns>(&data)
+        .unwrap()
+        .value
+        .map(|v| v.to_vec())
+}
+
+fn list(simulator: &Simulator, node: usize) -> ListReturns {
+    let data = simulator
+        .query(
+            node,
+            tx(
+                &AnonymousIdentity,
+                "web.list",
+                ListArgs {
+                    count: None,
+                    order: None,
+                    filter: None,
+                    page: None,
+                },
+            ),
+        )
+        .unwrap();
+    minicbor::decode(&data).unwrap()
+}
+
+#[test]
+fn deploys() {
+    let owner = generate_random_ed25519_identity();
+    let other = generate_random_ed25519_identity();
+    let mut simulator = simulator(4);
+
+    let results = simulator.block(vec![
+        deploy(&owner, "site", "<h1>Hello</h1>"),
+        deploy(&owner, "site", "<h1>Again</h1>"),
+        deploy(&AnonymousIdentity, "anonymous", "<h1>Hello</h1>"),
+        deploy(&other, "site", "<h1>Other</h1>"),
+    ]);
+    assert!(results[0].is_ok());
+    assert!(results[1].is_err());
+    assert!(results[2].is_err());
+    assert!(results[3].is_ok());
+
+    for node in 0..4 {
+        assert_eq!(
+            index(&simulator, node, &owner, "site"),
+            Some(b"<h1>Hello</h1>".to_vec())
+        );
+        assert_eq!(
+            index(&simulator, node, &other, "site"),
+            Some(b"<h1>Other</h1>".to_vec())
+        );
+        assert_eq!(list(&simulator, node).total_count, 2);
+    }
+}
+
+#[test]
+fn restarts() {
+    let owner = generate_random_ed25519_identity();
+    let mut simulator = simulator(3);
+
+    // Node 2 misses all blocks, and replays them when it starts again.
+    simulator.stop(2);
+    for i in 0..4 {
+        simulator.block(vec![deploy(&owner, &format!("site{i}"), "<h1>Hello</h1>")]);
+        simulator.restart(0);
+    }
+    let results = simulator.block(vec![tx(
+        &owner,
+        "web.remove",
+        RemoveArgs {
+            owner: None,
+            site_name: "site0".to_string(),
+            memo: None,
+        },
+    )]);
+    assert!(results[0].is_ok());
+    simulator.start(2);
+    simulator.empty_blocks(1);
+
+    for node in 0..3 {
+        assert_eq!(index(&simulator, node, &owner, "site0"), None);
+        assert_eq!(
+            index(&simulator, node, &owner, "site3"),
+            Some(b"<h1>Hello</h1>".to_vec())
+        );
+        assert_eq!(list(&simulator, node).total_count, 3);
+    }
+}
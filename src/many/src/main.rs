@@ -1,18 +1,24 @@
 use anyhow::anyhow;
-use async_recursion::async_recursion;
 use base64::{engine::general_purpose, Engine as _};
 use clap::{ArgGroup, Parser};
-use coset::{CborSerializable, CoseSign1};
+use coset::{CborSerializable, CoseKey, CoseSign1};
 use many_cli_helpers::error::ClientServerError;
+use many_cli_helpers::keystore::{
+    identity_from_pem_file, read_new_password, read_password, Keystore,
+};
+use many_cli_helpers::offline::{self, OfflineMessage};
+use many_client::client::wait;
+use many_client::client::BaseClient;
 use many_client::ManyClient;
+use many_error::ManyError;
 use many_identity::verifiers::AnonymousVerifier;
 use many_identity::{Address, AnonymousIdentity, Identity};
-use many_identity_dsa::{CoseKeyIdentity, CoseKeyVerifier};
-use many_identity_hsm::{Hsm, HsmIdentity, HsmMechanismType, HsmSessionType, HsmUserType};
+use many_identity_dsa::encryption::is_encrypted_pem;
+use many_identity_dsa::hd::{self, DerivationPath, HdKeyAlgorithm, HdSeed};
+use many_identity_dsa::CoseKeyVerifier;
+use many_identity_hsm::{HsmIdentity, HsmModule, HsmSession, HsmSessionType, HsmUserType};
 use many_identity_webauthn::WebAuthnIdentity;
-use many_mock::{parse_mockfile, server::ManyMockServer, MockEntries};
-use many_modules::r#async::attributes::AsyncAttribute;
-use many_modules::r#async::{StatusArgs, StatusReturn};
+use many_mock::{parse_mockfile, record::MockRecorder, server::ManyMockServer, MockEntries};
 use many_modules::{idstore, ledger};
 use many_protocol::{
     encode_cose_sign1_from_request, ManyUrl, RequestMessage, RequestMessageBuilder, ResponseMessage,
@@ -61,16 +67,67 @@ enum SubCommand {
 
     /// Get the token ID per string of a ledger's token.
     GetTokenId(GetTokenIdOpt),
+
+    /// Print the schemas of the arguments and return values of a server's
+    /// endpoints, as CDDL.
+    Describe(DescribeOpt),
+
+    /// Manage the keys of the local keystore.
+    Key(KeyOpt),
+
+    /// Create unsigned requests, sign them (e.g. on an offline machine),
+    /// inspect them and send them to a server.
+    Request(RequestOpt),
 }
 
 #[derive(Parser)]
+#[clap(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 struct IdOpt {
     /// An hexadecimal value to encode, an identity textual format to decode or
-    /// a PEM file to read
-    arg: String,
+    /// a PEM or COSE key file to read
+    #[clap(required = true)]
+    arg: Option<String>,
 
     /// Allow to generate the identity with a specific subresource ID.
     subid: Option<u32>,
+
+    #[clap(subcommand)]
+    subcommand: Option<IdSubCommand>,
+}
+
+#[derive(Parser)]
+enum IdSubCommand {
+    /// Derive identities from a BIP-39 mnemonic, read from `$MANY_MNEMONIC` or
+    /// prompted for.
+    Derive(IdDeriveOpt),
+}
+
+#[derive(Parser)]
+struct IdDeriveOpt {
+    /// The derivation path. All indices must be hardened, except the last one
+    /// which is then a subresource ID.
+    #[clap(long, default_value = hd::DEFAULT_DERIVATION_PATH)]
+    path: DerivationPath,
+
+    /// The key algorithm, `ed25519` or `ecdsa`.
+    #[clap(long, default_value = "ed25519")]
+    algorithm: HdKeyAlgorithm,
+
+    /// The number of consecutive identities to derive, starting at the path.
+    #[clap(long, default_value = "1")]
+    count: u32,
+
+    /// Prompt for a BIP-39 passphrase.
+    #[clap(long)]
+    passphrase: bool,
+
+    /// Generate a new mnemonic of this number of words and print it first.
+    #[clap(long, conflicts_with("passphrase"))]
+    generate: Option<usize>,
+
+    /// Print the private key PEM of each identity instead of its address.
+    #[clap(long)]
+    pem: bool,
 }
 
 #[derive(Parser)]
@@ -122,8 +179,11 @@ struct MessageOpt {
     #[clap(long)]
     pem: Option<PathBuf>,
 
+    #[clap(flatten)]
+    key_flags: many_cli_helpers::KeyFlags,
+
     /// Use Webauthn as the authentication scheme.
-    #[clap(long, conflicts_with("pem"))]
+    #[clap(long, conflicts_with_all(&["pem", "key"]))]
     webauthn: bool,
 
     /// The origin to use in the webauthn flow. By default will use the
@@ -181,15 +241,15 @@ struct MessageOpt {
     to: Option<Address>,
 
     /// HSM PKCS#11 module path
-    #[clap(long, conflicts_with("pem"))]
+    #[clap(long, conflicts_with_all(&["pem", "key"]))]
     module: Option<PathBuf>,
 
     /// HSM PKCS#11 slot ID
-    #[clap(long, conflicts_with("pem"))]
+    #[clap(long, conflicts_with_all(&["pem", "key"]))]
     slot: Option<u64>,
 
     /// HSM PKCS#11 key ID
-    #[clap(long, conflicts_with("pem"))]
+    #[clap(long, conflicts_with_all(&["pem", "key"]))]
     keyid: Option<String>,
 
     /// The method to call.
@@ -203,6 +263,11 @@ struct MessageOpt {
     /// the specification for more information.
     #[clap(long)]
     proof: Option<bool>,
+
+    /// Check the content of the message against the schema of the method,
+    /// described by the server, before sending it.
+    #[clap(long, requires("server"))]
+    validate: bool,
 }
 
 #[derive(Parser)]
@@ -223,6 +288,208 @@ struct ServerOpt {
     /// Default is mockfile.toml, gives an error if the file does not exist
     #[clap(long, short, value_parser = parse_mockfile)]
     mockfile: Option<MockEntries>,
+
+    /// Forward all requests to this server, and record them with their
+    /// responses in a mockfile.
+    #[clap(long, conflicts_with("mockfile"))]
+    record: Option<Url>,
+
+    /// The mockfile written when recording requests.
+    #[clap(long, default_value = "mockfile.toml")]
+    record_to: PathBuf,
+}
+
+#[derive(Parser)]
+struct KeyOpt {
+    /// The keystore directory. By default, `$MANY_KEYSTORE` or `$HOME/.many/keys`.
+    #[clap(long)]
+    keystore: Option<PathBuf>,
+
+    #[clap(subcommand)]
+    subcommand: KeySubCommand,
+}
+
+#[derive(Parser)]
+enum KeySubCommand {
+    /// Generate a new Ed25519 key and print its address.
+    Generate(KeyNameOpt),
+
+    /// List the names of the keys in the keystore.
+    List,
+
+    /// Import a PEM file, plaintext or encrypted, and print its address.
+    Import(KeyImportOpt),
+
+    /// Print the encrypted PEM of a key.
+    Export(KeyExportOpt),
+}
+
+#[derive(Parser)]
+struct KeyNameOpt {
+    /// The name of the key.
+    name: String,
+}
+
+#[derive(Parser)]
+struct KeyImportOpt {
+    /// The name of the key.
+    name: String,
+
+    /// The PEM file to import.
+    pem: PathBuf,
+}
+
+#[derive(Parser)]
+struct KeyExportOpt {
+    /// The name of the key.
+    name: String,
+
+    /// Print the key in plaintext instead.
+    #[clap(long)]
+    decrypt: bool,
+}
+
+#[derive(Parser)]
+struct RequestOpt {
+    #[clap(subcommand)]
+    subcommand: RequestSubCommand,
+}
+
+#[derive(Parser)]
+enum RequestSubCommand {
+    /// Create an unsigned request and write it to a file.
+    Create(RequestCreateOpt),
+
+    /// Sign a request file, and write the signed envelope to a file.
+    Sign(Box<RequestSignOpt>),
+
+    /// Print the content of request or envelope files.
+    Inspect(RequestInspectOpt),
+
+    /// Send envelope files to a server, one after the other, and print their
+    /// responses. Stops at the first error.
+    Submit(RequestSubmitOpt),
+}
+
+#[derive(Parser)]
+struct RequestCreateOpt {
+    /// The identity which will sign the request. By default, the request is
+    /// from the identity signing it.
+    #[clap(long)]
+    from: Option<Address>,
+
+    /// The identity to send it to.
+    #[clap(long)]
+    to: Option<Address>,
+
+    /// Request a proof of the value.
+    #[clap(long)]
+    proof: bool,
+
+    /// The file to write the request to.
+    #[clap(long, short)]
+    out: PathBuf,
+
+    /// The method to call.
+    method: String,
+
+    /// The content of the message itself (its payload), in CBOR diagnostic
+    /// notation.
+    data: Option<String>,
+}
+
+#[derive(Parser)]
+#[clap(group(
+    ArgGroup::new("hsm")
+        .multiple(true)
+        .args(&["module", "slot", "keyid"])
+        .requires_all(&["module", "slot", "keyid"])
+))]
+struct RequestSignOpt {
+    /// The request file to sign.
+    request: PathBuf,
+
+    /// The file to write the signed envelope to.
+    #[clap(long, short)]
+    out: PathBuf,
+
+    /// Keep the timestamp of the request instead of using the current time.
+    #[clap(long)]
+    keep_timestamp: bool,
+
+    /// A pem file to sign the request.
+    #[clap(long)]
+    pem: Option<PathBuf>,
+
+    #[clap(flatten)]
+    key_flags: many_cli_helpers::KeyFlags,
+
+    /// Use Webauthn to sign the request, with the key registered on this
+    /// relying party.
+    #[clap(long, conflicts_with("pem"))]
+    webauthn: Option<ManyUrl>,
+
+    /// The origin to use in the webauthn flow. By default will use the
+    /// relying party's protocol, hostname and port.
+    #[clap(long, requires("webauthn"))]
+    webauthn_origin: Option<ManyUrl>,
+
+    /// The recall phrase for webauthn.
+    #[clap(long, requires("webauthn"), conflicts_with("address"))]
+    phrase: Option<String>,
+
+    /// The address for webauthn.
+    #[clap(long, requires("webauthn"), conflicts_with("phrase"))]
+    address: Option<Address>,
+
+    /// The Relaying party Identifier. By default, this will be the hostname
+    /// of the origin URL.
+    #[clap(long, requires("webauthn"))]
+    rp_id: Option<String>,
+
+    /// HSM PKCS#11 module path
+    #[clap(long, conflicts_with_all(&["pem", "key"]))]
+    module: Option<PathBuf>,
+
+    /// HSM PKCS#11 slot ID
+    #[clap(long, conflicts_with_all(&["pem", "key"]))]
+    slot: Option<u64>,
+
+    /// HSM PKCS#11 key ID
+    #[clap(long, conflicts_with_all(&["pem", "key"]))]
+    keyid: Option<String>,
+}
+
+#[derive(Parser)]
+struct RequestInspectOpt {
+    /// The request or envelope files.
+    #[clap(required = true)]
+    files: Vec<PathBuf>,
+}
+
+#[derive(Parser)]
+struct RequestSubmitOpt {
+    /// The server to send the envelopes to.
+    #[clap(long)]
+    server: Url,
+
+    /// Show the async tokens and continue right away, instead of waiting for
+    /// the results of async operations.
+    #[clap(long)]
+    r#async: bool,
+
+    /// The envelope files, in the order to send them.
+    #[clap(required = true)]
+    files: Vec<PathBuf>,
+}
+
+#[derive(Parser)]
+struct DescribeOpt {
+    /// The server to call.
+    server: Url,
+
+    /// Only describe these methods.
+    methods: Vec<String>,
 }
 
 #[derive(Parser)]
@@ -235,70 +502,40 @@ struct GetTokenIdOpt {
     symbol: String,
 }
 
-#[async_recursion(?Send)]
-async fn show_response<'a>(
-    response: &'a ResponseMessage,
-    client: ManyClient<impl Identity + 'a>,
+async fn show_response(
+    response: ResponseMessage,
+    client: ManyClient<impl Identity>,
     r#async: bool,
 ) -> Result<(), ClientServerError> {
-    let ResponseMessage {
-        data, attributes, ..
-    } = response;
-
-    let payload = data.clone()?;
-    if payload.is_empty() {
-        let attr = attributes.get::<AsyncAttribute>().unwrap();
-        info!("Async token: {}", hex::encode(&attr.token));
-
-        // Allow eprint/ln for showing the progress bar, when we're interactive.
-        #[allow(clippy::print_stderr)]
-        fn progress(str: &str, done: bool) {
-            if stderr().is_terminal() {
-                if done {
-                    eprintln!("{str}");
-                } else {
-                    eprint!("{str}");
-                }
+    // Allow eprint/ln for showing the progress, when we're interactive.
+    #[allow(clippy::print_stderr)]
+    fn progress(str: &str, done: bool) {
+        if stderr().is_terminal() {
+            if done {
+                eprintln!("{str}");
+            } else {
+                eprint!("{str}");
             }
         }
+    }
 
-        if !r#async {
-            progress("Waiting.", false);
-
-            // TODO: improve on this by using duration and thread and watchdog.
-            // Wait for the server for ~60 seconds by pinging it every second.
-            for _ in 0..60 {
-                let response = client
-                    .call(
-                        "async.status",
-                        StatusArgs {
-                            token: attr.token.clone(),
-                        },
-                    )
-                    .await?;
-                let status: StatusReturn = minicbor::decode(&response.data?)?;
-                match status {
-                    StatusReturn::Done { response } => {
-                        progress(".", true);
-                        let response: ResponseMessage =
-                            minicbor::decode(&response.payload.ok_or_else(|| {
-                                anyhow!("Envelope with empty payload. Expected ResponseMessage")
-                            })?)?;
-                        return show_response(&response, client, r#async).await;
-                    }
-                    StatusReturn::Expired => {
-                        progress(".", true);
-                        info!("Async token expired before we could check it.");
-                        return Ok(());
-                    }
-                    _ => {
-                        progress(".", false);
-                        std::thread::sleep(Duration::from_secs(1));
-                    }
-                }
+    let response = match wait::async_token(&response) {
+        Some(token) => {
+            info!("Async token: {}", hex::encode(&token));
+            if r#async {
+                return Ok(());
             }
+
+            progress("Waiting...", false);
+            let response = client.wait_response(response).await;
+            progress("", true);
+            response?
         }
-    } else {
+        None => response,
+    };
+
+    let payload = response.data?;
+    if !payload.is_empty() {
         println!(
             "{}",
             cbor_diag::parse_bytes(&payload).unwrap().to_diag_pretty()
@@ -318,10 +555,26 @@ async fn message(
     timestamp: Option<SystemTime>,
     r#async: bool,
     proof: bool,
+    validate: bool,
 ) -> Result<(), ClientServerError> {
     let address = key.address();
     let client = ManyClient::new(s, to, key).unwrap();
 
+    if validate {
+        let descriptions = BaseClient::new(client.clone()).describe().await?;
+        let description = descriptions
+            .0
+            .get(&method)
+            .ok_or_else(|| anyhow!("The server does not describe '{method}'."))?;
+        if let Some(argument) = &description.argument {
+            // An empty content is decoded as `null`.
+            let data = if data.is_empty() { &[0xf6][..] } else { &data };
+            argument
+                .validate(data)
+                .map_err(|e| anyhow!("Invalid content for '{method}': {e}"))?;
+        }
+    }
+
     let mut nonce = [0u8; 16];
     rand::RngCore::fill_bytes(&mut rand::thread_rng(), &mut nonce);
 
@@ -353,7 +606,7 @@ async fn message(
 
     let response = client.send_message(message).await.map_err(|e| anyhow!(e))?;
 
-    show_response(&response, client, r#async).await
+    show_response(response, client, r#async).await
 }
 
 async fn message_from_hex(
@@ -372,7 +625,27 @@ async fn message_from_hex(
     let response =
         ResponseMessage::decode_and_verify(&cose_sign1, &(AnonymousVerifier, CoseKeyVerifier))?;
 
-    show_response(&response, client, r#async).await
+    show_response(response, client, r#async).await
+}
+
+/// Open an HSM session, prompting for the user PIN, to sign with a key.
+fn hsm_identity(module: PathBuf, slot: u64, keyid: String) -> HsmIdentity {
+    trace!("Getting user PIN");
+    let pin = rpassword::prompt_password("Please enter the HSM user PIN: ")
+        .expect("I/O error when reading HSM PIN");
+    let keyid = hex::decode(keyid).expect("Failed to decode keyid to hex");
+
+    let module = HsmModule::load(module).expect("Failed to initialize HSM module");
+    let session = HsmSession::open(
+        &module,
+        slot,
+        HsmSessionType::RO,
+        Some(HsmUserType::User),
+        Some(pin),
+    )
+    .expect("Failed to open HSM session");
+
+    HsmIdentity::new(session, keyid).expect("Unable to create identity from HSM")
 }
 
 async fn create_webauthn_identity(
@@ -431,8 +704,18 @@ async fn main() {
         .init();
 
     match subcommand {
+        SubCommand::Id(IdOpt {
+            subcommand: Some(IdSubCommand::Derive(o)),
+            ..
+        }) => {
+            if let Err(e) = derive(o) {
+                error!("{e}");
+                process::exit(1);
+            }
+        }
         SubCommand::Id(o) => {
-            if let Ok(data) = hex::decode(&o.arg) {
+            let arg = o.arg.expect("The argument is required");
+            if let Ok(data) = hex::decode(&arg) {
                 match Address::try_from(data.as_slice()) {
                     Ok(mut i) => {
                         if let Some(subid) = o.subid {
@@ -447,16 +730,31 @@ async fn main() {
                         std::process::exit(1);
                     }
                 }
-            } else if let Ok(mut i) = Address::try_from(o.arg.clone()) {
+            } else if let Ok(mut i) = Address::try_from(arg.clone()) {
                 if let Some(subid) = o.subid {
                     i = i
                         .with_subresource_id(subid)
                         .expect("Invalid subresource id");
                 }
                 println!("{}", hex::encode(i.to_vec()));
-            } else if let Ok(pem_content) = std::fs::read_to_string(&o.arg) {
-                // Create the identity from the public key hash.
-                let mut i = CoseKeyIdentity::from_pem(pem_content).unwrap().address();
+            } else if let Ok(content) = std::fs::read(&arg) {
+                // Create the identity from the public key hash. The file is either
+                // a PEM file or a CBOR encoded COSE key.
+                let mut i = match String::from_utf8(content) {
+                    Ok(_) => identity_from_pem_file(&arg)
+                        .unwrap_or_else(|e| {
+                            error!("Could not read the PEM file: {e}");
+                            process::exit(2);
+                        })
+                        .address(),
+                    Err(e) => CoseKey::from_slice(e.as_bytes())
+                        .map_err(|e| ManyError::unknown(e.to_string()))
+                        .and_then(|key| many_identity_dsa::address(&key))
+                        .unwrap_or_else(|e| {
+                            error!("Could not read the COSE key: {e}");
+                            process::exit(2);
+                        }),
+                };
                 if let Some(subid) = o.subid {
                     i = i
                         .with_subresource_id(subid)
@@ -472,18 +770,12 @@ async fn main() {
         SubCommand::HsmId(o) => {
             let keyid = hex::decode(o.keyid).expect("Failed to decode keyid to hex");
 
-            {
-                let mut hsm = Hsm::get_instance().expect("HSM mutex poisoned");
-                hsm.init(o.module, keyid)
-                    .expect("Failed to initialize HSM module");
+            let module = HsmModule::load(o.module).expect("Failed to initialize HSM module");
+            let session = HsmSession::open(&module, o.slot, HsmSessionType::RO, None, None)
+                .expect("Failed to open HSM session");
 
-                // The session will stay open until the application terminates
-                hsm.open_session(o.slot, HsmSessionType::RO, None, None)
-                    .expect("Failed to open HSM session");
-            }
-
-            let mut id = HsmIdentity::new(HsmMechanismType::ECDSA)
-                .expect("Unable to create CoseKeyIdentity from HSM")
+            let mut id = HsmIdentity::new(session, keyid)
+                .expect("Unable to create identity from HSM")
                 .address();
 
             if let Some(subid) = o.subid {
@@ -509,50 +801,33 @@ async fn main() {
                 .data
                 .map_or(vec![], |d| cbor_diag::parse_diag(d).unwrap().to_bytes());
 
-            let from_identity: Box<dyn Identity> = if let (Some(module), Some(slot), Some(keyid)) =
-                (o.module, o.slot, o.keyid)
-            {
-                trace!("Getting user PIN");
-                let pin = rpassword::prompt_password("Please enter the HSM user PIN: ")
-                    .expect("I/O error when reading HSM PIN");
-                let keyid = hex::decode(keyid).expect("Failed to decode keyid to hex");
-
+            let from_identity: Box<dyn Identity> =
+                if let (Some(module), Some(slot), Some(keyid)) = (o.module, o.slot, o.keyid) {
+                    Box::new(hsm_identity(module, slot, keyid))
+                } else if let Some(identity) = o
+                    .key_flags
+                    .identity(o.pem.as_deref())
+                    .expect("Could not load the identity")
                 {
-                    let mut hsm = Hsm::get_instance().expect("HSM mutex poisoned");
-                    hsm.init(module, keyid)
-                        .expect("Failed to initialize HSM module");
-
-                    // The session will stay open until the application terminates
-                    hsm.open_session(slot, HsmSessionType::RO, Some(HsmUserType::User), Some(pin))
-                        .expect("Failed to open HSM session");
-                }
-
-                // Only ECDSA is supported at the moment. It should be easy to add support for
-                // new EC mechanisms.
-                Box::new(
-                    HsmIdentity::new(HsmMechanismType::ECDSA)
-                        .expect("Unable to create CoseKeyIdentity from HSM"),
-                )
-            } else if let Some(p) = o.pem {
-                // If `pem` is not provided, use anonymous and don't sign.
-                Box::new(CoseKeyIdentity::from_pem(std::fs::read_to_string(p).unwrap()).unwrap())
-            } else if o.webauthn {
-                let rp =
-                    o.rp.as_ref()
-                        .or(o.server.as_ref())
-                        .expect("Must pass a server or --rp");
-                let identity = create_webauthn_identity(
-                    rp.clone(),
-                    o.webauthn_origin,
-                    o.phrase,
-                    o.address,
-                    o.rp_id,
-                )
-                .await;
-                Box::new(identity)
-            } else {
-                Box::new(AnonymousIdentity)
-            };
+                    // If no key is provided, use anonymous and don't sign.
+                    Box::new(identity)
+                } else if o.webauthn {
+                    let rp =
+                        o.rp.as_ref()
+                            .or(o.server.as_ref())
+                            .expect("Must pass a server or --rp");
+                    let identity = create_webauthn_identity(
+                        rp.clone(),
+                        o.webauthn_origin,
+                        o.phrase,
+                        o.address,
+                        o.rp_id,
+                    )
+                    .await;
+                    Box::new(identity)
+                } else {
+                    Box::new(AnonymousIdentity)
+                };
 
             if let Some(s) = o.server {
                 let result = if let Some(hex) = o.from_hex {
@@ -567,6 +842,7 @@ async fn main() {
                         timestamp,
                         o.r#async,
                         o.proof.unwrap_or_default(),
+                        o.validate,
                     )
                     .await
                 };
@@ -612,10 +888,8 @@ async fn main() {
             }
         }
         SubCommand::Server(o) => {
-            let pem = std::fs::read_to_string(&o.pem).expect("Could not read PEM file.");
             let key = Arc::new(
-                CoseKeyIdentity::from_pem(&pem)
-                    .expect("Could not generate identity from PEM file."),
+                identity_from_pem_file(&o.pem).expect("Could not generate identity from PEM file."),
             );
 
             let many = ManyServer::simple(
@@ -624,6 +898,12 @@ async fn main() {
                 (AnonymousVerifier, CoseKeyVerifier),
                 Some(std::env!("CARGO_PKG_VERSION").to_string()),
             );
+            if let Some(upstream) = o.record {
+                let recorder = MockRecorder::new(upstream, o.record_to);
+                HttpServer::new(recorder).bind(o.addr).await.unwrap();
+                return;
+            }
+
             let mockfile = o.mockfile.unwrap_or_default();
             if !mockfile.is_empty() {
                 let mut many_locked = many.lock().unwrap();
@@ -663,5 +943,179 @@ async fn main() {
 
             println!("{id}");
         }
+        SubCommand::Describe(o) => {
+            if let Err(e) = describe(o).await {
+                error!("{e}");
+                process::exit(1);
+            }
+        }
+        SubCommand::Key(o) => {
+            if let Err(e) = key(o) {
+                error!("{e}");
+                process::exit(1);
+            }
+        }
+        SubCommand::Request(o) => {
+            if let Err(e) = request(o).await {
+                error!("{e}");
+                process::exit(1);
+            }
+        }
+    }
+}
+
+async fn request(o: RequestOpt) -> Result<(), ClientServerError> {
+    match o.subcommand {
+        RequestSubCommand::Create(o) => {
+            let data = o
+                .data
+                .map(|d| cbor_diag::parse_diag(d).map(|d| d.to_bytes()))
+                .transpose()
+                .map_err(|e| anyhow!("Invalid data: {e:?}"))?
+                .unwrap_or_default();
+            let mut request = offline::request(
+                o.from.unwrap_or_default(),
+                o.to.unwrap_or_default(),
+                o.method,
+                data,
+            )?;
+            if o.proof {
+                request.attributes.insert(Attribute::id(3));
+            }
+            offline::write(&o.out, &OfflineMessage::Request(request))?;
+        }
+        RequestSubCommand::Sign(o) => {
+            let request = offline::read(&o.request)?.request()?;
+            let identity: Box<dyn Identity> = if let (Some(module), Some(slot), Some(keyid)) =
+                (o.module, o.slot, o.keyid)
+            {
+                Box::new(hsm_identity(module, slot, keyid))
+            } else if let Some(rp) = o.webauthn {
+                Box::new(
+                    create_webauthn_identity(rp, o.webauthn_origin, o.phrase, o.address, o.rp_id)
+                        .await,
+                )
+            } else {
+                Box::new(
+                    o.key_flags
+                        .identity(o.pem.as_deref())?
+                        .ok_or_else(|| anyhow!("A key is required to sign the request."))?,
+                )
+            };
+
+            let envelope = offline::sign(request, &identity, o.keep_timestamp)?;
+            offline::write(&o.out, &OfflineMessage::Envelope(envelope))?;
+        }
+        RequestSubCommand::Inspect(o) => {
+            for file in o.files {
+                println!("# {}", file.display());
+                println!("{}", offline::describe(&offline::read(&file)?)?);
+            }
+        }
+        RequestSubCommand::Submit(o) => {
+            for file in o.files {
+                info!("Sending {}", file.display());
+                let message = offline::read(&file)?;
+                let to = message.request()?.to;
+                let envelope = message.into_envelope()?;
+
+                let cose_sign1 =
+                    many_client::client::send_envelope(o.server.clone(), envelope).await?;
+                let response = ResponseMessage::decode_and_verify(
+                    &cose_sign1,
+                    &(AnonymousVerifier, CoseKeyVerifier),
+                )?;
+                let client = ManyClient::new(o.server.clone(), to, AnonymousIdentity)
+                    .map_err(|e| anyhow!(e))?;
+                show_response(response, client, o.r#async).await?;
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn describe(o: DescribeOpt) -> Result<(), ClientServerError> {
+    let client = ManyClient::new(o.server, Address::anonymous(), AnonymousIdentity)
+        .map_err(|e| anyhow!(e))?;
+    let descriptions = BaseClient::new(client).describe().await?;
+
+    for (method, description) in descriptions.0 {
+        if !o.methods.is_empty() && !o.methods.contains(&method) {
+            continue;
+        }
+        println!("; {method}");
+        if let Some(argument) = description.argument {
+            println!("{}", argument.to_cddl(&format!("{method}-args")));
+        }
+        println!(
+            "{}",
+            description.returns.to_cddl(&format!("{method}-returns"))
+        );
+    }
+    Ok(())
+}
+
+fn derive(o: IdDeriveOpt) -> Result<(), anyhow::Error> {
+    let phrase = match o.generate {
+        Some(words) => {
+            let phrase = hd::generate_mnemonic(words)?;
+            println!("{phrase}");
+            phrase
+        }
+        None => match std::env::var("MANY_MNEMONIC") {
+            Ok(phrase) => phrase,
+            Err(_) => rpassword::prompt_password("Mnemonic: ")?,
+        },
+    };
+    let passphrase = if o.passphrase {
+        rpassword::prompt_password("Passphrase: ")?
+    } else {
+        String::new()
+    };
+
+    let seed = HdSeed::from_mnemonic(phrase.trim(), &passphrase)?;
+    for n in 0..o.count {
+        let path = o.path.sibling(n)?;
+        if o.pem {
+            print!("{}", seed.pem(o.algorithm, &path)?);
+        } else {
+            println!("{path} {}", seed.address(o.algorithm, &path)?);
+        }
     }
+    Ok(())
+}
+
+fn key(o: KeyOpt) -> Result<(), anyhow::Error> {
+    match o.subcommand {
+        KeySubCommand::Generate(KeyNameOpt { name }) => {
+            let keystore = Keystore::open_or_create(o.keystore)?;
+            let password = read_new_password()?;
+            println!("{}", keystore.generate(&name, &password)?);
+        }
+        KeySubCommand::List => {
+            for name in Keystore::open(o.keystore)?.list()? {
+                println!("{name}");
+            }
+        }
+        KeySubCommand::Import(KeyImportOpt { name, pem }) => {
+            let keystore = Keystore::open_or_create(o.keystore)?;
+            let pem = std::fs::read_to_string(pem)?;
+            let password = if is_encrypted_pem(&pem) {
+                read_password("Password of the PEM file: ")?
+            } else {
+                read_new_password()?
+            };
+            println!("{}", keystore.import(&name, &pem, &password)?);
+        }
+        KeySubCommand::Export(KeyExportOpt { name, decrypt }) => {
+            let keystore = Keystore::open(o.keystore)?;
+            if decrypt {
+                let password = read_password(&format!("Password for key \"{name}\": "))?;
+                print!("{}", keystore.export(&name, &password)?);
+            } else {
+                print!("{}", keystore.read_encrypted(&name)?);
+            }
+        }
+    }
+    Ok(())
 }
@@ -1,17 +1,13 @@
 use clap::Parser;
-use many_client::client::blocking::ManyClient;
+use many_client::client::blocking::{ManyClient, WebClient};
 use many_error::ManyError;
-use many_identity::{Address, AnonymousIdentity, Identity};
-use many_identity_dsa::CoseKeyIdentity;
-use many_modules::r#async::{StatusArgs, StatusReturn};
+use many_identity::{Address, Identity};
+use many_modules::web;
 use many_modules::web::ListArgs;
-use many_modules::{r#async, web};
-use many_protocol::ResponseMessage;
 use many_types::web::{WebDeploymentFilter, WebDeploymentSource};
 use many_types::{Memo, SortOrder};
 use std::path::PathBuf;
-use std::time::Duration;
-use tracing::{debug, error, info};
+use tracing::{debug, error};
 
 #[derive(Debug, Parser)]
 struct Opts {
@@ -31,6 +27,9 @@ struct Opts {
     #[clap(long)]
     pem: Option<PathBuf>,
 
+    #[clap(flatten)]
+    key_flags: many_cli_helpers::KeyFlags,
+
     #[clap(subcommand)]
     subcommand: SubCommand,
 }
@@ -152,13 +151,8 @@ fn deploy(
         memo,
         domain,
     };
-    let response = client.call("web.deploy", arguments)?;
-    let payload = wait_response(client, response)?;
-    println!(
-        "{}",
-        cbor_diag::parse_bytes(payload).unwrap().to_diag_pretty()
-    );
-    Ok(())
+    let result = WebClient::new(client).deploy(arguments)?;
+    print_cbor(result)
 }
 
 fn update(
@@ -180,13 +174,8 @@ fn update(
         memo,
         domain,
     };
-    let response = client.call("web.update", arguments)?;
-    let payload = wait_response(client, response)?;
-    println!(
-        "{}",
-        cbor_diag::parse_bytes(payload).unwrap().to_diag_pretty()
-    );
-    Ok(())
+    let result = WebClient::new(client).update(arguments)?;
+    print_cbor(result)
 }
 
 fn remove(
@@ -200,13 +189,8 @@ fn remove(
         site_name,
         memo,
     };
-    let response = client.call("web.remove", arguments)?;
-    let payload = wait_response(client, response)?;
-    println!(
-        "{}",
-        cbor_diag::parse_bytes(payload).unwrap().to_diag_pretty()
-    );
-    Ok(())
+    let result = WebClient::new(client).remove(arguments)?;
+    print_cbor(result)
 }
 
 fn list(
@@ -222,8 +206,13 @@ fn list(
         filter,
         page,
     };
-    let response = client.call("web.list", args)?;
-    let payload = wait_response(client, response)?;
+    let result = WebClient::new(client).list(args)?;
+    print_cbor(result)
+}
+
+/// Print a response in CBOR diagnostic notation.
+fn print_cbor(result: impl minicbor::Encode<()>) -> Result<(), ManyError> {
+    let payload = minicbor::to_vec(result).map_err(ManyError::serialization_error)?;
     println!(
         "{}",
         cbor_diag::parse_bytes(payload).unwrap().to_diag_pretty()
@@ -231,74 +220,10 @@ fn list(
     Ok(())
 }
 
-pub(crate) fn wait_response(
-    client: ManyClient<impl Identity>,
-    response: ResponseMessage,
-) -> Result<Vec<u8>, ManyError> {
-    let ResponseMessage {
-        data, attributes, ..
-    } = response;
-
-    let payload = data?;
-    debug!("response: {}", hex::encode(&payload));
-    if payload.is_empty() {
-        let attr = match attributes.get::<r#async::attributes::AsyncAttribute>() {
-            Ok(attr) => attr,
-            _ => {
-                info!("Empty payload.");
-                return Ok(Vec::new());
-            }
-        };
-        info!("Async token: {}", hex::encode(&attr.token));
-
-        let progress =
-            indicatif::ProgressBar::new_spinner().with_message("Waiting for async response");
-        progress.enable_steady_tick(Duration::from_millis(100));
-
-        // TODO: improve on this by using duration and thread and watchdog.
-        // Wait for the server for ~60 seconds by pinging it every second.
-        for _ in 0..60 {
-            let response = client.call(
-                "async.status",
-                StatusArgs {
-                    token: attr.token.clone(),
-                },
-            )?;
-            let status: StatusReturn =
-                minicbor::decode(&response.data?).map_err(ManyError::deserialization_error)?;
-            match status {
-                StatusReturn::Done { response } => {
-                    progress.finish();
-                    let response: ResponseMessage =
-                        minicbor::decode(&response.payload.ok_or_else(|| {
-                            ManyError::deserialization_error(
-                                "Empty payload. Expected ResponseMessage.",
-                            )
-                        })?)
-                        .map_err(ManyError::deserialization_error)?;
-                    return wait_response(client, response);
-                }
-                StatusReturn::Expired => {
-                    progress.finish();
-                    info!("Async token expired before we could check it.");
-                    return Ok(Vec::new());
-                }
-                _ => {
-                    std::thread::sleep(Duration::from_secs(1));
-                }
-            }
-        }
-        Err(ManyError::unknown(
-            "Transport timed out waiting for async result.",
-        ))
-    } else {
-        Ok(payload)
-    }
-}
-
 fn main() {
     let Opts {
         pem,
+        key_flags,
         server,
         server_id,
         subcommand,
@@ -310,10 +235,9 @@ fn main() {
 
     debug!("{:?}", Opts::parse());
 
-    let key = pem.map_or_else(
-        || Box::new(AnonymousIdentity) as Box<dyn Identity>,
-        |p| Box::new(CoseKeyIdentity::from_pem(std::fs::read_to_string(p).unwrap()).unwrap()),
-    );
+    let key = key_flags
+        .identity_or_anonymous(pem.as_deref())
+        .expect("Could not load the identity");
 
     let client = ManyClient::new(server, server_id, key).unwrap();
     let result = match subcommand {
@@ -38,3 +38,20 @@ function teardown() {
     call_ledger --pem=4 --port=8000 send --account="$account_id" "$(identity 4)" 2000 MFX
     assert_output --partial "Sender needs role 'canLedgerTransact' to perform this operation."
 }
+
+@test "$SUITE: ledger history shows the sends of an account" {
+    call_ledger --pem=1 --port=8000 send "$(identity 3)" 1000 MFX
+    call_ledger --pem=2 --port=8000 send "$(identity 3)" 2000 MFX
+
+    call_ledger --port=8000 history "$(identity 3)"
+    assert_output --partial "received 1000 MFX from $(identity 1)"
+    assert_output --partial "received 2000 MFX from $(identity 2)"
+
+    call_ledger --port=8000 history --page-size=1 --format=csv "$(identity 1)"
+    assert_output --partial "id,time,kind,from,to,symbol,symbol_name,amount,memo,details"
+    assert_output --partial ",send,$(identity 1),$(identity 3),$MFX_ADDRESS,MFX,1000,,"
+    refute_output --partial "2000"
+
+    call_ledger --port=8000 history --format=json --kind=token-mint "$(identity 3)"
+    assert_output "[]"
+}